@@ -2,7 +2,10 @@
 #![feature(test)]
 extern crate test;
 
-use squirrel_json::Document;
+use squirrel_json::{
+    de::{BinaryFormat, ScanOptions},
+    Document,
+};
 
 use std::str;
 
@@ -14,6 +17,71 @@ fn read_10kb_event_stacktrace_offsets_simd(b: &mut test::Bencher) {
     b.iter(|| Document::scan_trusted(input))
 }
 
+#[bench]
+fn read_10kb_event_stacktrace_offsets_simd_interleave_utf8(b: &mut test::Bencher) {
+    let input = include_bytes!("../cases/10kb_event_stacktrace.json");
+    let options = ScanOptions::new().interleave_utf8(true);
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_trusted_with(input, &options))
+}
+
+fn events_16mb() -> String {
+    let event = str::from_utf8(include_bytes!("../cases/10kb_event_stacktrace.json")).unwrap();
+
+    let mut input = String::from("{\"events\":[");
+    while input.len() < 16 * 1024 * 1024 {
+        input.push_str(&serde_json::to_string(&event[..4000]).unwrap());
+        input.push(',');
+    }
+    input.push_str("null]}");
+
+    input
+}
+
+#[bench]
+fn read_16mb_events_offsets_simd(b: &mut test::Bencher) {
+    let input = events_16mb();
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_trusted(input.as_bytes()))
+}
+
+#[bench]
+fn read_16mb_events_offsets_simd_prefetch(b: &mut test::Bencher) {
+    let input = events_16mb();
+    let options = ScanOptions::new().prefetch(true);
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_trusted_with(input.as_bytes(), &options))
+}
+
+#[bench]
+fn read_10kb_event_stacktrace_offsets_simd_lazy(b: &mut test::Bencher) {
+    let input = include_bytes!("../cases/10kb_event_stacktrace.json");
+    let options = ScanOptions::new().lazy_depth(1);
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_trusted_with(input, &options))
+}
+
+#[bench]
+fn read_10kb_event_stacktrace_offsets_simd_flat(b: &mut test::Bencher) {
+    let input = include_bytes!("../cases/10kb_event_stacktrace.json");
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_trusted_flat(input))
+}
+
+#[bench]
+fn read_10kb_event_stacktrace_offsets_simd_presize(b: &mut test::Bencher) {
+    let input = include_bytes!("../cases/10kb_event_stacktrace.json");
+    let options = ScanOptions::new().presize(true);
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_trusted_with(input, &options))
+}
+
 #[bench]
 fn read_10kb_event_stacktrace_offsets_simd_from_const_parts(b: &mut test::Bencher) {
     let input = include_bytes!("../cases/10kb_event_stacktrace.json");
@@ -23,6 +91,32 @@ fn read_10kb_event_stacktrace_offsets_simd_from_const_parts(b: &mut test::Benche
     b.iter(|| unsafe { const_offsets.to_document_unchecked(input) })
 }
 
+#[bench]
+#[cfg(feature = "xxhash-rust")]
+fn read_10kb_event_stacktrace_offsets_cache_hit(b: &mut test::Bencher) {
+    let input = include_bytes!("../cases/10kb_event_stacktrace.json");
+    let mut cache = squirrel_json::de::OffsetsCache::new(1024 * 1024);
+
+    b.bytes = input.len() as u64;
+    b.iter(|| {
+        let doc = cache.scan_or_attach(input);
+        test::black_box(&doc);
+    })
+}
+
+#[bench]
+#[cfg(feature = "xxhash-rust")]
+fn read_600b_event_no_escape_offsets_cache_hit(b: &mut test::Bencher) {
+    let input = include_bytes!("../cases/600b_event_no_escape.json");
+    let mut cache = squirrel_json::de::OffsetsCache::new(1024 * 1024);
+
+    b.bytes = input.len() as u64;
+    b.iter(|| {
+        let doc = cache.scan_or_attach(input);
+        test::black_box(&doc);
+    })
+}
+
 #[bench]
 fn read_10kb_event_stacktrace_offsets_simd_reuse(b: &mut test::Bencher) {
     let input = include_bytes!("../cases/10kb_event_stacktrace.json");
@@ -47,6 +141,45 @@ fn read_10kb_event_stacktrace_offsets_fallback(b: &mut test::Bencher) {
     b.iter(|| Document::scan_trusted_fallback(input))
 }
 
+#[bench]
+fn read_10kb_event_stacktrace_keys(b: &mut test::Bencher) {
+    let input = include_bytes!("../cases/10kb_event_stacktrace.json");
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_keys(input))
+}
+
+#[bench]
+fn read_600b_event_no_escape_keys(b: &mut test::Bencher) {
+    let input = include_bytes!("../cases/600b_event_no_escape.json");
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_keys(input))
+}
+
+#[bench]
+fn read_10kb_event_stacktrace_contains_key_raw_missing(b: &mut test::Bencher) {
+    let input = include_bytes!("../cases/10kb_event_stacktrace.json");
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::contains_key_raw(input, "not a key"))
+}
+
+#[bench]
+fn read_10kb_event_stacktrace_transcode_utf16(b: &mut test::Bencher) {
+    let input: Vec<u16> = str::from_utf8(include_bytes!("../cases/10kb_event_stacktrace.json"))
+        .unwrap()
+        .encode_utf16()
+        .collect();
+    let mut out = Vec::with_capacity(input.len());
+
+    b.bytes = (input.len() * 2) as u64;
+    b.iter(|| {
+        out.clear();
+        squirrel_json::transcode_utf16_to_utf8(&input, &mut out);
+    })
+}
+
 #[bench]
 fn read_10kb_event_stacktrace_value_serde_json(b: &mut test::Bencher) {
     let input = include_bytes!("../cases/10kb_event_stacktrace.json");
@@ -187,6 +320,44 @@ fn unescape_10kb_event_stacktrace(b: &mut test::Bencher) {
     b.iter(|| stacktrace.to_unescaped())
 }
 
+#[bench]
+fn unescape_unicode_escapes(b: &mut test::Bencher) {
+    let mut input = String::new();
+    while input.len() < 10 * 1024 {
+        input.push_str("{\\u4f60\\u597d} \\ud83d\\ude04 \\u00e9\\u00e8\\u00ea\\n");
+    }
+
+    let input = format!("{{\"@m\":\"{}\"}}", input);
+    let doc = Document::scan_trusted(input.as_bytes());
+
+    let message = doc.as_map().entries().next().unwrap().1.as_str().unwrap();
+
+    b.bytes = input.len() as u64;
+    b.iter(|| message.to_unescaped())
+}
+
+#[bench]
+fn unescape_short(b: &mut test::Bencher) {
+    let input = b"{\"@m\":\"Request \\\"GET /api\\\" failed\\n\"}";
+    let doc = Document::scan_trusted(input);
+
+    let message = doc.as_map().entries().next().unwrap().1.as_str().unwrap();
+
+    b.bytes = input.len() as u64;
+    b.iter(|| message.to_unescaped())
+}
+
+#[bench]
+fn unescape_short_inline(b: &mut test::Bencher) {
+    let input = b"{\"@m\":\"Request \\\"GET /api\\\" failed\\n\"}";
+    let doc = Document::scan_trusted(input);
+
+    let message = doc.as_map().entries().next().unwrap().1.as_str().unwrap();
+
+    b.bytes = input.len() as u64;
+    b.iter(|| message.to_unescaped_inline())
+}
+
 #[bench]
 fn unescape_10kb_event_stacktrace_to_string(b: &mut test::Bencher) {
     let input = include_bytes!("../cases/10kb_event_stacktrace.json");
@@ -223,6 +394,54 @@ fn read_600b_event_no_escape_offsets_simd(b: &mut test::Bencher) {
     b.iter(|| Document::scan_trusted(input))
 }
 
+#[bench]
+fn read_600b_event_no_escape_offsets_flat(b: &mut test::Bencher) {
+    let input = include_bytes!("../cases/600b_event_no_escape.json");
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_trusted_flat(input))
+}
+
+#[bench]
+fn read_600b_event_no_escape_offsets_simd_str(b: &mut test::Bencher) {
+    let input = include_str!("../cases/600b_event_no_escape.json");
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_trusted_str(input))
+}
+
+#[bench]
+fn read_arr_of_numbers_offsets_simd(b: &mut test::Bencher) {
+    let mut input = String::from("{\"a\":[");
+    for i in 0..2000 {
+        if i > 0 {
+            input.push(',');
+        }
+
+        input.push_str(&(i as f64 * -1234.5678).to_string());
+    }
+    input.push_str("]}");
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_trusted(input.as_bytes()))
+}
+
+#[bench]
+fn read_small_event_offsets_simd_forced(b: &mut test::Bencher) {
+    let input = b"{\"@t\":\"2021-01-01T00:00:00Z\",\"@mt\":\"A small event\",\"n\":42}";
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_trusted_simd(input))
+}
+
+#[bench]
+fn read_small_event_offsets_fallback(b: &mut test::Bencher) {
+    let input = b"{\"@t\":\"2021-01-01T00:00:00Z\",\"@mt\":\"A small event\",\"n\":42}";
+
+    b.bytes = input.len() as u64;
+    b.iter(|| Document::scan_trusted_fallback(input))
+}
+
 #[bench]
 fn read_600b_event_no_escape_offsets_fallback(b: &mut test::Bencher) {
     let input = include_bytes!("../cases/600b_event_no_escape.json");
@@ -288,3 +507,18 @@ fn read_600b_event_no_escape_validate_utf8(b: &mut test::Bencher) {
     b.bytes = input.len() as u64;
     b.iter(|| str::from_utf8(input).unwrap())
 }
+
+#[bench]
+fn transcode_10kb_event_stacktrace_msgpack(b: &mut test::Bencher) {
+    let input = include_bytes!("../cases/10kb_event_stacktrace.json");
+    let doc = Document::scan_trusted(input);
+
+    let mut out = Vec::with_capacity(input.len());
+
+    b.bytes = input.len() as u64;
+    b.iter(|| {
+        out.clear();
+        doc.transcode_into(BinaryFormat::MessagePack, &mut out);
+        test::black_box(&out);
+    })
+}
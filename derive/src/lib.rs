@@ -0,0 +1,130 @@
+/*!
+A derive for projecting `squirrel-json` documents into structs.
+
+The derive generates an implementation of `squirrel_json::de::FromDocument` that pulls
+the value of each field from the root map of a document in a single pass:
+
+```ignore
+#[derive(FromDocument)]
+struct Event<'a> {
+    #[sqj(key = "@t")]
+    timestamp: &'a str,
+    #[sqj(key = "@l")]
+    level: Option<&'a str>,
+    count: u64,
+}
+```
+
+Fields use their name as their key unless it's set using `#[sqj(key = "...")]`.
+Each field is converted using `squirrel_json::de::FromKind`.
+*/
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, GenericParam, LitStr};
+
+#[proc_macro_derive(FromDocument, attributes(sqj))]
+pub fn derive_from_document(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let ident = &input.ident;
+
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "`FromDocument` can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "`FromDocument` can only be derived for structs",
+            ))
+        }
+    };
+
+    // the lifetime of the struct, if it has one, is the lifetime of the input
+    let mut lifetimes = Vec::new();
+    for param in &input.generics.params {
+        match param {
+            GenericParam::Lifetime(param) => lifetimes.push(&param.lifetime),
+            param => return Err(Error::new_spanned(
+                param,
+                "`FromDocument` can only be derived for structs without type or const parameters",
+            )),
+        }
+    }
+
+    let (impl_generics, ty, input_lifetime) = match lifetimes.as_slice() {
+        [] => (quote!(<'input>), quote!(#ident), quote!('input)),
+        [lifetime] => (
+            quote!(<#lifetime>),
+            quote!(#ident<#lifetime>),
+            quote!(#lifetime),
+        ),
+        _ => {
+            return Err(Error::new_spanned(
+                &input.generics,
+                "`FromDocument` can only be derived for structs with at most one lifetime",
+            ))
+        }
+    };
+
+    let mut keys = Vec::new();
+    let mut values = Vec::new();
+    let mut inits = Vec::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        let field_ident = field.ident.as_ref().expect("named fields have idents");
+
+        let mut key = field_ident.to_string();
+        for attr in &field.attrs {
+            if !attr.path().is_ident("sqj") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") {
+                    key = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported `sqj` attribute"))
+                }
+            })?;
+        }
+
+        let value = format_ident!("__value{}", i);
+
+        inits.push(quote! {
+            #field_ident: ::squirrel_json::de::FromKind::from_kind(#value)
+                .ok_or_else(|| ::squirrel_json::de::ProjectionError::new(#key))?
+        });
+        keys.push(key);
+        values.push(value);
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::squirrel_json::de::FromDocument<#input_lifetime> for #ty {
+            fn from_map(
+                map: &::squirrel_json::de::Map<#input_lifetime, '_>,
+            ) -> ::std::result::Result<Self, ::squirrel_json::de::ProjectionError> {
+                let [#(#values),*] = map.extract([#(#keys),*]);
+
+                ::std::result::Result::Ok(#ident {
+                    #(#inits),*
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,223 @@
+/*!
+Scan minified JSON documents and print them.
+
+Documents are read as newline-delimited JSON from the given files, or from stdin if there aren't any.
+
+```shell
+cargo run --release --example sqj -- [--pretty] [--get <path>] [--stats] [files...]
+```
+
+- `--pretty`: print each document (or value) indented instead of minified.
+- `--get <path>`: print the value at a path like `a.b.0.c` instead of the whole document.
+- `--stats`: print the number of documents, elements, and errors, along with throughput, to stderr.
+*/
+
+use std::{
+    env, fs,
+    io::{self, BufRead, BufWriter, Write},
+    process,
+    time::Instant,
+};
+
+use squirrel_json::{de::Kind, Document};
+
+#[derive(Default)]
+struct Args {
+    pretty: bool,
+    get: Option<String>,
+    stats: bool,
+    files: Vec<String>,
+}
+
+#[derive(Default)]
+struct Stats {
+    documents: usize,
+    bytes: usize,
+    elements: usize,
+    errors: usize,
+    missing: usize,
+}
+
+fn main() {
+    let args = parse_args();
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    let mut stats = Stats::default();
+    let start = Instant::now();
+
+    let result = if args.files.is_empty() {
+        scan_lines(io::stdin().lock(), &args, &mut stats, &mut out)
+    } else {
+        args.files.iter().try_for_each(|file| {
+            let file = fs::File::open(file)?;
+            scan_lines(io::BufReader::new(file), &args, &mut stats, &mut out)
+        })
+    };
+
+    if let Err(err) = result.and_then(|_| out.flush()) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+
+    if args.stats {
+        let elapsed = start.elapsed();
+
+        eprintln!("documents: {}", stats.documents);
+        eprintln!("bytes: {}", stats.bytes);
+        eprintln!("elements: {}", stats.elements);
+        eprintln!("errors: {}", stats.errors);
+        if args.get.is_some() {
+            eprintln!("missing: {}", stats.missing);
+        }
+        eprintln!("elapsed: {:?}", elapsed);
+        eprintln!(
+            "throughput: {:.2} MiB/s",
+            stats.bytes as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64()
+        );
+    }
+}
+
+fn parse_args() -> Args {
+    let mut args = Args::default();
+    let mut raw = env::args().skip(1);
+
+    while let Some(arg) = raw.next() {
+        match arg.as_str() {
+            "--pretty" => args.pretty = true,
+            "--stats" => args.stats = true,
+            "--get" => match raw.next() {
+                Some(path) => args.get = Some(path),
+                None => usage("`--get` needs a path"),
+            },
+            "--help" | "-h" => usage(""),
+            arg if arg.starts_with("--") => usage(&format!("unknown argument `{}`", arg)),
+            _ => args.files.push(arg),
+        }
+    }
+
+    args
+}
+
+fn usage(err: &str) -> ! {
+    if !err.is_empty() {
+        eprintln!("error: {}", err);
+    }
+
+    eprintln!("usage: sqj [--pretty] [--get <path>] [--stats] [files...]");
+    process::exit(if err.is_empty() { 0 } else { 2 });
+}
+
+fn scan_lines(
+    mut input: impl BufRead,
+    args: &Args,
+    stats: &mut Stats,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if input.read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+
+        // documents are minified, but lines may still end with `\r\n`
+        let trimmed = match line.iter().rposition(|b| !b.is_ascii_whitespace()) {
+            Some(end) => &line[..=end],
+            None => continue,
+        };
+
+        let document = Document::scan_trusted(trimmed);
+
+        stats.documents += 1;
+        stats.bytes += trimmed.len();
+        stats.elements += document.offsets().len();
+
+        if document.is_err() {
+            stats.errors += 1;
+            eprintln!(
+                "error: invalid document {} at input offset {}",
+                stats.documents,
+                document.valid_up_to()
+            );
+            continue;
+        }
+
+        let value = match args.get {
+            Some(ref path) => match get(&document, path) {
+                Some(value) => value,
+                None => {
+                    stats.missing += 1;
+                    continue;
+                }
+            },
+            None => Kind::Map(document.as_map()),
+        };
+
+        if args.pretty {
+            write_pretty(out, &value, 0)?;
+        } else {
+            out.write_all(value.as_json().as_bytes())?;
+        }
+        out.write_all(b"\n")?;
+    }
+}
+
+/**
+Get the value at a path like `a.b.0.c`.
+*/
+fn get<'input, 'offsets>(
+    document: &'offsets Document<'input>,
+    path: &str,
+) -> Option<Kind<'input, 'offsets>> {
+    let mut value = Kind::Map(document.as_map());
+
+    for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+        value = match value {
+            Kind::Map(map) => map.get(segment)?,
+            Kind::Arr(arr) => arr.iter().nth(segment.parse().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(value)
+}
+
+fn write_pretty(out: &mut impl Write, value: &Kind, depth: usize) -> io::Result<()> {
+    const INDENT: &str = "  ";
+
+    match value {
+        Kind::Map(map) if !map.is_empty() => {
+            out.write_all(b"{\n")?;
+
+            for (i, (k, v)) in map.entries().enumerate() {
+                if i > 0 {
+                    out.write_all(b",\n")?;
+                }
+
+                write!(out, "{}{}: ", INDENT.repeat(depth + 1), k.as_json())?;
+                write_pretty(out, &v, depth + 1)?;
+            }
+
+            write!(out, "\n{}}}", INDENT.repeat(depth))
+        }
+        Kind::Arr(arr) if !arr.is_empty() => {
+            out.write_all(b"[\n")?;
+
+            for (i, v) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b",\n")?;
+                }
+
+                out.write_all(INDENT.repeat(depth + 1).as_bytes())?;
+                write_pretty(out, &v, depth + 1)?;
+            }
+
+            write!(out, "\n{}]", INDENT.repeat(depth))
+        }
+        // everything else, including empty maps and arrays, is written as it appears in the input
+        value => out.write_all(value.as_json().as_bytes()),
+    }
+}
@@ -1,3 +1,5 @@
+use squirrel_json::diagnostics::Divergence;
+
 pub fn de(input: &[u8]) {
     // Make sure we don't panic when reading documents
     let simd = squirrel_json::Document::scan_trusted(input);
@@ -10,10 +12,35 @@ pub fn de(input: &[u8]) {
             let fallback_value = fallback.to_value();
 
             assert_eq!(simd_value, fallback_value);
+
+            // Make sure the offsets themselves are equal
+            let divergence = squirrel_json::diagnostics::compare(input);
+            assert!(
+                matches!(divergence, Divergence::None | Divergence::Unsupported),
+                "{:?}",
+                divergence
+            );
         }
     }
 }
 
+pub fn reattach(input: &[u8]) {
+    // Make sure offsets can be detached from their document and attached to the same input again
+    let document = squirrel_json::Document::scan_trusted(input);
+
+    if !document.is_err() {
+        let expected = document.to_value();
+
+        let offsets = document.into_offsets().into_owned();
+
+        // SAFETY: the offsets were produced from this input
+        let reattached = unsafe { offsets.to_document_unchecked(input) };
+
+        assert!(!reattached.is_err());
+        assert_eq!(expected, reattached.to_value());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,6 +61,7 @@ mod tests {
 
                 // Just make sure we never panic
                 de(&input);
+                reattach(&input);
             }
         }
     }
@@ -52,6 +80,7 @@ mod tests {
 
                 // Just make sure we never panic
                 de(&crash);
+                reattach(&crash);
             }
         }
     }
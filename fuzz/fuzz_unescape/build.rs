@@ -0,0 +1,5 @@
+include!("../../build/config.rs");
+
+fn main() {
+    config::Cfgs::new().apply();
+}
@@ -0,0 +1,62 @@
+pub fn unescape(input: &[u8]) {
+    // Strings to unescape are always valid UTF8
+    let input = match std::str::from_utf8(input) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+
+    // Make sure we don't panic when unescaping strings
+    let unescaped = squirrel_json::unescape_json_str(input);
+
+    // Strings can only fail to unescape if they end with an unescaped `\`
+    let trailing = input.bytes().rev().take_while(|b| *b == b'\\').count();
+    assert_eq!(trailing % 2 != 0, unescaped.is_err(), "{:?}", input);
+
+    // If the input is a valid JSON string then make sure it unescapes the same way
+    if let Ok(expected) = serde_json::from_str::<String>(&format!("\"{}\"", input)) {
+        assert_eq!(Ok(expected), unescaped, "{:?}", input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{fs, io::Read};
+
+    #[test]
+    fn inputs() {
+        if let Ok(inputs) = fs::read_dir("../in") {
+            for input in inputs {
+                let input = input.expect("invalid file").path();
+
+                println!("input: {:?}", input);
+
+                let mut f = fs::File::open(input).expect("failed to open");
+                let mut input = Vec::new();
+                f.read_to_end(&mut input).expect("failed to read file");
+
+                // Just make sure we never panic
+                unescape(&input);
+            }
+        }
+    }
+
+    #[test]
+    fn crashes() {
+        if let Ok(crashes) = fs::read_dir("../../target/fuzz_unescape/crashes") {
+            for crash in crashes {
+                let crash = crash.expect("invalid file").path();
+
+                println!("repro: {:?}", crash);
+
+                let mut f = fs::File::open(crash).expect("failed to open");
+                let mut crash = Vec::new();
+                f.read_to_end(&mut crash).expect("failed to read file");
+
+                // Just make sure we never panic
+                unescape(&crash);
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+/*!
+Statistics gathered across many documents.
+
+Discovering the schema of a stream of events usually starts with finding out which keys
+appear in them and how often. These statistics only walk the keys of each document, so they
+don't need to convert any values along the way.
+
+Batches of documents can also be filtered on the value at a path without scanning them.
+With the `arrow` feature enabled, paths can also be extracted from batches of documents
+into Arrow arrays for analytical engines.
+*/
+
+use std::collections::HashMap;
+
+use crate::de::{Document, Elems, Entries, Kind};
+
+mod filter;
+
+#[cfg(any(test, feature = "arrow"))]
+mod arrow;
+
+pub use filter::{filter, Predicate};
+
+#[cfg(any(test, feature = "arrow"))]
+pub use arrow::to_arrow;
+
+/**
+Count the number of documents that each key at the root of a document appears in.
+
+Keys are unescaped. A key that appears more than once in the same document is only counted once.
+*/
+pub fn key_histogram<'a, 'input: 'a>(
+    docs: impl IntoIterator<Item = &'a Document<'input>>,
+) -> HashMap<String, u64> {
+    let mut histogram = KeyHistogram::new();
+
+    for doc in docs {
+        histogram.add(doc);
+    }
+
+    histogram.into_counts()
+}
+
+/**
+An accumulator that counts the number of documents that each key appears in.
+
+Documents are added one at a time, so the histogram can be built from a stream
+without keeping the documents around.
+*/
+#[derive(Debug, Clone)]
+pub struct KeyHistogram {
+    counts: HashMap<String, Count>,
+    documents: u64,
+    nested: Option<String>,
+    path: String,
+}
+
+enum Frame<'input, 'offsets> {
+    // the entries left in a map, and the length of the path to it
+    Map(Entries<'input, 'offsets>, usize),
+    Arr(Elems<'input, 'offsets>),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Count {
+    documents: u64,
+    // the last document this key was counted for, so duplicates aren't counted twice
+    last_seen: u64,
+}
+
+impl Default for KeyHistogram {
+    #[inline]
+    fn default() -> Self {
+        KeyHistogram::new()
+    }
+}
+
+impl KeyHistogram {
+    /**
+    Create an empty histogram that only counts keys at the root of documents.
+    */
+    #[inline]
+    pub fn new() -> Self {
+        KeyHistogram {
+            counts: HashMap::new(),
+            documents: 0,
+            nested: None,
+            path: String::new(),
+        }
+    }
+
+    /**
+    Also count the keys of maps nested within documents.
+
+    Nested keys are counted by their path from the root, with the keys along the path joined
+    by a separator, like `a.b.c`. Arrays don't add a segment to the path, so the keys of
+    every map in an array are counted together.
+    */
+    #[inline]
+    pub fn nested(mut self, separator: impl Into<String>) -> Self {
+        self.nested = Some(separator.into());
+        self
+    }
+
+    /**
+    Count the keys in a document.
+    */
+    pub fn add(&mut self, doc: &Document) {
+        self.documents += 1;
+        self.path.clear();
+
+        self.add_kind(&Kind::Map(doc.as_map()));
+    }
+
+    /**
+    The number of documents added to the histogram.
+    */
+    #[inline]
+    pub fn documents(&self) -> u64 {
+        self.documents
+    }
+
+    /**
+    The number of documents that a key appeared in.
+
+    Nested keys are looked up by their full path.
+    */
+    #[inline]
+    pub fn get(&self, key: &str) -> u64 {
+        self.counts
+            .get(key)
+            .map(|count| count.documents)
+            .unwrap_or(0)
+    }
+
+    /**
+    Iterate over the keys in the histogram and the number of documents they appeared in.
+
+    Keys are returned in an unspecified order.
+    */
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.counts
+            .iter()
+            .map(|(key, count)| (&**key, count.documents))
+    }
+
+    /**
+    Convert the histogram into a map of each key to the number of documents it appeared in.
+    */
+    pub fn into_counts(self) -> HashMap<String, u64> {
+        self.counts
+            .into_iter()
+            .map(|(key, count)| (key, count.documents))
+            .collect()
+    }
+
+    /**
+    Count the keys in a map, and any maps nested within it.
+
+    Nested maps and arrays are walked using an explicit stack of the elements left in each.
+    */
+    fn add_kind(&mut self, kind: &Kind) {
+        let mut stack = Vec::new();
+        self.add_begin(&mut stack, kind);
+
+        while let Some(frame) = stack.last_mut() {
+            match frame {
+                Frame::Map(entries, parent_len) => {
+                    let parent_len = *parent_len;
+
+                    match entries.next() {
+                        Some((key, value)) => {
+                            self.path.truncate(parent_len);
+
+                            if parent_len > 0 {
+                                if let Some(ref separator) = self.nested {
+                                    self.path.push_str(separator);
+                                }
+                            }
+
+                            self.path.push_str(&key.to_unescaped_inline());
+                            self.count_path();
+
+                            if self.nested.is_some() {
+                                self.add_begin(&mut stack, &value);
+                            }
+                        }
+                        None => {
+                            self.path.truncate(parent_len);
+                            stack.pop();
+                        }
+                    }
+                }
+                Frame::Arr(elems) => match elems.next() {
+                    Some(elem) => self.add_begin(&mut stack, &elem),
+                    None => {
+                        stack.pop();
+                    }
+                },
+            }
+        }
+    }
+
+    #[inline]
+    fn add_begin<'input, 'offsets>(
+        &mut self,
+        stack: &mut Vec<Frame<'input, 'offsets>>,
+        value: &Kind<'input, 'offsets>,
+    ) {
+        match value {
+            // the keys of a map are appended to the path of the map itself
+            Kind::Map(ref map) => stack.push(Frame::Map(map.iter_entries(), self.path.len())),
+            Kind::Arr(ref arr) => stack.push(Frame::Arr(arr.iter_elems())),
+            // the scanned document doesn't contain any lazy values itself, so this only nests once
+            Kind::Lazy(lazy) => {
+                if let Some(nested) = lazy.scan() {
+                    self.add_kind(&nested.as_kind());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    #[inline]
+    fn count_path(&mut self) {
+        let documents = self.documents;
+
+        // avoid allocating a new key for paths that have already been seen
+        if let Some(count) = self.counts.get_mut(&self.path) {
+            if count.last_seen != documents {
+                count.documents += 1;
+                count.last_seen = documents;
+            }
+        } else {
+            self.counts.insert(
+                self.path.clone(),
+                Count {
+                    documents: 1,
+                    last_seen: documents,
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,127 @@
+/*!
+Extract paths from batches of documents into Arrow arrays.
+
+Each column is built directly from the documents, so values are only converted once,
+straight into their Arrow representation.
+*/
+
+use std::sync::Arc;
+
+use arrow_array::{
+    builder::{BooleanBuilder, Float64Builder, Int64Builder, StringBuilder},
+    ArrayRef, RecordBatch,
+};
+use arrow_schema::{ArrowError, DataType, SchemaRef};
+
+use crate::de::{Document, Kind};
+
+/**
+Extract paths from a batch of documents into a record batch of Arrow arrays.
+
+The name of each field in the schema is the path to extract from each document, like `a.b.0.c`.
+Segments are matched against the unescaped keys of maps, or the indexes of arrays. If a map
+contains the same key more than once then the first entry is used.
+
+Fields can have the following data types:
+
+- `Utf8`: strings are unescaped.
+- `Int64`: numbers that are integers.
+- `Float64`: any numbers, which may lose precision.
+- `Boolean`: booleans.
+
+A value that's missing, or doesn't match the data type of its field, is null.
+If the field isn't nullable then an error is returned instead.
+*/
+pub fn to_arrow<'a, 'input: 'a>(
+    docs: impl IntoIterator<Item = &'a Document<'input>>,
+    schema: SchemaRef,
+) -> Result<RecordBatch, ArrowError> {
+    let mut columns = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let builder = match field.data_type() {
+                DataType::Utf8 => Column::Utf8(StringBuilder::new()),
+                DataType::Int64 => Column::Int64(Int64Builder::new()),
+                DataType::Float64 => Column::Float64(Float64Builder::new()),
+                DataType::Boolean => Column::Bool(BooleanBuilder::new()),
+                data_type => {
+                    return Err(ArrowError::InvalidArgumentError(format!(
+                        "field `{}` has unsupported data type {}",
+                        field.name(),
+                        data_type
+                    )))
+                }
+            };
+
+            Ok((field.name().as_str(), builder))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for doc in docs {
+        for (path, column) in &mut columns {
+            column.append(get_path(doc, path));
+        }
+    }
+
+    let columns = columns
+        .into_iter()
+        .map(|(_, column)| column.finish())
+        .collect();
+
+    RecordBatch::try_new(schema, columns)
+}
+
+enum Column {
+    Utf8(StringBuilder),
+    Int64(Int64Builder),
+    Float64(Float64Builder),
+    Bool(BooleanBuilder),
+}
+
+impl Column {
+    fn append(&mut self, value: Option<Kind>) {
+        match (self, value) {
+            (Column::Utf8(builder), Some(Kind::Str(s))) => {
+                builder.append_value(s.to_unescaped_inline())
+            }
+            (Column::Int64(builder), Some(Kind::Num(n))) => {
+                builder.append_option(n.trim().parse().ok())
+            }
+            (Column::Float64(builder), Some(Kind::Num(n))) => {
+                builder.append_option(n.trim().parse().ok())
+            }
+            (Column::Bool(builder), Some(Kind::Bool(b))) => builder.append_value(b),
+            (Column::Utf8(builder), _) => builder.append_null(),
+            (Column::Int64(builder), _) => builder.append_null(),
+            (Column::Float64(builder), _) => builder.append_null(),
+            (Column::Bool(builder), _) => builder.append_null(),
+        }
+    }
+
+    fn finish(self) -> ArrayRef {
+        match self {
+            Column::Utf8(mut builder) => Arc::new(builder.finish()),
+            Column::Int64(mut builder) => Arc::new(builder.finish()),
+            Column::Float64(mut builder) => Arc::new(builder.finish()),
+            Column::Bool(mut builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+fn get_path<'input, 'offsets>(
+    doc: &'offsets Document<'input>,
+    path: &str,
+) -> Option<Kind<'input, 'offsets>> {
+    let mut value = Kind::Map(doc.as_map());
+
+    for segment in path.split('.') {
+        value = match value {
+            Kind::Map(map) => map.get(segment)?,
+            Kind::Arr(arr) => arr.iter().nth(segment.parse().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(value)
+}
@@ -0,0 +1,250 @@
+/*!
+Filter documents on the value at a single path, without scanning them.
+
+Simple filters only ever look at one value in each document. Instead of building offsets for
+the whole document, its raw input is walked only as far as the value at the path, skipping over
+the values before it along the way. Nothing after the value is looked at.
+*/
+
+use std::{borrow::Cow, ops::RangeInclusive, str};
+
+use crate::unescape_json_str;
+
+/**
+A predicate on the value at a path.
+*/
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Predicate<'a> {
+    /**
+    The value is a string that's equal to this one once it's unescaped.
+    */
+    StrEq(&'a str),
+    /**
+    The value is a string that contains this one once it's unescaped.
+    */
+    StrContains(&'a str),
+    /**
+    The value is a number within this range.
+    */
+    NumRange(RangeInclusive<f64>),
+}
+
+impl<'a> Predicate<'a> {
+    fn matches(&self, value: RawValue) -> bool {
+        match (self, value) {
+            (Predicate::StrEq(expected), RawValue::Str(s)) => s == *expected,
+            (Predicate::StrContains(expected), RawValue::Str(s)) => s.contains(expected),
+            (Predicate::NumRange(range), RawValue::Num(n)) => {
+                n.trim().parse::<f64>().is_ok_and(|n| range.contains(&n))
+            }
+            _ => false,
+        }
+    }
+}
+
+/**
+Filter a batch of JSON object byte buffers, keeping the ones where the value at a path,
+like `a.b.0.c`, matches a predicate.
+
+Segments are matched against the unescaped keys of maps, or the indexes of arrays.
+If a map contains the same key more than once then the first entry is used. Documents
+that don't have a value at the path, or that are invalid up to it, don't match.
+
+Each document is only walked as far as the value at the path. The input isn't validated,
+beyond the value itself, so documents that are invalid after the value may still match.
+*/
+pub fn filter<'a, I>(
+    docs: I,
+    path: &'a str,
+    predicate: Predicate<'a>,
+) -> impl Iterator<Item = I::Item> + 'a
+where
+    I: IntoIterator,
+    I::IntoIter: 'a,
+    I::Item: AsRef<[u8]>,
+{
+    docs.into_iter().filter(move |input| {
+        find_raw(input.as_ref(), path).is_some_and(|value| predicate.matches(value))
+    })
+}
+
+/**
+The raw value at a path.
+*/
+enum RawValue<'input> {
+    Str(Cow<'input, str>),
+    Num(&'input str),
+    Other,
+}
+
+/**
+Walk the input to find the raw value at a path.
+*/
+fn find_raw<'input>(input: &'input [u8], path: &str) -> Option<RawValue<'input>> {
+    // leading whitespace is skipped the same way the scanner skips it
+    let start = input.iter().take_while(|b| b.is_ascii_whitespace()).count();
+
+    if input.get(start) != Some(&b'{') {
+        return None;
+    }
+
+    // an empty path is the root map
+    if path.is_empty() {
+        return Some(RawValue::Other);
+    }
+
+    let mut input_offset = start;
+
+    for segment in path.split('.') {
+        input_offset = match *input.get(input_offset)? {
+            b'{' => find_key(input, input_offset + 1, segment)?,
+            b'[' => find_index(input, input_offset + 1, segment.parse().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    match *input.get(input_offset)? {
+        b'"' => {
+            let end = skip_str(input, input_offset)?;
+            Some(RawValue::Str(to_str(
+                input.get(input_offset + 1..end - 1)?,
+            )?))
+        }
+        b'-' | b'0'..=b'9' => {
+            let end = skip_value(input, input_offset)?;
+            Some(RawValue::Num(
+                str::from_utf8(input.get(input_offset..end)?).ok()?,
+            ))
+        }
+        _ => Some(RawValue::Other),
+    }
+}
+
+/**
+Find the value for a key in the map whose entries start at an offset.
+
+Returns the offset of the value.
+*/
+fn find_key(input: &[u8], mut input_offset: usize, key: &str) -> Option<usize> {
+    loop {
+        match *input.get(input_offset)? {
+            b'"' => (),
+            _ => return None,
+        }
+
+        let key_end = skip_str(input, input_offset)?;
+        let candidate = input.get(input_offset + 1..key_end - 1)?;
+
+        if *input.get(key_end)? != b':' {
+            return None;
+        }
+
+        let value_offset = key_end + 1;
+
+        // keys without escapes can be compared directly
+        let matches = if candidate.contains(&b'\\') {
+            to_str(candidate).is_some_and(|candidate| candidate == key)
+        } else {
+            candidate == key.as_bytes()
+        };
+
+        if matches {
+            return Some(value_offset);
+        }
+
+        input_offset = next_part(input, skip_value(input, value_offset)?)?;
+    }
+}
+
+/**
+Find the element at an index in the array whose elements start at an offset.
+
+Returns the offset of the element.
+*/
+fn find_index(input: &[u8], mut input_offset: usize, index: usize) -> Option<usize> {
+    for _ in 0..index {
+        input_offset = next_part(input, skip_value(input, input_offset)?)?;
+    }
+
+    // an empty array doesn't have any elements
+    match *input.get(input_offset)? {
+        b']' | b'}' => None,
+        _ => Some(input_offset),
+    }
+}
+
+/**
+Move past the `,` after a value to the next entry or element.
+
+If the map or array is finished instead then there's no next part.
+*/
+#[inline]
+fn next_part(input: &[u8], input_offset: usize) -> Option<usize> {
+    match *input.get(input_offset)? {
+        b',' => Some(input_offset + 1),
+        _ => None,
+    }
+}
+
+/**
+Skip over the value at an offset.
+
+Returns the offset just past the end of the value.
+*/
+fn skip_value(input: &[u8], mut input_offset: usize) -> Option<usize> {
+    let mut depth = 0usize;
+
+    loop {
+        match *input.get(input_offset)? {
+            b'"' => {
+                input_offset = skip_str(input, input_offset)?;
+                continue;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' if depth == 0 => return Some(input_offset),
+            b'}' | b']' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(input_offset + 1);
+                }
+            }
+            b',' if depth == 0 => return Some(input_offset),
+            _ => (),
+        }
+
+        input_offset += 1;
+    }
+}
+
+/**
+Skip over the string that starts with a `"` at an offset.
+
+Returns the offset just past its closing `"`.
+*/
+fn skip_str(input: &[u8], mut input_offset: usize) -> Option<usize> {
+    input_offset += 1;
+
+    loop {
+        match *input.get(input_offset)? {
+            b'"' => return Some(input_offset + 1),
+            // skip over the escaped character
+            b'\\' => input_offset += 2,
+            _ => input_offset += 1,
+        }
+    }
+}
+
+/**
+Validate and unescape the contents of a string.
+*/
+fn to_str(raw: &[u8]) -> Option<Cow<'_, str>> {
+    let raw = str::from_utf8(raw).ok()?;
+
+    if raw.contains('\\') {
+        unescape_json_str(raw).ok().map(Cow::Owned)
+    } else {
+        Some(Cow::Borrowed(raw))
+    }
+}
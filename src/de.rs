@@ -23,28 +23,116 @@ We don't take special advantage of SIMD intrinsics to perform validation or tran
 in constant-time, which is something `simd_json` does heavily, just because it
 would cause our fallback and vectorized implementations to diverge and they
 need to be able to work together.
+
+Offsets are indexed using `u16`s, so a single document can contain at most
+[`Document::MAX_ELEMENTS`] elements. Documents with more elements than that are erroneous,
+with a [`ScanErrorKind::TooManyElements`] error. This keeps offsets
+small for the many little documents we expect to scan, but means very large documents,
+like multi-megabyte array exports, can't be scanned at all.
+
+Positions in the input are stored as `u32`s, so a single document can be at most
+[`Document::MAX_INPUT_LEN`] bytes. Larger inputs are erroneous rather than producing
+offsets that silently wrap around. The `large-documents` feature stores positions as `u64`s
+instead, so documents with a few very large strings can be scanned, at the cost of larger
+offsets for every document.
 */
 
 #![allow(overflowing_literals)] // we do this on purpose
 
+#[cfg(any(test, feature = "rkyv"))]
+mod archive;
+
+#[cfg(any(test, feature = "xxhash-rust"))]
+mod binding;
+
+#[cfg(any(test, feature = "xxhash-rust"))]
+mod cache;
+
+mod capture;
+mod cmp;
+mod cursor;
 mod document;
+mod dump;
+
+#[cfg(any(test, feature = "serde"))]
+mod deserializer;
 
 mod fallback;
 mod interest;
+mod keys;
+mod lazy;
+mod owned;
+mod pool;
+mod preflight;
+mod problems;
+mod projection;
+mod query;
+mod raw;
+mod rewrite;
+mod scanner;
+mod schema;
+
+#[cfg(any(test, feature = "serde"))]
+mod serialize;
+
+mod transcode;
+mod unescaped;
+mod warnings;
 
 #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 mod simd;
 
-use std::{borrow::Cow, mem, str};
+use std::{borrow::Cow, error, fmt, mem, str, sync::Arc};
+
+use smallvec::SmallVec;
 
 use interest::*;
 
+use crate::utf8;
+
 #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 use simd::Simd;
 
+pub use cursor::Cursor;
 pub use document::*;
+pub use lazy::{Lazy, NestedDocument};
+pub use owned::{CowDocument, OwnedDocument};
+pub use pool::DocumentPool;
+pub use problems::{Problem, ProblemKind, ScanProblems};
+pub use projection::{ConversionError, FromDocument, FromKind, ProjectionError};
+pub use query::{JsonPointer, KindTag, Path, PathSegment};
+pub use raw::{Chain, RawKind, RawOffset, RawPosition};
+pub use rewrite::{KeySet, MergeStrategy, SummaryLimits};
+pub use scanner::Scanner;
+pub use transcode::BinaryFormat;
+pub use unescaped::UnescapedStr;
+pub use warnings::{ScanWarnings, Warning, WarningKind};
+
+#[cfg(any(test, feature = "xxhash-rust"))]
+pub use cache::OffsetsCache;
+
+#[cfg(any(test, feature = "serde"))]
+pub use deserializer::Deserializer;
+
+#[cfg(feature = "derive")]
+pub use squirrel_json_derive::FromDocument;
 
 impl<'input> Document<'input> {
+    /**
+    The maximum length of an input that can be scanned.
+
+    Inputs that are longer than this produce an erroneous document that's valid up to this length.
+    */
+    pub const MAX_INPUT_LEN: usize = InputPos::MAX as usize;
+
+    /**
+    The maximum number of elements that can be scanned in a single document.
+
+    Each key, value, and element in an array counts towards this limit.
+    Inputs with more elements than this produce an erroneous document.
+    */
+    pub const MAX_ELEMENTS: usize = u16::MAX as usize;
+
     /**
     Scan a JSON object byte buffer into an indexable document.
 
@@ -61,7 +149,7 @@ impl<'input> Document<'input> {
     # What does _valid_ mean?
 
     A buffer containing a single JSON object with no additional whitespace
-    (besides a possible trailing newline) will be parsed as expected.
+    (besides possible leading and trailing whitespace, like a newline) will be parsed as expected.
     Some invalid content may also parse, such as maps that are terminated
     by a `]` instead of a `}`, or invalid atoms like `nool` instead of `null`.
 
@@ -72,7 +160,93 @@ impl<'input> Document<'input> {
     */
     #[inline]
     pub fn scan_trusted(input: &'input [u8]) -> Self {
-        scan(input, DetachedDocument::default())
+        scan(
+            input,
+            scan_begin(input),
+            DetachedDocument::default(),
+            &ScanOptions::DEFAULT,
+        )
+    }
+
+    /**
+    Scan a JSON byte buffer into an indexable document using the given options.
+
+    This method has the same guarantees as [`scan_trusted`].
+    */
+    #[inline]
+    pub fn scan_trusted_with(input: &'input [u8], options: &ScanOptions) -> Self {
+        Self::scan_trusted_attach_with(input, DetachedDocument::default(), options)
+    }
+
+    /**
+    Scan only the first `max_bytes` of a JSON byte buffer into an indexable document.
+
+    This is a cheap way to preview huge documents without scanning the whole of them.
+    If the input is longer than `max_bytes` then the rest of it isn't validated or scanned,
+    and any containers that are still open at the cut-off point are closed, like they
+    are by [`ScanOptions::recover`]. Values that may have been cut short are discarded.
+    The document will report that it's truncated, erroneous, and partial, unless the prefix
+    is too short to contain any of the document at all.
+
+    If the input fits within `max_bytes` then it's scanned in full, like [`scan_trusted`].
+    This method has the same guarantees as [`scan_trusted`].
+    */
+    #[inline]
+    pub fn scan_prefix(input: &'input [u8], max_bytes: usize) -> Self {
+        // trailing whitespace isn't scanned, so it doesn't need to fit
+        let trailing_whitespace = input
+            .iter()
+            .rev()
+            .take_while(|b| b.is_ascii_whitespace())
+            .count();
+
+        if input.len() - trailing_whitespace <= max_bytes {
+            return Self::scan_trusted(input);
+        }
+
+        let prefix = get_unchecked!(input, ..max_bytes);
+
+        let mut document = scan(
+            prefix,
+            scan_begin_prefix(prefix),
+            DetachedDocument::default(),
+            &ScanOptions::new().recover(true),
+        );
+
+        document.offsets.to_mut().truncated = true;
+        document
+    }
+
+    /**
+    Scan a JSON object string into an indexable document.
+
+    The input is already known to be valid UTF8, so it won't be validated again.
+    This method has the same guarantees as [`scan_trusted`].
+    */
+    #[inline]
+    pub fn scan_trusted_str(input: &'input str) -> Self {
+        scan(
+            input.as_bytes(),
+            scan_begin_utf8(input),
+            DetachedDocument::default(),
+            &ScanOptions::DEFAULT,
+        )
+    }
+
+    /**
+    Scan a JSON object byte buffer into an indexable document without validating
+    that it's UTF8.
+
+    This method has the same guarantees as [`scan_trusted`], so long as the input is UTF8.
+
+    # Safety
+
+    The input must be valid UTF8. Strings returned from the document are
+    constructed without checking them, so invalid UTF8 will result in UB.
+    */
+    #[inline]
+    pub unsafe fn scan_trusted_utf8_unchecked(input: &'input [u8]) -> Self {
+        Self::scan_trusted_str(from_utf8_unchecked!(input))
     }
 
     /**
@@ -83,13 +257,103 @@ impl<'input> Document<'input> {
     */
     #[inline]
     pub fn scan_trusted_attach(input: &'input [u8], detached: DetachedDocument) -> Self {
-        scan(input, detached)
+        scan(input, scan_begin(input), detached, &ScanOptions::DEFAULT)
+    }
+
+    /**
+    Scan a JSON byte buffer into an indexable document using the given options, re-using
+    the allocations from a previous document.
+
+    This method has the same guarantees as [`scan_trusted`].
+    */
+    #[inline]
+    pub fn scan_trusted_attach_with(
+        input: &'input [u8],
+        detached: DetachedDocument,
+        options: &ScanOptions,
+    ) -> Self {
+        match options.backend {
+            Backend::Auto if options.interleave_utf8 => scan_interleaved(input, detached, options),
+            Backend::Auto => scan(input, scan_begin(input), detached, options),
+            Backend::Scalar => scan_fallback(input, scan_begin(input), detached, options),
+            Backend::Simd => scan_simd(input, scan_begin(input), detached, options),
+        }
+    }
+
+    /**
+    Scan a JSON byte buffer into an indexable document using the scalar backend.
+
+    The scalar backend processes the input byte-by-byte. It's available on every target
+    and always produces the same results for the same input, even if that input is invalid.
+    This method has the same guarantees as [`scan_trusted`].
+    */
+    #[inline]
+    pub fn scan_trusted_scalar(input: &'input [u8]) -> Self {
+        scan_fallback(
+            input,
+            scan_begin(input),
+            DetachedDocument::default(),
+            &ScanOptions::DEFAULT,
+        )
+    }
+
+    /**
+    Scan a JSON byte buffer into an indexable document using a specific backend.
+
+    This method has the same guarantees as [`scan_trusted`].
+    */
+    #[inline]
+    pub fn scan_trusted_backend(input: &'input [u8], backend: Backend) -> Self {
+        Self::scan_trusted_with(input, &ScanOptions::new().backend(backend))
+    }
+
+    /**
+    Scan a JSON byte buffer into an indexable document using the vectorized backend.
+
+    The vectorized backend is used regardless of the size of the input, so this method
+    is useful for benchmarking and testing it in isolation. The smallest blocks of input
+    that can't be vectorized are still scanned byte-by-byte.
+
+    If the current CPU doesn't support vectorization then the returned document will be
+    erroneous. In debug builds this method will panic instead.
+    This method has the same guarantees as [`scan_trusted`] otherwise.
+    */
+    #[inline]
+    pub fn scan_trusted_simd(input: &'input [u8]) -> Self {
+        scan_simd(
+            input,
+            scan_begin(input),
+            DetachedDocument::default(),
+            &ScanOptions::DEFAULT,
+        )
+    }
+
+    /**
+    Scan a flat JSON byte buffer into an indexable document.
+
+    Flat documents only have maps or arrays in the entries of their root map, and none
+    nested within those, so their [`Document::max_depth`] is at most `1`. Structured events
+    are usually flat. The scanner is specialized for them at compile-time, so it does less
+    bookkeeping as maps and arrays are begun and ended.
+
+    Documents that are nested more deeply are erroneous, and are only valid up to the first
+    map or array that's too deep, so they can be scanned again with [`scan_trusted`].
+    This method has the same guarantees as [`scan_trusted`] otherwise.
+    */
+    #[inline]
+    pub fn scan_trusted_flat(input: &'input [u8]) -> Self {
+        scan_profile::<true>(
+            input,
+            scan_begin(input),
+            DetachedDocument::default(),
+            &ScanOptions::DEFAULT,
+        )
     }
 
     // used by tests and benches
     #[doc(hidden)]
     pub fn scan_trusted_fallback(input: &'input [u8]) -> Self {
-        scan_fallback(input, DetachedDocument::default())
+        Self::scan_trusted_scalar(input)
     }
 
     #[doc(hidden)]
@@ -111,15 +375,39 @@ impl<'input> Document<'input> {
     }
 
     #[cold]
-    fn err(input: &'input [u8]) -> Self {
+    fn err(input: &'input [u8], valid_up_to: usize, problems: ScanProblems) -> Self {
+        Document::err_kind(input, valid_up_to, ScanErrorKind::Invalid, problems)
+    }
+
+    #[cold]
+    fn err_kind(
+        input: &'input [u8],
+        valid_up_to: usize,
+        err_kind: ScanErrorKind,
+        mut problems: ScanProblems,
+    ) -> Self {
+        problems.finish();
+
         Document {
             input,
             offsets: Cow::Owned(Offsets {
-                elements: Vec::new(),
+                elements: SmallVec::new(),
                 err: true,
-                root_size_hint: 0,
+                err_kind,
+                partial: false,
+                truncated: false,
+                valid_up_to,
+                root_parts: 0,
+                root_raw: Slice { offset: 0, len: 0 },
+                max_depth: 0,
+                lazy_elements: 0,
+                warnings: ScanWarnings::new(),
+                problems,
+                #[cfg(any(test, feature = "xxhash-rust"))]
+                binding: None,
             }),
             _detached_stack: Vec::new(),
+            root: None,
         }
     }
 
@@ -134,6 +422,147 @@ impl<'input> Document<'input> {
         self.offsets.err
     }
 
+    /**
+    Whether or not the document is erroneous, but still contains the content
+    that was scanned before the error.
+
+    Partial documents are only produced when scanning with [`ScanOptions::keep_partial`]
+    or [`ScanOptions::recover`].
+    */
+    #[inline]
+    pub fn is_partial(&self) -> bool {
+        self.offsets.partial
+    }
+
+    /**
+    Whether or not the document was cut short by [`Document::scan_prefix`].
+
+    A truncated document only contains the content that was scanned before the cut-off point.
+    */
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        self.offsets.truncated
+    }
+
+    /**
+    Get the warnings collected while scanning the document.
+
+    Warnings are only collected when scanning with [`ScanOptions::collect_warnings`].
+    */
+    #[inline]
+    pub fn warnings(&self) -> &ScanWarnings {
+        &self.offsets.warnings
+    }
+
+    /**
+    Get the problems collected while scanning the document.
+
+    Problems are only collected when scanning with [`ScanOptions::collect_problems`].
+    A document that isn't erroneous won't have any problems.
+    */
+    #[inline]
+    pub fn problems(&self) -> &ScanProblems {
+        &self.offsets.problems
+    }
+
+    /**
+    The total number of keys and values scanned in the document, at any depth.
+
+    Each map entry counts as two elements; one for its key and one for its value.
+    The contents of maps and arrays kept lazily by [`ScanOptions::lazy_depth`] are
+    counted too, even though they aren't kept in the offsets. The count is tracked while
+    scanning, so it's cheap to check before walking the document.
+
+    For a nested map treated as a document, like with [`Map::as_document`], the count only
+    covers that map. It's computed by walking the map, scanning any maps or arrays within it
+    that were kept lazily.
+    */
+    #[inline]
+    pub fn element_count(&self) -> usize {
+        match self.root {
+            None => self.offsets.elements.len() + self.offsets.lazy_elements,
+            Some(_) => self.walk_counts().0,
+        }
+    }
+
+    /**
+    The deepest level of maps or arrays nested below the root map.
+
+    Depths are counted the same way as [`ScanOptions::lazy_depth`], so a map or array in
+    an entry of the root map is at a depth of `1`. A document without any nested maps or
+    arrays has a max depth of `0`.
+
+    For a nested map treated as a document, like with [`Map::as_document`], the depth is
+    counted from that map in the same way as [`Document::element_count`].
+    */
+    #[inline]
+    pub fn max_depth(&self) -> usize {
+        match self.root {
+            None => self.offsets.max_depth as usize,
+            Some(_) => self.walk_counts().1,
+        }
+    }
+
+    /**
+    Count the elements and max depth of the document by walking it.
+    */
+    #[cold]
+    fn walk_counts(&self) -> (usize, usize) {
+        let mut walk = query::Walk::new(self);
+
+        let (mut elements, mut max_depth) = (0, 0);
+        while let Some(value) = walk.advance() {
+            let depth = walk.depth();
+
+            // the key of a map entry is an element too
+            elements += if walk.in_map() { 2 } else { 1 };
+
+            match value {
+                Kind::Map(_) | Kind::Arr(_) => max_depth = max_depth.max(depth),
+                Kind::Lazy(lazy) => match lazy.scan() {
+                    // the lazy map or array is the first element in its own scanned document
+                    Some(nested) => {
+                        let nested = &nested.document;
+
+                        elements += nested.element_count() - 1;
+                        max_depth = max_depth.max(depth - 1 + nested.max_depth());
+                    }
+                    None => max_depth = max_depth.max(depth),
+                },
+                _ => (),
+            }
+        }
+
+        (elements, max_depth)
+    }
+
+    /**
+    The offset in the input up to which the parser didn't encounter any invalid content.
+
+    If the document isn't erroneous then this will be the length of the input.
+    If it is erroneous then it will be the offset of the first error that was detected,
+    which isn't necessarily where the input first became invalid.
+    */
+    #[inline]
+    pub fn valid_up_to(&self) -> usize {
+        self.offsets.valid_up_to
+    }
+
+    /**
+    The error the parser encountered, if the document is erroneous.
+    */
+    #[inline]
+    pub fn scan_error(&self) -> Option<ScanError> {
+        if !self.offsets.err {
+            return None;
+        }
+
+        Some(ScanError {
+            valid_up_to: self.offsets.valid_up_to,
+            kind: self.offsets.err_kind,
+        })
+    }
+
     /**
     Detach the allocations from this document so that they can be reused for parsing other documents.
     */
@@ -165,16 +594,453 @@ impl<'input> Document<'input> {
     }
 }
 
+impl<'input> TryFrom<&'input [u8]> for Document<'input> {
+    type Error = ScanError;
+
+    /**
+    Scan a JSON object byte buffer into an indexable document.
+
+    If the scanner encounters any invalid content then an error is returned.
+    */
+    #[inline]
+    fn try_from(input: &'input [u8]) -> Result<Self, Self::Error> {
+        let document = Document::scan_trusted(input);
+
+        if let Some(err) = document.scan_error() {
+            return Err(err);
+        }
+
+        Ok(document)
+    }
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+impl<'a, 'input> From<&'a Document<'input>> for serde_json::Value {
+    #[inline]
+    fn from(document: &'a Document<'input>) -> Self {
+        document.to_value()
+    }
+}
+
+/**
+An error encountered while scanning a document.
+
+Errors are only returned by conversions that scan documents. Scanning a document
+directly will produce an erroneous document instead.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanError {
+    valid_up_to: usize,
+    kind: ScanErrorKind,
+}
+
+/**
+The kind of problem that made a document erroneous.
+*/
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ScanErrorKind {
+    /**
+    The input isn't a valid minified JSON object.
+    */
+    Invalid,
+    /**
+    The input contains more than [`Document::MAX_ELEMENTS`] elements.
+
+    The offsets for a document this large can't be indexed, so none of it is kept,
+    even if partial or recovered documents are requested.
+    */
+    TooManyElements {
+        /**
+        The number of elements the parser found.
+        */
+        count: usize,
+    },
+    /**
+    The input doesn't have a root map to scan.
+    */
+    Root(RootError),
+}
+
+/**
+The reason an input doesn't have a root map to scan.
+*/
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RootError {
+    /**
+    The input is empty, or only contains whitespace.
+    */
+    Empty,
+    /**
+    The input doesn't begin with a `{`.
+    */
+    NotAnObject,
+    /**
+    The input isn't valid UTF8 before its root map begins.
+    */
+    InvalidUtf8,
+}
+
+impl ScanErrorKind {
+    /**
+    The kind of error for a problem that stopped an input from being scanned at all.
+    */
+    #[cold]
+    fn from_root_problem(input: &[u8], problem: &Problem) -> Self {
+        match problem.kind() {
+            ProblemKind::NotAnObject if input.iter().all(u8::is_ascii_whitespace) => {
+                ScanErrorKind::Root(RootError::Empty)
+            }
+            ProblemKind::NotAnObject => ScanErrorKind::Root(RootError::NotAnObject),
+            ProblemKind::InvalidUtf8 => ScanErrorKind::Root(RootError::InvalidUtf8),
+            _ => ScanErrorKind::Invalid,
+        }
+    }
+}
+
+impl ScanError {
+    /**
+    The offset in the input up to which the parser didn't encounter any invalid content.
+
+    See [`Document::valid_up_to`] for more details.
+    */
+    #[inline]
+    pub fn valid_up_to(&self) -> usize {
+        self.valid_up_to
+    }
+
+    /**
+    The kind of problem that made the document erroneous.
+    */
+    #[inline]
+    pub fn kind(&self) -> ScanErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ScanErrorKind::TooManyElements { count } => write!(
+                f,
+                "JSON document has {} elements, but at most {} can be scanned",
+                count,
+                Document::MAX_ELEMENTS
+            ),
+            ScanErrorKind::Root(RootError::Empty) => write!(f, "JSON document is empty"),
+            ScanErrorKind::Root(RootError::NotAnObject) => {
+                write!(f, "JSON document isn't an object")
+            }
+            ScanErrorKind::Root(RootError::InvalidUtf8) => write!(
+                f,
+                "JSON document isn't valid UTF8 at input offset {}",
+                self.valid_up_to
+            ),
+            _ => write!(
+                f,
+                "invalid JSON document at input offset {}",
+                self.valid_up_to
+            ),
+        }
+    }
+}
+
+impl error::Error for ScanError {}
+
+/**
+The implementation to use for scanning a document.
+
+For valid input, every backend will produce the same document.
+For invalid input, different backends may produce different documents.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Backend {
+    /**
+    Pick a backend based on the features of the current CPU and the size of the input.
+    */
+    Auto,
+    /**
+    Process the input byte-by-byte.
+
+    This backend is available on every target.
+    */
+    Scalar,
+    /**
+    Process the input in vectorized blocks, regardless of its size.
+
+    This backend is only available on CPUs that support AVX2 or Neon.
+    */
+    Simd,
+}
+
+impl Default for Backend {
+    #[inline]
+    fn default() -> Self {
+        Backend::Auto
+    }
+}
+
+/**
+Options for scanning a document.
+*/
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    backend: Backend,
+    partial: bool,
+    recover: bool,
+    warnings: bool,
+    verify_atoms: bool,
+    allow_trailing_commas: bool,
+    lazy_depth: usize,
+    max_problems: usize,
+    interleave_utf8: bool,
+    prefetch: bool,
+    presize: bool,
+    capture: Option<Arc<capture::FieldCapture>>,
+}
+
+impl Default for ScanOptions {
+    #[inline]
+    fn default() -> Self {
+        ScanOptions::DEFAULT
+    }
+}
+
+impl ScanOptions {
+    const DEFAULT: ScanOptions = ScanOptions {
+        backend: Backend::Auto,
+        partial: false,
+        recover: false,
+        warnings: false,
+        verify_atoms: false,
+        allow_trailing_commas: true,
+        lazy_depth: usize::MAX,
+        max_problems: 0,
+        interleave_utf8: false,
+        prefetch: false,
+        presize: false,
+        capture: None,
+    };
+
+    /**
+    Create a default set of options.
+    */
+    #[inline]
+    pub const fn new() -> Self {
+        ScanOptions::DEFAULT
+    }
+
+    /**
+    Use a specific backend for scanning.
+    */
+    #[inline]
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /**
+    Keep the offsets of a document that's erroneous instead of discarding them.
+
+    The document will still report that it's erroneous, but will contain any entries
+    that were completely scanned before the error. Containers that weren't finished
+    will be empty. Use [`Document::valid_up_to`] to find where the error was detected.
+
+    This option also lets documents that are truncated or contain invalid UTF8 be
+    scanned up to the point where they became invalid.
+    */
+    #[inline]
+    pub fn keep_partial(mut self, keep: bool) -> Self {
+        self.partial = keep;
+        self
+    }
+
+    /**
+    Recover from errors by closing any open containers at the point the error was detected.
+
+    The document will still report that it's erroneous, and also that it's partial.
+    Unlike [`ScanOptions::keep_partial`], containers that weren't finished will keep
+    any entries or elements that were completely scanned before the error.
+    Any value that may have been truncated by the error is discarded.
+    */
+    #[inline]
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+
+    /**
+    Collect warnings about content that isn't valid JSON, but isn't considered erroneous either.
+
+    Use [`Document::warnings`] to get the warnings after scanning.
+    */
+    #[inline]
+    pub fn collect_warnings(mut self, collect: bool) -> Self {
+        self.warnings = collect;
+        self
+    }
+
+    /**
+    Verify atoms in full instead of only by their first character.
+
+    Any atom that isn't exactly `true`, `false`, or `null` will make the document erroneous.
+    */
+    #[inline]
+    pub fn verify_atoms(mut self, verify: bool) -> Self {
+        self.verify_atoms = verify;
+        self
+    }
+
+    /**
+    Accept a `,` after the last entry in a map or element in an array, like `{"a":1,}`.
+
+    Trailing commas are accepted by default. They're ignored, so the map or array is scanned
+    as if they weren't there. If this is `false` then a trailing comma makes the document erroneous.
+    */
+    #[inline]
+    pub fn allow_trailing_commas(mut self, allow: bool) -> Self {
+        self.allow_trailing_commas = allow;
+        self
+    }
+
+    /**
+    Keep maps and arrays that are nested more than `depth` levels below the root as raw spans.
+
+    The entries of the root map are at a depth of `1`, so a `depth` of `1` keeps any maps
+    or arrays within them as raw spans. These containers are still read through to find
+    where they end, but none of their contents are kept in the offsets. They're returned
+    as a [`Kind::Lazy`], which can be scanned on demand using [`Kind::scan_nested`].
+    Empty maps and arrays don't have any contents to defer, so they're never kept lazily.
+
+    Most queries only look at the first few levels of a document, so this keeps the table
+    of offsets small for documents with large nested values.
+    */
+    #[inline]
+    pub fn lazy_depth(mut self, depth: usize) -> Self {
+        self.lazy_depth = depth;
+        self
+    }
+
+    /**
+    Collect up to `max` of the problems that make a document erroneous.
+
+    The scanner keeps going after the first error it finds, so this can report more than one
+    reason a document is erroneous. Use [`Document::problems`] to get the problems after scanning.
+    A `max` of `0` disables collecting problems.
+    */
+    #[inline]
+    pub fn collect_problems(mut self, max: usize) -> Self {
+        self.max_problems = max;
+        self
+    }
+
+    /**
+    Validate the input as UTF8 while it's being scanned, instead of in a separate pass before it.
+
+    Large documents are read through once instead of twice, which keeps more of them in cache.
+    This only applies to the [`Backend::Auto`] backend when the vectorized implementation is used.
+    If the input turns out not to be UTF8 then it's scanned again as if this option wasn't set,
+    so the resulting document is always the same either way. Documents scanned with
+    [`ScanOptions::capture`] are always validated before they're scanned, so captures are only
+    ever invoked with valid UTF8.
+    */
+    #[inline]
+    pub fn interleave_utf8(mut self, interleave: bool) -> Self {
+        self.interleave_utf8 = interleave;
+        self
+    }
+
+    /**
+    Prefetch the input ahead of where it's being scanned for documents that are larger than L2.
+
+    This only applies when the vectorized implementation is used. Hardware prefetching usually
+    keeps up with the scanner on its own, so this is only worth enabling if profiles of scanning
+    huge documents show stalls waiting on memory.
+    */
+    #[inline]
+    pub fn prefetch(mut self, prefetch: bool) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /**
+    Count the separators in the input before scanning it, so its offsets can be allocated once.
+
+    The count is a cheap upper bound on the number of elements in the document, so the offsets
+    never need to grow while it's scanned. This trades an extra pass over the input for avoiding
+    reallocations, which is worth it when documents of unpredictable sizes are scanned without
+    a [`DetachedDocument`] that's already large enough for them.
+    */
+    #[inline]
+    pub fn presize(mut self, presize: bool) -> Self {
+        self.presize = presize;
+        self
+    }
+
+    /**
+    Invoke a callback with the values of some keys at the root of the document while it's scanned.
+
+    The callback is invoked with the key and its value as soon as the value has been scanned,
+    before anything after it in the document is looked at, so decisions like whether to drop or
+    forward a document can be made early. Keys are compared with their unescaped values. If the
+    document contains the same key more than once then the callback is invoked for each one.
+
+    Nothing is captured after the scanner encounters any invalid content, so the callback may
+    not be invoked at all for erroneous documents.
+    */
+    pub fn capture(
+        mut self,
+        keys: impl IntoIterator<Item = impl Into<String>>,
+        f: impl Fn(&str, Kind) + Send + Sync + 'static,
+    ) -> Self {
+        self.capture = Some(Arc::new(capture::FieldCapture::new(keys, f)));
+        self
+    }
+
+    #[inline]
+    fn keeps_partial(&self) -> bool {
+        self.partial || self.recover
+    }
+}
+
 /**
 A previously parsed table of offsets.
 
 The offsets can be cached and re-attached to an input buffer to avoid parsing again.
 */
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
 #[derive(Debug, Clone)]
 pub struct Offsets {
-    elements: Vec<Offset>,
+    elements: Elements,
     err: bool,
-    root_size_hint: u16,
+    err_kind: ScanErrorKind,
+    partial: bool,
+    truncated: bool,
+    valid_up_to: usize,
+    root_parts: u16,
+    root_raw: Slice,
+    max_depth: u16,
+    lazy_elements: usize,
+    warnings: ScanWarnings,
+    problems: ScanProblems,
+    #[cfg(any(test, feature = "xxhash-rust"))]
+    binding: Option<Box<binding::InputBinding>>,
 }
 
 /**
@@ -185,47 +1051,145 @@ to be from the same buffer.
 */
 #[derive(Clone)]
 pub struct DetachedDocument {
-    offsets: Vec<Offset>,
+    offsets: Elements,
     stack: Vec<ActiveMapArr>,
 }
 
 impl Default for DetachedDocument {
     #[inline]
     fn default() -> Self {
+        // small documents don't need to allocate at all
+        // the stack is only allocated for documents with nested maps or arrays
         DetachedDocument {
-            offsets: Vec::with_capacity(48),
-            stack: Vec::with_capacity(6),
+            offsets: SmallVec::new(),
+            stack: Vec::new(),
         }
     }
 }
 
+/**
+The number of bytes allocated for offsets outside of their inline storage.
+*/
+#[inline]
+fn elements_memory_usage(elements: &Elements) -> usize {
+    if elements.spilled() {
+        mem::size_of::<Offset>() * elements.capacity()
+    } else {
+        0
+    }
+}
+
+impl DetachedDocument {
+    /**
+    The number of bytes allocated for this detached document, including any excess capacity.
+    */
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<Self>()
+            + elements_memory_usage(&self.offsets)
+            + (mem::size_of::<ActiveMapArr>() * self.stack.capacity())
+    }
+
+    /**
+    Release any excess capacity held by this detached document.
+
+    Scanning one unusually large document grows the allocation to fit it, and it's kept
+    for every document scanned into it afterwards. Shrinking releases that capacity, but the
+    next document scanned into it will need to grow it again.
+    */
+    pub fn shrink_to_fit(&mut self) {
+        self.offsets.shrink_to_fit();
+        self.stack.shrink_to_fit();
+    }
+}
+
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct Offset {
     kind: OffsetKind,
     position: Part,
     next: Option<u16>,
+    /**
+    The previous part at the same position within the same map or array.
+
+    The first part points back to the last one instead, so a map or array can be walked
+    in reverse without starting from the front. This is a bare `u16` rather than an `Option`
+    so it fits in what would otherwise be padding. It's [`NO_PREV`] for parts in a map or array
+    that wasn't finished.
+    */
+    prev: u16,
 }
 
+/**
+The number of offsets that are kept inline, before they're moved into their own allocation.
+
+This is enough for small documents, like `{"ok":true}`, to be scanned without allocating.
+It's kept small because the offsets are stored inline in every [`Document`], so larger
+documents pay for a single allocation instead.
+*/
+const INLINE_ELEMENTS: usize = 8;
+
+type Elements = SmallVec<[Offset; INLINE_ELEMENTS]>;
+
+/**
+The `prev` of a part that doesn't have a previous part to point to.
+
+Elements are indexed by `u16`, but there can't be more than [`Document::MAX_ELEMENTS`] of them,
+so the last index is never used.
+*/
+const NO_PREV: u16 = u16::MAX;
+
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum OffsetKind {
     Str(Slice, bool),
     Num(Slice),
     Bool(bool),
     Null,
-    Map(u16),
-    Arr(u16),
+    Map(u16, Slice),
+    Arr(u16, Slice),
+    // a map or array that's kept as a raw span, along with whether it's a map
+    Lazy(Slice, bool),
 }
 
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct Slice {
-    offset: u32,
-    len: u32,
+    offset: InputPos,
+    len: InputPos,
 }
 
+/**
+A position in the input.
+
+Positions are `u32`s unless the `large-documents` feature is enabled.
+*/
+#[cfg(not(feature = "large-documents"))]
+type InputPos = u32;
+
+#[cfg(feature = "large-documents")]
+type InputPos = u64;
+
 /**
 The position of an element within a document.
 */
 // note: these fields cannot be changed without `PrevPartOffsets`
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[repr(u8)]
 enum Part {
@@ -248,60 +1212,206 @@ type PrevPartOffsets = [Option<u16>; 4];
 impl Offsets {
     pub fn empty() -> Self {
         Offsets {
-            elements: Vec::new(),
+            elements: SmallVec::new(),
+            err: false,
+            err_kind: ScanErrorKind::Invalid,
+            partial: false,
+            truncated: false,
+            valid_up_to: 0,
+            root_parts: 0,
+            root_raw: Slice { offset: 0, len: 0 },
+            max_depth: 0,
+            lazy_elements: 0,
+            warnings: ScanWarnings::new(),
+            problems: ScanProblems::new(0),
+            #[cfg(any(test, feature = "xxhash-rust"))]
+            binding: None,
+        }
+    }
+
+    #[inline]
+    fn attach(elements: Elements) -> Self {
+        Offsets {
+            elements,
             err: false,
-            root_size_hint: 0,
+            err_kind: ScanErrorKind::Invalid,
+            partial: false,
+            truncated: false,
+            valid_up_to: 0,
+            root_parts: 0,
+            root_raw: Slice { offset: 0, len: 0 },
+            max_depth: 0,
+            lazy_elements: 0,
+            warnings: ScanWarnings::new(),
+            problems: ScanProblems::new(0),
+            #[cfg(any(test, feature = "xxhash-rust"))]
+            binding: None,
         }
     }
 
-    #[inline]
-    fn attach(elements: Vec<Offset>) -> Self {
-        Offsets {
-            elements,
-            err: false,
-            root_size_hint: 0,
+    /**
+    Build a document from these offsets and an input buffer without validating
+    that the input matches the offsets.
+
+    # Safety
+
+    This method is unsafe because the offsets may point to incorrect positions in
+    the input if it is not exactly equal to the input that originally produced the offsets.
+    */
+    #[inline]
+    pub unsafe fn to_document_unchecked<'a>(&'a self, input: &'a [u8]) -> Document<'a> {
+        Document {
+            input,
+            offsets: Cow::Borrowed(self),
+            _detached_stack: Vec::new(),
+            root: None,
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, part: Offset) {
+        self.elements.push(part);
+    }
+
+    pub fn approximate_size(&self) -> usize {
+        // inline offsets are already counted in the size of the table itself
+        if self.elements.spilled() {
+            mem::size_of::<Self>() + (mem::size_of::<Offset>() * self.elements.len())
+        } else {
+            mem::size_of::<Self>()
+        }
+    }
+
+    /**
+    The number of bytes allocated for these offsets, including any excess capacity.
+
+    Unlike [`Offsets::approximate_size`], this counts the capacity that's been reserved,
+    rather than just the elements that are in use.
+    */
+    pub fn memory_usage(&self) -> usize {
+        #[cfg(any(test, feature = "xxhash-rust"))]
+        let binding = self
+            .binding
+            .as_ref()
+            .map_or(0, |_| mem::size_of::<binding::InputBinding>());
+        #[cfg(not(any(test, feature = "xxhash-rust")))]
+        let binding = 0;
+
+        mem::size_of::<Self>()
+            + elements_memory_usage(&self.elements)
+            + self.warnings.memory_usage()
+            + self.problems.memory_usage()
+            + binding
+    }
+
+    /**
+    Release any excess capacity held by these offsets.
+
+    Offsets that are kept around for a long time, like in a cache, may have been
+    scanned into an allocation that was sized for a much larger document.
+    */
+    pub fn shrink_to_fit(&mut self) {
+        self.elements.shrink_to_fit();
+        self.warnings.shrink_to_fit();
+        self.problems.shrink_to_fit();
+    }
+
+    /**
+    Find the index of the first element that's different between two sets of offsets.
+
+    If one set of offsets is a prefix of the other, the index will be the length of the shorter one.
+    */
+    pub(crate) fn first_divergence(&self, other: &Offsets) -> Option<usize> {
+        let diverged = self
+            .elements
+            .iter()
+            .zip(other.elements.iter())
+            .position(|(a, b)| a != b);
+
+        match diverged {
+            Some(index) => Some(index),
+            None if self.elements.len() != other.elements.len() => {
+                Some(self.elements.len().min(other.elements.len()))
+            }
+            None => None,
         }
     }
 
     /**
-    Build a document from these offsets and an input buffer without validating
-    that the input matches the offsets.
+    The offset in the input that an element starts from, if it's known.
 
-    # Safety
-
-    This method is unsafe because the offsets may point to incorrect positions in
-    the input if it is not exactly equal to the input that originally produced the offsets.
+    Atoms like `true` and `null` don't track where they appear in the input.
     */
-    #[inline]
-    pub unsafe fn to_document_unchecked<'a>(&'a self, input: &'a [u8]) -> Document<'a> {
-        Document {
-            input,
-            offsets: Cow::Borrowed(self),
-            _detached_stack: Vec::new(),
+    pub(crate) fn input_offset(&self, index: usize) -> Option<usize> {
+        match self.elements.get(index)?.kind {
+            // include the leading `"`
+            OffsetKind::Str(s, _) => Some(s.offset as usize - 1),
+            OffsetKind::Num(s)
+            | OffsetKind::Map(_, s)
+            | OffsetKind::Arr(_, s)
+            | OffsetKind::Lazy(s, _) => Some(s.offset as usize),
+            OffsetKind::Bool(_) | OffsetKind::Null => None,
         }
     }
 
-    #[inline]
-    fn push(&mut self, part: Offset) {
-        self.elements.push(part);
+    /**
+    A description of an element for diagnostics.
+    */
+    pub(crate) fn describe(&self, index: usize) -> Option<String> {
+        self.elements
+            .get(index)
+            .map(|offset| format!("{:?}", offset))
     }
+}
 
-    pub fn approximate_size(&self) -> usize {
-        mem::size_of::<Self>() + (mem::size_of::<Offset>() * self.elements.len())
+/**
+Whether or not the current CPU supports the vectorized backend.
+*/
+#[inline]
+pub(crate) fn is_simd_supported() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        is_x86_feature_detected!("avx2")
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        std::arch::is_aarch64_feature_detected!("neon")
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        false
     }
 }
 
+#[inline]
+fn scan<'input>(
+    input: &'input [u8],
+    bounds: Result<Bounds, Problem>,
+    detached: DetachedDocument,
+    options: &ScanOptions,
+) -> Document<'input> {
+    scan_profile::<false>(input, bounds, detached, options)
+}
+
+/**
+Scan an input, picking a backend based on its size.
+
+If `FLAT` is set then the input is scanned with the flat profile used by
+[`Document::scan_trusted_flat`].
+*/
 #[inline]
 #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
-fn scan(input: &[u8], detached: DetachedDocument) -> Document {
-    let (start, end) = match scan_begin(input) {
-        Some(bounds) => bounds,
-        None => return Document::err(input),
+fn scan_profile<'input, const FLAT: bool>(
+    input: &'input [u8],
+    bounds: Result<Bounds, Problem>,
+    detached: DetachedDocument,
+    options: &ScanOptions,
+) -> Document<'input> {
+    let (mut scan, mut offsets) = match Scan::begin(input, bounds, detached, options) {
+        Ok(begin) => begin,
+        Err(err) => return err,
     };
 
-    let mut scan = Scan::attach(detached.stack, start, end);
-    let mut offsets = Offsets::attach(detached.offsets);
-
     // when SIMD is available, we can vectorize
     // HEURISTIC: small documents aren't worth vectorizing
     #[cfg(target_arch = "x86_64")]
@@ -311,8 +1421,8 @@ fn scan(input: &[u8], detached: DetachedDocument) -> Document {
         {
             // SAFETY: the input is UTF8
             // SAFETY: avx2 is available
-            unsafe { simd::scan_x86_64_avx2(input, &mut scan, &mut offsets) };
-            return scan_end(input, scan, offsets);
+            unsafe { simd::scan_x86_64_avx2::<FLAT>(input, &mut scan, &mut offsets) };
+            return scan_end(input, scan, offsets, options);
         }
     }
     #[cfg(target_arch = "aarch64")]
@@ -322,70 +1432,374 @@ fn scan(input: &[u8], detached: DetachedDocument) -> Document {
         {
             // SAFETY: the input is UTF8
             // SAFETY: neon is available
-            unsafe { simd::scan_aarch64_neon(input, &mut scan, &mut offsets) };
-            return scan_end(input, scan, offsets);
+            unsafe { simd::scan_aarch64_neon::<FLAT>(input, &mut scan, &mut offsets) };
+            return scan_end(input, scan, offsets, options);
         }
     }
 
     // when SIMD is not available, we need to fallback
     // SAFETY: the input is UTF8
-    unsafe { fallback::scan(input, &mut scan, &mut offsets) };
-    scan_end(input, scan, offsets)
+    unsafe { fallback::scan::<FLAT>(input, &mut scan, &mut offsets) };
+    scan_end(input, scan, offsets, options)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+use self::scan_fallback_profile as scan_profile;
+
+/**
+Scan an input that hasn't been validated as UTF8, validating it alongside the structural scan.
+
+If the input can't be vectorized, isn't UTF8, or doesn't look like a complete map,
+then it's scanned the regular way instead.
+*/
+#[inline]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn scan_interleaved<'input>(
+    input: &'input [u8],
+    detached: DetachedDocument,
+    options: &ScanOptions,
+) -> Document<'input> {
+    // captures are invoked with strings from the input while it's scanned, before the input
+    // has been validated, so documents with captures are always validated up-front
+    let bounds = match scan_begin_unvalidated(input) {
+        Some(bounds) if is_simd_supported() && options.capture.is_none() => bounds,
+        _ => return scan(input, scan_begin(input), detached, options),
+    };
+
+    #[cfg(target_arch = "x86_64")]
+    let vectorize =
+        (bounds.end as isize - bounds.start) as usize > simd::X86_64_AVX2_VECTORIZATION_THRESHOLD;
+    #[cfg(target_arch = "aarch64")]
+    let vectorize =
+        (bounds.end as isize - bounds.start) as usize > simd::AARCH64_NEON_VECTORIZATION_THRESHOLD;
+
+    // HEURISTIC: small documents aren't worth vectorizing
+    if !vectorize {
+        return scan(input, scan_begin(input), detached, options);
+    }
+
+    let (mut scan, mut offsets) = match Scan::begin(input, Ok(bounds), detached, options) {
+        Ok(begin) => begin,
+        Err(err) => return err,
+    };
+
+    // SAFETY: the input is validated while it's scanned
+    // SAFETY: the scan doesn't capture any keys
+    // SAFETY: avx2 is available
+    #[cfg(target_arch = "x86_64")]
+    let utf8 = unsafe { simd::scan_validate_x86_64_avx2(input, &mut scan, &mut offsets) };
+    // SAFETY: the input is validated while it's scanned
+    // SAFETY: the scan doesn't capture any keys
+    // SAFETY: neon is available
+    #[cfg(target_arch = "aarch64")]
+    let utf8 = unsafe { simd::scan_validate_aarch64_neon(input, &mut scan, &mut offsets) };
+
+    if utf8 {
+        return scan_end(input, scan, offsets, options);
+    }
+
+    // the input isn't UTF8, so it's scanned again the regular way
+    // this finds the same errors, and keeps the same partial documents, as not interleaving
+    let mut detached = DetachedDocument {
+        offsets: offsets.elements,
+        stack: scan.stack.bottom,
+    };
+    detached.offsets.clear();
+    detached.stack.clear();
+
+    self::scan(input, scan_begin(input), detached, options)
 }
 
 #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
-use self::scan_fallback as scan;
+fn scan_interleaved<'input>(
+    input: &'input [u8],
+    detached: DetachedDocument,
+    options: &ScanOptions,
+) -> Document<'input> {
+    scan(input, scan_begin(input), detached, options)
+}
+
+#[inline]
+fn scan_simd<'input>(
+    input: &'input [u8],
+    bounds: Result<Bounds, Problem>,
+    detached: DetachedDocument,
+    options: &ScanOptions,
+) -> Document<'input> {
+    #[allow(unused_mut, unused_variables)]
+    let (mut scan, mut offsets) = match Scan::begin(input, bounds, detached, options) {
+        Ok(begin) => begin,
+        Err(err) => return err,
+    };
+
+    // inputs that are smaller than a few blocks can't be vectorized
+    // so they're scanned using the fallback implementation instead
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            if scan.input_remaining() > simd::X86_64_AVX2_MIN_INPUT_LEN {
+                // SAFETY: the input is UTF8
+                // SAFETY: avx2 is available
+                unsafe { simd::scan_x86_64_avx2::<false>(input, &mut scan, &mut offsets) };
+            } else {
+                // SAFETY: the input is UTF8
+                unsafe { fallback::scan::<false>(input, &mut scan, &mut offsets) };
+            }
+
+            return scan_end(input, scan, offsets, options);
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            if scan.input_remaining() > simd::AARCH64_NEON_MIN_INPUT_LEN {
+                // SAFETY: the input is UTF8
+                // SAFETY: neon is available
+                unsafe { simd::scan_aarch64_neon::<false>(input, &mut scan, &mut offsets) };
+            } else {
+                // SAFETY: the input is UTF8
+                unsafe { fallback::scan::<false>(input, &mut scan, &mut offsets) };
+            }
+
+            return scan_end(input, scan, offsets, options);
+        }
+    }
+
+    scan_simd_unsupported(input)
+}
+
+#[cold]
+#[cfg_attr(debug, allow(unused_variables))]
+fn scan_simd_unsupported(input: &[u8]) -> Document<'_> {
+    #[cfg(debug)]
+    {
+        panic!("the current CPU doesn't support vectorized scanning");
+    }
+
+    #[cfg(not(debug))]
+    {
+        Document::err(input, 0, ScanProblems::new(0))
+    }
+}
 use std::borrow::Borrow;
 
 #[inline]
-fn scan_fallback(input: &[u8], detached: DetachedDocument) -> Document {
-    let (start, end) = match scan_begin(input) {
-        Some(bounds) => bounds,
-        None => return Document::err(input),
+fn scan_fallback<'input>(
+    input: &'input [u8],
+    bounds: Result<Bounds, Problem>,
+    detached: DetachedDocument,
+    options: &ScanOptions,
+) -> Document<'input> {
+    scan_fallback_profile::<false>(input, bounds, detached, options)
+}
+
+#[inline]
+fn scan_fallback_profile<'input, const FLAT: bool>(
+    input: &'input [u8],
+    bounds: Result<Bounds, Problem>,
+    detached: DetachedDocument,
+    options: &ScanOptions,
+) -> Document<'input> {
+    let (mut scan, mut offsets) = match Scan::begin(input, bounds, detached, options) {
+        Ok(begin) => begin,
+        Err(err) => return err,
     };
 
-    let mut scan = Scan::attach(detached.stack, start, end);
-    let mut offsets = Offsets::attach(detached.offsets);
+    unsafe { fallback::scan::<FLAT>(input, &mut scan, &mut offsets) };
+    scan_end(input, scan, offsets, options)
+}
+
+/**
+The bounds of the input to scan within.
+*/
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    start: isize,
+    end: usize,
+    /**
+    An error that was detected before scanning.
 
-    unsafe { fallback::scan(input, &mut scan, &mut offsets) };
-    scan_end(input, scan, offsets)
+    If the input is truncated or contains invalid UTF8, it can still be scanned
+    up to that point.
+    */
+    err: Option<Problem>,
 }
 
 /**
 Validate the input is UTF8 and return the bounds to read within.
 
 The input is expected to be a JSON object. The start and end tokens are omitted.
+If the input can't be scanned at all then the error is returned.
 */
 #[inline]
-fn scan_begin(input: &[u8]) -> Option<(isize, usize)> {
+fn scan_begin(input: &[u8]) -> Result<Bounds, Problem> {
     // ensure the input is valid UTF8
     // we mostly scan through 7byte ASCII, but construct strings
     // from offsets within the document
-    let input = match str::from_utf8(input) {
-        Ok(input) => input.trim_end().as_bytes(),
-        _ => return None,
-    };
+    match utf8::from_utf8(input) {
+        Ok(input) => scan_begin_utf8(input),
+        Err(err) => scan_begin_utf8_err(input, err.valid_up_to()),
+    }
+}
+
+/**
+Return the bounds to read within for input that hasn't been validated as UTF8.
 
-    if input.len() < 2 {
+This only succeeds for input that's a complete map, with nothing but ASCII whitespace after it,
+so that it has the same bounds as it would after being validated.
+*/
+#[inline]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn scan_begin_unvalidated(input: &[u8]) -> Option<Bounds> {
+    let len = input.len()
+        - input
+            .iter()
+            .rev()
+            .take_while(|b| b.is_ascii_whitespace())
+            .count();
+
+    let start = leading_whitespace(input);
+
+    if !(2..=Document::MAX_INPUT_LEN).contains(&len)
+        || len - start < 2
+        || *get_unchecked!(input, start) != b'{'
+        || *get_unchecked!(input, len - 1) != b'}'
+    {
         return None;
     }
 
+    Some(Bounds {
+        start: start as isize + 1,
+        end: len - 1,
+        err: None,
+    })
+}
+
+/**
+The number of ASCII whitespace characters before the start of the input.
+
+Leading whitespace, like a stray separator in a framed stream, is skipped over before the root map.
+*/
+#[inline]
+fn leading_whitespace(input: &[u8]) -> usize {
+    input.iter().take_while(|b| b.is_ascii_whitespace()).count()
+}
+
+/**
+Estimate the number of elements in an input, for allocating its offsets up-front.
+
+Every key and value in a map has a `:` between them, and every element in an array follows
+a `[` or `,`, so this never underestimates. Separators within strings are also counted, so
+it may overestimate. The estimate is capped just past the most elements a document can have.
+*/
+#[inline]
+fn estimate_elements(input: &[u8]) -> usize {
+    // this is a branchless count that the compiler vectorizes
+    let estimate: usize = input
+        .iter()
+        .map(|&b| ((b == b':') as usize * 2) + ((b == b',') | (b == b'[')) as usize)
+        .sum();
+
+    estimate.min(Document::MAX_ELEMENTS + 1)
+}
+
+/**
+Return the bounds to read within for input that's only UTF8 up to a point.
+
+The input can only be scanned up to where it's valid.
+*/
+#[cold]
+fn scan_begin_utf8_err(input: &[u8], valid_up_to: usize) -> Result<Bounds, Problem> {
+    let valid = from_utf8_unchecked!(get_unchecked!(input, ..valid_up_to));
+
+    let mut bounds = match scan_begin_utf8(valid) {
+        Ok(bounds) => bounds,
+        // if the input becomes invalid before its root map has begun then that's why it can't be scanned
+        Err(problem)
+            if problem.kind() == ProblemKind::NotAnObject
+                && (valid.trim().is_empty() || valid.trim_start().starts_with('{')) =>
+        {
+            return Err(Problem::new(ProblemKind::InvalidUtf8, valid_up_to));
+        }
+        Err(problem) => return Err(problem),
+    };
+    bounds.err = Some(Problem::new(ProblemKind::InvalidUtf8, valid_up_to));
+
+    Ok(bounds)
+}
+
+/**
+Return the bounds to read within for the prefix of some input.
+
+The prefix is always treated as truncated, even if it happens to end in a `}`,
+because that `}` may close a container within the input rather than the input itself.
+*/
+#[inline]
+fn scan_begin_prefix(prefix: &[u8]) -> Result<Bounds, Problem> {
+    let mut bounds = match utf8::from_utf8(prefix) {
+        Ok(prefix) => scan_begin_utf8(prefix)?,
+        // the prefix may have cut a character in two
+        // it's only invalid if it's invalid before the cut
+        Err(err) if err.error_len().is_none() => scan_begin_utf8(from_utf8_unchecked!(
+            get_unchecked!(prefix, ..err.valid_up_to())
+        ))?,
+        Err(err) => return scan_begin_utf8_err(prefix, err.valid_up_to()),
+    };
+
+    bounds
+        .err
+        .get_or_insert(Problem::new(ProblemKind::Truncated, bounds.end + 1));
+
+    Ok(bounds)
+}
+
+/**
+Return the bounds to read within for input that's already known to be UTF8.
+*/
+#[inline]
+fn scan_begin_utf8(input: &str) -> Result<Bounds, Problem> {
+    let input = input.trim_end().as_bytes();
+
+    // positions in the input are stored as `InputPos`s, so they can't index past this point
+    #[allow(clippy::absurd_extreme_comparisons)]
+    // `MAX_INPUT_LEN` is `usize::MAX` for large documents
+    if input.len() > Document::MAX_INPUT_LEN {
+        return Err(Problem::new(
+            ProblemKind::InputTooLarge,
+            Document::MAX_INPUT_LEN,
+        ));
+    }
+
+    let start = leading_whitespace(input);
+
+    if input.len() - start < 2 {
+        return Err(Problem::new(ProblemKind::NotAnObject, 0));
+    }
+
     // ensure the input is an object
     // doing this lets us guarantee that lookaheads will always be in bounds
     // because we never look past 1 char, and never lookahead on `}`
 
-    if *get_unchecked!(input, 0) != b'{' {
-        return None;
+    if *get_unchecked!(input, start) != b'{' {
+        return Err(Problem::new(ProblemKind::NotAnObject, start));
     }
 
-    if *get_unchecked!(input, input.len() - 1) != b'}' {
-        return None;
-    }
+    // if the input isn't terminated then it's probably truncated
+    // it can still be scanned up to its last character
+    let err = if *get_unchecked!(input, input.len() - 1) != b'}' {
+        Some(Problem::new(ProblemKind::Truncated, input.len()))
+    } else {
+        None
+    };
 
-    // ignore the leading and trailing object chars along with any trailing whitespace
+    // ignore the leading and trailing object chars along with any surrounding whitespace
     // by ignoring the outer map the parser can avoid an unnecessary item in the offsets,
     // since every document is expected to be a map.
-    Some((1, input.len() - 1))
+    Ok(Bounds {
+        start: start as isize + 1,
+        end: input.len() - 1,
+        err,
+    })
 }
 
 /**
@@ -394,7 +1808,12 @@ Validate the produced output.
 There may be some trailing unprocessed input to deal with because the object markers are ignored.
 */
 #[inline]
-fn scan_end(input: &[u8], mut scan: Scan, mut offsets: Offsets) -> Document {
+fn scan_end<'input>(
+    input: &'input [u8],
+    mut scan: Scan,
+    mut offsets: Offsets,
+    options: &ScanOptions,
+) -> Document<'input> {
     // ensure the input is complete
     match scan.stack.active_map_arr.active_primitive.kind {
         // if there's no start kind then we're finished
@@ -403,11 +1822,17 @@ fn scan_end(input: &[u8], mut scan: Scan, mut offsets: Offsets) -> Document {
         // if there's a number then finish it
         // since we trim the leading and trailing `{` `}` characters there may be a trailing
         // number to finish
+        // if the input is already erroneous then the number may be truncated so it's discarded
+        // the problem that made the input erroneous has already been collected
+        ActivePrimitiveKind::Num if scan.error => {
+            let start = scan.stack.active_map_arr.active_primitive.input_offset;
+            scan.err_offset = scan.err_offset.min(start);
+        }
         ActivePrimitiveKind::Num => {
             let input_offset = scan.input_offset as usize;
             let curr = offset_deref_unchecked!(input, scan.input_offset);
 
-            interest_num_end(ScanFnInput {
+            interest_num_end(ScanFnInput::<false> {
                 curr_offset: input_offset,
                 curr,
                 input,
@@ -418,32 +1843,219 @@ fn scan_end(input: &[u8], mut scan: Scan, mut offsets: Offsets) -> Document {
 
         // if there's a string then the input is truncated
         ActivePrimitiveKind::Str => {
-            scan.error = true;
-            test_unreachable!("unterminated string");
+            // input that's already known to be truncated is expected to end in a string
+            if !scan.error {
+                test_unreachable!("unterminated string");
+            }
+
+            // the string begins at its leading `"`
+            let start = scan.stack.active_map_arr.active_primitive.input_offset - 1;
+            scan.err(start, ProblemKind::Truncated);
         }
 
         // if there's an atom then we're finished
         ActivePrimitiveKind::Atom => (),
     }
 
+    // the last entry in the root map isn't followed by a `,` so it's captured here
+    capture::capture_last_entry(input, &scan, &offsets);
+
+    #[cfg(any(test, feature = "metrics"))]
+    crate::metrics::record(
+        input.len(),
+        &scan.metrics,
+        scan.error || offsets.elements.len() > Document::MAX_ELEMENTS,
+    );
+
     // if the offsets count is greater than `u16::max_value` then we've overflowed
-    if offsets.elements.len() > u16::MAX as usize {
-        scan.error = true;
-        test_unreachable!("overflowed max offset size");
-    }
+    // the document can't be kept, even partially, because its offsets may be cyclic
+    if offsets.elements.len() > Document::MAX_ELEMENTS {
+        scan.err(0, ProblemKind::TooManyElements);
 
-    // set the root size hint for the document
-    offsets.root_size_hint = scan.stack.active_map_arr.len >> 1;
+        return Document::err_kind(
+            input,
+            0,
+            ScanErrorKind::TooManyElements {
+                count: offsets.elements.len(),
+            },
+            scan.problems,
+        );
+    }
 
     // only return a document if the parser didn't produce an error
     if !scan.error {
+        // set the number of parts in the root map
+        offsets.root_parts = scan.stack.active_map_arr.len;
+        scan.stack
+            .active_map_arr
+            .link_last_parts(&mut offsets.elements);
+
+        // the root map spans the input from its leading `{` up to its trailing `}`
+        let start = leading_whitespace(input);
+        offsets.root_raw = Slice {
+            offset: start as InputPos,
+            len: (scan.input_len - start) as InputPos + 1,
+        };
+
+        offsets.valid_up_to = input.len();
+
+        if scan.warn {
+            warnings::collect(input, &mut offsets);
+        }
+
         Document {
             input,
             offsets: Cow::Owned(offsets),
             _detached_stack: scan.stack.bottom,
+            root: None,
         }
+    } else if options.recover {
+        let detached = DetachedDocument {
+            offsets: offsets.elements,
+            stack: scan.stack.bottom,
+        };
+
+        scan_recover(
+            input,
+            scan.err_offset,
+            scan.input_len,
+            scan.warn,
+            scan.problems,
+            detached,
+        )
+    } else if options.partial {
+        scan_end_partial(input, scan, offsets)
     } else {
-        Document::err(input)
+        Document::err(input, scan.err_offset, scan.problems)
+    }
+}
+
+/**
+Keep the offsets of an erroneous document.
+*/
+#[cold]
+fn scan_end_partial(input: &[u8], mut scan: Scan, mut offsets: Offsets) -> Document<'_> {
+    // the root may not be the active map if containers weren't closed
+    let root = scan
+        .stack
+        .flat_root
+        .as_ref()
+        .or(scan.stack.bottom.first())
+        .unwrap_or(&scan.stack.active_map_arr);
+
+    offsets.root_parts = root.len;
+    root.link_last_parts(&mut offsets.elements);
+
+    offsets.err = true;
+    offsets.partial = true;
+    offsets.valid_up_to = scan.err_offset;
+
+    scan.problems.finish();
+    offsets.problems = scan.problems;
+
+    if scan.warn {
+        warnings::collect(input, &mut offsets);
+    }
+
+    Document {
+        input,
+        offsets: Cow::Owned(offsets),
+        _detached_stack: scan.stack.bottom,
+        root: None,
+    }
+}
+
+/**
+Recover an erroneous document by scanning it up to its first error and closing any open containers.
+
+Offsets after the first error may be nonsense, so the input is scanned again from the start.
+*/
+#[cold]
+fn scan_recover(
+    input: &[u8],
+    err_offset: usize,
+    end: usize,
+    warn: bool,
+    mut problems: ScanProblems,
+    mut detached: DetachedDocument,
+) -> Document<'_> {
+    detached.offsets.clear();
+    detached.stack.clear();
+
+    // if the error is at or beyond the end of the input then it's truncated
+    // otherwise the error was detected at some structural character within it
+    let truncated = err_offset >= end;
+    let end = err_offset.min(end);
+
+    let start = leading_whitespace(input) as isize + 1;
+    let mut scan = Scan::attach(detached.stack, start, end);
+    let mut offsets = Offsets::attach(detached.offsets);
+
+    scan.warn = warn;
+
+    if truncated {
+        scan.err(err_offset, ProblemKind::Truncated);
+    }
+
+    // SAFETY: the input is UTF8 up to the end
+    unsafe { fallback::scan::<false>(input, &mut scan, &mut offsets) };
+
+    match scan.stack.active_map_arr.active_primitive.kind {
+        // if the input isn't truncated then a number is terminated by the error
+        // a number is started right after a `,`, so it may not have any content yet
+        ActivePrimitiveKind::Num
+            if !truncated && scan.stack.active_map_arr.active_primitive.input_offset < end =>
+        {
+            let curr = offset_deref_unchecked!(input, scan.input_offset);
+
+            interest_num_end(ScanFnInput::<false> {
+                curr_offset: end,
+                curr,
+                input,
+                scan: &mut scan,
+                offsets: &mut offsets,
+            });
+        }
+        // any other value may be truncated, so it's discarded
+        ActivePrimitiveKind::Num | ActivePrimitiveKind::Str => {
+            scan.stack.active_map_arr.active_primitive.take();
+        }
+        ActivePrimitiveKind::None | ActivePrimitiveKind::Atom => (),
+    }
+
+    // close any containers that were left open
+    let curr = offset_deref_unchecked!(input, scan.input_offset);
+    ScanFnInput::<false> {
+        curr_offset: end,
+        curr,
+        input,
+        scan: &mut scan,
+        offsets: &mut offsets,
+    }
+    .close_all();
+
+    offsets.root_parts = scan.stack.active_map_arr.len;
+    scan.stack
+        .active_map_arr
+        .link_last_parts(&mut offsets.elements);
+
+    offsets.err = true;
+    offsets.partial = true;
+    offsets.valid_up_to = err_offset;
+
+    // problems are only collected from the original scan
+    problems.finish();
+    offsets.problems = problems;
+
+    if warn {
+        warnings::collect(input, &mut offsets);
+    }
+
+    Document {
+        input,
+        offsets: Cow::Owned(offsets),
+        _detached_stack: scan.stack.bottom,
+        root: None,
     }
 }
 
@@ -477,6 +2089,39 @@ struct Scan {
     */
     error: bool,
     /**
+    The offset in the input of the first error the parser encountered.
+    */
+    err_offset: usize,
+    /**
+    Whether or not to collect warnings while scanning.
+    */
+    warn: bool,
+    /**
+    Whether or not atoms that aren't exactly `true`, `false`, or `null` are errors.
+    */
+    verify_atoms: bool,
+    /**
+    Whether or not a `,` after the last part of a map or array is ignored instead of an error.
+    */
+    allow_trailing_commas: bool,
+    /**
+    The depth of the stack that maps and arrays are kept as raw spans at.
+    */
+    lazy_level: usize,
+    /**
+    The keys at the root of the document to capture while scanning, if any.
+    */
+    capture: Option<Arc<capture::FieldCapture>>,
+    /**
+    The problems encountered by the parser, if they're being collected.
+    */
+    problems: ScanProblems,
+    /**
+    Counters for the work done by the parser.
+    */
+    #[cfg(any(test, feature = "metrics"))]
+    metrics: crate::metrics::ScanMetrics,
+    /**
     State specifically for the SIMD implementation.
 
     Even when the input isn't being processed using SIMD, its state needs to be kept consistent
@@ -501,6 +2146,12 @@ The depth is increased for each map or array.
 struct Stack {
     active_map_arr: ActiveMapArr,
     bottom: Vec<ActiveMapArr>,
+    /**
+    The root map while a map or array is open within it, when scanning with the flat profile.
+
+    Flat documents only ever have one map or array open at a time, so it doesn't need the stack.
+    */
+    flat_root: Option<ActiveMapArr>,
 }
 
 /**
@@ -519,6 +2170,10 @@ struct ActiveMapArr {
     */
     start_from_offset: u16,
     /**
+    The offset in the input of the leading `{` or `[` for this map or array.
+    */
+    raw_offset: InputPos,
+    /**
     The current number of offsets in this map or array.
     */
     len: u16,
@@ -587,6 +2242,75 @@ impl ActivePrimitive {
 }
 
 impl Scan {
+    /**
+    Begin scanning the input.
+
+    If the input can't be scanned then an erroneous document is returned.
+    */
+    #[inline]
+    // the erroneous document is returned to callers as-is, so boxing it wouldn't save anything
+    #[allow(clippy::result_large_err)]
+    fn begin<'input>(
+        input: &'input [u8],
+        bounds: Result<Bounds, Problem>,
+        detached: DetachedDocument,
+        options: &ScanOptions,
+    ) -> Result<(Scan, Offsets), Document<'input>> {
+        let bounds = match bounds {
+            Ok(bounds) => bounds,
+            Err(err) => {
+                let mut problems = ScanProblems::new(options.max_problems);
+                problems.push(err.kind(), err.input_offset());
+
+                #[cfg(any(test, feature = "metrics"))]
+                crate::metrics::record(input.len(), &Default::default(), true);
+
+                return Err(Document::err_kind(
+                    input,
+                    err.input_offset(),
+                    ScanErrorKind::from_root_problem(input, &err),
+                    problems,
+                ));
+            }
+        };
+
+        let mut scan = Scan::attach(detached.stack, bounds.start, bounds.end);
+        let mut offsets = Offsets::attach(detached.offsets);
+
+        if options.presize {
+            offsets
+                .elements
+                .reserve(estimate_elements(get_unchecked!(input, ..bounds.end)));
+        }
+
+        scan.warn = options.warnings;
+        scan.verify_atoms = options.verify_atoms;
+        scan.allow_trailing_commas = options.allow_trailing_commas;
+        scan.lazy_level = options.lazy_depth.saturating_add(1);
+        scan.capture = options.capture.clone();
+        scan.problems = ScanProblems::new(options.max_problems);
+
+        #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+        {
+            scan.simd.prefetch = options.prefetch;
+        }
+
+        // if the input is known to be invalid then it may still be scanned
+        // up to that point if partial documents are kept
+        if let Some(err) = bounds.err {
+            scan.err(err.input_offset(), err.kind());
+
+            if !options.keeps_partial() {
+                #[cfg(any(test, feature = "metrics"))]
+                crate::metrics::record(input.len(), &scan.metrics, true);
+
+                return Err(Document::err(input, err.input_offset(), scan.problems));
+            }
+        }
+
+        Ok((scan, offsets))
+    }
+
     #[inline]
     fn attach(stack: Vec<ActiveMapArr>, start: isize, end: usize) -> Self {
         Scan {
@@ -594,12 +2318,34 @@ impl Scan {
             input_len: end,
             escape: false,
             error: false,
+            err_offset: usize::MAX,
+            warn: false,
+            verify_atoms: false,
+            allow_trailing_commas: true,
+            lazy_level: usize::MAX,
+            capture: None,
+            problems: ScanProblems::new(0),
+            #[cfg(any(test, feature = "metrics"))]
+            metrics: Default::default(),
             stack: Stack::attach(stack),
             #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
             simd: Simd::new(),
         }
     }
 
+    /**
+    Record an error at the given offset in the input.
+
+    Errors are recorded in the order they're encountered, so the first one
+    will usually have the lowest offset.
+    */
+    #[cold]
+    fn err(&mut self, input_offset: usize, kind: ProblemKind) {
+        self.error = true;
+        self.err_offset = self.err_offset.min(input_offset);
+        self.problems.push(kind, input_offset);
+    }
+
     #[inline]
     #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
     fn input_remaining(&self) -> usize {
@@ -622,11 +2368,21 @@ impl Stack {
             active_map_arr: ActiveMapArr {
                 active_primitive: Default::default(),
                 start_from_offset: 0,
+                raw_offset: 0,
                 len: 0,
                 parts: [Part::Key, Part::Value],
                 prev_part_offsets: [None; 4],
             },
             bottom,
+            flat_root: None,
         }
     }
+
+    /**
+    Whether the root map is the active map, so there aren't any maps or arrays open within it.
+    */
+    #[inline(always)]
+    fn is_root(&self) -> bool {
+        self.bottom.is_empty() && self.flat_root.is_none()
+    }
 }
@@ -0,0 +1,134 @@
+/*!
+Archiving offsets with `rkyv`.
+
+Archived offsets can be stored alongside their input, like in a sidecar index, and
+validated and inspected in-place when they're loaded, without deserializing them.
+Documents own or borrow a complete table of offsets, so attaching archived offsets
+to their input still copies the table out of the archive, but doesn't re-scan the input.
+
+Archived offsets that were bound to their input with [`Document::into_bound_offsets`] can be
+attached with [`ArchivedOffsets::attach_verified`], which checks the input before copying anything.
+Validating an archive only checks its layout, not that its offsets were produced by scanning,
+so attaching archived offsets is always unsafe.
+*/
+
+use std::borrow::Cow;
+
+use rkyv::{AlignedVec, Deserialize, Infallible};
+
+use super::{ArchivedOffsets, Document, Offsets};
+
+impl Offsets {
+    /**
+    Archive these offsets into an aligned buffer.
+
+    The buffer can be loaded again using [`ArchivedOffsets::from_bytes`].
+    */
+    pub fn archive(&self) -> AlignedVec {
+        rkyv::to_bytes::<_, 256>(self).expect("failed to archive offsets")
+    }
+}
+
+impl ArchivedOffsets {
+    /**
+    Validate and access archived offsets in a buffer without deserializing them.
+
+    If the buffer doesn't contain valid archived offsets then `None` is returned.
+    The buffer must be aligned to at least 16 bytes.
+    */
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Option<&ArchivedOffsets> {
+        rkyv::check_archived_root::<Offsets>(bytes).ok()
+    }
+
+    /**
+    The number of elements in the archived offsets.
+    */
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /**
+    Whether or not the archived offsets are empty.
+    */
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /**
+    Whether or not the archived offsets are for an erroneous document.
+    */
+    #[inline]
+    pub fn is_err(&self) -> bool {
+        self.err
+    }
+
+    /**
+    Copy the archived offsets into a new table of offsets.
+    */
+    #[inline]
+    pub fn to_offsets(&self) -> Offsets {
+        match self.deserialize(&mut Infallible) {
+            Ok(offsets) => offsets,
+            Err(never) => match never {},
+        }
+    }
+
+    /**
+    Whether or not the archived offsets are bound to an input.
+    */
+    #[cfg(any(test, feature = "xxhash-rust"))]
+    #[inline]
+    pub fn is_bound(&self) -> bool {
+        self.binding.is_some()
+    }
+
+    /**
+    Build a document from these archived offsets and an input buffer, verifying that the
+    input matches the one they were bound to.
+
+    If the offsets aren't bound to an input, or the input doesn't match, then `None` is returned
+    without copying the offsets out of the archive. This catches archived offsets being attached
+    to the wrong input by mistake, which [`ArchivedOffsets::to_document_unchecked`] doesn't.
+
+    # Safety
+
+    The archive must have been produced by [`Offsets::archive`] from offsets that were bound
+    with [`Document::into_bound_offsets`], and not modified since. An archive is only validated
+    for its layout, so one crafted with arbitrary offsets and a matching binding isn't caught.
+
+    The input is only compared with the one the offsets were bound to by its length and checksum.
+    Callers must ensure the input isn't a different one crafted to have the same length and checksum.
+    */
+    #[cfg(any(test, feature = "xxhash-rust"))]
+    #[inline]
+    pub unsafe fn attach_verified<'a>(&self, input: &'a [u8]) -> Option<Document<'a>> {
+        match self.binding.as_ref() {
+            // SAFETY: the input has the same length and checksum as the one that produced the offsets
+            // SAFETY: the caller ensures the archive and input weren't crafted to match
+            Some(binding) if binding.verify(input) => Some(self.to_document_unchecked(input)),
+            _ => None,
+        }
+    }
+
+    /**
+    Build a document from these archived offsets and an input buffer without validating
+    that the input matches the offsets.
+
+    # Safety
+
+    This method is unsafe because the offsets may point to incorrect positions in
+    the input if it is not exactly equal to the input that originally produced the offsets.
+    */
+    #[inline]
+    pub unsafe fn to_document_unchecked<'a>(&self, input: &'a [u8]) -> Document<'a> {
+        Document {
+            input,
+            offsets: Cow::Owned(self.to_offsets()),
+            _detached_stack: Vec::new(),
+            root: None,
+        }
+    }
+}
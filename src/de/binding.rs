@@ -0,0 +1,112 @@
+/*!
+Binding offsets to the input they were scanned from.
+
+Offsets that are cached separately from their input can be attached to the wrong input
+by mistake. Binding records the length and a checksum of the input so it can be verified
+before the offsets are attached to it again.
+
+The checksum catches mistakes, but isn't a proof that two inputs are the same. A different
+input can be crafted with the same length and checksum, so attaching offsets is still unsafe.
+*/
+
+use xxhash_rust::xxh3::xxh3_64;
+
+use super::{Document, Offsets};
+
+/**
+The length and checksum of an input that offsets were scanned from.
+*/
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct InputBinding {
+    len: usize,
+    checksum: u64,
+}
+
+impl InputBinding {
+    #[inline]
+    fn new(input: &[u8]) -> Self {
+        InputBinding {
+            len: input.len(),
+            checksum: xxh3_64(input),
+        }
+    }
+
+    #[inline]
+    fn verify(&self, input: &[u8]) -> bool {
+        // the length is checked first because it's much cheaper than the checksum
+        self.len == input.len() && self.checksum == xxh3_64(input)
+    }
+}
+
+#[cfg(any(test, feature = "rkyv"))]
+impl ArchivedInputBinding {
+    #[inline]
+    pub(super) fn verify(&self, input: &[u8]) -> bool {
+        self.len as usize == input.len() && self.checksum == xxh3_64(input)
+    }
+}
+
+impl<'input> Document<'input> {
+    /**
+    Take the offsets from this document, bound to its input.
+
+    The offsets can be attached to the same input again using [`Offsets::attach_verified`].
+    */
+    #[inline]
+    pub fn into_bound_offsets(self) -> Offsets {
+        let mut offsets = self.offsets.into_owned();
+        offsets.bind(self.input);
+
+        offsets
+    }
+}
+
+impl Offsets {
+    /**
+    Bind these offsets to the input they were scanned from.
+
+    This records the length and a checksum of the input, which are verified by
+    [`Offsets::attach_verified`].
+    */
+    #[inline]
+    pub(crate) fn bind(&mut self, input: &[u8]) {
+        // the binding is boxed so it doesn't grow the offsets of documents that don't need it
+        self.binding = Some(Box::new(InputBinding::new(input)));
+    }
+
+    /**
+    Whether or not these offsets are bound to an input.
+    */
+    #[inline]
+    pub fn is_bound(&self) -> bool {
+        self.binding.is_some()
+    }
+
+    /**
+    Build a document from these offsets and an input buffer, verifying that the
+    input matches the one they were bound to.
+
+    If the offsets aren't bound to an input, or the input doesn't match, then `None` is returned.
+    This catches offsets being attached to the wrong input by mistake, which
+    [`Offsets::to_document_unchecked`] doesn't.
+
+    # Safety
+
+    The input is only compared with the one the offsets were bound to by its length and checksum.
+    Callers must ensure the input isn't a different one crafted to have the same length and checksum.
+    */
+    #[inline]
+    pub unsafe fn attach_verified<'a>(&'a self, input: &'a [u8]) -> Option<Document<'a>> {
+        match self.binding {
+            // SAFETY: the input has the same length and checksum as the one that produced the offsets
+            // SAFETY: the caller ensures the input isn't a collision crafted to match them
+            Some(ref binding) if binding.verify(input) => Some(self.to_document_unchecked(input)),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,270 @@
+/*!
+A cache of offsets for inputs that are scanned repeatedly.
+
+Offsets never change for the same input, so an input that's scanned over and over
+can have its offsets cached and re-attached instead. Entries are keyed by the xxh3 checksum
+of their input, and keep a copy of it so a hit can be compared byte-for-byte before its offsets
+are attached. A checksum alone can be collided on purpose. A hit only needs to hash and compare
+the input once, which is much cheaper than scanning it again.
+
+Entries are kept in a list from most to least recently used, so they can be moved to the
+front of the list when they're hit, and evicted from the back of it, without searching.
+*/
+
+use std::{
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+};
+
+use xxhash_rust::xxh3::xxh3_64;
+
+use super::{Document, Offsets};
+
+/**
+A size-bounded cache of offsets, keyed by a hash of their input.
+
+When the cache is full, the least recently used entries are evicted.
+*/
+#[derive(Debug, Clone)]
+pub struct OffsetsCache {
+    index: HashMap<u64, usize, BuildHasherDefault<KeyHasher>>,
+    entries: Vec<CacheEntry>,
+    // the most recently used entry
+    head: Option<usize>,
+    // the least recently used entry
+    tail: Option<usize>,
+    max_size: usize,
+    size: usize,
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    key: u64,
+    input: Box<[u8]>,
+    offsets: Offsets,
+    // the next most recently used entry
+    prev: Option<usize>,
+    // the next least recently used entry
+    next: Option<usize>,
+}
+
+impl CacheEntry {
+    #[inline]
+    fn size(&self) -> usize {
+        self.input.len() + self.offsets.approximate_size()
+    }
+}
+
+impl OffsetsCache {
+    /**
+    Create a cache that holds up to `max_size` bytes of inputs and their offsets.
+    */
+    #[inline]
+    pub fn new(max_size: usize) -> Self {
+        OffsetsCache {
+            index: HashMap::default(),
+            entries: Vec::new(),
+            head: None,
+            tail: None,
+            max_size,
+            size: 0,
+        }
+    }
+
+    /**
+    The number of inputs with cached offsets.
+    */
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /**
+    Whether or not the cache is empty.
+    */
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /**
+    The approximate number of bytes used by the inputs and offsets in the cache.
+    */
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /**
+    Remove all entries from the cache.
+    */
+    #[inline]
+    pub fn clear(&mut self) {
+        self.index.clear();
+        self.entries.clear();
+        self.head = None;
+        self.tail = None;
+        self.size = 0;
+    }
+
+    /**
+    Attach the cached offsets for an input, or scan it and cache its offsets.
+
+    Erroneous documents and documents that are too large to fit in the cache aren't cached.
+    This method has the same guarantees as [`Document::scan_trusted`].
+    */
+    pub fn scan_or_attach<'a>(&'a mut self, input: &'a [u8]) -> Document<'a> {
+        let key = xxh3_64(input);
+
+        let entry = match self.index.get(&key).copied() {
+            Some(entry) if *self.entries[entry].input == *input => {
+                self.touch(entry);
+                entry
+            }
+            collision => {
+                let document = Document::scan_trusted(input);
+
+                if document.is_err()
+                    || input.len() + document.offsets().approximate_size() > self.max_size
+                {
+                    return document;
+                }
+
+                // a different input with the same hash is replaced
+                if let Some(collision) = collision {
+                    self.remove(collision);
+                }
+
+                self.insert(key, input.into(), document.offsets.into_owned())
+            }
+        };
+
+        // SAFETY: the input is the same as the one that produced the offsets
+        unsafe { self.entries[entry].offsets.to_document_unchecked(input) }
+    }
+
+    /**
+    Add an entry to the front of the list, evicting entries from the back to make room for it.
+    */
+    fn insert(&mut self, key: u64, input: Box<[u8]>, offsets: Offsets) -> usize {
+        let entry = CacheEntry {
+            key,
+            input,
+            offsets,
+            prev: None,
+            next: None,
+        };
+
+        let needed = entry.size();
+        while self.size + needed > self.max_size {
+            match self.tail {
+                Some(lru) => self.remove(lru),
+                None => break,
+            }
+        }
+
+        let index = self.entries.len();
+
+        self.size += needed;
+        self.entries.push(entry);
+        self.index.insert(key, index);
+        self.push_front(index);
+
+        index
+    }
+
+    /**
+    Remove an entry from the cache.
+
+    The last entry is moved into its place, so any indexes into the entries that point
+    to it are updated.
+    */
+    fn remove(&mut self, index: usize) {
+        self.unlink(index);
+
+        let removed = self.entries.swap_remove(index);
+        self.index.remove(&removed.key);
+        self.size -= removed.size();
+
+        if index < self.entries.len() {
+            let moved = &self.entries[index];
+            let (key, prev, next) = (moved.key, moved.prev, moved.next);
+
+            self.index.insert(key, index);
+
+            match prev {
+                Some(prev) => self.entries[prev].next = Some(index),
+                None => self.head = Some(index),
+            }
+
+            match next {
+                Some(next) => self.entries[next].prev = Some(index),
+                None => self.tail = Some(index),
+            }
+        }
+    }
+
+    /**
+    Move an entry to the front of the list.
+    */
+    #[inline]
+    fn touch(&mut self, index: usize) {
+        if self.head != Some(index) {
+            self.unlink(index);
+            self.push_front(index);
+        }
+    }
+
+    #[inline]
+    fn push_front(&mut self, index: usize) {
+        let head = self.head.replace(index);
+
+        self.entries[index].prev = None;
+        self.entries[index].next = head;
+
+        match head {
+            Some(head) => self.entries[head].prev = Some(index),
+            None => self.tail = Some(index),
+        }
+    }
+
+    #[inline]
+    fn unlink(&mut self, index: usize) {
+        let (prev, next) = (self.entries[index].prev, self.entries[index].next);
+
+        match prev {
+            Some(prev) => self.entries[prev].next = next,
+            None => self.head = next,
+        }
+
+        match next {
+            Some(next) => self.entries[next].prev = prev,
+            None => self.tail = prev,
+        }
+    }
+}
+
+/**
+A hasher for keys that are already hashes of their input.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+struct KeyHasher(u64);
+
+impl Hasher for KeyHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0 << 8) | *b as u64;
+        }
+    }
+
+    #[inline]
+    fn write_u64(&mut self, key: u64) {
+        self.0 = key;
+    }
+}
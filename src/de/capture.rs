@@ -0,0 +1,114 @@
+/*!
+Capture the values of keys at the root of a document while it's being scanned.
+
+Some decisions about a document, like whether to drop or forward it, only depend on a few of
+the keys at its root. Instead of waiting for the whole document to be scanned, a callback is
+invoked for those keys as soon as their values have been scanned, while the rest of the document
+still hasn't been looked at.
+
+Captures only cost anything for documents scanned with [`ScanOptions::capture`]. Otherwise the
+scanner only checks whether any keys are being captured at each `,`.
+*/
+
+use std::fmt;
+
+use super::{Document, Kind, Offsets, Scan, ScanFnInput};
+
+type CaptureFn = dyn Fn(&str, Kind) + Send + Sync;
+
+/**
+A set of keys at the root of a document to capture, along with the callback to invoke for them.
+*/
+pub(super) struct FieldCapture {
+    keys: Box<[Box<str>]>,
+    f: Box<CaptureFn>,
+}
+
+impl fmt::Debug for FieldCapture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FieldCapture")
+            .field("keys", &self.keys)
+            .finish()
+    }
+}
+
+impl FieldCapture {
+    pub(super) fn new(
+        keys: impl IntoIterator<Item = impl Into<String>>,
+        f: impl Fn(&str, Kind) + Send + Sync + 'static,
+    ) -> Self {
+        FieldCapture {
+            keys: keys
+                .into_iter()
+                .map(|key| key.into().into_boxed_str())
+                .collect(),
+            f: Box::new(f),
+        }
+    }
+}
+
+impl<'a, 'scan, const FLAT: bool> ScanFnInput<'a, 'scan, FLAT> {
+    /**
+    Whether the value of an entry in the root map has just been finished, and needs to be
+    checked against the keys being captured.
+    */
+    #[inline(always)]
+    pub(super) fn captures_root_value(&self) -> bool {
+        self.scan.capture.is_some() && self.scan.stack.is_root()
+    }
+
+    /**
+    Invoke the capture callback for the last entry in the root map, if its key is being captured.
+    */
+    #[inline(never)]
+    pub(super) fn capture_root_value(&self) {
+        capture_last_entry(self.input, self.scan, self.offsets);
+    }
+}
+
+/**
+Invoke the capture callback for the last entry in the root map, if its key is being captured.
+
+Nothing is captured if the input is already erroneous, because its offsets may be nonsense.
+*/
+pub(super) fn capture_last_entry(input: &[u8], scan: &Scan, offsets: &Offsets) {
+    let capture = match scan.capture {
+        Some(ref capture) => capture,
+        None => return,
+    };
+
+    let root = &scan.stack.active_map_arr;
+
+    // the entry must be in the root map, and have both a key and a value
+    if scan.error
+        || !scan.stack.is_root()
+        || root.len == 0
+        || root.len & 1 == 1
+        || offsets.elements.len() > Document::MAX_ELEMENTS
+    {
+        return;
+    }
+
+    let (key_offset, value_offset) = match root.prev_part_offsets {
+        [_, Some(key), Some(value), _] => (key, value),
+        _ => return,
+    };
+
+    let key = match offsets.elements.get(key_offset as usize) {
+        Some(key) => match key.to_str(input) {
+            Some(key) => key,
+            None => return,
+        },
+        None => return,
+    };
+
+    if let Some(name) = capture.keys.iter().find(|name| key.eq_unescaped(name)) {
+        let value = get_unchecked!(offsets.elements, value_offset as usize).to_element(
+            input,
+            offsets,
+            value_offset,
+        );
+
+        (capture.f)(name, value);
+    }
+}
@@ -0,0 +1,147 @@
+/*!
+A total order over the elements of a document.
+
+Elements are compared straight from their views in the document, so they don't need to be
+converted into another representation first. The order is:
+
+1. `null`
+2. booleans, with `false` before `true`
+3. numbers, compared numerically
+4. strings, compared by their unescaped bytes
+5. arrays, compared element by element, then by length
+6. maps, compared entry by entry using keys then values, then by length
+*/
+
+use std::cmp::Ordering;
+
+use super::{Elems, Entries, Kind, Str};
+
+impl<'input, 'offsets> Kind<'input, 'offsets> {
+    /**
+    Compare this element with another.
+
+    Elements of different kinds are ordered by their kind, and elements of the same kind
+    are ordered by their values. See the [module docs](self) for details.
+    Numbers that are equal numerically, like `1` and `1.0`, compare as equal.
+    */
+    pub fn json_cmp(&self, other: &Kind) -> Ordering {
+        let mut stack = Vec::new();
+        let mut ord = cmp_begin(&mut stack, self, other);
+
+        // maps and arrays are compared using an explicit stack of the elements left in each
+        while ord == Ordering::Equal {
+            let frame = match stack.last_mut() {
+                Some(frame) => frame,
+                None => break,
+            };
+
+            ord = match frame {
+                Frame::Arr(a, b) => match (a.next(), b.next()) {
+                    (Some(a), Some(b)) => cmp_begin(&mut stack, &a, &b),
+                    (a, b) => {
+                        stack.pop();
+                        a.is_some().cmp(&b.is_some())
+                    }
+                },
+                Frame::Map(a, b) => match (a.next(), b.next()) {
+                    (Some((ak, av)), Some((bk, bv))) => match str_cmp(&ak, &bk) {
+                        Ordering::Equal => cmp_begin(&mut stack, &av, &bv),
+                        ord => ord,
+                    },
+                    (a, b) => {
+                        stack.pop();
+                        a.is_some().cmp(&b.is_some())
+                    }
+                },
+            };
+        }
+
+        ord
+    }
+}
+
+enum Frame<'a, 'b, 'c, 'd> {
+    Arr(Elems<'a, 'b>, Elems<'c, 'd>),
+    Map(Entries<'a, 'b>, Entries<'c, 'd>),
+}
+
+/**
+Compare two elements, or begin comparing their contents if they're both maps or arrays.
+*/
+fn cmp_begin<'a, 'b, 'c, 'd>(
+    stack: &mut Vec<Frame<'a, 'b, 'c, 'd>>,
+    a: &Kind<'a, 'b>,
+    b: &Kind<'c, 'd>,
+) -> Ordering {
+    match (a, b) {
+        // lazy maps and arrays are scanned so they compare the same as if they'd been scanned up-front
+        // the scanned document doesn't contain any lazy values itself, so this only nests once per side
+        (Kind::Lazy(lazy), _) => match lazy.scan() {
+            Some(a) => a.as_kind().json_cmp(b),
+            None => rank(a).cmp(&rank(b)),
+        },
+        (_, Kind::Lazy(lazy)) => match lazy.scan() {
+            Some(b) => a.json_cmp(&b.as_kind()),
+            None => rank(a).cmp(&rank(b)),
+        },
+        (Kind::Bool(a), Kind::Bool(b)) => a.cmp(b),
+        (Kind::Num(a), Kind::Num(b)) => num_cmp(a, b),
+        (Kind::Str(a), Kind::Str(b)) => str_cmp(a, b),
+        (Kind::Arr(a), Kind::Arr(b)) => {
+            stack.push(Frame::Arr(a.iter_elems(), b.iter_elems()));
+            Ordering::Equal
+        }
+        (Kind::Map(a), Kind::Map(b)) => {
+            stack.push(Frame::Map(a.iter_entries(), b.iter_entries()));
+            Ordering::Equal
+        }
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[inline]
+fn str_cmp(a: &Str, b: &Str) -> Ordering {
+    if !a.1 && !b.1 {
+        a.as_raw().cmp(b.as_raw())
+    } else {
+        a.to_unescaped().cmp(&b.to_unescaped())
+    }
+}
+
+#[inline]
+fn rank(kind: &Kind) -> u8 {
+    match kind {
+        Kind::Null => 0,
+        Kind::Bool(_) => 1,
+        Kind::Num(_) => 2,
+        Kind::Str(_) => 3,
+        Kind::Arr(_) => 4,
+        Kind::Map(_) => 5,
+        Kind::Lazy(lazy) if lazy.map => 5,
+        Kind::Lazy(_) => 4,
+    }
+}
+
+/**
+Compare two numbers.
+
+Integers are compared exactly, and anything else is compared as a float.
+Numbers that can't be parsed are ordered after any that can.
+*/
+fn num_cmp(a: &str, b: &str) -> Ordering {
+    let is_int = |n: &str| !n.bytes().any(|b| matches!(b, b'.' | b'e' | b'E'));
+
+    if is_int(a) && is_int(b) {
+        if let (Ok(a), Ok(b)) = (a.parse::<i128>(), b.parse::<i128>()) {
+            return a.cmp(&b);
+        }
+    }
+
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        // `-0.0` and `0.0` are equal numerically, even though they're not equal bitwise
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or_else(|| a.total_cmp(&b)),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
@@ -0,0 +1,211 @@
+/*!
+A cursor for navigating up and down through a document.
+
+Offsets only point forwards to their next sibling, so the cursor keeps the path of
+ancestors it took to reach the current element. That way parents can be revisited
+without needing to store a link back to them in every offset.
+*/
+
+use super::{Document, ElementId, Kind, OffsetKind, RawPosition, Str};
+
+/**
+A cursor over the elements of a document.
+
+The cursor begins at the root map and can move down into children,
+across to siblings, and back up to parents.
+*/
+#[derive(Debug, Clone)]
+pub struct Cursor<'input, 'offsets> {
+    document: &'offsets Document<'input>,
+    /**
+    The offset of the current element, or `None` for the root map.
+    */
+    current: Option<u16>,
+    ancestors: Vec<Option<u16>>,
+}
+
+impl<'input> Document<'input> {
+    /**
+    Get a cursor positioned at the root map of the document.
+    */
+    #[inline]
+    pub fn cursor<'brw>(&'brw self) -> Cursor<'input, 'brw> {
+        Cursor {
+            document: self,
+            current: None,
+            ancestors: Vec::new(),
+        }
+    }
+}
+
+impl<'input, 'offsets> Cursor<'input, 'offsets> {
+    /**
+    The element the cursor is positioned at.
+    */
+    #[inline]
+    pub fn kind(&self) -> Kind<'input, 'offsets> {
+        match self.current {
+            Some(current) => get_unchecked!(self.document.offsets.elements, current as usize)
+                .to_element(self.document.input, &self.document.offsets, current),
+            None => Kind::Map(self.document.as_map()),
+        }
+    }
+
+    /**
+    The key of the element the cursor is positioned at, if its parent is a map.
+    */
+    #[inline]
+    pub fn key(&self) -> Option<Str<'input>> {
+        match (self.current, self.ancestors.last()) {
+            (Some(current), Some(parent)) if self.is_map(*parent) => {
+                // keys always immediately precede their values
+                get_unchecked!(self.document.offsets.elements, current as usize - 1)
+                    .to_str(self.document.input)
+            }
+            _ => None,
+        }
+    }
+
+    /**
+    The id of the element the cursor is positioned at.
+
+    The root map doesn't have an id.
+    */
+    #[inline]
+    pub fn id(&self) -> Option<ElementId> {
+        self.current.map(ElementId)
+    }
+
+    /**
+    The position of the element the cursor is positioned at within its parent map or array.
+
+    The root map doesn't have a position.
+    */
+    #[inline]
+    pub fn position(&self) -> Option<RawPosition> {
+        self.current.and_then(|current| {
+            RawPosition::from_part(
+                get_unchecked!(self.document.offsets.elements, current as usize).position,
+            )
+        })
+    }
+
+    /**
+    The number of ancestors between the element the cursor is positioned at and the root.
+    */
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.ancestors.len()
+    }
+
+    /**
+    Whether or not the cursor is positioned at the root map.
+    */
+    #[inline]
+    pub fn is_root(&self) -> bool {
+        self.current.is_none()
+    }
+
+    /**
+    Move to the first child of the current element.
+
+    If the current element isn't a map or array, or is empty, then the cursor doesn't
+    move and this method returns `false`.
+    */
+    #[inline]
+    pub fn to_first_child(&mut self) -> bool {
+        let first = match self.current {
+            None => self
+                .document
+                .as_map()
+                .first_part_offset()
+                .and_then(|first| self.value_after_key(first)),
+            Some(current) => {
+                match get_unchecked!(self.document.offsets.elements, current as usize).kind {
+                    OffsetKind::Map(parts, _) if parts >> 1 > 0 => {
+                        self.value_after_key(current + 1)
+                    }
+                    OffsetKind::Arr(len, _) if len > 0 => Some(current + 1),
+                    _ => None,
+                }
+            }
+        };
+
+        if let Some(first) = first {
+            self.ancestors.push(self.current.replace(first));
+            true
+        } else {
+            false
+        }
+    }
+
+    /**
+    Move to the next sibling of the current element.
+
+    If the current element is the last one in its parent then the cursor doesn't
+    move and this method returns `false`.
+    */
+    #[inline]
+    pub fn to_next_sibling(&mut self) -> bool {
+        let (current, parent) = match (self.current, self.ancestors.last()) {
+            (Some(current), Some(parent)) => (current, *parent),
+            _ => return false,
+        };
+
+        let next = match get_unchecked!(self.document.offsets.elements, current as usize).next {
+            // the next value in a map is the one after the next key
+            Some(next) if self.is_map(parent) => self.value_after_key(next - 1),
+            next => next,
+        };
+
+        if let Some(next) = next {
+            self.current = Some(next);
+            true
+        } else {
+            false
+        }
+    }
+
+    /**
+    Move to the parent of the current element.
+
+    If the cursor is positioned at the root then it doesn't move and this method returns `false`.
+    */
+    #[inline]
+    pub fn to_parent(&mut self) -> bool {
+        if let Some(parent) = self.ancestors.pop() {
+            self.current = parent;
+            true
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    fn is_map(&self, offset: Option<u16>) -> bool {
+        match offset {
+            None => true,
+            Some(offset) => matches!(
+                get_unchecked!(self.document.offsets.elements, offset as usize).kind,
+                OffsetKind::Map(..)
+            ),
+        }
+    }
+
+    /**
+    Get the value for a key, as long as the key is a string.
+
+    This matches the way entries are iterated, which stops at the first key that isn't a string.
+    */
+    #[inline]
+    fn value_after_key(&self, key: u16) -> Option<u16> {
+        let elements = &self.document.offsets.elements;
+
+        match (elements.get(key as usize), elements.get(key as usize + 1)) {
+            (Some(key_offset), Some(_)) if matches!(key_offset.kind, OffsetKind::Str(..)) => {
+                Some(key + 1)
+            }
+            _ => None,
+        }
+    }
+}
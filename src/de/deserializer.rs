@@ -0,0 +1,512 @@
+/*!
+A `serde` deserializer for documents.
+
+Elements are deserialized directly from the offsets in a document without going
+through an intermediate value. Strings that aren't escaped are borrowed from the
+input, and strings that are escaped are unescaped on-demand.
+
+The deserializer only ever holds the single element it's currently visiting, so it can be
+streamed into any serializer using something like `serde_transcode`. Documents can also
+be serialized directly, which does the same thing.
+
+The document has already been scanned, so deserializing isn't recursive in the
+parser, only in the visitors driving it.
+
+When a visitor only needs a string by reference, like when matching the fields of a struct,
+escaped strings are unescaped into a reusable per-thread scratch buffer instead of allocating.
+*/
+
+use serde::de::{
+    self, value::BorrowedStrDeserializer, value::Error, value::StringDeserializer, DeserializeSeed,
+    IntoDeserializer, Unexpected, Visitor,
+};
+
+use std::{borrow::Cow, cell::RefCell};
+
+use super::{Arr, Document, Elems, Entries, Kind, Lazy, Map, NestedDocument, Str};
+
+use crate::unescape::unescape_trusted_into;
+
+/**
+Escaped strings larger than this are unescaped into their own allocation instead of the scratch buffer.
+
+This stops a single very large string from keeping its buffer alive for the rest of the thread.
+*/
+const MAX_SCRATCH_LEN: usize = 16 * 1024;
+
+thread_local! {
+    static SCRATCH: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/**
+A `serde` deserializer for an element within a document.
+*/
+#[derive(Debug, Clone)]
+pub struct Deserializer<'input, 'offsets> {
+    kind: Kind<'input, 'offsets>,
+}
+
+impl<'input, 'offsets> Deserializer<'input, 'offsets> {
+    /**
+    Deserialize the root map of a document.
+    */
+    #[inline]
+    pub fn from_document(document: &'offsets Document<'input>) -> Self {
+        Deserializer::from_kind(Kind::Map(document.as_map()))
+    }
+
+    /**
+    Deserialize an individual element.
+    */
+    #[inline]
+    pub fn from_kind(kind: Kind<'input, 'offsets>) -> Self {
+        Deserializer { kind }
+    }
+}
+
+impl<'input> Document<'input> {
+    /**
+    Deserialize the document into a value.
+
+    Strings that aren't escaped can be borrowed from the input.
+    */
+    #[inline]
+    pub fn deserialize<T>(&self) -> Result<T, Error>
+    where
+        T: de::Deserialize<'input>,
+    {
+        T::deserialize(Deserializer::from_document(self))
+    }
+}
+
+impl<'input, 'offsets> Map<'input, 'offsets> {
+    /**
+    Deserialize the value of the first entry with the given key using a seed.
+
+    If there's no entry with the key then this method will return `Ok(None)`.
+    */
+    #[inline]
+    pub fn deserialize_entry_seed<S>(&self, key: &str, seed: S) -> Result<Option<S::Value>, Error>
+    where
+        S: DeserializeSeed<'input>,
+    {
+        for (k, v) in self.iter_entries() {
+            if k.to_unescaped() == key {
+                return seed.deserialize(Deserializer::from_kind(v)).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl<'input, 'offsets> Arr<'input, 'offsets> {
+    /**
+    Iterate through elements in the array, deserializing each one using a seed.
+
+    The seed is cloned for each element, so it can carry state like a reusable
+    buffer between them.
+    */
+    #[inline]
+    pub fn iter_seed<'brw, S>(
+        &'brw self,
+        seed: S,
+    ) -> impl Iterator<Item = Result<S::Value, Error>> + 'brw
+    where
+        S: DeserializeSeed<'input> + Clone + 'brw,
+    {
+        self.iter_elems()
+            .map(move |elem| seed.clone().deserialize(Deserializer::from_kind(elem)))
+    }
+}
+
+impl<'input, 'offsets> IntoDeserializer<'input, Error> for Deserializer<'input, 'offsets> {
+    type Deserializer = Self;
+
+    #[inline]
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'input, 'offsets> IntoDeserializer<'input, Error> for &'offsets Document<'input> {
+    type Deserializer = Deserializer<'input, 'offsets>;
+
+    #[inline]
+    fn into_deserializer(self) -> Self::Deserializer {
+        Deserializer::from_document(self)
+    }
+}
+
+impl<'input, 'offsets> IntoDeserializer<'input, Error> for Kind<'input, 'offsets> {
+    type Deserializer = Deserializer<'input, 'offsets>;
+
+    #[inline]
+    fn into_deserializer(self) -> Self::Deserializer {
+        Deserializer::from_kind(self)
+    }
+}
+
+impl<'input, 'offsets> de::Deserializer<'input> for Deserializer<'input, 'offsets> {
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'input>,
+    {
+        match self.kind {
+            Kind::Str(s) => visit_str(s, visitor),
+            Kind::Num(n) => visit_num(n, visitor),
+            Kind::Bool(b) => visitor.visit_bool(b),
+            Kind::Null => visitor.visit_unit(),
+            Kind::Map(map) => visitor.visit_map(MapAccess {
+                size_hint: map.len(),
+                entries: map.iter_entries(),
+                value: None,
+            }),
+            Kind::Arr(arr) => visitor.visit_seq(SeqAccess {
+                size_hint: arr.len(),
+                elems: arr.iter_elems(),
+            }),
+            Kind::Lazy(lazy) => {
+                let nested = scan_lazy(lazy)?;
+
+                Deserializer::from_kind(nested.as_kind()).deserialize_any(visitor)
+            }
+        }
+    }
+
+    #[inline]
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'input>,
+    {
+        match self.kind {
+            Kind::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'input>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    #[inline]
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'input>,
+    {
+        match self.kind {
+            // unit variants are strings
+            Kind::Str(s) => match s.to_unescaped() {
+                Cow::Borrowed(s) => visitor.visit_enum(BorrowedStrDeserializer::new(s)),
+                Cow::Owned(s) => visitor.visit_enum(StringDeserializer::new(s)),
+            },
+            // other variants are maps with a single entry
+            Kind::Map(map) => {
+                let mut entries = map.iter_entries();
+
+                match (entries.next(), entries.next()) {
+                    (Some((variant, value)), None) => {
+                        visitor.visit_enum(EnumAccess { variant, value })
+                    }
+                    _ => Err(de::Error::invalid_value(
+                        Unexpected::Map,
+                        &"a map with a single entry",
+                    )),
+                }
+            }
+            Kind::Lazy(lazy) => {
+                let nested = scan_lazy(lazy)?;
+
+                Deserializer::from_kind(nested.as_kind()).deserialize_enum(name, variants, visitor)
+            }
+            _ => Err(de::Error::invalid_type(
+                self.unexpected(),
+                &"a string or map",
+            )),
+        }
+    }
+
+    #[inline]
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'input>,
+    {
+        match self.kind {
+            Kind::Str(s) => visit_str_scratch(s, visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    #[inline]
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'input>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    #[inline]
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'input>,
+    {
+        // the element has already been scanned, so there's nothing to skip over
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        <W: Visitor<'input>>
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+    }
+}
+
+impl<'input, 'offsets> Deserializer<'input, 'offsets> {
+    fn unexpected(&self) -> Unexpected<'input> {
+        match self.kind {
+            Kind::Str(s) => Unexpected::Str(s.as_raw()),
+            Kind::Num(_) => Unexpected::Other("number"),
+            Kind::Bool(b) => Unexpected::Bool(b),
+            Kind::Null => Unexpected::Unit,
+            Kind::Map(_) => Unexpected::Map,
+            Kind::Arr(_) => Unexpected::Seq,
+            Kind::Lazy(lazy) if lazy.map => Unexpected::Map,
+            Kind::Lazy(_) => Unexpected::Seq,
+        }
+    }
+}
+
+/**
+Scan a map or array that was kept as a raw span so it can be visited.
+*/
+#[cold]
+fn scan_lazy(lazy: Lazy) -> Result<NestedDocument, Error> {
+    lazy.scan()
+        .ok_or_else(|| de::Error::custom("the map or array is too large to scan"))
+}
+
+#[inline]
+fn visit_str<'input, V>(s: Str<'input>, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'input>,
+{
+    match s.to_unescaped() {
+        Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+        Cow::Owned(s) => visitor.visit_string(s),
+    }
+}
+
+/**
+Visit a string that's only needed by reference.
+
+Strings that aren't escaped are borrowed from the input. Strings that are escaped are
+unescaped into the scratch buffer, unless it's already in use further up the stack.
+*/
+#[inline]
+fn visit_str_scratch<'input, V>(s: Str<'input>, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'input>,
+{
+    if !s.1 {
+        return visitor.visit_borrowed_str(s.as_raw());
+    }
+
+    if s.as_raw().len() > MAX_SCRATCH_LEN {
+        return visitor.visit_str(&s.to_unescaped());
+    }
+
+    SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+        Ok(mut scratch) => {
+            scratch.clear();
+
+            // SAFETY: The string to unescape was parsed from JSON
+            // So it can't end with an unescaped `\`
+            unsafe { unescape_trusted_into(s.as_raw(), &mut scratch) };
+
+            visitor.visit_str(&scratch)
+        }
+        Err(_) => visitor.visit_str(&s.to_unescaped()),
+    })
+}
+
+#[inline]
+fn visit_num<'input, V>(n: &'input str, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'input>,
+{
+    let n = n.trim();
+
+    match parse_num(n) {
+        Some(Num::U64(n)) => visitor.visit_u64(n),
+        Some(Num::I64(n)) => visitor.visit_i64(n),
+        Some(Num::F64(n)) => visitor.visit_f64(n),
+        None => Err(de::Error::invalid_value(Unexpected::Str(n), &"a number")),
+    }
+}
+
+/**
+A number parsed from a document.
+*/
+pub(super) enum Num {
+    U64(u64),
+    I64(i64),
+    F64(f64),
+}
+
+#[inline]
+pub(super) fn parse_num(n: &str) -> Option<Num> {
+    // integers that don't fit in 64 bits are treated as floats
+    if !n.bytes().any(|b| matches!(b, b'.' | b'e' | b'E')) {
+        if n.starts_with('-') {
+            if let Ok(n) = n.parse() {
+                return Some(Num::I64(n));
+            }
+        } else if let Ok(n) = n.parse() {
+            return Some(Num::U64(n));
+        }
+    }
+
+    n.parse().ok().map(Num::F64)
+}
+
+struct MapAccess<'input, 'offsets> {
+    size_hint: usize,
+    entries: Entries<'input, 'offsets>,
+    value: Option<Kind<'input, 'offsets>>,
+}
+
+impl<'input, 'offsets> de::MapAccess<'input> for MapAccess<'input, 'offsets> {
+    type Error = Error;
+
+    #[inline]
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'input>,
+    {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+
+                seed.deserialize(Deserializer::from_kind(Kind::Str(key)))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'input>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(Deserializer::from_kind(value)),
+            None => Err(de::Error::custom(
+                "attempt to deserialize a value without a key",
+            )),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.size_hint)
+    }
+}
+
+struct SeqAccess<'input, 'offsets> {
+    size_hint: usize,
+    elems: Elems<'input, 'offsets>,
+}
+
+impl<'input, 'offsets> de::SeqAccess<'input> for SeqAccess<'input, 'offsets> {
+    type Error = Error;
+
+    #[inline]
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'input>,
+    {
+        match self.elems.next() {
+            Some(elem) => seed.deserialize(Deserializer::from_kind(elem)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.size_hint)
+    }
+}
+
+struct EnumAccess<'input, 'offsets> {
+    variant: Str<'input>,
+    value: Kind<'input, 'offsets>,
+}
+
+impl<'input, 'offsets> de::EnumAccess<'input> for EnumAccess<'input, 'offsets> {
+    type Error = Error;
+    type Variant = Deserializer<'input, 'offsets>;
+
+    #[inline]
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'input>,
+    {
+        let variant = seed.deserialize(Deserializer::from_kind(Kind::Str(self.variant)))?;
+
+        Ok((variant, Deserializer::from_kind(self.value)))
+    }
+}
+
+impl<'input, 'offsets> de::VariantAccess<'input> for Deserializer<'input, 'offsets> {
+    type Error = Error;
+
+    #[inline]
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    #[inline]
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'input>,
+    {
+        seed.deserialize(self)
+    }
+
+    #[inline]
+    fn tuple_variant<V>(self, _: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'input>,
+    {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    #[inline]
+    fn struct_variant<V>(
+        self,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'input>,
+    {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
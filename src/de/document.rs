@@ -1,8 +1,17 @@
-use std::{borrow::Cow, fmt, str};
+use std::{
+    borrow::Cow,
+    fmt, io,
+    ops::{Deref, Range},
+    str,
+};
 
-use super::{Offset, OffsetKind, Offsets, Slice};
+use super::{Lazy, Offset, OffsetKind, Offsets, RawPosition, Slice, NO_PREV};
 
-use crate::{de::ActiveMapArr, unescape::unescape_trusted};
+use crate::{
+    de::ActiveMapArr,
+    std_ext::char::try_from_utf16_surrogate_pair,
+    unescape::{chunk_end, hex4, unescape_trusted, unescape_trusted_into, UnescapeScratch},
+};
 
 /**
 A JSON document that's borrowed from an input buffer.
@@ -17,6 +26,10 @@ pub struct Document<'input> {
     pub(super) input: &'input [u8],
     pub(super) offsets: Cow<'input, Offsets>,
     pub(super) _detached_stack: Vec<ActiveMapArr>,
+    /**
+    The offset of a nested map to treat as the root, or `None` for the scanned root.
+    */
+    pub(super) root: Option<u16>,
 }
 
 impl<'input> fmt::Debug for Document<'input> {
@@ -50,10 +63,10 @@ impl<'input> fmt::Debug for Document<'input> {
                         OffsetKind::Num(n) => {
                             list.entry(&(n.as_str(self.0.input), offset.position, i, offset.next));
                         }
-                        OffsetKind::Map(any) => {
+                        OffsetKind::Map(any, _) => {
                             list.entry(&(Map, any, offset.position, i, offset.next));
                         }
-                        OffsetKind::Arr(any) => {
+                        OffsetKind::Arr(any, _) => {
                             list.entry(&(Arr, any, offset.position, i, offset.next));
                         }
                         OffsetKind::Bool(b) => {
@@ -62,6 +75,14 @@ impl<'input> fmt::Debug for Document<'input> {
                         OffsetKind::Null => {
                             list.entry(&(Null, offset.position, i, offset.next));
                         }
+                        OffsetKind::Lazy(raw, _) => {
+                            list.entry(&(
+                                raw.as_str(self.0.input),
+                                offset.position,
+                                i,
+                                offset.next,
+                            ));
+                        }
                     }
                 }
 
@@ -79,8 +100,12 @@ impl<'input> fmt::Debug for Document<'input> {
 
 /**
 The kind of an element within a document.
+
+New kinds may be added in the future, like [`Kind::Lazy`] was, so matches on them
+outside of this crate need a wildcard arm.
 */
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum Kind<'input, 'offsets> {
     Str(Str<'input>),
     Num(&'input str),
@@ -88,9 +113,30 @@ pub enum Kind<'input, 'offsets> {
     Null,
     Map(Map<'input, 'offsets>),
     Arr(Arr<'input, 'offsets>),
+    /**
+    A map or array that was kept as a raw span instead of being scanned.
+
+    This is only produced when scanning with [`ScanOptions::lazy_depth`](super::ScanOptions::lazy_depth).
+    Queries and walks treat it as a leaf; use [`Kind::scan_nested`] to look inside it.
+    */
+    Lazy(Lazy<'input>),
 }
 
 impl<'input, 'offsets> Kind<'input, 'offsets> {
+    /**
+    Treat the element as a standalone document, if it's a map.
+    */
+    #[inline]
+    pub fn into_document(self) -> Option<Document<'offsets>>
+    where
+        'input: 'offsets,
+    {
+        match self {
+            Kind::Map(map) => Some(map.as_document()),
+            _ => None,
+        }
+    }
+
     pub fn as_str(&self) -> Option<Str<'input>> {
         if let Kind::Str(s) = self {
             Some(*s)
@@ -98,10 +144,44 @@ impl<'input, 'offsets> Kind<'input, 'offsets> {
             None
         }
     }
+
+    /**
+    Returns the raw JSON for this element, exactly as it appears in the input.
+
+    Strings are quoted and escaped, and maps and arrays include all of their contents.
+    */
+    #[inline]
+    pub fn as_json(&self) -> &'input str {
+        match self {
+            Kind::Str(s) => s.as_json(),
+            Kind::Num(n) => n,
+            Kind::Bool(true) => "true",
+            Kind::Bool(false) => "false",
+            Kind::Null => "null",
+            Kind::Map(map) => map.as_json(),
+            Kind::Arr(arr) => arr.as_json(),
+            Kind::Lazy(lazy) => lazy.as_json(),
+        }
+    }
 }
 
+/**
+An opaque handle to an element within a document.
+
+Ids can be used to revisit an element later using [`Document::get_by_id`] without
+walking back through the document. An id is only meaningful for the document
+whose offsets produced it.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ElementId(pub(super) u16);
+
+/**
+A string within a document.
+
+The string is stored with its surrounding quotes so its raw JSON is available.
+*/
 #[derive(Debug, Clone, Copy)]
-pub struct Str<'input>(&'input str, bool);
+pub struct Str<'input>(pub(super) &'input str, pub(super) bool);
 
 /**
 A map within a document.
@@ -109,7 +189,9 @@ A map within a document.
 #[derive(Debug, Clone)]
 pub struct Map<'input, 'offsets> {
     input: &'input [u8],
-    size_hint: u16,
+    raw: Slice,
+    parts: u16,
+    self_offset: Option<u16>,
     start_from_offset: Option<u16>,
     offsets: &'offsets Offsets,
 }
@@ -120,7 +202,8 @@ An array within a document.
 #[derive(Debug, Clone)]
 pub struct Arr<'input, 'offsets> {
     input: &'input [u8],
-    size_hint: u16,
+    raw: Slice,
+    len: u16,
     start_from_offset: Option<u16>,
     offsets: &'offsets Offsets,
 }
@@ -131,17 +214,67 @@ impl<'input> Document<'input> {
     */
     #[inline]
     pub fn as_map<'brw>(&'brw self) -> Map<'input, 'brw> {
-        Map {
-            input: self.input,
-            size_hint: self.offsets.root_size_hint,
-            start_from_offset: if self.offsets.root_size_hint > 0 {
-                Some(0)
-            } else {
-                None
+        match self.root {
+            None => Map {
+                input: self.input,
+                raw: self.offsets.root_raw,
+                parts: self.offsets.root_parts,
+                self_offset: None,
+                start_from_offset: if self.offsets.root_parts >> 1 > 0 {
+                    Some(0)
+                } else {
+                    None
+                },
+                offsets: &self.offsets,
+            },
+            Some(root) => match get_unchecked!(self.offsets.elements, root as usize).to_element(
+                self.input,
+                &self.offsets,
+                root,
+            ) {
+                Kind::Map(map) => map,
+                _ => unreachable!("the root of a document must be a map"),
             },
-            offsets: &self.offsets,
         }
     }
+
+    /**
+    Get an element by its id.
+
+    If the id doesn't point to an element in this document then this method returns `None`.
+    */
+    #[inline]
+    pub fn get_by_id<'brw>(&'brw self, id: ElementId) -> Option<Kind<'input, 'brw>> {
+        self.offsets
+            .elements
+            .get(id.0 as usize)
+            .map(|offset| offset.to_element(self.input, &self.offsets, id.0))
+    }
+
+    /**
+    Get the position of an element within its parent map or array, using an id for it.
+
+    If the id doesn't point to an element in this document then this method returns `None`.
+    */
+    #[inline]
+    pub fn position_of(&self, id: ElementId) -> Option<RawPosition> {
+        self.offsets
+            .elements
+            .get(id.0 as usize)
+            .and_then(|offset| RawPosition::from_part(offset.position))
+    }
+}
+
+impl ElementId {
+    /**
+    The index of the element in the table of offsets.
+
+    This can be used to look up the element with [`Offsets::get`](Offsets::get).
+    */
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
 }
 
 impl<'input> Str<'input> {
@@ -149,7 +282,16 @@ impl<'input> Str<'input> {
     Returns the underlying string, without attempting to unescape it.
     */
     #[inline]
-    pub fn as_raw(&self) -> &str {
+    pub fn as_raw(&self) -> &'input str {
+        // strip the leading and trailing `"`
+        get_unchecked!(self.0, 1..self.0.len() - 1)
+    }
+
+    /**
+    Returns the raw JSON for the string, including its quotes and without attempting to unescape it.
+    */
+    #[inline]
+    pub fn as_json(&self) -> &'input str {
         self.0
     }
 
@@ -163,256 +305,1458 @@ impl<'input> Str<'input> {
         if self.1 {
             // SAFETY: The string to unescape was parsed from JSON
             // So it can't end with an unescaped `\`
-            Cow::Owned(unsafe { unescape_trusted(self.0) })
+            Cow::Owned(unsafe { unescape_trusted(self.as_raw()) })
         } else {
-            Cow::Borrowed(self.0)
+            Cow::Borrowed(self.as_raw())
         }
     }
-}
 
-impl<'input, 'offsets> Map<'input, 'offsets> {
     /**
-    The number of entries in the map, if known.
+    Returns the underlying string, unescaping it into a reusable scratch buffer if it's escaped.
+
+    Strings that aren't escaped are borrowed from the input, like [`Str::to_unescaped`].
+    Escaped strings replace anything previously unescaped into the scratch buffer.
     */
     #[inline]
-    pub fn size_hint(&self) -> usize {
-        self.size_hint as usize
+    pub fn to_unescaped_with<'a>(&self, scratch: &'a mut UnescapeScratch) -> &'a str
+    where
+        'input: 'a,
+    {
+        if self.1 {
+            // SAFETY: The string to unescape was parsed from JSON
+            // So it can't end with an unescaped `\`
+            unsafe { scratch.unescape_trusted(self.as_raw()) }
+        } else {
+            self.as_raw()
+        }
     }
 
     /**
-    Iterate through entries in the map.
+    Write the underlying string to a writer.
+
+    If the string is escaped then it's unescaped in chunks into a single reused buffer,
+    so very large strings don't need to be unescaped into a single allocation.
     */
-    #[inline]
-    pub fn entries<'brw>(
-        &'brw self,
-    ) -> impl Iterator<Item = (Str<'input>, Kind<'input, 'offsets>)> + 'brw {
-        #[derive(Debug)]
-        struct Entries<'brw, 'input, 'offsets> {
-            inner: &'brw Map<'input, 'offsets>,
-            key: Option<&'offsets Offset>,
-            value: Option<(u16, &'offsets Offset)>,
-        }
-
-        impl<'brw, 'input, 'offsets> Iterator for Entries<'brw, 'input, 'offsets> {
-            type Item = (Str<'input>, Kind<'input, 'offsets>);
-
-            #[inline]
-            fn next(&mut self) -> Option<Self::Item> {
-                let key = self.key.take();
-                let value = self.value.take();
-
-                match (key, value) {
-                    (Some(key), Some((value_offset, value))) => {
-                        // the key must be a string
-                        let entry_key = if let Some(key) = key.to_str(self.inner.input) {
-                            key
-                        } else {
-                            return None;
-                        };
+    pub fn write_unescaped(&self, mut write: impl io::Write) -> io::Result<()> {
+        const CHUNK_LEN: usize = 8 * 1024;
 
-                        let entry_value =
-                            value.to_element(self.inner.input, self.inner.offsets, value_offset);
+        let raw = self.as_raw();
 
-                        if let Some(next) = key.next {
-                            self.key =
-                                Some(get_unchecked!(self.inner.offsets.elements, next as usize));
-                        }
+        if !self.1 {
+            return write.write_all(raw.as_bytes());
+        }
 
-                        if let Some(next) = value.next {
-                            self.value = Some((
-                                next,
-                                get_unchecked!(self.inner.offsets.elements, next as usize),
-                            ));
-                        }
+        let mut buf = String::new();
+        let mut start = 0;
 
-                        Some((entry_key, entry_value))
-                    }
-                    _ => None,
-                }
-            }
-        }
+        while start < raw.len() {
+            let end = start + chunk_end(get_unchecked!(raw, start..), CHUNK_LEN);
 
-        if let Some(first_part_offset) = self.start_from_offset {
-            Entries {
-                inner: self,
-                key: Some(get_unchecked!(
-                    self.offsets.elements,
-                    first_part_offset as usize
-                )),
-                value: Some((
-                    first_part_offset + 1,
-                    get_unchecked!(self.offsets.elements, first_part_offset as usize + 1),
-                )),
-            }
-        } else {
-            Entries {
-                inner: self,
-                key: None,
-                value: None,
-            }
+            buf.clear();
+
+            // SAFETY: The string to unescape was parsed from JSON
+            // Chunks never end with a `\`, so they can't end with an unescaped `\`
+            unsafe { unescape_trusted_into(get_unchecked!(raw, start..end), &mut buf) };
+            write.write_all(buf.as_bytes())?;
+
+            start = end;
         }
-    }
-}
 
-impl<'input, 'offsets> Arr<'input, 'offsets> {
-    /**
-    The number of elements in the array, if known.
-    */
-    #[inline]
-    pub fn size_hint(&self) -> usize {
-        self.size_hint as usize
+        Ok(())
     }
 
     /**
-    Iterate through elements in the array.
+    Whether or not the underlying string is equal to the given one.
+
+    If the string is escaped then it's compared without allocating, unless it
+    contains escape sequences that aren't valid JSON.
     */
     #[inline]
-    pub fn iter<'brw>(&'brw self) -> impl Iterator<Item = Kind<'input, 'offsets>> + 'brw {
-        struct Iter<'brw, 'input, 'offsets> {
-            inner: &'brw Arr<'input, 'offsets>,
-            elem: Option<(u16, &'offsets Offset)>,
+    pub fn eq_unescaped(&self, other: &str) -> bool {
+        if !self.1 {
+            return self.as_raw() == other;
         }
 
-        impl<'brw, 'input, 'offsets> Iterator for Iter<'brw, 'input, 'offsets> {
-            type Item = Kind<'input, 'offsets>;
+        match eq_escaped(self.as_raw().as_bytes(), other.as_bytes()) {
+            Some(eq) => eq,
+            // strings with invalid escapes are compared the same way they're unescaped
+            None => self.to_unescaped() == other,
+        }
+    }
+}
 
-            #[inline]
-            fn next(&mut self) -> Option<Self::Item> {
-                let elem = self.elem.take();
+/**
+Compare an escaped string with an unescaped one, unescaping each escape sequence as it's reached.
 
-                match elem {
-                    Some((elem_offset, elem)) => {
-                        let iter_elem =
-                            elem.to_element(self.inner.input, self.inner.offsets, elem_offset);
+If the escaped string contains an escape sequence that isn't valid JSON then this function returns `None`.
+*/
+fn eq_escaped(raw: &[u8], other: &[u8]) -> Option<bool> {
+    let (mut i, mut j) = (0, 0);
 
-                        if let Some(next) = elem.next {
-                            self.elem = Some((
-                                next,
-                                get_unchecked!(self.inner.offsets.elements, next as usize),
-                            ));
-                        }
+    while i < raw.len() {
+        // compare everything up to the next escape directly
+        if raw[i] != b'\\' {
+            let run = raw[i..]
+                .iter()
+                .position(|b| *b == b'\\')
+                .unwrap_or(raw.len() - i);
 
-                        Some(iter_elem)
-                    }
-                    _ => None,
-                }
+            if other.get(j..j + run) != Some(&raw[i..i + run]) {
+                return Some(false);
             }
+
+            i += run;
+            j += run;
+            continue;
         }
 
-        if let Some(first_part_offset) = self.start_from_offset {
-            Iter {
-                inner: self,
-                elem: Some((
-                    first_part_offset,
-                    get_unchecked!(self.offsets.elements, first_part_offset as usize),
-                )),
-            }
-        } else {
-            Iter {
-                inner: self,
-                elem: None,
+        let mut buf = [0; 4];
+
+        let (unescaped, escape_len): (&[u8], usize) = match raw.get(i + 1)? {
+            b'"' => (b"\"", 2),
+            b'\\' => (b"\\", 2),
+            b'/' => (b"/", 2),
+            b'b' => (b"\x08", 2),
+            b'f' => (b"\x0c", 2),
+            b'n' => (b"\n", 2),
+            b'r' => (b"\r", 2),
+            b't' => (b"\t", 2),
+            b'u' => {
+                let code = hex4(raw, i + 2)?;
+
+                let (c, escape_len) = match char::from_u32(code as u32) {
+                    Some(c) => (c, 6),
+                    // a surrogate must be followed by the other half of its pair
+                    None => {
+                        if raw.get(i + 6..i + 8)? != b"\\u" {
+                            return None;
+                        }
+
+                        let low = hex4(raw, i + 8)?;
+
+                        (try_from_utf16_surrogate_pair(code, low).ok()?, 12)
+                    }
+                };
+
+                (c.encode_utf8(&mut buf).as_bytes(), escape_len)
             }
+            _ => return None,
+        };
+
+        if other.get(j..j + unescaped.len()) != Some(unescaped) {
+            return Some(false);
         }
+
+        i += escape_len;
+        j += unescaped.len();
     }
+
+    Some(j == other.len())
 }
 
-impl Offset {
+impl<'input> Deref for Str<'input> {
+    type Target = str;
+
+    /**
+    Get the underlying string, without attempting to unescape it.
+    */
     #[inline]
-    fn to_str<'input>(&self, input: &'input [u8]) -> Option<Str<'input>> {
-        match self.kind {
-            OffsetKind::Str(s, escaped) => Some(Str(s.as_str(input), escaped)),
-            _ => None,
-        }
+    fn deref(&self) -> &str {
+        self.as_raw()
     }
+}
 
+impl<'input> AsRef<str> for Str<'input> {
     #[inline]
-    fn to_element<'input, 'offsets>(
-        &self,
-        input: &'input [u8],
-        offsets: &'offsets Offsets,
-        self_offset: u16,
-    ) -> Kind<'input, 'offsets> {
-        match self.kind {
-            OffsetKind::Str(s, escaped) => Kind::Str(Str(s.as_str(input), escaped)),
-            OffsetKind::Num(n) => Kind::Num(n.as_str(input)),
-            OffsetKind::Map(len) => Kind::Map(Map {
-                input,
-                size_hint: len,
-                start_from_offset: if len > 0 { Some(self_offset + 1) } else { None },
-                offsets,
-            }),
-            OffsetKind::Arr(len) => Kind::Arr(Arr {
-                input,
-                size_hint: len,
-                start_from_offset: if len > 0 { Some(self_offset + 1) } else { None },
-                offsets,
-            }),
-            OffsetKind::Bool(b) => Kind::Bool(b),
-            OffsetKind::Null => Kind::Null,
-        }
+    fn as_ref(&self) -> &str {
+        self.as_raw()
     }
 }
 
-impl Slice {
+impl<'input> PartialEq<str> for Str<'input> {
+    /**
+    Whether or not the underlying string is equal to the given one.
+
+    See [`Str::eq_unescaped`].
+    */
     #[inline]
-    fn as_str<'input>(&self, input: &'input [u8]) -> &'input str {
-        from_utf8_unchecked!(offset_from_raw_parts!(
-            input.as_ptr(),
-            input.len(),
-            self.offset as usize,
-            self.len as usize
-        ))
+    fn eq(&self, other: &str) -> bool {
+        self.eq_unescaped(other)
     }
 }
 
-#[cfg(any(test, feature = "serde_json"))]
-impl<'input> Document<'input> {
+impl<'input> PartialEq<&str> for Str<'input> {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self.eq_unescaped(other)
+    }
+}
+
+impl<'input> PartialEq<Str<'input>> for str {
+    #[inline]
+    fn eq(&self, other: &Str<'input>) -> bool {
+        other.eq_unescaped(self)
+    }
+}
+
+impl<'input> PartialEq<Str<'input>> for &str {
+    #[inline]
+    fn eq(&self, other: &Str<'input>) -> bool {
+        other.eq_unescaped(self)
+    }
+}
+
+impl<'input> fmt::Display for Str<'input> {
     /**
-    Convert a document into a [`serde_json::Value`].
+    Format the underlying string, unescaping it if needed.
     */
-    pub fn to_value(&self) -> serde_json::Value {
-        use std::str::FromStr;
-
-        impl<'input, 'offsets> Kind<'input, 'offsets> {
-            fn to_value(&self) -> serde_json::Value {
-                match self {
-                    Kind::Str(ref s) => serde_json::Value::String(s.to_unescaped().into_owned()),
-                    Kind::Num(n) => match serde_json::Number::from_str(n.trim()) {
-                        Ok(n) => serde_json::Value::Number(n),
-                        _ => serde_json::Value::String((*n).to_owned()),
-                    },
-                    Kind::Bool(b) => serde_json::Value::Bool(*b),
-                    Kind::Null => serde_json::Value::Null,
-                    Kind::Map(ref map) => {
-                        let mut value = serde_json::Map::with_capacity(map.size_hint());
-
-                        for (k, v) in map.entries() {
-                            value.insert(k.to_unescaped().into_owned(), v.to_value());
-                        }
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_unescaped())
+    }
+}
 
-                        serde_json::Value::Object(value)
-                    }
-                    Kind::Arr(ref arr) => {
-                        let mut value = Vec::with_capacity(arr.size_hint());
+impl<'input, 'offsets> fmt::Display for Kind<'input, 'offsets> {
+    /**
+    Format the element as minified JSON.
 
-                        for e in arr.iter() {
-                            value.push(e.to_value());
-                        }
+    Unlike formatting a [`Str`] directly, strings are quoted and escaped.
+    */
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Kind::Map(map) => fmt::Display::fmt(map, f),
+            Kind::Arr(arr) => fmt::Display::fmt(arr, f),
+            Kind::Num(n) => f.write_str(n.trim()),
+            kind => f.write_str(kind.as_json()),
+        }
+    }
+}
 
-                        serde_json::Value::Array(value)
-                    }
-                }
+impl<'input, 'offsets> fmt::Display for Map<'input, 'offsets> {
+    /**
+    Format the map as minified JSON.
+
+    The map is written from its raw span. Maps in erroneous documents that don't have
+    one are written from their entries instead.
+    */
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.raw.len > 0 {
+            return f.write_str(self.as_json());
+        }
+
+        f.write_str("{")?;
+
+        for (i, (k, v)) in self.iter_entries().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
             }
+
+            write!(f, "{}:{}", k.as_json(), v)?;
         }
 
-        let doc = self.as_map();
+        f.write_str("}")
+    }
+}
 
-        let mut map = serde_json::Map::with_capacity(doc.size_hint());
+impl<'input, 'offsets> fmt::Display for Arr<'input, 'offsets> {
+    /**
+    Format the array as minified JSON.
 
-        for (k, v) in doc.entries() {
-            map.insert(k.to_unescaped().into_owned(), v.to_value());
+    The array is written from its raw span. Arrays in erroneous documents that don't have
+    one are written from their elements instead.
+    */
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.raw.len > 0 {
+            return f.write_str(self.as_json());
         }
 
-        serde_json::Value::Object(map)
+        f.write_str("[")?;
+
+        for (i, v) in self.iter_elems().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+
+            fmt::Display::fmt(&v, f)?;
+        }
+
+        f.write_str("]")
+    }
+}
+
+impl<'input, 'offsets> Map<'input, 'offsets> {
+    /**
+    Returns the raw JSON for the map, including all of its entries.
+    */
+    #[inline]
+    pub fn as_json(&self) -> &'input str {
+        // maps in erroneous documents may not have a raw span
+        if self.raw.len == 0 {
+            return "{}";
+        }
+
+        self.raw.as_str(self.input)
+    }
+
+    /**
+    The number of entries in the map.
+
+    A trailing key without a value isn't counted as an entry.
+    */
+    #[inline]
+    pub fn len(&self) -> usize {
+        // using `x >> 1` on a non-negative int is the same `floor(x / 2)`, but much faster
+        (self.parts >> 1) as usize
+    }
+
+    /**
+    Whether or not the map has any entries.
+    */
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /**
+    Whether or not the map ended with a key that didn't have a value.
+
+    Maps in valid documents never have a dangling key. It's dropped when iterating.
+    */
+    #[inline]
+    pub fn has_dangling_key(&self) -> bool {
+        self.parts & 1 == 1
+    }
+
+    /**
+    The number of entries in the map.
+
+    This method is the same as [`Map::len`].
+    */
+    #[inline]
+    pub fn size_hint(&self) -> usize {
+        self.len()
+    }
+
+    /**
+    Treat the map as a standalone document.
+
+    The document borrows the same input and offsets as the map, so no re-scanning is needed.
+    */
+    #[inline]
+    pub fn as_document(&self) -> Document<'offsets>
+    where
+        'input: 'offsets,
+    {
+        Document {
+            input: self.input,
+            offsets: Cow::Borrowed(self.offsets),
+            _detached_stack: Vec::new(),
+            root: self.self_offset,
+        }
+    }
+
+    /**
+    The offset of the first part in the map, if it has any entries.
+    */
+    #[inline]
+    pub(super) fn first_part_offset(&self) -> Option<u16> {
+        self.start_from_offset
+    }
+
+    /**
+    Iterate through entries in the map.
+    */
+    #[inline]
+    pub fn entries<'brw>(
+        &'brw self,
+    ) -> impl Iterator<Item = (Str<'input>, Kind<'input, 'offsets>)> + 'brw {
+        self.iter_entries()
+    }
+
+    /**
+    Iterate through entries in the map in reverse, starting from the last one.
+
+    The last entry is found without walking through the map from the start, so this is
+    cheap even for large maps. Forward iteration stops at the first key that isn't a string,
+    which only happens in erroneous documents, so reverse iteration stops at the last one.
+    */
+    #[inline]
+    pub fn entries_rev<'brw>(
+        &'brw self,
+    ) -> impl Iterator<Item = (Str<'input>, Kind<'input, 'offsets>)> + 'brw {
+        // keys always immediately precede their values, so only values need to be walked
+        let mut values = PartsRev::new(self.offsets, self.start_from_offset.map(|first| first + 1));
+
+        std::iter::from_fn(move || {
+            let value_offset = values.next()?;
+            let key = get_unchecked!(self.offsets.elements, value_offset as usize - 1)
+                .to_str(self.input)?;
+
+            let value = get_unchecked!(self.offsets.elements, value_offset as usize).to_element(
+                self.input,
+                self.offsets,
+                value_offset,
+            );
+
+            Some((key, value))
+        })
+    }
+
+    /**
+    Get the last entry in the map.
+
+    See [`Map::entries_rev`] for details.
+    */
+    #[inline]
+    pub fn last_entry(&self) -> Option<(Str<'input>, Kind<'input, 'offsets>)> {
+        self.entries_rev().next()
+    }
+
+    /**
+    Iterate through entries in the map, along with the id of each value.
+    */
+    #[inline]
+    pub fn entries_with_ids<'brw>(
+        &'brw self,
+    ) -> impl Iterator<Item = (ElementId, Str<'input>, Kind<'input, 'offsets>)> + 'brw {
+        let mut entries = self.iter_entries();
+
+        std::iter::from_fn(move || entries.next_with_id())
+    }
+
+    /**
+    Iterate through entries in the map, along with the raw JSON of each value.
+
+    Values are returned exactly as they appear in the input, whatever their kind,
+    so they can be re-emitted without unescaping or re-formatting them.
+    */
+    #[inline]
+    pub fn raw_entries<'brw>(
+        &'brw self,
+    ) -> impl Iterator<Item = (Str<'input>, &'input str)> + 'brw {
+        self.iter_entries().map(|(k, v)| (k, v.as_json()))
+    }
+
+    /**
+    Get the value of the first entry with the given key.
+
+    Keys are compared with their unescaped values, without allocating.
+    */
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<Kind<'input, 'offsets>> {
+        self.iter_entries()
+            .find(|(k, _)| k.eq_unescaped(key))
+            .map(|(_, v)| v)
+    }
+
+    /**
+    Get the range of the input that the value of the first entry with the given key was
+    scanned from.
+
+    The range covers the raw JSON of the value, the same as [`Kind::as_json`], so strings
+    include their surrounding quotes. It's relative to the input the document was scanned
+    from, so a value can be overwritten in-place in that buffer with another value of the
+    same length, like masking the contents of a string, without re-serializing the document.
+
+    Maps and arrays in erroneous documents that weren't finished don't have a range.
+    */
+    pub fn value_span(&self, key: &str) -> Option<Range<usize>> {
+        let (id, k, _) = self
+            .entries_with_ids()
+            .find(|(_, k, _)| k.eq_unescaped(key))?;
+
+        match get_unchecked!(self.offsets.elements, id.0 as usize).kind {
+            OffsetKind::Str(s, _) => {
+                Some(s.offset as usize - 1..s.offset as usize + s.len as usize + 1)
+            }
+            // numbers may be followed by whitespace before the next control character
+            OffsetKind::Num(n) => {
+                let start = n.offset as usize;
+                Some(start..start + n.as_str(self.input).trim_end().len())
+            }
+            OffsetKind::Map(_, raw) | OffsetKind::Arr(_, raw) | OffsetKind::Lazy(raw, _) => {
+                if raw.len == 0 {
+                    return None;
+                }
+
+                Some(raw.offset as usize..raw.offset as usize + raw.len as usize)
+            }
+            // booleans and nulls don't keep their offset, so they're found after their key
+            OffsetKind::Bool(b) => self.literal_span(k, if b { 4 } else { 5 }),
+            OffsetKind::Null => self.literal_span(k, 4),
+        }
+    }
+
+    /**
+    Get the value of the first entry with the given key, if it's a string.
+    */
+    #[inline]
+    pub fn get_str(&self, key: &str) -> Option<Str<'input>> {
+        self.get(key)?.as_str()
+    }
+
+    /**
+    Get the value of the first entry with the given key, if it's a number that fits in an `i64`.
+    */
+    #[inline]
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        match self.get(key)? {
+            Kind::Num(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /**
+    Get the value of the first entry with the given key, if it's a number.
+
+    Integers are converted into floats, so they may lose precision.
+    */
+    #[inline]
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        match self.get(key)? {
+            Kind::Num(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /**
+    Get the value of the first entry with the given key, if it's a boolean.
+    */
+    #[inline]
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)? {
+            Kind::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /**
+    Get the value of the first entry with the given key, if it's a map.
+    */
+    #[inline]
+    pub fn get_map(&self, key: &str) -> Option<Map<'input, 'offsets>> {
+        match self.get(key)? {
+            Kind::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /**
+    Get the value of the first entry with the given key, if it's an array.
+    */
+    #[inline]
+    pub fn get_arr(&self, key: &str) -> Option<Arr<'input, 'offsets>> {
+        match self.get(key)? {
+            Kind::Arr(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    /**
+    Get the values of the first entries with each of the given keys in a single pass over the map.
+
+    Values are returned in the same order as their keys. This is cheaper than calling [`Map::get`]
+    for each key, which walks the entries of the map again each time.
+    */
+    pub fn extract<const N: usize>(&self, keys: [&str; N]) -> [Option<Kind<'input, 'offsets>>; N] {
+        let mut values = [(); N].map(|_| None);
+        let mut remaining = N;
+
+        for (k, v) in self.iter_entries() {
+            if remaining == 0 {
+                break;
+            }
+
+            for (key, value) in keys.iter().zip(values.iter_mut()) {
+                // the first entry for a key is the one that's used
+                if value.is_none() && k.eq_unescaped(key) {
+                    *value = Some(v);
+                    remaining -= 1;
+                    break;
+                }
+            }
+        }
+
+        values
+    }
+
+    /**
+    Find the range of a `true`, `false`, or `null` literal that's the value of a key.
+    */
+    fn literal_span(&self, key: Str, len: usize) -> Option<Range<usize>> {
+        let key_end = key.0.as_ptr() as usize - self.input.as_ptr() as usize + key.0.len();
+
+        // inputs are minified, so the value directly follows the `:` after its key
+        let start = key_end + 1;
+
+        if start + len > self.input.len() {
+            return None;
+        }
+
+        Some(start..start + len)
+    }
+
+    /**
+    Iterate through entries in the map without borrowing it.
+    */
+    #[inline]
+    pub(crate) fn iter_entries(&self) -> Entries<'input, 'offsets> {
+        if let Some(first_part_offset) = self.start_from_offset {
+            Entries {
+                input: self.input,
+                offsets: self.offsets,
+                key: Some(get_unchecked!(
+                    self.offsets.elements,
+                    first_part_offset as usize
+                )),
+                value: Some((
+                    first_part_offset + 1,
+                    get_unchecked!(self.offsets.elements, first_part_offset as usize + 1),
+                )),
+            }
+        } else {
+            Entries {
+                input: self.input,
+                offsets: self.offsets,
+                key: None,
+                value: None,
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Entries<'input, 'offsets> {
+    input: &'input [u8],
+    offsets: &'offsets Offsets,
+    key: Option<&'offsets Offset>,
+    value: Option<(u16, &'offsets Offset)>,
+}
+
+impl<'input, 'offsets> Iterator for Entries<'input, 'offsets> {
+    type Item = (Str<'input>, Kind<'input, 'offsets>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_id().map(|(_, key, value)| (key, value))
+    }
+}
+
+impl<'input, 'offsets> Entries<'input, 'offsets> {
+    #[inline]
+    fn next_with_id(&mut self) -> Option<(ElementId, Str<'input>, Kind<'input, 'offsets>)> {
+        let key = self.key.take();
+        let value = self.value.take();
+
+        match (key, value) {
+            (Some(key), Some((value_offset, value))) => {
+                // the key must be a string
+                let entry_key = if let Some(key) = key.to_str(self.input) {
+                    key
+                } else {
+                    return None;
+                };
+
+                let entry_value = value.to_element(self.input, self.offsets, value_offset);
+
+                if let Some(next) = key.next {
+                    self.key = Some(get_unchecked!(self.offsets.elements, next as usize));
+                }
+
+                if let Some(next) = value.next {
+                    self.value = Some((next, get_unchecked!(self.offsets.elements, next as usize)));
+                }
+
+                Some((ElementId(value_offset), entry_key, entry_value))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'input, 'offsets> Arr<'input, 'offsets> {
+    /**
+    Returns the raw JSON for the array, including all of its elements.
+    */
+    #[inline]
+    pub fn as_json(&self) -> &'input str {
+        // arrays in erroneous documents may not have a raw span
+        if self.raw.len == 0 {
+            return "[]";
+        }
+
+        self.raw.as_str(self.input)
+    }
+
+    /**
+    The number of elements in the array.
+    */
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /**
+    Whether or not the array has any elements.
+    */
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+    The number of elements in the array.
+
+    This method is the same as [`Arr::len`].
+    */
+    #[inline]
+    pub fn size_hint(&self) -> usize {
+        self.len()
+    }
+
+    /**
+    Iterate through elements in the array.
+    */
+    #[inline]
+    pub fn iter<'brw>(&'brw self) -> impl Iterator<Item = Kind<'input, 'offsets>> + 'brw {
+        self.iter_elems()
+    }
+
+    /**
+    Iterate through elements in the array in reverse, starting from the last one.
+
+    The last element is found without walking through the array from the start,
+    so this is cheap even for large arrays.
+    */
+    #[inline]
+    pub fn iter_rev<'brw>(&'brw self) -> impl Iterator<Item = Kind<'input, 'offsets>> + 'brw {
+        PartsRev::new(self.offsets, self.start_from_offset).map(move |elem_offset| {
+            get_unchecked!(self.offsets.elements, elem_offset as usize).to_element(
+                self.input,
+                self.offsets,
+                elem_offset,
+            )
+        })
+    }
+
+    /**
+    Iterate through elements in the array, along with the id of each one.
+    */
+    #[inline]
+    pub fn iter_with_ids<'brw>(
+        &'brw self,
+    ) -> impl Iterator<Item = (ElementId, Kind<'input, 'offsets>)> + 'brw {
+        let mut elems = self.iter_elems();
+
+        std::iter::from_fn(move || elems.next_with_id())
+    }
+
+    /**
+    Iterate through elements in the array without borrowing it.
+    */
+    #[inline]
+    pub(crate) fn iter_elems(&self) -> Elems<'input, 'offsets> {
+        if let Some(first_part_offset) = self.start_from_offset {
+            Elems {
+                input: self.input,
+                offsets: self.offsets,
+                elem: Some((
+                    first_part_offset,
+                    get_unchecked!(self.offsets.elements, first_part_offset as usize),
+                )),
+            }
+        } else {
+            Elems {
+                input: self.input,
+                offsets: self.offsets,
+                elem: None,
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Elems<'input, 'offsets> {
+    input: &'input [u8],
+    offsets: &'offsets Offsets,
+    elem: Option<(u16, &'offsets Offset)>,
+}
+
+impl<'input, 'offsets> Iterator for Elems<'input, 'offsets> {
+    type Item = Kind<'input, 'offsets>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_id().map(|(_, elem)| elem)
+    }
+}
+
+impl<'input, 'offsets> Elems<'input, 'offsets> {
+    #[inline]
+    fn next_with_id(&mut self) -> Option<(ElementId, Kind<'input, 'offsets>)> {
+        let elem = self.elem.take();
+
+        match elem {
+            Some((elem_offset, elem)) => {
+                let iter_elem = elem.to_element(self.input, self.offsets, elem_offset);
+
+                if let Some(next) = elem.next {
+                    self.elem = Some((next, get_unchecked!(self.offsets.elements, next as usize)));
+                }
+
+                Some((ElementId(elem_offset), iter_elem))
+            }
+            _ => None,
+        }
+    }
+}
+
+/**
+The offsets of parts at the same position in a map or array, from the last to the first.
+*/
+struct PartsRev<'offsets> {
+    offsets: &'offsets Offsets,
+    first: u16,
+    curr: Option<u16>,
+}
+
+impl<'offsets> PartsRev<'offsets> {
+    #[inline]
+    fn new(offsets: &'offsets Offsets, first: Option<u16>) -> Self {
+        match first {
+            // the first part points back to the last one
+            Some(first) => PartsRev {
+                offsets,
+                first,
+                curr: Some(get_unchecked!(offsets.elements, first as usize).prev)
+                    .filter(|last| *last != NO_PREV),
+            },
+            None => PartsRev {
+                offsets,
+                first: 0,
+                curr: None,
+            },
+        }
+    }
+}
+
+impl<'offsets> Iterator for PartsRev<'offsets> {
+    type Item = u16;
+
+    #[inline]
+    fn next(&mut self) -> Option<u16> {
+        let curr = self.curr.take()?;
+
+        if curr != self.first {
+            let prev = get_unchecked!(self.offsets.elements, curr as usize).prev;
+            self.curr = Some(prev).filter(|prev| *prev != NO_PREV);
+        }
+
+        Some(curr)
+    }
+}
+
+impl Offset {
+    #[inline]
+    pub(super) fn to_str<'input>(&self, input: &'input [u8]) -> Option<Str<'input>> {
+        match self.kind {
+            OffsetKind::Str(s, escaped) => Some(Str(s.as_quoted_str(input), escaped)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub(super) fn to_element<'input, 'offsets>(
+        &self,
+        input: &'input [u8],
+        offsets: &'offsets Offsets,
+        self_offset: u16,
+    ) -> Kind<'input, 'offsets> {
+        match self.kind {
+            OffsetKind::Str(s, escaped) => Kind::Str(Str(s.as_quoted_str(input), escaped)),
+            OffsetKind::Num(n) => Kind::Num(n.as_str(input)),
+            OffsetKind::Map(parts, raw) => Kind::Map(Map {
+                input,
+                raw,
+                parts,
+                self_offset: Some(self_offset),
+                start_from_offset: if parts >> 1 > 0 {
+                    Some(self_offset + 1)
+                } else {
+                    None
+                },
+                offsets,
+            }),
+            OffsetKind::Arr(len, raw) => Kind::Arr(Arr {
+                input,
+                raw,
+                len,
+                start_from_offset: if len > 0 { Some(self_offset + 1) } else { None },
+                offsets,
+            }),
+            OffsetKind::Bool(b) => Kind::Bool(b),
+            OffsetKind::Null => Kind::Null,
+            OffsetKind::Lazy(raw, map) => Kind::Lazy(Lazy {
+                raw: raw.as_str(input),
+                map,
+            }),
+        }
+    }
+}
+
+impl Slice {
+    #[inline]
+    fn as_str<'input>(&self, input: &'input [u8]) -> &'input str {
+        from_utf8_unchecked!(offset_from_raw_parts!(
+            input.as_ptr(),
+            input.len(),
+            self.offset as usize,
+            self.len as usize
+        ))
+    }
+
+    /**
+    Get the string along with its surrounding quotes.
+
+    Strings never include their quotes in their slice.
+    */
+    #[inline]
+    fn as_quoted_str<'input>(&self, input: &'input [u8]) -> &'input str {
+        from_utf8_unchecked!(offset_from_raw_parts!(
+            input.as_ptr(),
+            input.len(),
+            self.offset as usize - 1,
+            self.len as usize + 2
+        ))
+    }
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+impl<'input, 'offsets> Kind<'input, 'offsets> {
+    /**
+    Borrow an element as a [`serde_json::value::RawValue`] without re-escaping it.
+
+    The raw value borrows directly from the input, so it can be embedded in other
+    `serde_json` serialization. The JSON is validated by `serde_json` before it's borrowed.
+
+    Maps and arrays from erroneous documents may not be complete, so they return `None`.
+    Any other JSON that isn't valid also returns `None`.
+    */
+    #[inline]
+    pub fn to_raw_value(&self) -> Option<&'input serde_json::value::RawValue> {
+        let json = match self {
+            Kind::Map(map) if map.offsets.err => return None,
+            Kind::Arr(arr) if arr.offsets.err => return None,
+            Kind::Num(n) => n.trim(),
+            kind => kind.as_json(),
+        };
+
+        serde_json::from_str(json).ok()
+    }
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+impl<'input> Document<'input> {
+    /**
+    Convert a document into a [`serde_json::Value`].
+
+    Keys in maps are sorted, unless the `preserve_order` feature is enabled. In that case,
+    they keep the order they appear in the document. If a map contains the same key more than
+    once then the last entry for it is used, in the position of the first.
+    */
+    pub fn to_value(&self) -> serde_json::Value {
+        let mut map = serde_json::Map::with_capacity(self.as_map().len());
+
+        self.to_value_into(&mut map);
+
+        serde_json::Value::Object(map)
+    }
+
+    /**
+    Convert a document into a caller-provided [`serde_json::Map`].
+
+    The map is cleared before the entries of the document are inserted into it.
+    */
+    pub fn to_value_into(&self, map: &mut serde_json::Map<String, serde_json::Value>) {
+        map.clear();
+
+        // the last entry always wins, so this can't fail
+        let _ = map_to_value(
+            &self.as_map(),
+            map,
+            &mut |s| s.into_owned(),
+            DuplicateKeys::LastWins,
+            Numbers::Lossy,
+        );
+    }
+
+    /**
+    Convert a document into a [`serde_json::Value`], handling maps that contain the same key
+    more than once using the given policy.
+
+    This method only fails if the policy is [`DuplicateKeys::Error`] and there's a duplicate key.
+    */
+    pub fn to_value_with(
+        &self,
+        duplicates: DuplicateKeys,
+    ) -> Result<serde_json::Value, DuplicateKeyError> {
+        let mut map = serde_json::Map::with_capacity(self.as_map().len());
+
+        map_to_value(
+            &self.as_map(),
+            &mut map,
+            &mut |s| s.into_owned(),
+            duplicates,
+            Numbers::Lossy,
+        )?;
+
+        Ok(serde_json::Value::Object(map))
+    }
+
+    /**
+    Convert a document into a [`serde_json::Value`], converting numbers using the given policy.
+
+    This method is useful for documents with numbers that need to round-trip exactly,
+    like decimal amounts with more digits than a `f64` can hold.
+    */
+    pub fn to_value_with_numbers(&self, numbers: Numbers) -> serde_json::Value {
+        let mut map = serde_json::Map::with_capacity(self.as_map().len());
+
+        // the last entry always wins, so this can't fail
+        let _ = map_to_value(
+            &self.as_map(),
+            &mut map,
+            &mut |s| s.into_owned(),
+            DuplicateKeys::LastWins,
+            numbers,
+        );
+
+        serde_json::Value::Object(map)
+    }
+
+    /**
+    Convert a document into a caller-provided [`serde_json::Map`], reusing the
+    allocations of any strings in it.
+
+    The keys and string values in the map are moved into the pool before it's cleared,
+    and then taken from the pool as the entries of the document are inserted into it.
+    This makes converting documents in a loop cheaper than calling [`Document::to_value`].
+    */
+    pub fn to_value_into_pooled(
+        &self,
+        map: &mut serde_json::Map<String, serde_json::Value>,
+        pool: &mut StringPool,
+    ) {
+        for (k, v) in std::mem::take(map) {
+            pool.recycle(k);
+            pool.recycle_value(v);
+        }
+
+        // the last entry always wins, so this can't fail
+        let _ = map_to_value(
+            &self.as_map(),
+            map,
+            &mut |s| pool.string(&s),
+            DuplicateKeys::LastWins,
+            Numbers::Lossy,
+        );
+    }
+}
+
+/**
+How to convert maps that contain the same key more than once.
+*/
+#[cfg(any(test, feature = "serde_json"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DuplicateKeys {
+    /**
+    Use the value of the first entry with the key.
+    */
+    FirstWins,
+    /**
+    Use the value of the last entry with the key.
+
+    This is the same as [`Document::to_value`].
+    */
+    LastWins,
+    /**
+    Collect the values of each entry with the key into an array, in the order they appear.
+
+    Keys that only appear once aren't collected into an array.
+    */
+    CollectIntoArray,
+    /**
+    Fail with a [`DuplicateKeyError`].
+    */
+    Error,
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+impl Default for DuplicateKeys {
+    #[inline]
+    fn default() -> Self {
+        DuplicateKeys::LastWins
+    }
+}
+
+/**
+How to convert numbers into [`serde_json::Number`]s.
+*/
+#[cfg(any(test, feature = "serde_json"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Numbers {
+    /**
+    Convert numbers into the closest `i64`, `u64`, or `f64`.
+
+    Numbers with more precision than a `f64` can hold are rounded. Numbers that are
+    out of the range of a `f64` are converted into strings with their raw value.
+    If the `arbitrary_precision` feature is enabled then `serde_json` keeps the raw value of
+    every number, so none are rounded.
+
+    This is the same as [`Document::to_value`].
+    */
+    Lossy,
+    /**
+    Convert numbers that can't be held exactly by an `i64`, `u64`, or `f64` into strings
+    with their raw value.
+
+    Numbers in a `f64` are considered exact if they have no more than 15 significant digits.
+    */
+    RawString,
+    /**
+    Convert numbers into arbitrary precision numbers that keep their raw value.
+    */
+    #[cfg(feature = "arbitrary_precision")]
+    ArbitraryPrecision,
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+impl Default for Numbers {
+    #[inline]
+    fn default() -> Self {
+        Numbers::Lossy
+    }
+}
+
+/**
+An error converting a document with a duplicate key.
+*/
+#[cfg(any(test, feature = "serde_json"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKeyError {
+    key: String,
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+impl DuplicateKeyError {
+    /**
+    The unescaped key that appeared more than once in the same map.
+    */
+    #[inline]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+impl fmt::Display for DuplicateKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "duplicate key `{}`", self.key)
+    }
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+impl std::error::Error for DuplicateKeyError {}
+
+/**
+Compare a document with a [`serde_json::Value`] without converting it.
+
+The result is the same as comparing the value with [`Document::to_value`].
+*/
+#[cfg(any(test, feature = "serde_json"))]
+impl<'input> PartialEq<serde_json::Value> for Document<'input> {
+    fn eq(&self, other: &serde_json::Value) -> bool {
+        match other {
+            serde_json::Value::Object(other) => map_eq(&self.as_map(), other),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+impl<'input> PartialEq<Document<'input>> for serde_json::Value {
+    #[inline]
+    fn eq(&self, other: &Document<'input>) -> bool {
+        other == self
+    }
+}
+
+/**
+A pool of previously allocated strings to reuse when converting documents.
+
+See [`Document::to_value_into_pooled`].
+*/
+#[cfg(any(test, feature = "serde_json"))]
+#[derive(Debug, Clone, Default)]
+pub struct StringPool {
+    strings: Vec<String>,
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+impl StringPool {
+    /**
+    Create an empty pool.
+    */
+    #[inline]
+    pub fn new() -> Self {
+        StringPool::default()
+    }
+
+    /**
+    The number of strings in the pool.
+    */
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /**
+    Whether or not the pool is empty.
+    */
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    #[inline]
+    fn string(&mut self, s: &str) -> String {
+        match self.strings.pop() {
+            Some(mut string) => {
+                string.clear();
+                string.push_str(s);
+
+                string
+            }
+            None => s.to_owned(),
+        }
+    }
+
+    #[inline]
+    fn recycle(&mut self, string: String) {
+        if string.capacity() > 0 {
+            self.strings.push(string);
+        }
+    }
+
+    fn recycle_value(&mut self, value: serde_json::Value) {
+        // values are recycled using a stack so deeply nested ones don't overflow
+        let mut stack = vec![value];
+
+        while let Some(value) = stack.pop() {
+            match value {
+                serde_json::Value::String(s) => self.recycle(s),
+                serde_json::Value::Array(arr) => stack.extend(arr),
+                serde_json::Value::Object(map) => {
+                    for (k, v) in map {
+                        self.recycle(k);
+                        stack.push(v);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+fn map_to_value(
+    doc: &Map,
+    map: &mut serde_json::Map<String, serde_json::Value>,
+    string: &mut impl FnMut(Cow<str>) -> String,
+    duplicates: DuplicateKeys,
+    numbers: Numbers,
+) -> Result<(), DuplicateKeyError> {
+    use serde_json::map::Entry;
+
+    // the keys whose values have been collected into an array
+    // this distinguishes them from keys whose first value is an array
+    let mut collected = Vec::new();
+
+    for (k, v) in doc.entries() {
+        let k = string(k.to_unescaped());
+        let v = kind_to_value(&v, string, duplicates, numbers)?;
+
+        if let DuplicateKeys::LastWins = duplicates {
+            map.insert(k, v);
+            continue;
+        }
+
+        match map.entry(k) {
+            Entry::Vacant(entry) => {
+                entry.insert(v);
+            }
+            Entry::Occupied(mut entry) => match duplicates {
+                DuplicateKeys::LastWins => {
+                    entry.insert(v);
+                }
+                DuplicateKeys::FirstWins => (),
+                DuplicateKeys::CollectIntoArray => {
+                    if !collected.contains(entry.key()) {
+                        collected.push(entry.key().clone());
+
+                        let first = entry.get_mut().take();
+                        entry.insert(serde_json::Value::Array(vec![first]));
+                    }
+
+                    if let serde_json::Value::Array(values) = entry.get_mut() {
+                        values.push(v);
+                    }
+                }
+                DuplicateKeys::Error => {
+                    return Err(DuplicateKeyError {
+                        key: entry.key().clone(),
+                    })
+                }
+            },
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+fn kind_to_value(
+    kind: &Kind,
+    string: &mut impl FnMut(Cow<str>) -> String,
+    duplicates: DuplicateKeys,
+    numbers: Numbers,
+) -> Result<serde_json::Value, DuplicateKeyError> {
+    use std::str::FromStr;
+
+    Ok(match kind {
+        Kind::Str(ref s) => serde_json::Value::String(string(s.to_unescaped())),
+        Kind::Num(n) => match numbers {
+            Numbers::RawString if !num_is_exact(n.trim()) => {
+                serde_json::Value::String(string(Cow::Borrowed(n.trim())))
+            }
+            _ => match serde_json::Number::from_str(n.trim()) {
+                Ok(n) => serde_json::Value::Number(n),
+                _ => serde_json::Value::String(string(Cow::Borrowed(n))),
+            },
+        },
+        Kind::Bool(b) => serde_json::Value::Bool(*b),
+        Kind::Null => serde_json::Value::Null,
+        Kind::Map(ref doc) => {
+            let mut map = serde_json::Map::with_capacity(doc.len());
+
+            map_to_value(doc, &mut map, string, duplicates, numbers)?;
+
+            serde_json::Value::Object(map)
+        }
+        Kind::Arr(ref arr) => {
+            let mut value = Vec::with_capacity(arr.len());
+
+            for e in arr.iter() {
+                value.push(kind_to_value(&e, string, duplicates, numbers)?);
+            }
+
+            serde_json::Value::Array(value)
+        }
+        Kind::Lazy(lazy) => match lazy.scan() {
+            Some(nested) => kind_to_value(&nested.as_kind(), string, duplicates, numbers)?,
+            // spans that are too large to scan on their own are parsed instead
+            None => serde_json::from_str(lazy.as_json()).unwrap_or(serde_json::Value::Null),
+        },
+    })
+}
+
+/**
+Whether a number can be held exactly by an `i64`, `u64`, or `f64`.
+*/
+#[cfg(any(test, feature = "serde_json"))]
+fn num_is_exact(n: &str) -> bool {
+    // integers without a fraction or exponent are exact if they fit in 64 bits
+    if !n.bytes().any(|b| matches!(b, b'.' | b'e' | b'E')) {
+        return n.parse::<i64>().is_ok() || n.parse::<u64>().is_ok();
+    }
+
+    // count the significant digits in the mantissa, ignoring leading and trailing zeros
+    let mantissa = n.split(['e', 'E']).next().unwrap_or(n);
+
+    let mut significant = 0;
+    let mut digits = 0;
+    for b in mantissa.bytes().filter(u8::is_ascii_digit) {
+        if b != b'0' {
+            digits += 1;
+            significant = digits;
+        } else if digits > 0 {
+            digits += 1;
+        }
+    }
+
+    // a `f64` can hold any decimal with up to 15 significant digits,
+    // as long as it's not so large it overflows or so small it loses precision
+    match n.parse::<f64>() {
+        Ok(f) => {
+            significant <= 15 && f.is_finite() && (significant == 0 || f.abs() >= f64::MIN_POSITIVE)
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+fn map_eq(doc: &Map, other: &serde_json::Map<String, serde_json::Value>) -> bool {
+    // the keys in the other map are used to find any duplicates in the document
+    let mut seen = std::collections::HashSet::with_capacity(other.len());
+    let mut eq = true;
+
+    for (k, v) in doc.entries() {
+        let (k, other_v) = match other.get_key_value(&*k.to_unescaped()) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        // when a key is duplicated, only its last value is kept
+        // this is rare, so the map is just converted
+        if !seen.insert(k.as_str()) {
+            let mut map = serde_json::Map::with_capacity(doc.len());
+            let _ = map_to_value(
+                doc,
+                &mut map,
+                &mut |s| s.into_owned(),
+                DuplicateKeys::LastWins,
+                Numbers::Lossy,
+            );
+
+            return map == *other;
+        }
+
+        eq = eq && kind_eq(&v, other_v);
+    }
+
+    eq && seen.len() == other.len()
+}
+
+#[cfg(any(test, feature = "serde_json"))]
+fn kind_eq(kind: &Kind, other: &serde_json::Value) -> bool {
+    use std::str::FromStr;
+
+    match (kind, other) {
+        (Kind::Str(s), serde_json::Value::String(other)) => s.to_unescaped() == other.as_str(),
+        (Kind::Num(n), other) => match serde_json::Number::from_str(n.trim()) {
+            Ok(n) => matches!(other, serde_json::Value::Number(other) if n == *other),
+            _ => matches!(other, serde_json::Value::String(other) if n == other),
+        },
+        (Kind::Bool(b), serde_json::Value::Bool(other)) => b == other,
+        (Kind::Null, serde_json::Value::Null) => true,
+        (Kind::Map(doc), serde_json::Value::Object(other)) => map_eq(doc, other),
+        (Kind::Arr(arr), serde_json::Value::Array(other)) => {
+            arr.len() == other.len() && arr.iter().zip(other).all(|(e, other)| kind_eq(&e, other))
+        }
+        (Kind::Lazy(lazy), other) => lazy
+            .scan()
+            .is_some_and(|nested| kind_eq(&nested.as_kind(), other)),
+        _ => false,
     }
 }
@@ -0,0 +1,143 @@
+/*!
+A readable dump of a document's structure for debugging.
+
+The `Debug` output of a document shows its table of offsets, which needs some knowledge
+of how they're laid out to make sense of. A dump shows the same document as an indented
+JSON-like tree instead, using only the elements a caller already works with.
+*/
+
+use std::fmt::Write;
+
+use super::{Document, Elems, Entries, Kind};
+
+impl<'input> Document<'input> {
+    /**
+    Dump the document as an indented JSON-like tree.
+
+    Strings and numbers are written exactly as they appear in the input. Maps and arrays
+    that were kept as raw spans are written as-is, followed by a `/* lazy */` comment.
+    Erroneous documents are preceded by a comment saying so, followed by whatever
+    partial content they kept.
+
+    The format is only meant to be read by people, so it may change between versions.
+    */
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+
+        if self.is_err() {
+            out.push_str(if self.is_partial() {
+                "/* erroneous, partial */\n"
+            } else {
+                "/* erroneous */\n"
+            });
+        }
+
+        kind(&mut out, &Kind::Map(self.as_map()));
+
+        out
+    }
+}
+
+/**
+Write an element.
+
+Maps and arrays are written using an explicit stack of the elements left in each,
+so the depth of the stack is also the depth to indent their contents by.
+*/
+fn kind(out: &mut String, kind: &Kind) {
+    let mut stack = Vec::new();
+    kind_begin(out, &mut stack, kind);
+
+    loop {
+        let depth = stack.len();
+
+        let frame = match stack.last_mut() {
+            Some(frame) => frame,
+            None => break,
+        };
+
+        let value = match frame {
+            Frame::Map(entries, first) => match entries.next() {
+                Some((k, v)) => {
+                    if !std::mem::take(first) {
+                        out.push_str(",\n");
+                    }
+
+                    indent(out, depth);
+                    out.push_str(k.as_json());
+                    out.push_str(": ");
+
+                    v
+                }
+                None => {
+                    stack.pop();
+
+                    out.push('\n');
+                    indent(out, depth - 1);
+                    out.push('}');
+
+                    continue;
+                }
+            },
+            Frame::Arr(elems, first) => match elems.next() {
+                Some(e) => {
+                    if !std::mem::take(first) {
+                        out.push_str(",\n");
+                    }
+
+                    indent(out, depth);
+
+                    e
+                }
+                None => {
+                    stack.pop();
+
+                    out.push('\n');
+                    indent(out, depth - 1);
+                    out.push(']');
+
+                    continue;
+                }
+            },
+        };
+
+        kind_begin(out, &mut stack, &value);
+    }
+}
+
+// the elements left in a map or array, and whether the next one is the first
+enum Frame<'input, 'offsets> {
+    Map(Entries<'input, 'offsets>, bool),
+    Arr(Elems<'input, 'offsets>, bool),
+}
+
+#[inline]
+fn kind_begin<'input, 'offsets>(
+    out: &mut String,
+    stack: &mut Vec<Frame<'input, 'offsets>>,
+    kind: &Kind<'input, 'offsets>,
+) {
+    match kind {
+        Kind::Map(ref map) if map.is_empty() => out.push_str("{}"),
+        Kind::Map(ref map) => {
+            out.push_str("{\n");
+            stack.push(Frame::Map(map.iter_entries(), true));
+        }
+        Kind::Arr(ref arr) if arr.is_empty() => out.push_str("[]"),
+        Kind::Arr(ref arr) => {
+            out.push_str("[\n");
+            stack.push(Frame::Arr(arr.iter_elems(), true));
+        }
+        Kind::Lazy(lazy) => {
+            let _ = write!(out, "{} /* lazy */", lazy.as_json());
+        }
+        kind => out.push_str(kind.as_json()),
+    }
+}
+
+#[inline]
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
@@ -2,9 +2,19 @@ use super::*;
 
 // SAFETY: Callers must ensure `input` is valid UTF8
 #[inline(always)]
-pub(super) unsafe fn scan<'scan>(input: &'scan [u8], scan: &mut Scan, offsets: &mut Offsets) {
+pub(super) unsafe fn scan<'scan, const FLAT: bool>(
+    input: &'scan [u8],
+    scan: &mut Scan,
+    offsets: &mut Offsets,
+) {
     let read_to = scan.input_len as isize;
-    scan_block(ScanBlockInput {
+
+    #[cfg(any(test, feature = "metrics"))]
+    {
+        scan.metrics.fallback_bytes += (read_to - scan.input_offset).max(0) as usize;
+    }
+
+    scan_block::<FLAT>(ScanBlockInput {
         input,
         scan,
         offsets,
@@ -15,13 +25,18 @@ pub(super) unsafe fn scan<'scan>(input: &'scan [u8], scan: &mut Scan, offsets: &
 // SAFETY: Callers must ensure `input` is valid UTF8
 #[inline(always)]
 #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
-pub(super) unsafe fn scan_to<'scan>(
+pub(super) unsafe fn scan_to<'scan, const FLAT: bool>(
     input: &'scan [u8],
     scan: &mut Scan,
     offsets: &mut Offsets,
     read_to: isize,
 ) {
-    scan_block(ScanBlockInput {
+    #[cfg(any(test, feature = "metrics"))]
+    {
+        scan.metrics.fallback_bytes += (read_to - scan.input_offset).max(0) as usize;
+    }
+
+    scan_block::<FLAT>(ScanBlockInput {
         input,
         scan,
         offsets,
@@ -30,14 +45,14 @@ pub(super) unsafe fn scan_to<'scan>(
 }
 
 #[inline(always)]
-fn scan_block(i: ScanBlockInput) {
+fn scan_block<const FLAT: bool>(i: ScanBlockInput) {
     'interest: while i.scan.input_offset < i.read_to {
         match i.scan.stack.active_map_arr.active_primitive.kind {
             ActivePrimitiveKind::None => {
                 let curr_offset = i.scan.input_offset as usize;
                 let curr = offset_deref_unchecked!(i.input, i.scan.input_offset);
 
-                match_interest(ScanFnInput {
+                match_interest(ScanFnInput::<FLAT> {
                     input: i.input,
                     scan: i.scan,
                     offsets: i.offsets,
@@ -54,7 +69,7 @@ fn scan_block(i: ScanBlockInput) {
 
                     match curr {
                         b'\\' => {
-                            interest_escape(ScanFnInput {
+                            interest_escape(ScanFnInput::<FLAT> {
                                 input: i.input,
                                 scan: i.scan,
                                 offsets: i.offsets,
@@ -63,7 +78,7 @@ fn scan_block(i: ScanBlockInput) {
                             });
                         }
                         b'"' => {
-                            interest_str(ScanFnInput {
+                            interest_str(ScanFnInput::<FLAT> {
                                 input: i.input,
                                 scan: i.scan,
                                 offsets: i.offsets,
@@ -93,7 +108,7 @@ fn scan_block(i: ScanBlockInput) {
 
                     match curr {
                         b',' => {
-                            interest_value_elem_end(ScanFnInput {
+                            interest_value_elem_end(ScanFnInput::<FLAT> {
                                 input: i.input,
                                 scan: i.scan,
                                 offsets: i.offsets,
@@ -105,7 +120,7 @@ fn scan_block(i: ScanBlockInput) {
                             break 'num;
                         }
                         b'}' => {
-                            interest_map_end(ScanFnInput {
+                            interest_map_end(ScanFnInput::<FLAT> {
                                 input: i.input,
                                 scan: i.scan,
                                 offsets: i.offsets,
@@ -117,7 +132,7 @@ fn scan_block(i: ScanBlockInput) {
                             break 'num;
                         }
                         b']' => {
-                            interest_arr_end(ScanFnInput {
+                            interest_arr_end(ScanFnInput::<FLAT> {
                                 input: i.input,
                                 scan: i.scan,
                                 offsets: i.offsets,
@@ -141,7 +156,7 @@ fn scan_block(i: ScanBlockInput) {
 
                     match curr {
                         b',' => {
-                            interest_value_elem_end(ScanFnInput {
+                            interest_value_elem_end(ScanFnInput::<FLAT> {
                                 input: i.input,
                                 scan: i.scan,
                                 offsets: i.offsets,
@@ -153,7 +168,7 @@ fn scan_block(i: ScanBlockInput) {
                             break 'atom;
                         }
                         b'}' => {
-                            interest_map_end(ScanFnInput {
+                            interest_map_end(ScanFnInput::<FLAT> {
                                 input: i.input,
                                 scan: i.scan,
                                 offsets: i.offsets,
@@ -165,7 +180,7 @@ fn scan_block(i: ScanBlockInput) {
                             break 'atom;
                         }
                         b']' => {
-                            interest_arr_end(ScanFnInput {
+                            interest_arr_end(ScanFnInput::<FLAT> {
                                 input: i.input,
                                 scan: i.scan,
                                 offsets: i.offsets,
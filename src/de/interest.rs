@@ -2,7 +2,7 @@ use std::{borrow::BorrowMut, fmt, mem};
 
 use super::*;
 
-pub(super) struct ScanFnInput<'a, 'scan> {
+pub(super) struct ScanFnInput<'a, 'scan, const FLAT: bool> {
     /**
     The complete raw input buffer
     */
@@ -28,7 +28,7 @@ pub(super) struct ScanFnInput<'a, 'scan> {
     pub(super) offsets: &'a mut Offsets,
 }
 
-impl<'a, 'scan> fmt::Debug for ScanFnInput<'a, 'scan> {
+impl<'a, 'scan, const FLAT: bool> fmt::Debug for ScanFnInput<'a, 'scan, FLAT> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("ScanFnInput")
             .field("scan", &self.scan)
@@ -38,15 +38,18 @@ impl<'a, 'scan> fmt::Debug for ScanFnInput<'a, 'scan> {
     }
 }
 
-impl<'a, 'scan> ScanFnInput<'a, 'scan> {
+impl<'a, 'scan, const FLAT: bool> ScanFnInput<'a, 'scan, FLAT> {
     /**
     Begin a map by pushing to the stack.
     */
     #[inline(always)]
     fn map_begin(&mut self) {
+        let raw_offset = self.curr_offset as InputPos;
+
         self.begin(|start_from_offset| ActiveMapArr {
             active_primitive: Default::default(),
             start_from_offset,
+            raw_offset,
             len: 0,
             parts: [Part::Key, Part::Value],
             prev_part_offsets: [None; 4],
@@ -58,9 +61,12 @@ impl<'a, 'scan> ScanFnInput<'a, 'scan> {
     */
     #[inline(always)]
     fn arr_begin(&mut self) {
+        let raw_offset = self.curr_offset as InputPos;
+
         self.begin(|start_from_offset| ActiveMapArr {
             active_primitive: Default::default(),
             start_from_offset,
+            raw_offset,
             len: 0,
             parts: [Part::Elem, Part::Elem],
             prev_part_offsets: [None; 4],
@@ -72,13 +78,9 @@ impl<'a, 'scan> ScanFnInput<'a, 'scan> {
     */
     #[inline(always)]
     fn map_end(&mut self) {
-        self.end(|len| {
-            // the map len is the number of entries
-            // using `x >> 1` on a non-negative int is the same `floor(x / 2)`, but much faster
-            // ignoring any mismatched pairs makes it safe to assume any map
-            // with a non-zero length has at least one valid entry
-            OffsetKind::Map(len >> 1)
-        });
+        // the map len is the number of parts, rather than entries
+        // so that a trailing key without a value can be detected
+        self.end(OffsetKind::Map);
     }
 
     /**
@@ -89,36 +91,114 @@ impl<'a, 'scan> ScanFnInput<'a, 'scan> {
         self.end(OffsetKind::Arr);
     }
 
+    /**
+    Complete any maps or arrays that are still open, popping them all from the stack.
+
+    The containers don't have a raw span because they weren't terminated.
+    */
+    #[cold]
+    pub(super) fn close_all(&mut self) {
+        const NO_RAW: Slice = Slice { offset: 0, len: 0 };
+
+        // containers without a raw span can't be kept lazily
+        self.scan.lazy_level = usize::MAX;
+
+        while !self.scan.stack.is_root() {
+            match self.scan.stack.active_map_arr.parts {
+                [Part::Key, Part::Value] => self.end(|len, _| OffsetKind::Map(len, NO_RAW)),
+                _ => self.end(|len, _| OffsetKind::Arr(len, NO_RAW)),
+            }
+        }
+    }
+
     #[inline(always)]
     fn begin(&mut self, f: impl FnOnce(u16) -> ActiveMapArr) {
+        let start_from_offset = self.offsets.elements.len() as u16;
+
+        if FLAT {
+            // flat documents only ever have a single map or array open within the root
+            // so the root is kept in its own slot instead of being pushed onto the stack
+            if self.scan.stack.flat_root.is_some() {
+                self.err(ProblemKind::TooDeep);
+                return;
+            }
+
+            self.scan.stack.flat_root = Some(mem::replace(
+                &mut self.scan.stack.active_map_arr,
+                f(start_from_offset),
+            ));
+
+            self.offsets.max_depth = 1;
+            return;
+        }
+
         // put a hard limit on the depth of the stack
         // since 1 byte of input can cause a 20+byte allocation
         // we don't want to get into any potential OOM situations
         if self.scan.stack.bottom.len() > Stack::MAX_DEPTH {
-            self.err();
+            self.err(ProblemKind::TooDeep);
             return;
         }
 
-        let start_from_offset = self.offsets.elements.len() as u16;
-
         self.scan.stack.bottom.push(mem::replace(
             &mut self.scan.stack.active_map_arr,
             f(start_from_offset),
         ));
+
+        // the depth is bounded by `Stack::MAX_DEPTH` so it always fits
+        let depth = self.scan.stack.bottom.len() as u16;
+        if depth > self.offsets.max_depth {
+            self.offsets.max_depth = depth;
+        }
     }
 
     #[inline(always)]
-    fn end(&mut self, f: impl FnOnce(u16) -> OffsetKind) {
-        if let Some(last) = self.scan.stack.bottom.pop() {
+    fn end(&mut self, f: impl FnOnce(u16, Slice) -> OffsetKind) {
+        let last = if FLAT {
+            self.scan.stack.flat_root.take()
+        } else {
+            self.scan.stack.bottom.pop()
+        };
+
+        if let Some(last) = last {
             let start = self.scan.stack.active_map_arr.start_from_offset as usize - 1;
             let len = self.scan.stack.active_map_arr.len;
 
+            // the raw span includes the trailing `}` or `]`
+            let raw_offset = self.scan.stack.active_map_arr.raw_offset;
+            let raw = Slice {
+                offset: raw_offset,
+                len: self.curr_offset as InputPos + 1 - raw_offset,
+            };
+
+            self.scan
+                .stack
+                .active_map_arr
+                .link_last_parts(&mut self.offsets.elements);
             self.scan.stack.active_map_arr = last;
 
             // record whether or not the complex type contains any data
-            get_unchecked_mut!(&mut self.offsets.elements, start).kind = f(len);
+            let mut kind = f(len, raw);
+
+            // containers at the lazy depth are kept as raw spans instead
+            // any containers nested within them have already been finished
+            let depth = if FLAT {
+                1
+            } else {
+                self.scan.stack.bottom.len() + 1
+            };
+
+            if depth == self.scan.lazy_level {
+                kind = lazy(kind);
+
+                // the contents are still counted even though they're not kept
+                self.offsets.lazy_elements += self.offsets.elements.len() - (start + 1);
+                self.offsets.elements.truncate(start + 1);
+            }
+
+            get_unchecked_mut!(&mut self.offsets.elements, start).kind = kind;
         } else {
-            self.err();
+            self.err(ProblemKind::Unbalanced);
         }
     }
 
@@ -129,8 +209,8 @@ impl<'a, 'scan> ScanFnInput<'a, 'scan> {
     at the end of the process.
     */
     #[cold]
-    fn err(&mut self) {
-        self.scan.error = true;
+    fn err(&mut self, kind: ProblemKind) {
+        self.scan.err(self.curr_offset, kind);
         self.scan.stack.active_map_arr.parts = [Part::None, Part::None];
         self.scan.stack.active_map_arr.prev_part_offsets = [None; 4];
 
@@ -160,10 +240,24 @@ impl<'a, 'scan> ScanFnInput<'a, 'scan> {
             kind,
             position,
             next: None,
+            prev: prev_position_offset.unwrap_or(NO_PREV),
         });
     }
 }
 
+/**
+Convert a finished map or array into a raw span.
+*/
+#[cold]
+fn lazy(kind: OffsetKind) -> OffsetKind {
+    match kind {
+        // empty maps and arrays don't have any contents to defer
+        OffsetKind::Map(len, raw) if len > 0 => OffsetKind::Lazy(raw, true),
+        OffsetKind::Arr(len, raw) if len > 0 => OffsetKind::Lazy(raw, false),
+        kind => kind,
+    }
+}
+
 impl ActiveMapArr {
     /**
     Get the position and offsets to update the next pointer in a previous part.
@@ -181,10 +275,38 @@ impl ActiveMapArr {
 
         (curr_position, prev_position_offset)
     }
+
+    /**
+    Point the first part at each position back to the last one.
+
+    This is done once the map or array is finished, when its last parts are known.
+    */
+    #[inline(always)]
+    pub(super) fn link_last_parts(&self, elements: &mut [Offset]) {
+        let first = self.start_from_offset as usize;
+
+        // maps have keys and values, arrays only have elements
+        let [_, key, value, elem] = self.prev_part_offsets;
+
+        if let Some(last) = key.or(elem) {
+            get_unchecked_mut!(elements, first).prev = last;
+        }
+
+        if let Some(last) = value {
+            get_unchecked_mut!(elements, first + 1).prev = last;
+        }
+    }
 }
 
 #[inline(always)]
-pub(super) fn match_interest<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn match_interest<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     match i.curr {
@@ -201,7 +323,14 @@ pub(super) fn match_interest<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mu
 }
 
 #[inline(always)]
-pub(super) fn match_primitive<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn match_primitive<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     match i.curr {
@@ -215,7 +344,14 @@ pub(super) fn match_primitive<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(m
 }
 
 #[inline(always)]
-pub(super) fn interest_str<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_str<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     // if the string is escaped, then return
@@ -240,8 +376,8 @@ pub(super) fn interest_str<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut
 
             i.push(OffsetKind::Str(
                 Slice {
-                    offset: start as u32,
-                    len: (end - start) as u32,
+                    offset: start as InputPos,
+                    len: (end - start) as InputPos,
                 },
                 escaped,
             ));
@@ -263,7 +399,14 @@ pub(super) fn interest_str<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut
 }
 
 #[inline(always)]
-pub(super) fn interest_escape<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_escape<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     let escaped = i.scan.escape;
@@ -274,6 +417,11 @@ pub(super) fn interest_escape<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(m
         // the escape bit
         interest_unescape_now(i)
     } else {
+        #[cfg(any(test, feature = "metrics"))]
+        {
+            i.scan.metrics.escapes += 1;
+        }
+
         // peek the escape char
         i.curr_offset += 1;
         i.curr = *get_unchecked!(i.input, i.curr_offset);
@@ -289,7 +437,14 @@ pub(super) fn interest_escape<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(m
 }
 
 #[inline(always)]
-pub(super) fn interest_unescape_now<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_unescape_now<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     // shift to the next quote or escape
@@ -302,7 +457,14 @@ pub(super) fn interest_unescape_now<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'sca
 }
 
 #[inline(always)]
-pub(super) fn interest_unescape_later<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut _i: I) {
+pub(super) fn interest_unescape_later<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut _i: I,
+) {
     let _i = _i.borrow_mut();
 
     test_assert_eq!(
@@ -312,7 +474,14 @@ pub(super) fn interest_unescape_later<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 's
 }
 
 #[inline(always)]
-pub(super) fn interest_num_begin<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_num_begin<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     test_assert_eq!(
@@ -328,7 +497,14 @@ pub(super) fn interest_num_begin<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>
 }
 
 #[inline(always)]
-pub(super) fn interest_num_end<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_num_end<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     if let ActivePrimitive {
@@ -341,14 +517,21 @@ pub(super) fn interest_num_end<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(
         let end = i.curr_offset;
 
         i.push(OffsetKind::Num(Slice {
-            offset: start as u32,
-            len: (end - start) as u32,
+            offset: start as InputPos,
+            len: (end - start) as InputPos,
         }));
     }
 }
 
 #[inline(always)]
-pub(super) fn interest_null<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_null<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     test_assert_eq!(
@@ -357,11 +540,22 @@ pub(super) fn interest_null<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut
     );
     i.scan.stack.active_map_arr.active_primitive.kind = ActivePrimitiveKind::Atom;
 
+    if i.scan.warn || i.scan.verify_atoms {
+        i.check_atom(b"null");
+    }
+
     i.push(OffsetKind::Null);
 }
 
 #[inline(always)]
-pub(super) fn interest_true<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_true<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     test_assert_eq!(
@@ -370,11 +564,22 @@ pub(super) fn interest_true<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut
     );
     i.scan.stack.active_map_arr.active_primitive.kind = ActivePrimitiveKind::Atom;
 
+    if i.scan.warn || i.scan.verify_atoms {
+        i.check_atom(b"true");
+    }
+
     i.push(OffsetKind::Bool(true));
 }
 
 #[inline(always)]
-pub(super) fn interest_false<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_false<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     test_assert_eq!(
@@ -383,11 +588,22 @@ pub(super) fn interest_false<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mu
     );
     i.scan.stack.active_map_arr.active_primitive.kind = ActivePrimitiveKind::Atom;
 
+    if i.scan.warn || i.scan.verify_atoms {
+        i.check_atom(b"false");
+    }
+
     i.push(OffsetKind::Bool(false));
 }
 
 #[inline(always)]
-pub(super) fn interest_map_begin<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_map_begin<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     test_assert_eq!(
@@ -395,12 +611,19 @@ pub(super) fn interest_map_begin<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>
         ActivePrimitiveKind::None
     );
 
-    i.push(OffsetKind::Map(0));
+    i.push(OffsetKind::Map(0, Slice { offset: 0, len: 0 }));
     i.map_begin();
 }
 
 #[inline(always)]
-pub(super) fn interest_arr_begin<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_arr_begin<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     test_assert_eq!(
@@ -408,13 +631,20 @@ pub(super) fn interest_arr_begin<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>
         ActivePrimitiveKind::None
     );
 
-    i.push(OffsetKind::Arr(0));
+    i.push(OffsetKind::Arr(0, Slice { offset: 0, len: 0 }));
     i.arr_begin();
     interest_key_elem_begin(i);
 }
 
 #[inline(always)]
-pub(super) fn interest_key_elem_begin<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_key_elem_begin<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     test_assert_eq!(
@@ -431,7 +661,14 @@ pub(super) fn interest_key_elem_begin<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 's
 }
 
 #[inline(always)]
-pub(super) fn interest_key_end<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_key_end<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     test_assert_eq!(
@@ -447,7 +684,14 @@ pub(super) fn interest_key_end<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(
 }
 
 #[inline(always)]
-pub(super) fn interest_value_elem_end<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_value_elem_end<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
     interest_num_end(&mut *i);
@@ -461,11 +705,46 @@ pub(super) fn interest_value_elem_end<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 's
     i.curr_offset += 1;
     i.curr = *get_unchecked!(i.input, i.curr_offset);
 
-    match_primitive(i);
+    match i.curr {
+        // the map or array ends right after the `,`
+        b'}' | b']' => interest_trailing_comma(i),
+        _ => {
+            // the value before the `,` is finished, so it can be captured
+            if i.captures_root_value() {
+                i.capture_root_value();
+            }
+
+            match_primitive(i)
+        }
+    }
+}
+
+#[cold]
+pub(super) fn interest_trailing_comma<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
+    let i = i.borrow_mut();
+
+    // the `}` or `]` is handled as usual, so the trailing comma is simply ignored
+    if !i.scan.allow_trailing_commas {
+        i.scan.err(i.curr_offset - 1, ProblemKind::TrailingComma);
+    }
 }
 
 #[inline(always)]
-pub(super) fn interest_map_end<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_map_end<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
     interest_num_end(&mut *i);
 
@@ -478,7 +757,14 @@ pub(super) fn interest_map_end<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(
 }
 
 #[inline(always)]
-pub(super) fn interest_arr_end<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_arr_end<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
     interest_num_end(&mut *i);
 
@@ -491,7 +777,14 @@ pub(super) fn interest_arr_end<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(
 }
 
 #[inline(always)]
-pub(super) fn interest_none<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut _i: I) {
+pub(super) fn interest_none<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut _i: I,
+) {
     let _i = _i.borrow_mut();
 
     test_assert_eq!(
@@ -501,10 +794,17 @@ pub(super) fn interest_none<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut
 }
 
 #[cold]
-pub(super) fn interest_unreachable<'a, 'scan, I: BorrowMut<ScanFnInput<'a, 'scan>>>(mut i: I) {
+pub(super) fn interest_unreachable<
+    'a,
+    'scan,
+    const FLAT: bool,
+    I: BorrowMut<ScanFnInput<'a, 'scan, FLAT>>,
+>(
+    mut i: I,
+) {
     let i = i.borrow_mut();
 
-    i.scan.error = true;
+    i.scan.err(i.curr_offset, ProblemKind::Unexpected);
 
     test_unreachable!(
         "unexpected {:?} at offset {:?}",
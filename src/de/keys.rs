@@ -0,0 +1,264 @@
+/*!
+A fast scan for only the keys at the root of a document.
+
+Some callers only need to know which keys a document has, like when discovering its schema
+or deciding where to route it. This scan skips over values without classifying them or
+recording any offsets, only keeping track of how deeply nested it is and whether it's in a string.
+
+When SIMD is available, the scan uses the same quote, escape, and interest masks as the
+regular scanner to skip over whole runs of string contents and nested values at a time.
+*/
+
+use smallvec::SmallVec;
+
+use super::{leading_whitespace, scan_begin, Document, ScanError, ScanErrorKind, Str};
+
+use crate::{escape::escape_str, find::find};
+
+// most documents have few enough keys at their root to keep them inline
+const INLINE_KEYS: usize = 16;
+
+impl<'input> Document<'input> {
+    /**
+    Scan a JSON object byte buffer for only the keys at its root.
+
+    Keys are returned in the order they appear in the input, including any duplicates.
+    The input is validated as UTF8, but values are otherwise skipped over without checking them.
+    If the input is truncated, or its maps and arrays are unbalanced, then an error is returned.
+    */
+    pub fn scan_keys(
+        input: &'input [u8],
+    ) -> Result<SmallVec<[Str<'input>; INLINE_KEYS]>, ScanError> {
+        let err = |valid_up_to| ScanError {
+            valid_up_to,
+            kind: ScanErrorKind::Invalid,
+        };
+
+        let bounds = scan_begin(input).map_err(|problem| ScanError {
+            valid_up_to: problem.input_offset(),
+            kind: ScanErrorKind::from_root_problem(input, &problem),
+        })?;
+        if let Some(problem) = bounds.err {
+            return Err(err(problem.input_offset()));
+        }
+
+        let mut scan = KeyScan::new(bounds.start as usize, bounds.end);
+
+        scan_keys(input, &mut scan).map_err(err)?;
+
+        // strings and maps and arrays that are still open at the end are truncated
+        if let Some(str_start) = scan.str_start {
+            return Err(err(str_start));
+        }
+
+        if scan.depth != 0 {
+            return Err(err(scan.end));
+        }
+
+        Ok(scan.keys)
+    }
+
+    /**
+    Check whether a JSON object byte buffer has a key at its root, without scanning it.
+
+    The input is searched for the key as a quoted string followed by a `:`. Only matches
+    that are at the root, and not within some other string, are considered. Everything
+    up to a match is walked through to find that out. Nothing after a match is looked at.
+
+    The key is matched against the input as-is, escaped the same way a [`Writer`] escapes it.
+    Keys that are escaped differently in the input, like `"\u0061"` instead of `"a"`,
+    aren't found. The input isn't validated, so if it isn't a valid JSON object then the
+    result is unspecified, but this method won't panic.
+
+    [`Writer`]: crate::ser::Writer
+    */
+    pub fn contains_key_raw(input: &[u8], key: &str) -> bool {
+        // leading whitespace is skipped the same way the scanner skips it
+        let start = leading_whitespace(input);
+
+        if input.get(start) != Some(&b'{') {
+            return false;
+        }
+
+        let mut needle = Vec::with_capacity(key.len() + 3);
+        escape_str(&mut needle, key);
+        needle.push(b':');
+
+        // the root map's `{` is skipped, so the root is at a depth of 0
+        let mut input_offset = start + 1;
+        let mut depth = 0usize;
+        let mut in_str = false;
+
+        let mut search_offset = start + 1;
+
+        while let Some(candidate) = find(input, &needle, search_offset) {
+            // walk up to the candidate to find out where it is
+            while input_offset < candidate {
+                match (in_str, *get_unchecked!(input, input_offset)) {
+                    (true, b'"') => in_str = false,
+                    // skip over the escaped character
+                    (true, b'\\') => input_offset += 1,
+                    (true, _) => (),
+                    (false, b'"') => in_str = true,
+                    (false, b'{' | b'[') => depth += 1,
+                    (false, b'}' | b']') => depth = depth.saturating_sub(1),
+                    (false, _) => (),
+                }
+
+                input_offset += 1;
+            }
+
+            // if the candidate's `"` was escaped then it was skipped over
+            if input_offset == candidate && !in_str && depth == 0 {
+                return true;
+            }
+
+            search_offset = candidate + 1;
+        }
+
+        false
+    }
+}
+
+/**
+Scan the input for the keys at its root, using SIMD if it's available and worthwhile.
+*/
+#[inline]
+fn scan_keys<'input>(input: &'input [u8], scan: &mut KeyScan<'input>) -> Result<(), usize> {
+    // HEURISTIC: small documents aren't worth vectorizing
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2")
+            && scan.end - scan.input_offset > super::simd::X86_64_AVX2_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: avx2 is available
+            return unsafe { super::simd::scan_keys_x86_64_avx2(input, scan) };
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon")
+            && scan.end - scan.input_offset > super::simd::AARCH64_NEON_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: neon is available
+            return unsafe { super::simd::scan_keys_aarch64_neon(input, scan) };
+        }
+    }
+
+    // when SIMD is not available, we need to fallback
+    scan.scan_to(input, scan.end)
+}
+
+/**
+The state of a scan for the keys at the root of a document.
+
+Only quotes and escapes matter within strings, and only structural characters outside of them,
+so the scan can be given just those characters and skip over everything else.
+*/
+#[derive(Debug)]
+pub(super) struct KeyScan<'input> {
+    pub(super) input_offset: usize,
+    pub(super) end: usize,
+    depth: usize,
+    expect_key: bool,
+    // the offset of the opening quote of the string being scanned
+    str_start: Option<usize>,
+    escaped: bool,
+    // characters before this offset have been escaped
+    skip_to: usize,
+    keys: SmallVec<[Str<'input>; INLINE_KEYS]>,
+}
+
+impl<'input> KeyScan<'input> {
+    #[inline]
+    fn new(start: usize, end: usize) -> Self {
+        KeyScan {
+            input_offset: start,
+            end,
+            depth: 0,
+            expect_key: true,
+            str_start: None,
+            escaped: false,
+            skip_to: start,
+            keys: SmallVec::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub(super) fn in_str(&self) -> bool {
+        self.str_start.is_some()
+    }
+
+    /**
+    Scan byte-by-byte up to an offset, or the end of the input.
+    */
+    #[inline]
+    pub(super) fn scan_to(&mut self, input: &'input [u8], to: usize) -> Result<(), usize> {
+        let to = to.min(self.end);
+
+        while self.input_offset < to {
+            self.step(
+                input,
+                self.input_offset,
+                *get_unchecked!(input, self.input_offset),
+            )?;
+            self.input_offset += 1;
+        }
+
+        Ok(())
+    }
+
+    /**
+    Advance the scan past a character.
+
+    If the character closes a map or array that was never opened then its offset is returned.
+    */
+    #[inline(always)]
+    pub(super) fn step(
+        &mut self,
+        input: &'input [u8],
+        curr_offset: usize,
+        curr: u8,
+    ) -> Result<(), usize> {
+        if curr_offset < self.skip_to {
+            return Ok(());
+        }
+
+        match (self.str_start, curr) {
+            (Some(str_start), b'"') => {
+                if self.depth == 0 && self.expect_key {
+                    // the quotes are ASCII, so the string is on a UTF8 boundary
+                    let key =
+                        from_utf8_unchecked!(get_unchecked!(input, str_start..curr_offset + 1));
+
+                    self.keys.push(Str(key, self.escaped));
+                    self.expect_key = false;
+                }
+
+                self.str_start = None;
+            }
+            // skip over the escaped character
+            (Some(_), b'\\') => {
+                self.escaped = true;
+                self.skip_to = curr_offset + 2;
+            }
+            (Some(_), _) => (),
+            (None, b'"') => {
+                self.str_start = Some(curr_offset);
+                self.escaped = false;
+            }
+            (None, b'{' | b'[') => self.depth += 1,
+            (None, b'}' | b']') => {
+                if self.depth == 0 {
+                    return Err(curr_offset);
+                }
+
+                self.depth -= 1;
+            }
+            (None, b',') if self.depth == 0 => self.expect_key = true,
+            (None, _) => (),
+        }
+
+        Ok(())
+    }
+}
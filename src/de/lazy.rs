@@ -0,0 +1,123 @@
+/*!
+Maps and arrays that are kept as raw spans until they're needed.
+
+When documents are scanned with [`ScanOptions::lazy_depth`], containers below that depth
+are recorded as a single raw span instead of all of their contents. They can be scanned on
+demand, borrowing from the same input, whenever a query needs to look inside them.
+*/
+
+use std::fmt;
+
+use super::{
+    scan, Bounds, DetachedDocument, Document, InputPos, Kind, KindTag, ScanOptions, Slice,
+};
+
+/**
+A map or array that was kept as a raw span instead of being scanned.
+*/
+#[derive(Clone, Copy)]
+pub struct Lazy<'input> {
+    pub(super) raw: &'input str,
+    pub(super) map: bool,
+}
+
+impl<'input> fmt::Debug for Lazy<'input> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Lazy").field(&self.raw).finish()
+    }
+}
+
+impl<'input> Lazy<'input> {
+    /**
+    Whether the span is a map or an array.
+    */
+    #[inline]
+    pub fn kind(&self) -> KindTag {
+        if self.map {
+            KindTag::Map
+        } else {
+            KindTag::Arr
+        }
+    }
+
+    /**
+    Returns the raw JSON for the map or array, exactly as it appears in the input.
+    */
+    #[inline]
+    pub fn as_json(&self) -> &'input str {
+        self.raw
+    }
+
+    /**
+    Scan the map or array.
+
+    The span is scanned in full, so any containers within it aren't kept lazily.
+    If it can't be scanned, like when it contains more than [`Document::MAX_ELEMENTS`]
+    elements, then `None` is returned.
+    */
+    pub fn scan(&self) -> Option<NestedDocument<'input>> {
+        let input = self.raw.as_bytes();
+
+        // the span is scanned along with its own `{` or `[`, so it's the first element
+        // within the root, instead of being the root itself
+        let bounds = Bounds {
+            start: 0,
+            end: input.len(),
+            err: None,
+        };
+
+        let mut document = scan(
+            input,
+            Ok(bounds),
+            DetachedDocument::default(),
+            &ScanOptions::DEFAULT,
+        );
+
+        if document.is_err() || document.offsets.elements.is_empty() {
+            return None;
+        }
+
+        // there's no `{` and `}` around the span, so the root map spans the whole of it
+        document.offsets.to_mut().root_raw = Slice {
+            offset: 0,
+            len: input.len() as InputPos,
+        };
+
+        Some(NestedDocument { document })
+    }
+}
+
+/**
+A map or array that was scanned on demand from a raw span.
+*/
+#[derive(Debug, Clone)]
+pub struct NestedDocument<'input> {
+    pub(super) document: Document<'input>,
+}
+
+impl<'input> NestedDocument<'input> {
+    /**
+    Get a view of the scanned map or array.
+    */
+    #[inline]
+    pub fn as_kind(&self) -> Kind<'input, '_> {
+        let offsets: &super::Offsets = &self.document.offsets;
+
+        get_unchecked!(offsets.elements, 0).to_element(self.document.input, offsets, 0)
+    }
+}
+
+impl<'input, 'offsets> Kind<'input, 'offsets> {
+    /**
+    Scan a map or array that was kept as a raw span.
+
+    If the element isn't a [`Kind::Lazy`], or it can't be scanned, then `None` is returned.
+    */
+    #[inline]
+    pub fn scan_nested(&self) -> Option<NestedDocument<'input>> {
+        match self {
+            Kind::Lazy(lazy) => lazy.scan(),
+            _ => None,
+        }
+    }
+}
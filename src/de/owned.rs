@@ -0,0 +1,182 @@
+/*!
+Documents that own their input.
+
+Offsets only store positions in the input, so they can be kept alongside the input they
+were scanned from and re-attached to it whenever a [`Document`] is needed.
+*/
+
+use std::{borrow::Cow, fmt, str::FromStr};
+
+use crate::transcode_utf16_to_utf8;
+
+use super::{Document, Offsets, ScanError};
+
+/**
+A JSON document that owns its input buffer.
+
+Owned documents are scanned once when they're created. Borrowing them as a [`Document`]
+doesn't scan them again.
+
+Owned documents don't borrow from anything, so they can be stored in collections or sent
+between threads as a single `'static` value. They keep offsets instead of a [`Document`]
+that borrows from their own input, so they don't need to be self-referential.
+*/
+#[derive(Clone)]
+pub struct OwnedDocument {
+    input: Box<[u8]>,
+    offsets: Offsets,
+}
+
+impl fmt::Debug for OwnedDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.as_document(), f)
+    }
+}
+
+impl OwnedDocument {
+    /**
+    Scan a JSON object byte buffer into a document that owns it.
+
+    This method has the same guarantees as [`Document::scan_trusted`].
+    */
+    #[inline]
+    pub fn scan_trusted(input: impl Into<Box<[u8]>>) -> Self {
+        let input = input.into();
+        let offsets = Document::scan_trusted(&input).into_offsets().into_owned();
+
+        OwnedDocument { input, offsets }
+    }
+
+    /**
+    Transcode a UTF16 JSON object buffer into UTF8 and scan it into a document that owns it.
+
+    The input is in the native byte order. It's transcoded straight into the buffer the document
+    owns, see [`transcode_utf16_to_utf8`](crate::transcode_utf16_to_utf8) for details.
+    The transcoded input has the same guarantees as [`Document::scan_trusted`].
+    */
+    #[inline]
+    pub fn scan_utf16(input: &[u16]) -> Self {
+        let mut transcoded = Vec::new();
+        transcode_utf16_to_utf8(input, &mut transcoded);
+
+        OwnedDocument::scan_trusted(transcoded)
+    }
+
+    /**
+    Borrow the document.
+    */
+    #[inline]
+    pub fn as_document(&self) -> Document<'_> {
+        // SAFETY: the offsets were scanned from this input
+        unsafe { self.offsets.to_document_unchecked(&self.input) }
+    }
+
+    /**
+    Get the input buffer the document was scanned from.
+    */
+    #[inline]
+    pub fn input(&self) -> &[u8] {
+        &self.input
+    }
+}
+
+/**
+A JSON document that either borrows or owns its input buffer.
+
+The same type can be used for zero-copy reads over borrowed buffers and for
+detached copies that need to outlive them. Like [`OwnedDocument`], the input is
+scanned once when the document is created.
+*/
+#[derive(Clone)]
+pub struct CowDocument<'input> {
+    input: Cow<'input, [u8]>,
+    offsets: Offsets,
+}
+
+impl<'input> fmt::Debug for CowDocument<'input> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.as_document(), f)
+    }
+}
+
+impl<'input> CowDocument<'input> {
+    /**
+    Scan a JSON object byte buffer into a document that either borrows or owns it.
+
+    This method has the same guarantees as [`Document::scan_trusted`].
+    */
+    #[inline]
+    pub fn scan_trusted(input: impl Into<Cow<'input, [u8]>>) -> Self {
+        let input = input.into();
+        let offsets = Document::scan_trusted(&input).into_offsets().into_owned();
+
+        CowDocument { input, offsets }
+    }
+
+    /**
+    Borrow the document.
+    */
+    #[inline]
+    pub fn as_document(&self) -> Document<'_> {
+        // SAFETY: the offsets were scanned from this input
+        unsafe { self.offsets.to_document_unchecked(&self.input) }
+    }
+
+    /**
+    Get the input buffer the document was scanned from.
+    */
+    #[inline]
+    pub fn input(&self) -> &[u8] {
+        &self.input
+    }
+
+    /**
+    Whether or not the document borrows its input buffer.
+    */
+    #[inline]
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.input, Cow::Borrowed(_))
+    }
+
+    /**
+    Detach the document from a borrowed input buffer by copying it.
+
+    The input isn't scanned again. If the document already owns its input then it isn't copied.
+    */
+    #[inline]
+    pub fn into_owned(self) -> CowDocument<'static> {
+        CowDocument {
+            input: Cow::Owned(self.input.into_owned()),
+            offsets: self.offsets,
+        }
+    }
+}
+
+impl From<OwnedDocument> for CowDocument<'static> {
+    #[inline]
+    fn from(document: OwnedDocument) -> Self {
+        CowDocument {
+            input: Cow::Owned(document.input.into_vec()),
+            offsets: document.offsets,
+        }
+    }
+}
+
+impl FromStr for OwnedDocument {
+    type Err = ScanError;
+
+    /**
+    Scan a JSON object string into a document that owns a copy of it.
+
+    If the scanner encounters any invalid content then an error is returned.
+    */
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let owned = OwnedDocument::scan_trusted(input.as_bytes());
+
+        if let Some(err) = owned.as_document().scan_error() {
+            return Err(err);
+        }
+
+        Ok(owned)
+    }
+}
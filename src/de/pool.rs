@@ -0,0 +1,94 @@
+/*!
+A pool of allocations for documents that are only needed for a short time.
+
+A [`Scanner`](super::Scanner) reuses its allocations, but only one of its documents can be
+alive at a time. A pool hands out a separate allocation to each document it scans, and takes
+it back when the document is released. Once the pool has warmed up, a request that scans
+many short-lived documents doesn't need to go to the global allocator for any of them.
+*/
+
+use super::{DetachedDocument, Document, ScanOptions};
+
+/**
+A pool of allocations that documents can be scanned into and released back to.
+*/
+#[derive(Clone)]
+pub struct DocumentPool {
+    options: ScanOptions,
+    detached: Vec<DetachedDocument>,
+}
+
+impl Default for DocumentPool {
+    #[inline]
+    fn default() -> Self {
+        DocumentPool::new()
+    }
+}
+
+impl DocumentPool {
+    /**
+    Create an empty pool that scans documents with the default options.
+    */
+    #[inline]
+    pub fn new() -> Self {
+        DocumentPool::with_options(ScanOptions::new())
+    }
+
+    /**
+    Create an empty pool that scans documents with the given options.
+    */
+    #[inline]
+    pub fn with_options(options: ScanOptions) -> Self {
+        DocumentPool {
+            options,
+            detached: Vec::new(),
+        }
+    }
+
+    /**
+    The number of allocations waiting in the pool to be reused.
+    */
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.detached.len()
+    }
+
+    /**
+    Whether or not there are no allocations waiting in the pool.
+    */
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.detached.is_empty()
+    }
+
+    /**
+    Scan a JSON object byte buffer into an indexable document, using an allocation
+    from the pool if there is one.
+
+    This method has the same guarantees as [`Document::scan_trusted`].
+    */
+    #[inline]
+    pub fn scan<'input>(&mut self, input: &'input [u8]) -> Document<'input> {
+        let detached = self.detached.pop().unwrap_or_default();
+
+        Document::scan_trusted_attach_with(input, detached, &self.options)
+    }
+
+    /**
+    Release a document's allocation back into the pool so it can be reused.
+
+    The document doesn't need to have been scanned by the pool.
+    */
+    #[inline]
+    pub fn release(&mut self, document: Document) {
+        self.detached.push(document.detach());
+    }
+
+    /**
+    Drop all of the allocations waiting in the pool.
+    */
+    #[inline]
+    pub fn clear(&mut self) {
+        self.detached.clear();
+    }
+}
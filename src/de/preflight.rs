@@ -0,0 +1,94 @@
+/*!
+A cheap check for whether an input meets the conditions the scanner relies on.
+
+The scanner is fast because it trusts its input to be a single minified object. Callers that
+can't guarantee that up-front can use this check to route non-conforming inputs somewhere else
+before scanning them. Like scanning keys, it only keeps track of how deeply nested it is and
+whether it's in a string, so it doesn't record any offsets.
+*/
+
+use super::{scan_begin, Document, Problem, ProblemKind, Stack};
+
+impl<'input> Document<'input> {
+    /**
+    Check whether an input is a single minified JSON object that can be scanned as expected.
+
+    The input must be valid UTF8, begin with a `{`, and end with its matching `}`, surrounded
+    by nothing but whitespace. There must be no whitespace between its tokens, and its maps
+    and arrays must be balanced and not nested too deeply. Values themselves aren't validated,
+    so passing this check doesn't guarantee the input is valid JSON.
+
+    If the input doesn't meet these conditions then the first problem found is returned.
+    */
+    pub fn check_scannable(input: &[u8]) -> Result<(), Problem> {
+        let bounds = scan_begin(input)?;
+        if let Some(problem) = bounds.err {
+            return Err(problem);
+        }
+
+        let end = bounds.end;
+        let mut input_offset = bounds.start as usize;
+
+        let mut depth = 0usize;
+
+        while input_offset < end {
+            match *get_unchecked!(input, input_offset) {
+                b'"' => {
+                    let str_start = input_offset;
+
+                    input_offset += 1;
+
+                    // find the end of the string, skipping over escaped characters
+                    loop {
+                        if input_offset >= end {
+                            return Err(Problem::new(ProblemKind::Truncated, str_start));
+                        }
+
+                        match *get_unchecked!(input, input_offset) {
+                            b'"' => break,
+                            b'\\' => input_offset += 2,
+                            _ => input_offset += 1,
+                        }
+                    }
+                }
+                b'{' | b'[' => {
+                    // the depth is checked the same way as the scanner checks it
+                    if depth > Stack::MAX_DEPTH {
+                        return Err(Problem::new(ProblemKind::TooDeep, input_offset));
+                    }
+
+                    depth += 1;
+                }
+                b'}' | b']' => {
+                    if depth == 0 {
+                        return Err(Problem::new(ProblemKind::Unbalanced, input_offset));
+                    }
+
+                    depth -= 1;
+                }
+                b' ' | b'\t' | b'\n' | b'\r' => {
+                    return Err(Problem::new(ProblemKind::Unexpected, input_offset));
+                }
+                _ => (),
+            }
+
+            input_offset += 1;
+        }
+
+        if depth != 0 {
+            return Err(Problem::new(ProblemKind::Truncated, end));
+        }
+
+        Ok(())
+    }
+
+    /**
+    Whether an input is a single minified JSON object that can be scanned as expected.
+
+    See [`Document::check_scannable`] for details.
+    */
+    #[inline]
+    pub fn is_minified_object(input: &[u8]) -> bool {
+        Document::check_scannable(input).is_ok()
+    }
+}
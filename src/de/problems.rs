@@ -0,0 +1,195 @@
+/*!
+The problems that made a document erroneous.
+
+A document only reports whether or not it's erroneous, and where the first error was detected.
+The scanner doesn't stop at the first error though, so it can also collect each problem it
+finds along the way into a side buffer. That buffer is bounded so a badly broken input can't
+make the scanner allocate without limit.
+
+Problems are collected as they're detected, so their order depends on the backend used to scan.
+They're sorted by their offset in the input once scanning is finished.
+*/
+
+use std::slice;
+
+/**
+The problems collected while scanning a document.
+*/
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+#[derive(Debug, Clone, Default)]
+pub struct ScanProblems {
+    problems: Vec<Problem>,
+    max: usize,
+    dropped: usize,
+}
+
+/**
+A problem in the input of a document that made it erroneous.
+*/
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Problem {
+    kind: ProblemKind,
+    input_offset: usize,
+}
+
+/**
+The kind of problem a document has.
+*/
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProblemKind {
+    /**
+    The input isn't a JSON object.
+    */
+    NotAnObject,
+    /**
+    The input is longer than [`Document::MAX_INPUT_LEN`](super::Document::MAX_INPUT_LEN).
+    */
+    InputTooLarge,
+    /**
+    The input contains invalid UTF8.
+    */
+    InvalidUtf8,
+    /**
+    The input ends in the middle of a value, map, or array.
+    */
+    Truncated,
+    /**
+    A character appears somewhere it isn't expected, like whitespace between values.
+    */
+    Unexpected,
+    /**
+    A map or array is terminated without having been started.
+    */
+    Unbalanced,
+    /**
+    Maps and arrays are nested too deeply.
+    */
+    TooDeep,
+    /**
+    An atom isn't exactly `true`, `false`, or `null`.
+
+    This is only a problem when atoms are verified.
+    */
+    InvalidAtom,
+    /**
+    A map or array has a `,` after its last entry or element.
+
+    This is only a problem when trailing commas aren't allowed.
+    */
+    TrailingComma,
+    /**
+    The input contains more than [`Document::MAX_ELEMENTS`](super::Document::MAX_ELEMENTS) elements.
+    */
+    TooManyElements,
+}
+
+impl ScanProblems {
+    pub(super) const fn new(max: usize) -> Self {
+        ScanProblems {
+            problems: Vec::new(),
+            max,
+            dropped: 0,
+        }
+    }
+
+    pub(super) fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Problem>() * self.problems.capacity()
+    }
+
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.problems.shrink_to_fit();
+    }
+
+    /**
+    Whether or not any problems were collected.
+    */
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /**
+    The number of problems collected.
+    */
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.problems.len()
+    }
+
+    /**
+    The number of problems that were found, but not collected because the buffer was full.
+    */
+    #[inline]
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /**
+    Iterate over the problems in the order they appear in the input.
+    */
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, Problem> {
+        self.problems.iter()
+    }
+
+    #[cold]
+    pub(super) fn push(&mut self, kind: ProblemKind, input_offset: usize) {
+        if self.problems.len() < self.max {
+            self.problems.push(Problem { kind, input_offset });
+        } else if self.max > 0 {
+            self.dropped += 1;
+        }
+    }
+
+    #[cold]
+    pub(super) fn finish(&mut self) {
+        self.problems.sort_by_key(|problem| problem.input_offset);
+    }
+}
+
+impl<'a> IntoIterator for &'a ScanProblems {
+    type Item = &'a Problem;
+    type IntoIter = slice::Iter<'a, Problem>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Problem {
+    pub(super) const fn new(kind: ProblemKind, input_offset: usize) -> Self {
+        Problem { kind, input_offset }
+    }
+
+    /**
+    The kind of problem this is.
+    */
+    #[inline]
+    pub fn kind(&self) -> ProblemKind {
+        self.kind
+    }
+
+    /**
+    The offset in the input where the problem was detected.
+    */
+    #[inline]
+    pub fn input_offset(&self) -> usize {
+        self.input_offset
+    }
+}
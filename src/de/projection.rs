@@ -0,0 +1,245 @@
+/*!
+Project documents into structs in a single pass over their root map.
+
+Projections are usually implemented using `#[derive(FromDocument)]`, which is available
+when the `derive` feature is enabled. The derive generates a single call to [`Map::extract`]
+with the key of each field, and converts each value using [`FromKind`].
+
+Values can also be converted using `TryFrom`, so they can be used with code that's generic
+over the standard conversion traits.
+*/
+
+use std::{borrow::Cow, error, fmt};
+
+use super::{Document, Kind, KindTag, Map, Str};
+
+/**
+A type that can be projected from the root map of a document.
+*/
+pub trait FromDocument<'input>: Sized {
+    /**
+    Project the entries of a map into a value.
+    */
+    fn from_map(map: &Map<'input, '_>) -> Result<Self, ProjectionError>;
+}
+
+/**
+A type that can be converted from the value of an entry in a map.
+*/
+pub trait FromKind<'input>: Sized {
+    /**
+    Convert the value of an entry, which is `None` if the entry doesn't exist.
+
+    If the value can't be converted then this method returns `None`.
+    */
+    fn from_kind(kind: Option<Kind<'input, '_>>) -> Option<Self>;
+}
+
+/**
+An error attempting to project a document.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectionError {
+    key: String,
+}
+
+impl ProjectionError {
+    /**
+    Create an error for a key that's missing or has a value that can't be converted.
+    */
+    #[inline]
+    pub fn new(key: impl Into<String>) -> Self {
+        ProjectionError { key: key.into() }
+    }
+
+    /**
+    The key that's missing or has a value that can't be converted.
+    */
+    #[inline]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl fmt::Display for ProjectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "missing or invalid value for key `{}`", self.key)
+    }
+}
+
+impl error::Error for ProjectionError {}
+
+/**
+An error attempting to convert a value using `TryFrom`.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError {
+    kind: KindTag,
+}
+
+impl ConversionError {
+    /**
+    The kind of the value that couldn't be converted.
+    */
+    #[inline]
+    pub fn kind(&self) -> KindTag {
+        self.kind
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid value of kind `{:?}`", self.kind)
+    }
+}
+
+impl error::Error for ConversionError {}
+
+impl<'input> Document<'input> {
+    /**
+    Project the document into a value.
+    */
+    #[inline]
+    pub fn project<T>(&self) -> Result<T, ProjectionError>
+    where
+        T: FromDocument<'input>,
+    {
+        T::from_map(&self.as_map())
+    }
+}
+
+impl<'input> FromKind<'input> for Str<'input> {
+    #[inline]
+    fn from_kind(kind: Option<Kind<'input, '_>>) -> Option<Self> {
+        kind?.as_str()
+    }
+}
+
+impl<'input> FromKind<'input> for &'input str {
+    /**
+    Borrow a string from the input.
+
+    Strings that are escaped can't be borrowed, so they can't be converted.
+    Use [`Str`] or `Cow<str>` for strings that may be escaped.
+    */
+    #[inline]
+    fn from_kind(kind: Option<Kind<'input, '_>>) -> Option<Self> {
+        match kind?.as_str()? {
+            s if !s.1 => Some(s.as_raw()),
+            _ => None,
+        }
+    }
+}
+
+impl<'input> FromKind<'input> for Cow<'input, str> {
+    #[inline]
+    fn from_kind(kind: Option<Kind<'input, '_>>) -> Option<Self> {
+        Some(kind?.as_str()?.to_unescaped())
+    }
+}
+
+impl<'input> FromKind<'input> for String {
+    #[inline]
+    fn from_kind(kind: Option<Kind<'input, '_>>) -> Option<Self> {
+        Some(kind?.as_str()?.to_unescaped().into_owned())
+    }
+}
+
+impl<'input> FromKind<'input> for bool {
+    #[inline]
+    fn from_kind(kind: Option<Kind<'input, '_>>) -> Option<Self> {
+        match kind? {
+            Kind::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_from_kind_num {
+    ($($ty:ty),*) => {
+        $(
+            impl<'input> FromKind<'input> for $ty {
+                #[inline]
+                fn from_kind(kind: Option<Kind<'input, '_>>) -> Option<Self> {
+                    match kind? {
+                        Kind::Num(n) => n.parse().ok(),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_kind_num!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+impl<'input, T> FromKind<'input> for Option<T>
+where
+    T: FromKind<'input>,
+{
+    /**
+    Convert a value that may be missing or `null`.
+
+    Values that are present but can't be converted still can't be converted.
+    */
+    #[inline]
+    fn from_kind(kind: Option<Kind<'input, '_>>) -> Option<Self> {
+        match kind {
+            None | Some(Kind::Null) => Some(None),
+            kind => T::from_kind(kind).map(Some),
+        }
+    }
+}
+
+macro_rules! impl_try_from_kind {
+    ($($ty:ty),*) => {
+        $(
+            impl<'input, 'offsets> TryFrom<Kind<'input, 'offsets>> for $ty {
+                type Error = ConversionError;
+
+                #[inline]
+                fn try_from(kind: Kind<'input, 'offsets>) -> Result<Self, Self::Error> {
+                    let err = ConversionError {
+                        kind: kind_tag(&kind),
+                    };
+
+                    <$ty>::from_kind(Some(kind)).ok_or(err)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_kind!(i64, u64, f64, bool, String);
+
+impl<'input, 'offsets> TryFrom<Kind<'input, 'offsets>> for &'input str {
+    type Error = ConversionError;
+
+    /**
+    Borrow a string from the input.
+
+    Strings that are escaped can't be borrowed, so they can't be converted.
+    Use `String` for strings that may be escaped.
+    */
+    #[inline]
+    fn try_from(kind: Kind<'input, 'offsets>) -> Result<Self, Self::Error> {
+        let err = ConversionError {
+            kind: kind_tag(&kind),
+        };
+
+        <&'input str>::from_kind(Some(kind)).ok_or(err)
+    }
+}
+
+#[inline]
+fn kind_tag(kind: &Kind) -> KindTag {
+    match kind {
+        Kind::Str(_) => KindTag::Str,
+        Kind::Num(_) => KindTag::Num,
+        Kind::Bool(_) => KindTag::Bool,
+        Kind::Null => KindTag::Null,
+        Kind::Map(_) => KindTag::Map,
+        Kind::Arr(_) => KindTag::Arr,
+        Kind::Lazy(lazy) => lazy.kind(),
+    }
+}
@@ -0,0 +1,525 @@
+/*!
+Queries that walk through the whole tree of a document.
+
+Documents aren't recursive, so walking them doesn't need to be either. The walker keeps
+its own stack of iterators along with the path to the element it's currently on.
+*/
+
+use std::fmt::{self, Write};
+
+use super::{Document, Elems, Entries, Kind, OffsetKind, Str};
+
+/**
+A path to an element within a document.
+
+The path is formatted as a [JSON Pointer](https://datatracker.ietf.org/doc/html/rfc6901).
+*/
+#[derive(Debug, Clone)]
+pub struct JsonPointer<'input> {
+    segments: Vec<PathSegment<'input>>,
+}
+
+/**
+A single segment in the path to an element.
+*/
+#[derive(Debug, Clone, Copy)]
+pub enum PathSegment<'input> {
+    /**
+    The key of an entry in a map.
+    */
+    Key(Str<'input>),
+    /**
+    The index of an element in an array.
+    */
+    Index(usize),
+}
+
+/**
+The kind of a value, without a view of the value itself.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KindTag {
+    /**
+    A string.
+    */
+    Str,
+    /**
+    A number.
+    */
+    Num,
+    /**
+    A boolean.
+    */
+    Bool,
+    /**
+    A null.
+    */
+    Null,
+    /**
+    A map.
+    */
+    Map,
+    /**
+    An array.
+    */
+    Arr,
+}
+
+impl<'input> JsonPointer<'input> {
+    /**
+    The segments in the path, starting from the root of the document.
+    */
+    #[inline]
+    pub fn segments(&self) -> &[PathSegment<'input>] {
+        &self.segments
+    }
+
+    /**
+    Join the segments in the path using a separator, like `a.b.0.c`.
+
+    Keys are unescaped, but aren't otherwise changed, so they may contain the separator.
+    */
+    pub fn join(&self, separator: &str) -> String {
+        let mut joined = String::new();
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                joined.push_str(separator);
+            }
+
+            match segment {
+                PathSegment::Key(key) => joined.push_str(&key.to_unescaped()),
+                PathSegment::Index(index) => {
+                    let _ = write!(joined, "{}", index);
+                }
+            }
+        }
+
+        joined
+    }
+}
+
+impl<'input> fmt::Display for JsonPointer<'input> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for segment in &self.segments {
+            f.write_char('/')?;
+
+            match segment {
+                PathSegment::Key(key) => {
+                    for c in key.to_unescaped().chars() {
+                        match c {
+                            '~' => f.write_str("~0")?,
+                            '/' => f.write_str("~1")?,
+                            c => f.write_char(c)?,
+                        }
+                    }
+                }
+                PathSegment::Index(index) => write!(f, "{}", index)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'input> Document<'input> {
+    /**
+    Find every value in the document with the given key, no matter how deeply it's nested.
+
+    Values are returned in the order they appear in the input, along with the path to them.
+    */
+    #[inline]
+    pub fn find_all<'brw>(
+        &'brw self,
+        key: &'brw str,
+    ) -> impl Iterator<Item = (JsonPointer<'input>, Kind<'input, 'brw>)> + 'brw {
+        let mut walk = Walk::new(self);
+
+        std::iter::from_fn(move || loop {
+            let value = walk.advance()?;
+
+            if let Some(PathSegment::Key(k)) = walk.path.last() {
+                if k.to_unescaped() == key {
+                    return Some((walk.pointer(), value));
+                }
+            }
+        })
+    }
+
+    /**
+    Flatten the document into the paths and values of each of its leaves.
+
+    A leaf is any value that isn't a map or array, or a map or array that's empty.
+    Leaves are returned in the order they appear in the input.
+    */
+    #[inline]
+    pub fn flatten<'brw>(
+        &'brw self,
+    ) -> impl Iterator<Item = (JsonPointer<'input>, Kind<'input, 'brw>)> + 'brw {
+        let mut walk = Walk::new(self);
+
+        std::iter::from_fn(move || loop {
+            let value = walk.advance()?;
+
+            if is_leaf(&value) {
+                return Some((walk.pointer(), value));
+            }
+        })
+    }
+
+    /**
+    Flatten the document into the paths and values of each of its leaves,
+    joining the segments of each path with a separator, like `a.b.0.c`.
+
+    See [`JsonPointer::join`] for details on how paths are joined.
+    */
+    #[inline]
+    pub fn flatten_joined<'brw>(
+        &'brw self,
+        separator: &'brw str,
+    ) -> impl Iterator<Item = (String, Kind<'input, 'brw>)> + 'brw {
+        let mut walk = Walk::new(self);
+
+        std::iter::from_fn(move || loop {
+            let value = walk.advance()?;
+
+            if is_leaf(&value) {
+                return Some((walk.pointer().join(separator), value));
+            }
+        })
+    }
+}
+
+impl<'input> Document<'input> {
+    /**
+    Get the kind of the value at a path, like `a.b.0.c`, without constructing a view of it.
+
+    Segments are matched against the unescaped keys of maps, or the indexes of arrays.
+    If a map contains the same key more than once then the first entry is used.
+    An empty path is the root map.
+    */
+    pub fn kind_of(&self, path: &str) -> Option<KindTag> {
+        if path.is_empty() {
+            return Some(KindTag::Map);
+        }
+
+        let elements = &self.offsets.elements;
+
+        let root = self.as_map();
+        let (mut kind, mut first, mut len) = (KindTag::Map, root.first_part_offset(), root.len());
+
+        for segment in path.split('.') {
+            let mut offset = first?;
+
+            let value = match kind {
+                KindTag::Map => {
+                    let mut found = None;
+
+                    // keys point to the next key in their map
+                    for _ in 0..len {
+                        let key = elements.get(offset as usize)?.to_str(self.input)?;
+
+                        let matches = if key.1 {
+                            key.to_unescaped() == segment
+                        } else {
+                            key.as_raw() == segment
+                        };
+
+                        if matches {
+                            found = Some(offset + 1);
+                            break;
+                        }
+
+                        offset = elements.get(offset as usize)?.next?;
+                    }
+
+                    found?
+                }
+                KindTag::Arr => {
+                    let index = segment.parse::<usize>().ok().filter(|index| *index < len)?;
+
+                    // elements point to the next element in their array
+                    for _ in 0..index {
+                        offset = elements.get(offset as usize)?.next?;
+                    }
+
+                    offset
+                }
+                _ => return None,
+            };
+
+            (kind, first, len) = match elements.get(value as usize)?.kind {
+                OffsetKind::Str(..) => (KindTag::Str, None, 0),
+                OffsetKind::Num(..) => (KindTag::Num, None, 0),
+                OffsetKind::Bool(..) => (KindTag::Bool, None, 0),
+                OffsetKind::Null => (KindTag::Null, None, 0),
+                OffsetKind::Map(parts, _) => {
+                    let len = (parts >> 1) as usize;
+                    (KindTag::Map, Some(value + 1).filter(|_| len > 0), len)
+                }
+                OffsetKind::Arr(len, _) => {
+                    let len = len as usize;
+                    (KindTag::Arr, Some(value + 1).filter(|_| len > 0), len)
+                }
+                // lazy containers don't have any offsets for their contents
+                OffsetKind::Lazy(_, true) => (KindTag::Map, None, 0),
+                OffsetKind::Lazy(_, false) => (KindTag::Arr, None, 0),
+            };
+        }
+
+        Some(kind)
+    }
+}
+
+/**
+A path to a value, like `a.b.0.c`, that's been split into segments up-front.
+
+Compiled paths can be resolved against many documents without splitting them again,
+and several of them can be resolved at once using [`Document::select`].
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Path {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Segment {
+    key: Box<str>,
+    // the segment as an array index, if it is one
+    index: Option<usize>,
+}
+
+impl Path {
+    /**
+    Compile a path, like `a.b.0.c`.
+
+    Segments are matched against the unescaped keys of maps, or the indexes of arrays.
+    An empty path is the root map.
+    */
+    pub fn new(path: &str) -> Self {
+        if path.is_empty() {
+            return Path {
+                segments: Vec::new(),
+            };
+        }
+
+        Path {
+            segments: path
+                .split('.')
+                .map(|segment| Segment {
+                    key: segment.into(),
+                    index: segment.parse().ok(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Path {
+    #[inline]
+    fn from(path: &'a str) -> Self {
+        Path::new(path)
+    }
+}
+
+impl<'input> Document<'input> {
+    /**
+    Get the values at several paths at once.
+
+    Values are returned in the same order as their paths. Paths are resolved the same way as
+    [`Document::kind_of`]. Instead of resolving each path separately, the paths are resolved
+    together in a single walk, so each map or array they share a prefix through is only walked once.
+    */
+    pub fn select<'brw>(&'brw self, paths: &[Path]) -> Vec<Option<Kind<'input, 'brw>>> {
+        let mut selected = vec![None; paths.len()];
+
+        // sorting the paths puts the ones that share a prefix next to each other
+        let mut group: Vec<usize> = (0..paths.len()).collect();
+        group.sort_by(|a, b| paths[*a].segments.cmp(&paths[*b].segments));
+
+        select(Kind::Map(self.as_map()), paths, &group, &mut selected);
+
+        selected
+    }
+}
+
+/**
+Select the values for a group of paths from a root value.
+
+Groups of paths that share the same prefix up to a depth are kept on an explicit stack along
+with the value at that prefix, so each value is only visited once.
+*/
+fn select<'input, 'offsets>(
+    root: Kind<'input, 'offsets>,
+    paths: &[Path],
+    group: &[usize],
+    selected: &mut [Option<Kind<'input, 'offsets>>],
+) {
+    let mut stack = vec![(root, group, 0)];
+
+    while let Some((value, group, depth)) = stack.pop() {
+        // paths that end at this value sort before the ones that continue through it
+        let ends = group
+            .iter()
+            .take_while(|path| paths[**path].segments.len() == depth)
+            .count();
+
+        for path in &group[..ends] {
+            selected[*path] = Some(value.clone());
+        }
+
+        // split the rest of the group into runs of paths that share the next segment
+        let mut runs = Vec::new();
+        let mut rest = &group[ends..];
+
+        while let Some(first) = rest.first() {
+            let segment = &paths[*first].segments[depth];
+            let len = rest
+                .iter()
+                .take_while(|path| paths[**path].segments[depth] == *segment)
+                .count();
+
+            runs.push((segment, &rest[..len]));
+            rest = &rest[len..];
+        }
+
+        match value {
+            Kind::Map(ref map) => {
+                for (key, value) in map.iter_entries() {
+                    if runs.is_empty() {
+                        break;
+                    }
+
+                    // if a map contains the same key more than once then the first entry is used
+                    if let Some(run) = runs
+                        .iter()
+                        .position(|(segment, _)| key.eq_unescaped(&segment.key))
+                    {
+                        let (_, group) = runs.swap_remove(run);
+
+                        stack.push((value, group, depth + 1));
+                    }
+                }
+            }
+            Kind::Arr(ref arr) => {
+                for (index, value) in arr.iter_elems().enumerate() {
+                    if runs.is_empty() {
+                        break;
+                    }
+
+                    // segments like `1` and `01` are different, but refer to the same index
+                    while let Some(run) = runs
+                        .iter()
+                        .position(|(segment, _)| segment.index == Some(index))
+                    {
+                        let (_, group) = runs.swap_remove(run);
+
+                        stack.push((value.clone(), group, depth + 1));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[inline]
+fn is_leaf(value: &Kind) -> bool {
+    match value {
+        Kind::Map(map) => map.is_empty(),
+        Kind::Arr(arr) => arr.is_empty(),
+        _ => true,
+    }
+}
+
+/**
+A pre-order walk through every element in a document.
+*/
+pub(super) struct Walk<'input, 'offsets> {
+    stack: Vec<Frame<'input, 'offsets>>,
+    path: Vec<PathSegment<'input>>,
+}
+
+enum Frame<'input, 'offsets> {
+    Map(Entries<'input, 'offsets>),
+    Arr(Elems<'input, 'offsets>, usize),
+}
+
+impl<'input, 'offsets> Walk<'input, 'offsets> {
+    pub(super) fn new(document: &'offsets Document<'input>) -> Self {
+        Walk {
+            stack: vec![Frame::Map(document.as_map().iter_entries())],
+            path: Vec::new(),
+        }
+    }
+
+    /**
+    Move to the next element in the document.
+
+    The path to the element is available until the walk is advanced again.
+    */
+    pub(super) fn advance(&mut self) -> Option<Kind<'input, 'offsets>> {
+        loop {
+            let depth = self.stack.len();
+
+            let (segment, value) = match self.stack.last_mut()? {
+                Frame::Map(entries) => match entries.next() {
+                    Some((key, value)) => (PathSegment::Key(key), value),
+                    None => {
+                        self.stack.pop();
+                        continue;
+                    }
+                },
+                Frame::Arr(elems, index) => match elems.next() {
+                    Some(value) => {
+                        let segment = PathSegment::Index(*index);
+                        *index += 1;
+
+                        (segment, value)
+                    }
+                    None => {
+                        self.stack.pop();
+                        continue;
+                    }
+                },
+            };
+
+            // the path to an element at the top of the stack has the same length as the stack
+            self.path.truncate(depth - 1);
+            self.path.push(segment);
+
+            match value {
+                Kind::Map(ref map) => self.stack.push(Frame::Map(map.iter_entries())),
+                Kind::Arr(ref arr) => self.stack.push(Frame::Arr(arr.iter_elems(), 0)),
+                _ => (),
+            }
+
+            return Some(value);
+        }
+    }
+
+    /**
+    The depth of the current element, where entries in the root map are at a depth of `1`.
+    */
+    #[inline]
+    pub(super) fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    /**
+    Whether the current element is the value of an entry in a map.
+    */
+    #[inline]
+    pub(super) fn in_map(&self) -> bool {
+        matches!(self.path.last(), Some(PathSegment::Key(_)))
+    }
+
+    /**
+    The path to the current element.
+    */
+    pub(super) fn pointer(&self) -> JsonPointer<'input> {
+        JsonPointer {
+            segments: self.path.clone(),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+/*!
+A stable, read-only view over the table of offsets.
+
+The internal layout of offsets is free to change, so external indexes that need to
+work with them directly use this view instead.
+*/
+
+use std::ops::Range;
+
+use super::{Offset, OffsetKind, Offsets, Part};
+
+/**
+A single element in a table of offsets.
+
+# Structure
+
+Elements are stored in the order they appear in the input, with each map or array
+immediately followed by its contents. The root map itself doesn't have an element.
+
+The entries of a map are stored as a key element followed by a value element.
+Keys are always strings in valid documents.
+
+Each element may point to the index of the next element at the same position
+(key, value, or array element) within the same map or array. So keys point to the
+next key, values point to the next value, and array elements point to the next element.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawOffset {
+    kind: RawKind,
+    position: Option<RawPosition>,
+    input_range: Option<(usize, usize)>,
+    next: Option<usize>,
+}
+
+/**
+The kind of an element in a table of offsets.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RawKind {
+    /**
+    A string, along with whether or not it contains escape sequences.
+    */
+    Str {
+        /**
+        Whether or not the string contains escape sequences.
+        */
+        escaped: bool,
+    },
+    /**
+    A number.
+    */
+    Num,
+    /**
+    A boolean.
+    */
+    Bool(bool),
+    /**
+    A null.
+    */
+    Null,
+    /**
+    A map, along with the number of entries in it.
+    */
+    Map {
+        /**
+        The number of entries in the map.
+        */
+        entries: usize,
+    },
+    /**
+    An array, along with the number of elements in it.
+    */
+    Arr {
+        /**
+        The number of elements in the array.
+        */
+        len: usize,
+    },
+    /**
+    A map or array that was kept as a raw span, along with whether it's a map.
+    */
+    Lazy {
+        /**
+        Whether the span is a map, rather than an array.
+        */
+        map: bool,
+    },
+}
+
+/**
+The position of an element within its parent map or array.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawPosition {
+    /**
+    The key of an entry in a map.
+    */
+    Key,
+    /**
+    The value of an entry in a map.
+    */
+    Value,
+    /**
+    An element in an array.
+    */
+    Elem,
+}
+
+impl RawOffset {
+    /**
+    The kind of the element.
+    */
+    #[inline]
+    pub fn kind(&self) -> RawKind {
+        self.kind
+    }
+
+    /**
+    The position of the element within its parent map or array.
+
+    This is only `None` for documents that are erroneous.
+    */
+    #[inline]
+    pub fn position(&self) -> Option<RawPosition> {
+        self.position
+    }
+
+    /**
+    The range of the input that the element was scanned from.
+
+    Strings don't include their surrounding quotes. Maps and arrays include their
+    delimiters. Booleans and nulls don't have a range.
+    */
+    #[inline]
+    pub fn input_range(&self) -> Option<Range<usize>> {
+        self.input_range.map(|(start, end)| start..end)
+    }
+
+    /**
+    The index of the next element at the same position within the same map or array.
+    */
+    #[inline]
+    pub fn next(&self) -> Option<usize> {
+        self.next
+    }
+}
+
+impl Offsets {
+    /**
+    Iterate over the elements in the table of offsets.
+
+    See [`RawOffset`] for details on the structure of the table.
+    */
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = RawOffset> + '_ {
+        self.elements.iter().map(RawOffset::from_offset)
+    }
+
+    /**
+    Get the element at an index in the table of offsets.
+    */
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<RawOffset> {
+        self.elements.get(index).map(RawOffset::from_offset)
+    }
+
+    /**
+    The number of elements in the table of offsets.
+    */
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /**
+    Whether or not the table of offsets is empty.
+    */
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /**
+    The index of the first key in the root map, if it has any entries.
+    */
+    #[inline]
+    pub fn root_first(&self) -> Option<usize> {
+        if self.root_parts > 0 && !self.elements.is_empty() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    /**
+    The index of the first key or element within the map or array at an index.
+
+    If the element at the index isn't a map or array, or it's empty, then this method
+    returns `None`.
+    */
+    #[inline]
+    pub fn first_child(&self, index: usize) -> Option<usize> {
+        match self.elements.get(index)?.kind {
+            OffsetKind::Map(len, _) | OffsetKind::Arr(len, _) if len > 0 => Some(index + 1),
+            _ => None,
+        }
+    }
+
+    /**
+    The index of the next element at the same position within the same map or array
+    as the element at an index.
+
+    This is the same as [`RawOffset::next`], without building the rest of the [`RawOffset`].
+    */
+    #[inline]
+    pub fn next(&self, index: usize) -> Option<usize> {
+        self.elements.get(index)?.next.map(|next| next as usize)
+    }
+
+    /**
+    Follow the links from an element to the next elements at the same position.
+
+    Starting from the first key of a map walks its keys, and the value for each key is at
+    the index after it. Starting from the first element of an array walks its elements.
+    */
+    #[inline]
+    pub fn chain(&self, first: usize) -> Chain<'_> {
+        Chain {
+            elements: &self.elements,
+            next: Some(first).filter(|first| *first < self.elements.len()),
+        }
+    }
+}
+
+/**
+An iterator over the indexes of a chain of elements at the same position in a map or array.
+
+See [`Offsets::chain`].
+*/
+#[derive(Debug, Clone)]
+pub struct Chain<'a> {
+    elements: &'a [Offset],
+    next: Option<usize>,
+}
+
+impl<'a> Iterator for Chain<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        let current = self.next?;
+
+        self.next = self
+            .elements
+            .get(current)
+            .and_then(|offset| offset.next)
+            .map(|next| next as usize);
+
+        Some(current)
+    }
+}
+
+impl RawOffset {
+    #[inline]
+    fn from_offset(offset: &Offset) -> Self {
+        let (kind, slice) = match offset.kind {
+            OffsetKind::Str(s, escaped) => (RawKind::Str { escaped }, Some(s)),
+            OffsetKind::Num(n) => (RawKind::Num, Some(n)),
+            OffsetKind::Bool(b) => (RawKind::Bool(b), None),
+            OffsetKind::Null => (RawKind::Null, None),
+            OffsetKind::Map(parts, raw) => (
+                RawKind::Map {
+                    entries: (parts >> 1) as usize,
+                },
+                Some(raw),
+            ),
+            OffsetKind::Arr(len, raw) => (RawKind::Arr { len: len as usize }, Some(raw)),
+            OffsetKind::Lazy(raw, map) => (RawKind::Lazy { map }, Some(raw)),
+        };
+
+        RawOffset {
+            kind,
+            position: RawPosition::from_part(offset.position),
+            input_range: slice.map(|s| {
+                let start = s.offset as usize;
+                (start, start + s.len as usize)
+            }),
+            next: offset.next.map(|next| next as usize),
+        }
+    }
+}
+
+impl RawPosition {
+    #[inline]
+    pub(super) fn from_part(part: Part) -> Option<Self> {
+        match part {
+            Part::Key => Some(RawPosition::Key),
+            Part::Value => Some(RawPosition::Value),
+            Part::Elem => Some(RawPosition::Elem),
+            Part::None => None,
+        }
+    }
+}
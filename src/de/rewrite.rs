@@ -0,0 +1,629 @@
+/*!
+Re-emit documents as new minified buffers with some of their entries changed.
+
+Values that aren't changed are spliced directly from their raw spans in the input,
+so they're never unescaped or re-formatted. Maps and arrays are walked using an
+explicit stack, the same as other queries over the whole document.
+*/
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use super::{Document, Elems, Entries, Kind, Map, Str};
+
+use crate::escape::escape_str;
+
+/**
+A set of keys to redact from a document.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct KeySet {
+    keys: HashSet<String>,
+    placeholder: Option<String>,
+}
+
+impl KeySet {
+    /**
+    Create an empty set of keys.
+    */
+    #[inline]
+    pub fn new() -> Self {
+        KeySet::default()
+    }
+
+    /**
+    Add a key to redact.
+
+    Keys are matched against the unescaped keys in a document, at any depth.
+    */
+    #[inline]
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.keys.insert(key.into());
+        self
+    }
+
+    /**
+    Replace the values of redacted keys with a placeholder instead of removing them.
+
+    The placeholder is written as-is, so it must be valid minified JSON, like `"\"[redacted]\""`.
+    */
+    #[inline]
+    pub fn placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = Some(placeholder.into());
+        self
+    }
+
+    /**
+    Whether or not the set contains a key.
+    */
+    #[inline]
+    pub fn contains(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+}
+
+/**
+How to merge the entries of two documents.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MergeStrategy {
+    /**
+    Entries at the root of the other document replace entries with the same key.
+    */
+    Shallow,
+    /**
+    Entries whose values are maps in both documents are merged, at any depth.
+    Any other entries in the other document replace entries with the same key.
+    */
+    Deep,
+}
+
+/**
+Limits on how much of a document to keep in a summary.
+
+Everything is kept by default.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SummaryLimits {
+    max_str_chars: usize,
+    max_arr_len: usize,
+    max_depth: usize,
+}
+
+impl Default for SummaryLimits {
+    #[inline]
+    fn default() -> Self {
+        SummaryLimits::UNLIMITED
+    }
+}
+
+impl SummaryLimits {
+    const UNLIMITED: SummaryLimits = SummaryLimits {
+        max_str_chars: usize::MAX,
+        max_arr_len: usize::MAX,
+        max_depth: usize::MAX,
+    };
+
+    /**
+    Create a set of limits that keeps everything.
+    */
+    #[inline]
+    pub const fn new() -> Self {
+        SummaryLimits::UNLIMITED
+    }
+
+    /**
+    Truncate strings that are longer than `max` characters once they're unescaped.
+
+    Keys are never truncated.
+    */
+    #[inline]
+    pub fn max_str_chars(mut self, max: usize) -> Self {
+        self.max_str_chars = max;
+        self
+    }
+
+    /**
+    Keep at most `max` elements from each array.
+    */
+    #[inline]
+    pub fn max_arr_len(mut self, max: usize) -> Self {
+        self.max_arr_len = max;
+        self
+    }
+
+    /**
+    Keep maps and arrays nested at most `max` levels below the root.
+
+    A `max` of `0` only keeps the entries at the root whose values aren't maps or arrays.
+    */
+    #[inline]
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        self
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for KeySet {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        KeySet {
+            keys: iter.into_iter().map(Into::into).collect(),
+            placeholder: None,
+        }
+    }
+}
+
+impl<'input> Document<'input> {
+    /**
+    Write a new minified buffer with any entries that match the given keys redacted.
+
+    Matching entries are removed, or have their values replaced if the set has a placeholder.
+    */
+    pub fn redact(&self, keys: &KeySet) -> Vec<u8> {
+        rewrite(self, |key| {
+            if !keys.contains(&key.to_unescaped_inline()) {
+                return EntryRewrite::Keep;
+            }
+
+            match keys.placeholder {
+                Some(ref placeholder) => EntryRewrite::ReplaceValue(placeholder),
+                None => EntryRewrite::Drop,
+            }
+        })
+    }
+
+    /**
+    Write a new minified buffer with the entries of this document merged with another.
+
+    Entries keep the order they have in this document, with any entries that only
+    appear in the other document written after them. Values are copied from the
+    input of whichever document they came from as-is.
+    */
+    pub fn merge(&self, other: &Document, strategy: MergeStrategy) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.input.len() + other.input.len());
+
+        merge(&mut out, &self.as_map(), &other.as_map(), strategy);
+
+        out
+    }
+
+    /**
+    Write a new minified buffer with each key in each map transformed by a function.
+
+    The function is given the original key and returns the key to write, which will be escaped.
+    Values are copied from the input as-is.
+    */
+    pub fn rewrite_keys(&self, mut f: impl FnMut(Str<'input>) -> Cow<'input, str>) -> Vec<u8> {
+        rewrite(self, |key| EntryRewrite::RenameKey(f(key)))
+    }
+
+    /**
+    Write a new minified buffer with the same entries as this document, in a normalized form.
+
+    Strings are unescaped and escaped again, so equivalent strings are always written the same way.
+    Numbers have any whitespace around them removed, and maps and arrays are always terminated by
+    `}` and `]`, even if they weren't in the input.
+    */
+    pub fn to_minified_bytes(&self) -> Vec<u8> {
+        minify(self, false, &SummaryLimits::UNLIMITED)
+    }
+
+    /**
+    Write a new minified buffer with a shortened version of this document, for display.
+
+    Content that's over the given limits is replaced with an ellipsis (`…`):
+
+    - strings that are too long are cut short and end with `…`.
+    - arrays that are too long keep their first elements, followed by a `"…"` element.
+    - maps and arrays that are nested too deeply are replaced with `"…"`, unless they're empty.
+
+    Like [`Document::repair`], the summary is always valid JSON, even if the document isn't.
+    */
+    pub fn summarize(&self, limits: &SummaryLimits) -> Vec<u8> {
+        minify(self, true, limits)
+    }
+
+    /**
+    Write a new minified buffer that's structurally valid JSON from whatever content was scanned.
+
+    This is like [`Document::to_minified_bytes`], but numbers that aren't valid JSON, like `1.`
+    or `12abc`, are written as strings instead. Maps that are missing the value for their last
+    key have that key dropped. Erroneous documents can be repaired if they're scanned with
+    [`ScanOptions::keep_partial`](super::ScanOptions::keep_partial) or
+    [`ScanOptions::recover`](super::ScanOptions::recover), otherwise there's nothing to repair
+    them from and `None` is returned.
+    */
+    pub fn repair(&self) -> Option<Vec<u8>> {
+        if self.is_err() && !self.is_partial() {
+            return None;
+        }
+
+        Some(minify(self, true, &SummaryLimits::UNLIMITED))
+    }
+}
+
+/**
+What to do with an entry in a map.
+*/
+pub(super) enum EntryRewrite<'r> {
+    /**
+    Keep the entry as it is.
+
+    If the value is a map or array then its entries will also be rewritten.
+    */
+    Keep,
+    /**
+    Remove the entry entirely.
+    */
+    Drop,
+    /**
+    Replace the value of the entry with some raw JSON.
+    */
+    ReplaceValue(&'r str),
+    /**
+    Write the entry with a different key.
+
+    If the value is a map or array then its entries will also be rewritten.
+    */
+    RenameKey(Cow<'r, str>),
+}
+
+enum Frame<'input, 'offsets> {
+    Map(Entries<'input, 'offsets>, bool),
+    // arrays count the elements written so far, so they can be capped
+    Arr(Elems<'input, 'offsets>, usize),
+}
+
+/**
+Write a new minified buffer, calling a function for each entry of each map in the document.
+*/
+pub(super) fn rewrite<'input, 'r>(
+    document: &Document<'input>,
+    mut f: impl FnMut(Str<'input>) -> EntryRewrite<'r>,
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(document.input.len());
+
+    rewrite_kind(&mut out, Kind::Map(document.as_map()), &mut f);
+
+    out
+}
+
+/**
+Write a value, calling a function for each entry of each map within it.
+
+Maps and arrays kept lazily are scanned and written with a nested call. They're scanned in full,
+so there are no lazy values within them, and the nested call never goes any deeper.
+*/
+fn rewrite_kind<'input, 'offsets, 'r>(
+    out: &mut Vec<u8>,
+    value: Kind<'input, 'offsets>,
+    f: &mut impl FnMut(Str<'input>) -> EntryRewrite<'r>,
+) {
+    let mut stack = Vec::new();
+    rewrite_begin(out, &mut stack, value, f);
+
+    while let Some(frame) = stack.last_mut() {
+        let value = match frame {
+            Frame::Map(entries, first) => match entries.next() {
+                Some((key, value)) => {
+                    let (rename, replace) = match f(key) {
+                        EntryRewrite::Keep => (None, None),
+                        EntryRewrite::Drop => continue,
+                        EntryRewrite::ReplaceValue(replace) => (None, Some(replace)),
+                        EntryRewrite::RenameKey(rename) => (Some(rename), None),
+                    };
+
+                    if !std::mem::take(first) {
+                        out.push(b',');
+                    }
+
+                    match rename {
+                        // keys that haven't changed keep their original escaping
+                        Some(rename) if rename != key.to_unescaped() => escape_str(out, &rename),
+                        _ => out.extend_from_slice(key.as_json().as_bytes()),
+                    }
+                    out.push(b':');
+
+                    if let Some(replace) = replace {
+                        out.extend_from_slice(replace.as_bytes());
+                        continue;
+                    }
+
+                    value
+                }
+                None => {
+                    out.push(b'}');
+                    stack.pop();
+                    continue;
+                }
+            },
+            Frame::Arr(elems, written) => match elems.next() {
+                Some(value) => {
+                    if *written > 0 {
+                        out.push(b',');
+                    }
+                    *written += 1;
+
+                    value
+                }
+                None => {
+                    out.push(b']');
+                    stack.pop();
+                    continue;
+                }
+            },
+        };
+
+        rewrite_begin(out, &mut stack, value, f);
+    }
+}
+
+#[inline]
+fn rewrite_begin<'input, 'offsets, 'r>(
+    out: &mut Vec<u8>,
+    stack: &mut Vec<Frame<'input, 'offsets>>,
+    value: Kind<'input, 'offsets>,
+    f: &mut impl FnMut(Str<'input>) -> EntryRewrite<'r>,
+) {
+    // maps and arrays are walked into, everything else is copied from the input
+    match value {
+        Kind::Map(ref map) => {
+            out.push(b'{');
+            stack.push(Frame::Map(map.iter_entries(), true));
+        }
+        Kind::Arr(ref arr) => {
+            out.push(b'[');
+            stack.push(Frame::Arr(arr.iter_elems(), 0));
+        }
+        Kind::Lazy(lazy) => match lazy.scan() {
+            Some(nested) => rewrite_kind(out, nested.as_kind(), f),
+            // spans that are too large to scan on their own can't be rewritten
+            None => out.extend_from_slice(b"null"),
+        },
+        value => out.extend_from_slice(value.as_json().as_bytes()),
+    }
+}
+
+/**
+Write a document in a normalized form, eliding any content that's over the limits.
+
+This function isn't recursive; it keeps its own stack of iterators.
+*/
+fn minify(document: &Document, repair: bool, limits: &SummaryLimits) -> Vec<u8> {
+    let mut out = Vec::with_capacity(document.input.len());
+
+    out.push(b'{');
+    let mut stack = vec![Frame::Map(document.as_map().iter_entries(), true)];
+
+    while let Some(frame) = stack.last_mut() {
+        let value = match frame {
+            Frame::Map(entries, first) => match entries.next() {
+                Some((key, value)) => {
+                    if !std::mem::take(first) {
+                        out.push(b',');
+                    }
+
+                    escape_str(&mut out, &key.to_unescaped_inline());
+                    out.push(b':');
+
+                    value
+                }
+                None => {
+                    out.push(b'}');
+                    stack.pop();
+                    continue;
+                }
+            },
+            Frame::Arr(elems, written) => match elems.next() {
+                // the rest of the array is elided
+                Some(_) if *written == limits.max_arr_len => {
+                    if *written > 0 {
+                        out.push(b',');
+                    }
+                    out.extend_from_slice(ELLIPSIS_JSON.as_bytes());
+                    out.push(b']');
+                    stack.pop();
+                    continue;
+                }
+                Some(value) => {
+                    if *written > 0 {
+                        out.push(b',');
+                    }
+                    *written += 1;
+
+                    value
+                }
+                None => {
+                    out.push(b']');
+                    stack.pop();
+                    continue;
+                }
+            },
+        };
+
+        // the root map isn't counted, so the stack is one deeper than the value
+        let too_deep = stack.len() > limits.max_depth;
+
+        match value {
+            Kind::Map(ref map) if too_deep && !map.is_empty() => {
+                out.extend_from_slice(ELLIPSIS_JSON.as_bytes())
+            }
+            Kind::Arr(ref arr) if too_deep && !arr.is_empty() => {
+                out.extend_from_slice(ELLIPSIS_JSON.as_bytes())
+            }
+            Kind::Map(ref map) => {
+                out.push(b'{');
+                stack.push(Frame::Map(map.iter_entries(), true));
+            }
+            Kind::Arr(ref arr) => {
+                out.push(b'[');
+                stack.push(Frame::Arr(arr.iter_elems(), 0));
+            }
+            Kind::Str(s) => {
+                let s = s.to_unescaped_inline();
+
+                match s.char_indices().nth(limits.max_str_chars) {
+                    Some((truncate_at, _)) => {
+                        escape_str(&mut out, get_unchecked!(s, ..truncate_at));
+
+                        // replace the closing quote with the ellipsis
+                        out.pop();
+                        out.extend_from_slice(&ELLIPSIS_JSON.as_bytes()[1..]);
+                    }
+                    None => escape_str(&mut out, &s),
+                }
+            }
+            Kind::Num(n) if repair && !is_json_num(n.trim()) => escape_str(&mut out, n.trim()),
+            Kind::Num(n) => out.extend_from_slice(n.trim().as_bytes()),
+            Kind::Bool(true) => out.extend_from_slice(b"true"),
+            Kind::Bool(false) => out.extend_from_slice(b"false"),
+            Kind::Null => out.extend_from_slice(b"null"),
+            // lazy maps and arrays are never empty
+            Kind::Lazy(_) if too_deep => out.extend_from_slice(ELLIPSIS_JSON.as_bytes()),
+            // the span is copied as-is, without applying any limits within it
+            Kind::Lazy(lazy) => out.extend_from_slice(lazy.as_json().as_bytes()),
+        }
+    }
+
+    out
+}
+
+/**
+The marker for content that's elided from a summary, as a JSON string.
+*/
+const ELLIPSIS_JSON: &str = "\"…\"";
+
+/**
+Whether a number is valid according to the JSON grammar.
+*/
+fn is_json_num(n: &str) -> bool {
+    fn digits(n: &[u8]) -> usize {
+        n.iter().take_while(|b| b.is_ascii_digit()).count()
+    }
+
+    let mut n = n.as_bytes();
+
+    if let [b'-', rest @ ..] = n {
+        n = rest;
+    }
+
+    // the integer part can't have leading zeroes
+    match digits(n) {
+        0 => return false,
+        len if len > 1 && n[0] == b'0' => return false,
+        len => n = &n[len..],
+    }
+
+    if let [b'.', rest @ ..] = n {
+        match digits(rest) {
+            0 => return false,
+            len => n = &rest[len..],
+        }
+    }
+
+    if let [b'e' | b'E', rest @ ..] = n {
+        let rest = match rest {
+            [b'+' | b'-', rest @ ..] => rest,
+            rest => rest,
+        };
+
+        match digits(rest) {
+            0 => return false,
+            len => n = &rest[len..],
+        }
+    }
+
+    n.is_empty()
+}
+
+/**
+Write the merged entries of two maps.
+
+Maps that are merged deeply are walked using an explicit stack, like [`rewrite`].
+*/
+fn merge(out: &mut Vec<u8>, base: &Map, overrides: &Map, strategy: MergeStrategy) {
+    out.push(b'{');
+    let mut stack = vec![MergeFrame::new(base, overrides)];
+
+    while let Some(frame) = stack.last_mut() {
+        let (key, value) = match frame.base.next() {
+            Some(entry) => entry,
+            None => {
+                // entries that only appear in the other map are written after the base ones
+                for (key, value) in frame.overrides.entries() {
+                    if frame.base_keys.contains(&key.to_unescaped()) {
+                        continue;
+                    }
+
+                    if !std::mem::take(&mut frame.first) {
+                        out.push(b',');
+                    }
+
+                    out.extend_from_slice(key.as_json().as_bytes());
+                    out.push(b':');
+                    out.extend_from_slice(value.as_json().as_bytes());
+                }
+
+                out.push(b'}');
+                stack.pop();
+                continue;
+            }
+        };
+
+        if !std::mem::take(&mut frame.first) {
+            out.push(b',');
+        }
+
+        out.extend_from_slice(key.as_json().as_bytes());
+        out.push(b':');
+
+        let key = key.to_unescaped();
+        let value = match (strategy, value, frame.override_entries.get(&key)) {
+            (MergeStrategy::Deep, Kind::Map(base), Some(Kind::Map(overrides))) => {
+                let overrides = overrides.clone();
+                frame.base_keys.insert(key);
+
+                out.push(b'{');
+                stack.push(MergeFrame::new(&base, &overrides));
+                continue;
+            }
+            (_, _, Some(value)) => value.as_json(),
+            (_, value, None) => value.as_json(),
+        };
+
+        out.extend_from_slice(value.as_bytes());
+        frame.base_keys.insert(key);
+    }
+}
+
+/**
+A pair of maps being merged.
+*/
+struct MergeFrame<'input, 'offsets> {
+    base: Entries<'input, 'offsets>,
+    overrides: Map<'input, 'offsets>,
+    // the first entry for a key is the one that's used
+    override_entries: HashMap<Cow<'input, str>, Kind<'input, 'offsets>>,
+    base_keys: HashSet<Cow<'input, str>>,
+    first: bool,
+}
+
+impl<'input, 'offsets> MergeFrame<'input, 'offsets> {
+    fn new(base: &Map<'input, 'offsets>, overrides: &Map<'input, 'offsets>) -> Self {
+        let mut override_entries = HashMap::with_capacity(overrides.len());
+        for (key, value) in overrides.entries() {
+            override_entries.entry(key.to_unescaped()).or_insert(value);
+        }
+
+        MergeFrame {
+            base: base.iter_entries(),
+            overrides: overrides.clone(),
+            override_entries,
+            base_keys: HashSet::with_capacity(base.len()),
+            first: true,
+        }
+    }
+}
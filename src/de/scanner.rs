@@ -0,0 +1,103 @@
+/*!
+A reusable scanner for documents that are scanned one after another.
+
+Scanning a document allocates offsets and a stack for tracking maps and arrays.
+A scanner keeps those allocations between documents, along with the backend it picked
+for the current CPU, so a batch of documents doesn't pay for them more than once.
+*/
+
+use std::{borrow::Cow, mem};
+
+use super::{
+    is_simd_supported, scan, scan_begin, scan_fallback, scan_interleaved, scan_simd, ActiveMapArr,
+    Backend, DetachedDocument, Document, Offsets, ScanOptions,
+};
+
+/**
+A scanner that reuses its allocations across documents.
+
+Documents returned by the scanner borrow their offsets from it, so each one needs
+to be dropped before the next is scanned.
+*/
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    options: ScanOptions,
+    simd: bool,
+    offsets: Offsets,
+    stack: Vec<ActiveMapArr>,
+}
+
+impl Default for Scanner {
+    #[inline]
+    fn default() -> Self {
+        Scanner::new()
+    }
+}
+
+impl Scanner {
+    /**
+    Create a scanner with the default options.
+    */
+    #[inline]
+    pub fn new() -> Self {
+        Scanner::with_options(ScanOptions::new())
+    }
+
+    /**
+    Create a scanner with the given options.
+    */
+    #[inline]
+    pub fn with_options(options: ScanOptions) -> Self {
+        let DetachedDocument { offsets, stack } = DetachedDocument::default();
+
+        let mut empty = Offsets::empty();
+        empty.elements = offsets;
+
+        Scanner {
+            options,
+            simd: is_simd_supported(),
+            offsets: empty,
+            stack,
+        }
+    }
+
+    /**
+    Scan a JSON object byte buffer into an indexable document.
+
+    This method has the same guarantees as [`Document::scan_trusted`].
+    */
+    #[inline]
+    pub fn scan<'a>(&'a mut self, input: &'a [u8]) -> Document<'a> {
+        let mut elements = mem::take(&mut self.offsets.elements);
+        elements.clear();
+
+        let mut stack = mem::take(&mut self.stack);
+        stack.clear();
+
+        let detached = DetachedDocument {
+            offsets: elements,
+            stack,
+        };
+
+        let document = match (self.options.backend, self.simd) {
+            (Backend::Auto, true) if self.options.interleave_utf8 => {
+                scan_interleaved(input, detached, &self.options)
+            }
+            (Backend::Auto, true) => scan(input, scan_begin(input), detached, &self.options),
+            (Backend::Simd, _) => scan_simd(input, scan_begin(input), detached, &self.options),
+            (Backend::Auto, false) | (Backend::Scalar, _) => {
+                scan_fallback(input, scan_begin(input), detached, &self.options)
+            }
+        };
+
+        self.offsets = document.offsets.into_owned();
+        self.stack = document._detached_stack;
+
+        Document {
+            input,
+            offsets: Cow::Borrowed(&self.offsets),
+            _detached_stack: Vec::new(),
+            root: None,
+        }
+    }
+}
@@ -0,0 +1,186 @@
+/*!
+Fingerprints of the shape of a document.
+
+Documents that share the same keys, nesting, and kinds of values can usually be handled the
+same way, regardless of what those values are. A fingerprint is a hash of that shape, computed
+by walking the offsets of a document without converting any of its values.
+
+The hash is FNV-1a, so fingerprints are stable across processes, platforms, and versions of Rust.
+*/
+
+use super::{Document, Elems, Entries, Kind, KindTag};
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+// markers written between the parts of a shape so different nestings don't hash the same
+const MAP_BEGIN: u8 = b'{';
+const MAP_END: u8 = b'}';
+const ARR_BEGIN: u8 = b'[';
+const ARR_END: u8 = b']';
+const KEY_END: u8 = b':';
+
+impl<'input> Document<'input> {
+    /**
+    Compute a hash of the shape of the document.
+
+    The shape of a document is the names of its keys in the order they appear, how its maps
+    and arrays are nested, and the kinds of its values. The values themselves are ignored.
+    Keys are compared unescaped. Consecutive elements in an array with the same shape are only
+    counted once, so arrays of the same kinds of values have the same shape regardless of
+    their length.
+
+    Maps and arrays kept lazily with [`ScanOptions::lazy_depth`](super::ScanOptions::lazy_depth)
+    only contribute their kind, so only documents scanned with the same options should have
+    their fingerprints compared.
+    */
+    pub fn schema_fingerprint(&self) -> u64 {
+        fingerprint(&Kind::Map(self.as_map()))
+    }
+}
+
+/**
+Hash the shape of an element.
+
+Maps and arrays are walked using an explicit stack of the elements left in each. Maps are
+hashed along with their parent, but each element of an array is hashed on its own so runs
+of the same shape can be collapsed.
+*/
+fn fingerprint(kind: &Kind) -> u64 {
+    let mut hash = Fingerprint::new();
+    let mut stack = Vec::new();
+    let mut done = hash.begin(&mut stack, kind);
+
+    loop {
+        // if the value that was just hashed is an element of an array then add it to the array
+        if std::mem::take(&mut done) {
+            if let Some(Frame::Arr(_, arr, prev)) = stack.last_mut() {
+                let elem = hash.finish();
+
+                if *prev != Some(elem) {
+                    arr.write_u64(elem);
+                    *prev = Some(elem);
+                }
+            }
+        }
+
+        let value = match stack.last_mut() {
+            Some(Frame::Map(entries)) => match entries.next() {
+                Some((key, value)) => {
+                    hash.write(key.to_unescaped_inline().as_bytes());
+                    hash.write_u8(KEY_END);
+
+                    value
+                }
+                None => {
+                    hash.write_u8(MAP_END);
+                    stack.pop();
+
+                    done = true;
+                    continue;
+                }
+            },
+            Some(Frame::Arr(elems, arr, _)) => match elems.next() {
+                Some(elem) => {
+                    hash = Fingerprint::new();
+
+                    elem
+                }
+                None => {
+                    arr.write_u8(ARR_END);
+                    hash = *arr;
+                    stack.pop();
+
+                    done = true;
+                    continue;
+                }
+            },
+            None => return hash.finish(),
+        };
+
+        done = hash.begin(&mut stack, &value);
+    }
+}
+
+enum Frame<'input, 'offsets> {
+    Map(Entries<'input, 'offsets>),
+    // the elements left in an array, its hash, and the hash of its last element
+    Arr(Elems<'input, 'offsets>, Fingerprint, Option<u64>),
+}
+
+#[derive(Clone, Copy)]
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    #[inline]
+    fn new() -> Self {
+        Fingerprint(FNV_OFFSET)
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    #[inline]
+    fn write_u8(&mut self, b: u8) {
+        self.0 = (self.0 ^ b as u64).wrapping_mul(FNV_PRIME);
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.write_u8(*b);
+        }
+    }
+
+    #[inline]
+    fn write_u64(&mut self, v: u64) {
+        self.write(&v.to_le_bytes());
+    }
+
+    /**
+    Hash a value, or begin hashing its contents if it's a map or array.
+
+    Returns `true` if the value has been hashed completely.
+    */
+    fn begin<'input, 'offsets>(
+        &mut self,
+        stack: &mut Vec<Frame<'input, 'offsets>>,
+        kind: &Kind<'input, 'offsets>,
+    ) -> bool {
+        match kind {
+            Kind::Str(_) => self.tag(KindTag::Str),
+            Kind::Num(_) => self.tag(KindTag::Num),
+            Kind::Bool(_) => self.tag(KindTag::Bool),
+            Kind::Null => self.tag(KindTag::Null),
+            Kind::Map(ref map) => {
+                self.write_u8(MAP_BEGIN);
+                stack.push(Frame::Map(map.iter_entries()));
+
+                return false;
+            }
+            Kind::Arr(ref arr) => {
+                self.write_u8(ARR_BEGIN);
+                stack.push(Frame::Arr(arr.iter_elems(), *self, None));
+
+                return false;
+            }
+            Kind::Lazy(lazy) => self.tag(lazy.kind()),
+        }
+
+        true
+    }
+
+    #[inline]
+    fn tag(&mut self, tag: KindTag) {
+        self.write_u8(match tag {
+            KindTag::Str => b's',
+            KindTag::Num => b'n',
+            KindTag::Bool => b'b',
+            KindTag::Null => b'z',
+            KindTag::Map => b'm',
+            KindTag::Arr => b'a',
+        });
+    }
+}
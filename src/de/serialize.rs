@@ -0,0 +1,93 @@
+/*!
+`serde` serialization for documents.
+
+Elements are serialized directly from the offsets in a document, in the order they
+appear in it, without going through an intermediate value. This makes it possible
+to scan a document once and stream it into any `serde` serializer.
+*/
+
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use super::{
+    deserializer::{parse_num, Num},
+    Arr, Document, Kind, Map, Str,
+};
+
+impl<'input> Serialize for Document<'input> {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.as_map().serialize(serializer)
+    }
+}
+
+impl<'input, 'offsets> Serialize for Kind<'input, 'offsets> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Kind::Str(s) => s.serialize(serializer),
+            Kind::Num(n) => {
+                let n = n.trim();
+
+                match parse_num(n) {
+                    Some(Num::U64(n)) => serializer.serialize_u64(n),
+                    Some(Num::I64(n)) => serializer.serialize_i64(n),
+                    Some(Num::F64(n)) => serializer.serialize_f64(n),
+                    None => Err(ser::Error::custom(format_args!("invalid number `{}`", n))),
+                }
+            }
+            Kind::Bool(b) => serializer.serialize_bool(*b),
+            Kind::Null => serializer.serialize_unit(),
+            Kind::Map(map) => map.serialize(serializer),
+            Kind::Arr(arr) => arr.serialize(serializer),
+            Kind::Lazy(lazy) => match lazy.scan() {
+                Some(nested) => nested.as_kind().serialize(serializer),
+                None => Err(ser::Error::custom("the map or array is too large to scan")),
+            },
+        }
+    }
+}
+
+impl<'input> Serialize for Str<'input> {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_unescaped())
+    }
+}
+
+impl<'input, 'offsets> Serialize for Map<'input, 'offsets> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+
+        for (k, v) in self.iter_entries() {
+            map.serialize_entry(&k, &v)?;
+        }
+
+        map.end()
+    }
+}
+
+impl<'input, 'offsets> Serialize for Arr<'input, 'offsets> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+
+        for elem in self.iter_elems() {
+            seq.serialize_element(&elem)?;
+        }
+
+        seq.end()
+    }
+}
@@ -1,17 +1,26 @@
 use std::{mem, ops::Index};
 
-use super::*;
+use super::{keys::KeyScan, *};
 
 #[cfg(test)]
 const MAX_BLOCK_SIZE: usize = 32;
 
+// documents larger than this probably won't fit in L2, so they may be prefetched while scanning
+const PREFETCH_THRESHOLD: usize = 1024 * 1024;
+
+// blocks are only prefetched once for each cache line
+const CACHE_LINE_SIZE: usize = 64;
+
 trait ScanSimd {
     type Block: Sized + Clone + Copy;
     const BLOCK_SIZE: usize = mem::size_of::<Self::Block>();
+    const PREFETCH_DISTANCE: usize;
 
     fn load_block_aligned(ptr: *const u8) -> Self::Block;
+    fn prefetch(ptr: *const u8);
     fn mask_quote_escape(block: Self::Block) -> i32;
     fn mask_interest(block: Self::Block) -> i32;
+    fn mask_non_ascii(block: Self::Block) -> i32;
 }
 
 #[cfg(target_arch = "x86_64")]
@@ -22,17 +31,36 @@ mod x86_64;
 #[cfg(target_arch = "x86_64")]
 #[inline]
 #[target_feature(enable = "avx2")]
-pub(super) unsafe fn scan_x86_64_avx2<'scan>(
+pub(super) unsafe fn scan_x86_64_avx2<'scan, const FLAT: bool>(
     input: &'scan [u8],
     scan: &mut Scan,
     offsets: &mut Offsets,
 ) {
-    scan_simd::<x86_64::AVX2>(input, scan, offsets)
+    scan_blocks::<x86_64::AVX2, false, FLAT>(input, scan, offsets);
+}
+
+// SAFETY: Callers must ensure `avx2` is available
+// SAFETY: Callers must not use the input as UTF8 unless this function returns `true`
+// SAFETY: Callers must ensure the scan doesn't capture any keys
+// the input is scanned before it's validated, so no interest handler may create a `str` from it
+// captures are the only handlers that do, so they must be disabled
+#[cfg(target_arch = "x86_64")]
+#[inline]
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn scan_validate_x86_64_avx2(
+    input: &[u8],
+    scan: &mut Scan,
+    offsets: &mut Offsets,
+) -> bool {
+    scan_blocks::<x86_64::AVX2, true, false>(input, scan, offsets)
 }
 
 #[cfg(target_arch = "x86_64")]
 pub(super) const X86_64_AVX2_VECTORIZATION_THRESHOLD: usize = x86_64::AVX2::BLOCK_SIZE * 5;
 
+#[cfg(target_arch = "x86_64")]
+pub(super) const X86_64_AVX2_MIN_INPUT_LEN: usize = x86_64::AVX2::BLOCK_SIZE * 2;
+
 #[cfg(target_arch = "aarch64")]
 mod aarch64;
 
@@ -41,25 +69,115 @@ mod aarch64;
 #[cfg(target_arch = "aarch64")]
 #[inline]
 #[target_feature(enable = "neon")]
-pub(super) unsafe fn scan_aarch64_neon<'scan>(
+pub(super) unsafe fn scan_aarch64_neon<'scan, const FLAT: bool>(
     input: &'scan [u8],
     scan: &mut Scan,
     offsets: &mut Offsets,
 ) {
-    scan_simd::<aarch64::Neon>(input, scan, offsets)
+    scan_blocks::<aarch64::Neon, false, FLAT>(input, scan, offsets);
+}
+
+// SAFETY: Callers must ensure `neon` is available
+// SAFETY: Callers must not use the input as UTF8 unless this function returns `true`
+// SAFETY: Callers must ensure the scan doesn't capture any keys
+// the input is scanned before it's validated, so no interest handler may create a `str` from it
+// captures are the only handlers that do, so they must be disabled
+#[cfg(target_arch = "aarch64")]
+#[inline]
+#[target_feature(enable = "neon")]
+pub(super) unsafe fn scan_validate_aarch64_neon(
+    input: &[u8],
+    scan: &mut Scan,
+    offsets: &mut Offsets,
+) -> bool {
+    scan_blocks::<aarch64::Neon, true, false>(input, scan, offsets)
 }
 
 #[cfg(target_arch = "aarch64")]
 pub(super) const AARCH64_NEON_VECTORIZATION_THRESHOLD: usize = aarch64::Neon::BLOCK_SIZE * 5;
 
-// SAFETY: Callers must ensure `input` is valid UTF8
+#[cfg(target_arch = "aarch64")]
+pub(super) const AARCH64_NEON_MIN_INPUT_LEN: usize = aarch64::Neon::BLOCK_SIZE * 2;
+
+// SAFETY: Callers must ensure `avx2` is available
+#[cfg(target_arch = "x86_64")]
+#[inline]
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn scan_keys_x86_64_avx2<'input>(
+    input: &'input [u8],
+    scan: &mut KeyScan<'input>,
+) -> Result<(), usize> {
+    scan_keys_simd::<x86_64::AVX2>(input, scan)
+}
+
+// SAFETY: Callers must ensure `neon` is available
+#[cfg(target_arch = "aarch64")]
+#[inline]
+#[target_feature(enable = "neon")]
+pub(super) unsafe fn scan_keys_aarch64_neon<'input>(
+    input: &'input [u8],
+    scan: &mut KeyScan<'input>,
+) -> Result<(), usize> {
+    scan_keys_simd::<aarch64::Neon>(input, scan)
+}
+
+/**
+Scan the input in vectorized blocks, prefetching ahead of them for huge inputs if that's enabled.
+
+Whether or not to prefetch is decided up-front so it doesn't cost anything when it's not used.
+*/
+// SAFETY: Callers must ensure `input` is valid UTF8, or that `VALIDATE_UTF8` is set
 #[inline(always)]
-unsafe fn scan_simd<'scan, V>(input: &'scan [u8], scan: &mut Scan, offsets: &mut Offsets)
+unsafe fn scan_blocks<V, const VALIDATE_UTF8: bool, const FLAT: bool>(
+    input: &[u8],
+    scan: &mut Scan,
+    offsets: &mut Offsets,
+) -> bool
+where
+    V: ScanSimd,
+{
+    // HEURISTIC: huge documents may be memory bound, so it can be worth prefetching blocks ahead
+    if scan.simd.prefetch && scan.input_len > PREFETCH_THRESHOLD {
+        scan_simd::<V, VALIDATE_UTF8, true, FLAT>(input, scan, offsets)
+    } else {
+        scan_simd::<V, VALIDATE_UTF8, false, FLAT>(input, scan, offsets)
+    }
+}
+
+/**
+Scan the input in vectorized blocks.
+
+If `VALIDATE_UTF8` is set then the whole input is also validated as UTF8 while its blocks
+are already loaded, and the result of that validation is returned. Otherwise the input
+is assumed to be UTF8 and `true` is always returned.
+
+When validating, blocks are scanned before they're validated, including the leading unaligned
+portion. That's fine because scanning never treats the input as UTF8, except for captures,
+which create strings from it. Captures must be disabled when `VALIDATE_UTF8` is set.
+
+Validation uses the same approach as the `utf8` module. Runs of blocks that aren't ASCII
+are validated as a whole by the standard library once an ASCII block is found.
+
+If `PREFETCH` is set then cache lines are prefetched some distance ahead of the current block.
+
+If `FLAT` is set then the input is scanned with the flat profile used by
+[`Document::scan_trusted_flat`].
+*/
+// SAFETY: Callers must ensure `input` is valid UTF8, or that `VALIDATE_UTF8` is set
+#[inline(always)]
+unsafe fn scan_simd<'scan, V, const VALIDATE_UTF8: bool, const PREFETCH: bool, const FLAT: bool>(
+    input: &'scan [u8],
+    scan: &mut Scan,
+    offsets: &mut Offsets,
+) -> bool
 where
     V: ScanSimd,
 {
     test_assert!(V::BLOCK_SIZE <= MAX_BLOCK_SIZE);
+    // NOTE: this must match the `*_MIN_INPUT_LEN` constants
     test_assert!(scan.input_remaining() > V::BLOCK_SIZE * 2);
+    // NOTE: captures create strings from the input, which may not have been validated yet
+    test_assert!(!VALIDATE_UTF8 || scan.capture.is_none());
 
     // HEURISTIC: we're probably going to be loading a lot of blocks, so it's worth aligning reads
 
@@ -72,7 +190,14 @@ where
         let read_to = ((scan.input_offset as usize + V::BLOCK_SIZE) - aligned_start) as isize;
 
         // scan the leading unaligned portion
-        fallback::scan_to(input, scan, offsets, read_to);
+        fallback::scan_to::<FLAT>(input, scan, offsets, read_to);
+    }
+
+    // the start of a run of blocks that contain non-ASCII bytes
+    // the leading unaligned portion may end in the middle of a character, so it begins a run
+    let mut non_ascii_start = None;
+    if VALIDATE_UTF8 && !get_unchecked!(input, ..scan.input_offset as usize).is_ascii() {
+        non_ascii_start = Some(0);
     }
 
     // figure out the start of the last aligned block to read
@@ -84,6 +209,13 @@ where
         scan.input_len - (V::BLOCK_SIZE + offset)
     } as isize;
 
+    // prefetches are issued far enough ahead of the block being scanned to hide the latency of
+    // loading them, and stop before the end of the input
+    let prefetch_last_block_start = scan.input_len as isize - V::PREFETCH_DISTANCE as isize;
+
+    #[cfg(any(test, feature = "metrics"))]
+    let vectorized_from = scan.input_offset;
+
     'aligned: while scan.input_offset <= aligned_last_block_start {
         test_assert_eq!(
             0,
@@ -96,9 +228,40 @@ where
 
         test_assert!((scan.input_offset as usize) + V::BLOCK_SIZE <= scan.input_len);
 
+        let block_ptr = input.as_ptr().offset(scan.input_offset);
+
+        if PREFETCH
+            && scan.input_offset < prefetch_last_block_start
+            && block_ptr as usize & (CACHE_LINE_SIZE - 1) == 0
+        {
+            V::prefetch(block_ptr.add(V::PREFETCH_DISTANCE));
+        }
+
         // we only cast at aligned offsets
         #[allow(clippy::cast_ptr_alignment)]
-        let i = V::load_block_aligned(input.as_ptr().offset(scan.input_offset) as *const _);
+        let i = V::load_block_aligned(block_ptr as *const _);
+
+        if VALIDATE_UTF8 {
+            let block_start = scan.input_offset as usize;
+
+            match (V::mask_non_ascii(i), non_ascii_start) {
+                // the block is ASCII, and so are all the blocks before it
+                (0, None) => (),
+                // the block is ASCII, so the non-ASCII run before it can be validated
+                // the start of this block must be a character boundary
+                (0, Some(start)) => {
+                    if !is_utf8(input, start, block_start) {
+                        return false;
+                    }
+
+                    non_ascii_start = None;
+                }
+                // the block isn't ASCII, so begin a run
+                (_, None) => non_ascii_start = Some(block_start),
+                // the block isn't ASCII, and we're already in a run
+                (_, Some(_)) => (),
+            }
+        }
 
         // first, find quotes and escapes in the input
         // we do this separately to optimize the case where
@@ -124,6 +287,9 @@ where
             'block: while scan.simd.masks.interest != 0 {
                 // advance through the block by shifting over zeros in the mask
                 // this is more efficient than looking at each byte individually
+                // numbers and atoms don't need their own mask, because they're begun
+                // by the interest char before them and finished by the one after them
+                // so any digits in between are skipped in the same way strings are
                 let block_offset = scan.simd.masks[scan.simd.active_mask].trailing_zeros();
                 test_assert!(block_offset < MAX_BLOCK_SIZE as u32);
 
@@ -137,7 +303,7 @@ where
 
                 let curr = *get_unchecked!(input, input_offset);
 
-                match_interest(&mut ScanFnInput {
+                match_interest(&mut ScanFnInput::<FLAT> {
                     curr_offset: input_offset,
                     curr,
                     input,
@@ -152,8 +318,99 @@ where
 
     test_assert!(scan.input_len - (scan.input_offset as usize) < V::BLOCK_SIZE);
 
+    #[cfg(any(test, feature = "metrics"))]
+    {
+        scan.metrics.vectorized_bytes += (scan.input_offset - vectorized_from) as usize;
+    }
+
+    // the rest of the input includes the trailing `}` that isn't scanned
+    if VALIDATE_UTF8
+        && !is_utf8(
+            input,
+            non_ascii_start.unwrap_or(scan.input_offset as usize),
+            input.len(),
+        )
+    {
+        return false;
+    }
+
     // finish the input using the fallback byte-by-byte scanning
-    fallback::scan(input, scan, offsets);
+    fallback::scan::<FLAT>(input, scan, offsets);
+
+    true
+}
+
+/**
+Scan the input in vectorized blocks for only the keys at its root.
+
+Within strings, only quotes and escapes are passed to the scan, so blocks of string contents
+without any are skipped without looking at them at all. Outside of strings, only the
+interest characters are passed to the scan, so numbers and atoms are skipped the same way.
+*/
+#[inline(always)]
+unsafe fn scan_keys_simd<'input, V>(
+    input: &'input [u8],
+    scan: &mut KeyScan<'input>,
+) -> Result<(), usize>
+where
+    V: ScanSimd,
+{
+    test_assert!(V::BLOCK_SIZE <= MAX_BLOCK_SIZE);
+
+    // scan the leading unaligned portion so blocks can be loaded aligned
+    let aligned_start = input.as_ptr().add(scan.input_offset) as usize % V::BLOCK_SIZE;
+
+    if aligned_start != 0 {
+        scan.scan_to(input, scan.input_offset + V::BLOCK_SIZE - aligned_start)?;
+    }
+
+    while scan.input_offset + V::BLOCK_SIZE <= scan.end {
+        // we only cast at aligned offsets
+        #[allow(clippy::cast_ptr_alignment)]
+        let i = V::load_block_aligned(input.as_ptr().add(scan.input_offset) as *const _);
+
+        let mut mask_quote = V::mask_quote_escape(i);
+
+        // HEURISTIC: if there are no quotes or escapes and we're inside a string then
+        // there's no need to look for any other interest chars
+        if mask_quote != 0 || !scan.in_str() {
+            let mut mask_interest = V::mask_interest(i);
+
+            loop {
+                let mask = if scan.in_str() {
+                    mask_quote
+                } else {
+                    mask_interest
+                };
+
+                if mask == 0 {
+                    break;
+                }
+
+                let block_offset = mask.trailing_zeros();
+                test_assert!(block_offset < MAX_BLOCK_SIZE as u32);
+
+                let shift = (!0i64 << (block_offset + 1)) as i32;
+
+                mask_interest &= shift;
+                mask_quote &= shift;
+
+                let curr_offset = scan.input_offset + block_offset as usize;
+
+                scan.step(input, curr_offset, *get_unchecked!(input, curr_offset))?;
+            }
+        }
+
+        scan.input_offset += V::BLOCK_SIZE;
+    }
+
+    // finish the input byte-by-byte
+    scan.scan_to(input, scan.end)
+}
+
+#[inline(always)]
+fn is_utf8(input: &[u8], from: usize, to: usize) -> bool {
+    str::from_utf8(get_unchecked!(input, from..to)).is_ok()
 }
 
 impl Scan {
@@ -172,6 +429,7 @@ impl Scan {
 pub(super) struct Simd {
     masks: Masks,
     active_mask: ActiveMask,
+    pub(super) prefetch: bool,
 }
 
 impl Simd {
@@ -183,6 +441,7 @@ impl Simd {
                 quote: 0,
             },
             active_mask: ActiveMask::Interest,
+            prefetch: false,
         }
     }
 }
@@ -1,11 +1,12 @@
 use super::*;
 
 use crate::std_ext::arch::aarch64::*;
-use std::arch::aarch64::*;
+use std::arch::{aarch64::*, asm};
 
 pub(super) struct Neon;
 impl ScanSimd for Neon {
     type Block = uint8x8_t;
+    const PREFETCH_DISTANCE: usize = Self::BLOCK_SIZE * 64;
 
     #[inline(always)]
     fn load_block_aligned(ptr: *const u8) -> Self::Block {
@@ -13,6 +14,19 @@ impl ScanSimd for Neon {
         unsafe { vld1_u8(ptr) }
     }
 
+    #[inline(always)]
+    fn prefetch(ptr: *const u8) {
+        // SAFETY: Prefetching is only a hint, so it doesn't read or fault on the address
+        // the intrinsic for this isn't stable yet
+        unsafe {
+            asm!(
+                "prfm pldl1keep, [{ptr}]",
+                ptr = in(reg) ptr,
+                options(nostack, readonly, preserves_flags)
+            )
+        }
+    }
+
     #[inline(always)]
     fn mask_quote_escape(block: Self::Block) -> i32 {
         // SAFETY: In this module, Neon is always available
@@ -97,6 +111,20 @@ impl ScanSimd for Neon {
             vmovemask_u8(interest_hi_lo) as i32
         }
     }
+
+    #[inline(always)]
+    fn mask_non_ascii(block: Self::Block) -> i32 {
+        // SAFETY: In this module, Neon is always available
+        unsafe {
+            // non-ASCII bytes are the ones with their high bit set
+            let mask = vtst_u8(
+                block,
+                splat([0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80]),
+            );
+
+            vmovemask_u8(mask) as i32
+        }
+    }
 }
 
 #[cfg(test)]
@@ -5,12 +5,18 @@ use std::arch::x86_64::*;
 pub(super) struct AVX2;
 impl ScanSimd for AVX2 {
     type Block = __m256i;
+    const PREFETCH_DISTANCE: usize = Self::BLOCK_SIZE * 16;
 
     #[inline(always)]
     fn load_block_aligned(ptr: *const u8) -> Self::Block {
         unsafe { _mm256_load_si256(ptr as *const _) }
     }
 
+    #[inline(always)]
+    fn prefetch(ptr: *const u8) {
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(ptr as *const i8) }
+    }
+
     #[inline(always)]
     fn mask_quote_escape(block: Self::Block) -> i32 {
         unsafe {
@@ -79,6 +85,12 @@ impl ScanSimd for AVX2 {
             !mask_interest
         }
     }
+
+    #[inline(always)]
+    fn mask_non_ascii(block: Self::Block) -> i32 {
+        // non-ASCII bytes are the ones with their high bit set
+        unsafe { _mm256_movemask_epi8(block) }
+    }
 }
 
 #[cfg(test)]
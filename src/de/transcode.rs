@@ -0,0 +1,306 @@
+/*!
+Transcode documents directly into binary formats.
+
+Documents are written straight from their offsets without going through an intermediate
+value, so converting an archived event only needs the one output buffer. Maps and arrays
+know their length up-front, so they're always written with definite lengths.
+
+Numbers are written as integers if they fit in one, and as double-precision floats otherwise.
+Numbers that can't be parsed are written as strings.
+*/
+
+use super::{Document, Elems, Entries, Kind};
+
+/**
+A binary format to transcode documents into.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BinaryFormat {
+    /**
+    [MessagePack](https://github.com/msgpack/msgpack/blob/master/spec.md).
+    */
+    MessagePack,
+    /**
+    [CBOR](https://datatracker.ietf.org/doc/html/rfc8949).
+    */
+    Cbor,
+}
+
+impl<'input> Document<'input> {
+    /**
+    Transcode the document into a new buffer in a binary format.
+    */
+    pub fn transcode(&self, format: BinaryFormat) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.input.len());
+
+        self.transcode_into(format, &mut out);
+
+        out
+    }
+
+    /**
+    Transcode the document into a caller-provided buffer in a binary format.
+
+    The transcoded document is appended to the buffer.
+    */
+    pub fn transcode_into(&self, format: BinaryFormat, out: &mut Vec<u8>) {
+        match format {
+            BinaryFormat::MessagePack => kind::<MessagePack>(out, &Kind::Map(self.as_map())),
+            BinaryFormat::Cbor => kind::<Cbor>(out, &Kind::Map(self.as_map())),
+        }
+    }
+}
+
+trait Encode {
+    fn map_begin(out: &mut Vec<u8>, len: usize);
+    fn arr_begin(out: &mut Vec<u8>, len: usize);
+    fn str(out: &mut Vec<u8>, s: &str);
+    fn uint(out: &mut Vec<u8>, n: u64);
+    fn neg_int(out: &mut Vec<u8>, n: i64);
+    fn float(out: &mut Vec<u8>, n: f64);
+    fn bool(out: &mut Vec<u8>, b: bool);
+    fn null(out: &mut Vec<u8>);
+}
+
+/**
+Write an element.
+
+Maps and arrays are written using an explicit stack of the elements left in each.
+Their lengths are written up-front, so they don't need anything written after them.
+*/
+fn kind<E: Encode>(out: &mut Vec<u8>, kind: &Kind) {
+    let mut stack = Vec::new();
+    kind_begin::<E>(out, &mut stack, kind);
+
+    while let Some(frame) = stack.last_mut() {
+        let value = match frame {
+            Frame::Map(entries) => match entries.next() {
+                Some((k, v)) => {
+                    E::str(out, &k.to_unescaped_inline());
+                    v
+                }
+                None => {
+                    stack.pop();
+                    continue;
+                }
+            },
+            Frame::Arr(elems) => match elems.next() {
+                Some(e) => e,
+                None => {
+                    stack.pop();
+                    continue;
+                }
+            },
+        };
+
+        kind_begin::<E>(out, &mut stack, &value);
+    }
+}
+
+enum Frame<'input, 'offsets> {
+    Map(Entries<'input, 'offsets>),
+    Arr(Elems<'input, 'offsets>),
+}
+
+#[inline]
+fn kind_begin<'input, 'offsets, E: Encode>(
+    out: &mut Vec<u8>,
+    stack: &mut Vec<Frame<'input, 'offsets>>,
+    kind: &Kind<'input, 'offsets>,
+) {
+    match kind {
+        Kind::Str(s) => E::str(out, &s.to_unescaped_inline()),
+        Kind::Num(n) => num::<E>(out, n.trim()),
+        Kind::Bool(b) => E::bool(out, *b),
+        Kind::Null => E::null(out),
+        Kind::Map(ref map) => {
+            E::map_begin(out, map.len());
+            stack.push(Frame::Map(map.iter_entries()));
+        }
+        Kind::Arr(ref arr) => {
+            E::arr_begin(out, arr.len());
+            stack.push(Frame::Arr(arr.iter_elems()));
+        }
+        // the scanned document doesn't contain any lazy values itself, so this only nests once
+        Kind::Lazy(lazy) => match lazy.scan() {
+            Some(nested) => self::kind::<E>(out, &nested.as_kind()),
+            // spans that are too large to scan on their own can't be transcoded
+            None => E::null(out),
+        },
+    }
+}
+
+fn num<E: Encode>(out: &mut Vec<u8>, n: &str) {
+    // the standard library also parses things like `+1`, `inf`, and `NaN`,
+    // which aren't numbers in JSON
+    let numeric = n.starts_with(|c: char| c == '-' || c.is_ascii_digit());
+
+    if numeric && !n.contains(['.', 'e', 'E']) {
+        if let Ok(n) = n.parse::<u64>() {
+            return E::uint(out, n);
+        }
+
+        if let Ok(n) = n.parse::<i64>() {
+            // `-0` is parsed as `0`
+            return if n < 0 {
+                E::neg_int(out, n)
+            } else {
+                E::uint(out, n as u64)
+            };
+        }
+    }
+
+    match n.parse::<f64>() {
+        Ok(f) if numeric && f.is_finite() => E::float(out, f),
+        _ => E::str(out, n),
+    }
+}
+
+struct MessagePack;
+
+impl MessagePack {
+    fn len(out: &mut Vec<u8>, len: usize, fix: u8, fix_max: usize, tag16: u8, tag32: u8) {
+        if len <= fix_max {
+            out.push(fix | len as u8);
+        } else if len <= u16::MAX as usize {
+            out.push(tag16);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            out.push(tag32);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+}
+
+impl Encode for MessagePack {
+    fn map_begin(out: &mut Vec<u8>, len: usize) {
+        MessagePack::len(out, len, 0x80, 15, 0xde, 0xdf);
+    }
+
+    fn arr_begin(out: &mut Vec<u8>, len: usize) {
+        MessagePack::len(out, len, 0x90, 15, 0xdc, 0xdd);
+    }
+
+    fn str(out: &mut Vec<u8>, s: &str) {
+        // strings have an extra 8bit length that maps and arrays don't
+        if s.len() > 31 && s.len() <= u8::MAX as usize {
+            out.push(0xd9);
+            out.push(s.len() as u8);
+        } else {
+            MessagePack::len(out, s.len(), 0xa0, 31, 0xda, 0xdb);
+        }
+
+        out.extend_from_slice(s.as_bytes());
+    }
+
+    fn uint(out: &mut Vec<u8>, n: u64) {
+        if n <= 0x7f {
+            out.push(n as u8);
+        } else if n <= u8::MAX as u64 {
+            out.push(0xcc);
+            out.push(n as u8);
+        } else if n <= u16::MAX as u64 {
+            out.push(0xcd);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        } else if n <= u32::MAX as u64 {
+            out.push(0xce);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        } else {
+            out.push(0xcf);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+
+    fn neg_int(out: &mut Vec<u8>, n: i64) {
+        if n >= -32 {
+            out.push(n as u8);
+        } else if n >= i8::MIN as i64 {
+            out.push(0xd0);
+            out.push(n as u8);
+        } else if n >= i16::MIN as i64 {
+            out.push(0xd1);
+            out.extend_from_slice(&(n as i16).to_be_bytes());
+        } else if n >= i32::MIN as i64 {
+            out.push(0xd2);
+            out.extend_from_slice(&(n as i32).to_be_bytes());
+        } else {
+            out.push(0xd3);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+
+    fn float(out: &mut Vec<u8>, n: f64) {
+        out.push(0xcb);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+
+    fn bool(out: &mut Vec<u8>, b: bool) {
+        out.push(if b { 0xc3 } else { 0xc2 });
+    }
+
+    fn null(out: &mut Vec<u8>) {
+        out.push(0xc0);
+    }
+}
+
+struct Cbor;
+
+impl Cbor {
+    fn head(out: &mut Vec<u8>, major: u8, n: u64) {
+        let major = major << 5;
+
+        if n < 24 {
+            out.push(major | n as u8);
+        } else if n <= u8::MAX as u64 {
+            out.push(major | 24);
+            out.push(n as u8);
+        } else if n <= u16::MAX as u64 {
+            out.push(major | 25);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        } else if n <= u32::MAX as u64 {
+            out.push(major | 26);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        } else {
+            out.push(major | 27);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
+
+impl Encode for Cbor {
+    fn map_begin(out: &mut Vec<u8>, len: usize) {
+        Cbor::head(out, 5, len as u64);
+    }
+
+    fn arr_begin(out: &mut Vec<u8>, len: usize) {
+        Cbor::head(out, 4, len as u64);
+    }
+
+    fn str(out: &mut Vec<u8>, s: &str) {
+        Cbor::head(out, 3, s.len() as u64);
+        out.extend_from_slice(s.as_bytes());
+    }
+
+    fn uint(out: &mut Vec<u8>, n: u64) {
+        Cbor::head(out, 0, n);
+    }
+
+    fn neg_int(out: &mut Vec<u8>, n: i64) {
+        // negative integers are encoded as `-1 - n`
+        Cbor::head(out, 1, !n as u64);
+    }
+
+    fn float(out: &mut Vec<u8>, n: f64) {
+        out.push(0xfb);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+
+    fn bool(out: &mut Vec<u8>, b: bool) {
+        out.push(if b { 0xf5 } else { 0xf4 });
+    }
+
+    fn null(out: &mut Vec<u8>) {
+        out.push(0xf6);
+    }
+}
@@ -0,0 +1,160 @@
+/*!
+Unescape short strings without allocating.
+
+Most escaped strings in documents are short, like messages with a quoted name or paths with
+a `\` in them. Unescaping never makes a string longer, so any string whose raw value fits in
+a small fixed-size buffer can be unescaped into it on the stack instead of into a new `String`.
+*/
+
+use std::{borrow::Cow, fmt, hash, ops::Deref};
+
+use super::Str;
+
+use crate::unescape::unescape_trusted_into_slice;
+
+/**
+An unescaped string that's stored inline if it's short enough.
+
+Strings without escapes are borrowed from the input, short escaped strings are unescaped into an
+inline buffer, and only long escaped strings are unescaped into a new allocation.
+*/
+#[derive(Clone)]
+pub struct UnescapedStr<'input>(Inner<'input>);
+
+#[derive(Clone)]
+enum Inner<'input> {
+    Borrowed(&'input str),
+    Inline([u8; UnescapedStr::INLINE_CAPACITY], u8),
+    Owned(String),
+}
+
+impl<'input> Str<'input> {
+    /**
+    Returns the underlying string.
+
+    If the string is escaped and its raw value is longer than [`UnescapedStr::INLINE_CAPACITY`]
+    then this method will allocate and unescape it. Shorter strings are unescaped inline.
+    */
+    #[inline]
+    pub fn to_unescaped_inline(&self) -> UnescapedStr<'input> {
+        let raw = self.as_raw();
+
+        if !self.1 {
+            return UnescapedStr(Inner::Borrowed(raw));
+        }
+
+        if raw.len() <= UnescapedStr::INLINE_CAPACITY {
+            let mut buf = [0; UnescapedStr::INLINE_CAPACITY];
+
+            // SAFETY: The string to unescape was parsed from JSON
+            // So it can't end with an unescaped `\`
+            // SAFETY: The buffer is at least as long as the string
+            let len = unsafe { unescape_trusted_into_slice(raw, &mut buf) };
+
+            UnescapedStr(Inner::Inline(buf, len as u8))
+        } else {
+            UnescapedStr(Inner::Owned(self.to_unescaped().into_owned()))
+        }
+    }
+}
+
+impl<'input> UnescapedStr<'input> {
+    /**
+    The length of the longest raw escaped string that's unescaped inline.
+    */
+    pub const INLINE_CAPACITY: usize = 64;
+
+    /**
+    Get the unescaped string.
+    */
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        match self.0 {
+            Inner::Borrowed(s) => s,
+            Inner::Inline(ref buf, len) => {
+                from_utf8_unchecked!(get_unchecked!(buf, ..len as usize))
+            }
+            Inner::Owned(ref s) => s,
+        }
+    }
+
+    /**
+    Whether or not the string needed to be allocated.
+    */
+    #[inline]
+    pub fn is_allocated(&self) -> bool {
+        matches!(self.0, Inner::Owned(_))
+    }
+
+    /**
+    Convert the string into a `Cow`, borrowing from the input if possible.
+
+    Strings that are stored inline need to be allocated.
+    */
+    #[inline]
+    pub fn into_cow(self) -> Cow<'input, str> {
+        match self.0 {
+            Inner::Borrowed(s) => Cow::Borrowed(s),
+            Inner::Owned(s) => Cow::Owned(s),
+            Inner::Inline(..) => Cow::Owned(self.as_str().to_owned()),
+        }
+    }
+}
+
+impl<'input> Deref for UnescapedStr<'input> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'input> AsRef<str> for UnescapedStr<'input> {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'input> fmt::Debug for UnescapedStr<'input> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<'input> fmt::Display for UnescapedStr<'input> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl<'a, 'b> PartialEq<UnescapedStr<'b>> for UnescapedStr<'a> {
+    #[inline]
+    fn eq(&self, other: &UnescapedStr<'b>) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<'input> Eq for UnescapedStr<'input> {}
+
+impl<'input> PartialEq<str> for UnescapedStr<'input> {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a, 'input> PartialEq<&'a str> for UnescapedStr<'input> {
+    #[inline]
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<'input> hash::Hash for UnescapedStr<'input> {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
@@ -0,0 +1,249 @@
+/*!
+Non-fatal warnings collected while scanning a document.
+
+The scanner accepts some inputs that aren't valid JSON without considering them erroneous.
+Warnings let callers find out about them without having to fully validate documents.
+
+Most warnings are collected from the offsets after the input has been scanned, so they don't
+add any work to the scanner itself. Atoms are the exception, because their offsets don't
+carry a position in the input.
+*/
+
+use std::slice;
+
+use super::{interest::ScanFnInput, OffsetKind, Offsets, ProblemKind};
+
+use crate::{escape::find_control, unescape::invalid_escapes};
+
+/**
+The warnings collected while scanning a document.
+*/
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+#[derive(Debug, Clone, Default)]
+pub struct ScanWarnings {
+    warnings: Vec<Warning>,
+}
+
+/**
+A recoverable oddity in the input of a document.
+*/
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Warning {
+    kind: WarningKind,
+    input_offset: usize,
+}
+
+/**
+The kind of oddity a warning is for.
+*/
+#[cfg_attr(
+    any(test, feature = "rkyv"),
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /**
+    A string contains an escape sequence that isn't valid JSON.
+
+    The sequence is passed through as-is when unescaping.
+    */
+    InvalidEscape,
+    /**
+    A string contains a `\u` escape for a surrogate that isn't part of a pair.
+    */
+    LoneSurrogate,
+    /**
+    A map was terminated with a `]`, so it's treated as an array.
+    */
+    MapTerminatedAsArr,
+    /**
+    An array was terminated with a `}`, so it's treated as a map.
+    */
+    ArrTerminatedAsMap,
+    /**
+    An atom isn't exactly `true`, `false`, or `null`.
+
+    Only the first character of an atom is used to determine its value.
+    */
+    InvalidAtom,
+    /**
+    A string contains a control character that isn't escaped, like a raw newline or `NUL`.
+
+    The character is passed through as-is when unescaping.
+    */
+    UnescapedControl,
+}
+
+impl ScanWarnings {
+    pub(super) const fn new() -> Self {
+        ScanWarnings {
+            warnings: Vec::new(),
+        }
+    }
+
+    pub(super) fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Warning>() * self.warnings.capacity()
+    }
+
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.warnings.shrink_to_fit();
+    }
+
+    /**
+    Whether or not any warnings were collected.
+    */
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /**
+    The number of warnings collected.
+    */
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    /**
+    Iterate over the warnings in the order they appear in the input.
+    */
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, Warning> {
+        self.warnings.iter()
+    }
+
+    #[inline]
+    fn push(&mut self, kind: WarningKind, input_offset: usize) {
+        self.warnings.push(Warning { kind, input_offset });
+    }
+}
+
+impl<'a> IntoIterator for &'a ScanWarnings {
+    type Item = &'a Warning;
+    type IntoIter = slice::Iter<'a, Warning>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Warning {
+    /**
+    The kind of oddity this warning is for.
+    */
+    #[inline]
+    pub fn kind(&self) -> WarningKind {
+        self.kind
+    }
+
+    /**
+    The offset in the input where the oddity begins.
+    */
+    #[inline]
+    pub fn input_offset(&self) -> usize {
+        self.input_offset
+    }
+}
+
+impl<'a, 'scan, const FLAT: bool> ScanFnInput<'a, 'scan, FLAT> {
+    /**
+    Check that the atom at the current offset is exactly the expected one.
+
+    Mismatches are collected as warnings, and make the document erroneous if atoms are verified.
+    */
+    #[inline(never)]
+    pub(super) fn check_atom(&mut self, expected: &[u8]) {
+        let end = self.curr_offset + expected.len();
+
+        let exact = self.input.get(self.curr_offset..end) == Some(expected)
+            && matches!(self.input.get(end), Some(b',' | b'}' | b']') | None);
+
+        if !exact {
+            if self.scan.warn {
+                self.offsets
+                    .warnings
+                    .push(WarningKind::InvalidAtom, self.curr_offset);
+            }
+
+            if self.scan.verify_atoms {
+                self.scan.err(self.curr_offset, ProblemKind::InvalidAtom);
+            }
+        }
+    }
+}
+
+/**
+Collect warnings from the strings and containers in a scanned document.
+*/
+#[cold]
+pub(super) fn collect(input: &[u8], offsets: &mut Offsets) {
+    let Offsets {
+        elements, warnings, ..
+    } = offsets;
+
+    for offset in elements.iter() {
+        match offset.kind {
+            OffsetKind::Str(s, escaped) => {
+                let start = s.offset as usize;
+                let end = start + s.len as usize;
+
+                let s = get_unchecked!(input, start..end);
+
+                collect_controls(s, start, warnings);
+
+                // only strings with escapes need to be checked for invalid escapes
+                if escaped {
+                    collect_escapes(s, start, warnings);
+                }
+            }
+            // a map that's terminated by `]` is scanned as an array
+            OffsetKind::Arr(_, raw) if raw.len > 0 && input[raw.offset as usize] == b'{' => {
+                warnings.push(WarningKind::MapTerminatedAsArr, raw.offset as usize);
+            }
+            // an array that's terminated by `}` is scanned as a map
+            OffsetKind::Map(_, raw) if raw.len > 0 && input[raw.offset as usize] == b'[' => {
+                warnings.push(WarningKind::ArrTerminatedAsMap, raw.offset as usize);
+            }
+            _ => (),
+        }
+    }
+
+    // atoms are collected while scanning, so the warnings may be out of order
+    warnings
+        .warnings
+        .sort_by_key(|warning| warning.input_offset);
+}
+
+fn collect_controls(s: &[u8], input_offset: usize, warnings: &mut ScanWarnings) {
+    let mut i = 0;
+
+    while let Some(control) = find_control(get_unchecked!(s, i..)) {
+        warnings.push(WarningKind::UnescapedControl, input_offset + i + control);
+        i += control + 1;
+    }
+}
+
+fn collect_escapes(s: &[u8], input_offset: usize, warnings: &mut ScanWarnings) {
+    for invalid in invalid_escapes(s) {
+        let kind = if invalid.lone_surrogate {
+            WarningKind::LoneSurrogate
+        } else {
+            WarningKind::InvalidEscape
+        };
+
+        warnings.push(kind, input_offset + invalid.input_offset);
+    }
+}
@@ -0,0 +1,144 @@
+/*!
+Diagnostics for comparing the behavior of scanner backends.
+
+The vectorized and fallback scanners are expected to produce exactly the same offsets
+for valid input. If they don't then it's a bug, and [`compare`] can be used to
+pin down where it is. [`assert_backends_agree`] does the same check at every alignment
+the vectorized scanner could see an input at, so it can be used in integration tests.
+*/
+
+use crate::de::{self, Document};
+
+/**
+The first point where the vectorized and fallback scanners disagree on some input.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Divergence {
+    /**
+    Both scanners produced the same offsets.
+    */
+    None,
+    /**
+    The current CPU doesn't support the vectorized scanner, so there's nothing to compare.
+    */
+    Unsupported,
+    /**
+    One scanner detected an error and the other didn't.
+    */
+    Err {
+        simd_is_err: bool,
+        scalar_is_err: bool,
+    },
+    /**
+    The scanners produced a different element at the same index.
+
+    If one scanner produced fewer elements than the other then its element will be `None`.
+    */
+    Element {
+        index: usize,
+        input_offset: Option<usize>,
+        simd: Option<String>,
+        scalar: Option<String>,
+    },
+}
+
+impl Divergence {
+    /**
+    Whether or not the scanners agreed.
+    */
+    #[inline]
+    pub fn is_none(&self) -> bool {
+        matches!(self, Divergence::None)
+    }
+}
+
+/**
+Scan the input using both the vectorized and fallback scanners, and report the first point
+where their results differ.
+
+For invalid input, it's expected that the scanners may diverge.
+*/
+pub fn compare(input: &[u8]) -> Divergence {
+    if !de::is_simd_supported() {
+        return Divergence::Unsupported;
+    }
+
+    let simd = Document::scan_trusted_simd(input);
+    let scalar = Document::scan_trusted_scalar(input);
+
+    if simd.is_err() || scalar.is_err() {
+        return if simd.is_err() == scalar.is_err() {
+            Divergence::None
+        } else {
+            Divergence::Err {
+                simd_is_err: simd.is_err(),
+                scalar_is_err: scalar.is_err(),
+            }
+        };
+    }
+
+    let (simd, scalar) = (simd.offsets(), scalar.offsets());
+
+    match simd.first_divergence(scalar) {
+        Some(index) => Divergence::Element {
+            index,
+            input_offset: simd
+                .input_offset(index)
+                .or_else(|| scalar.input_offset(index)),
+            simd: simd.describe(index),
+            scalar: scalar.describe(index),
+        },
+        None => Divergence::None,
+    }
+}
+
+/**
+The alignment of blocks loaded by the vectorized scanners.
+
+Inputs are compared at every offset from this alignment.
+*/
+const MAX_ALIGNMENT: usize = 32;
+
+/**
+Assert that the vectorized and fallback scanners produce exactly the same offsets for an input.
+
+The input is copied so it starts at each of the 32 possible offsets from a 32 byte boundary,
+and scanned by both scanners at each one. The vectorized scanner is whichever one the current
+CPU supports, like AVX2 on x86_64 or NEON on aarch64. Their raw tables of offsets are compared,
+rather than any values converted from them, so any difference in the offsets is caught.
+
+If the current CPU doesn't support the vectorized scanner then there's nothing to compare,
+so this function doesn't do anything.
+
+# Panics
+
+This function panics if the scanners diverge at any alignment, with the alignment and the
+[`Divergence`] in the message.
+*/
+#[track_caller]
+pub fn assert_backends_agree(input: &[u8]) {
+    if !de::is_simd_supported() {
+        return;
+    }
+
+    let mut buf = Vec::<u8>::with_capacity(input.len() + MAX_ALIGNMENT * 2);
+
+    for align in 0..MAX_ALIGNMENT {
+        buf.clear();
+
+        // pad the buffer so the input starts `align` bytes after a boundary
+        let pad = buf.as_ptr().align_offset(MAX_ALIGNMENT) + align;
+        buf.resize(pad, 0);
+        buf.extend_from_slice(input);
+
+        let divergence = compare(&buf[pad..]);
+
+        assert!(
+            divergence.is_none(),
+            "the scanners diverged at alignment {}: {:?}",
+            align,
+            divergence
+        );
+    }
+}
@@ -0,0 +1,160 @@
+/*!
+String escaping for JSON documents.
+
+This module contains a vectorized implementation for escaping JSON strings.
+
+Most strings don't contain any characters that need escaping, so the vectorized
+implementation searches blocks for quotes, backslashes, and control characters,
+and copies the runs between them as-is.
+
+This implementation follows the same basic design as `unescape` for supporting a vectorized
+and fallback implementation.
+*/
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+mod simd;
+
+/**
+Write a string as a quoted and escaped JSON string.
+*/
+pub(crate) fn escape_str(buf: &mut Vec<u8>, s: &str) {
+    buf.reserve(s.len() + 2);
+
+    buf.push(b'"');
+    escape(buf, s.as_bytes());
+    buf.push(b'"');
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[inline]
+fn escape(buf: &mut Vec<u8>, input: &[u8]) {
+    // when SIMD is available, we can vectorize
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2")
+            && input.len() > simd::X86_64_AVX2_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: avx2 is available
+            unsafe { simd::escape_x86_64_avx2(buf, input) };
+            return;
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon")
+            && input.len() > simd::AARCH64_NEON_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: neon is available
+            unsafe { simd::escape_aarch64_neon(buf, input) };
+            return;
+        }
+    }
+
+    // when SIMD is not available, we need to fallback
+    escape_fallback(buf, input, 0, 0);
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[inline]
+fn escape(buf: &mut Vec<u8>, input: &[u8]) {
+    escape_fallback(buf, input, 0, 0);
+}
+
+/**
+Find the offset of the first control character in the input.
+
+Control characters must be escaped in JSON strings, so any that appear in a string's raw input are invalid.
+*/
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[inline]
+pub(crate) fn find_control(input: &[u8]) -> Option<usize> {
+    // when SIMD is available, we can vectorize
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2")
+            && input.len() > simd::X86_64_AVX2_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: avx2 is available
+            return unsafe { simd::find_control_x86_64_avx2(input) };
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon")
+            && input.len() > simd::AARCH64_NEON_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: neon is available
+            return unsafe { simd::find_control_aarch64_neon(input) };
+        }
+    }
+
+    // when SIMD is not available, we need to fallback
+    find_control_fallback(input, 0)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[inline]
+pub(crate) fn find_control(input: &[u8]) -> Option<usize> {
+    find_control_fallback(input, 0)
+}
+
+#[inline]
+fn find_control_fallback(input: &[u8], input_offset: usize) -> Option<usize> {
+    get_unchecked!(input, input_offset..)
+        .iter()
+        .position(|b| *b <= 0x1f)
+        .map(|i| input_offset + i)
+}
+
+/**
+Escape the input byte-by-byte, starting from an offset.
+
+Any input between `flushed` and `input_offset` is known not to need escaping.
+*/
+#[inline]
+fn escape_fallback(buf: &mut Vec<u8>, input: &[u8], mut flushed: usize, input_offset: usize) {
+    for (i, &b) in input.iter().enumerate().skip(input_offset) {
+        if needs_escape(b) {
+            flushed = escape_at(buf, input, flushed, i);
+        }
+    }
+
+    buf.extend_from_slice(get_unchecked!(input, flushed..));
+}
+
+#[inline(always)]
+fn needs_escape(b: u8) -> bool {
+    matches!(b, b'"' | b'\\' | 0x00..=0x1f)
+}
+
+/**
+Flush any input up to the byte at `escape_offset` and then escape it.
+
+Returns the offset of the input that's been flushed up to.
+*/
+#[inline(always)]
+fn escape_at(buf: &mut Vec<u8>, input: &[u8], flushed: usize, escape_offset: usize) -> usize {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    buf.extend_from_slice(get_unchecked!(input, flushed..escape_offset));
+
+    match *get_unchecked!(input, escape_offset) {
+        b'"' => buf.extend_from_slice(b"\\\""),
+        b'\\' => buf.extend_from_slice(b"\\\\"),
+        b'\n' => buf.extend_from_slice(b"\\n"),
+        b'\r' => buf.extend_from_slice(b"\\r"),
+        b'\t' => buf.extend_from_slice(b"\\t"),
+        0x08 => buf.extend_from_slice(b"\\b"),
+        0x0c => buf.extend_from_slice(b"\\f"),
+        b => buf.extend_from_slice(&[
+            b'\\',
+            b'u',
+            b'0',
+            b'0',
+            HEX[(b >> 4) as usize],
+            HEX[(b & 0xf) as usize],
+        ]),
+    }
+
+    escape_offset + 1
+}
@@ -0,0 +1,132 @@
+use std::mem;
+
+use super::*;
+
+#[cfg(test)]
+const MAX_BLOCK_SIZE: usize = 32;
+
+trait EscapeSimd {
+    type Block: Sized + Clone + Copy;
+    const BLOCK_SIZE: usize = mem::size_of::<Self::Block>();
+
+    fn load_block_unaligned(ptr: *const u8) -> Self::Block;
+    fn mask_escape(block: Self::Block) -> i32;
+    fn mask_control(block: Self::Block) -> i32;
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64;
+
+// SAFETY: Callers must ensure `avx2` is available
+#[cfg(target_arch = "x86_64")]
+#[inline]
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn escape_x86_64_avx2(buf: &mut Vec<u8>, input: &[u8]) {
+    escape_simd::<x86_64::AVX2>(buf, input)
+}
+
+// SAFETY: Callers must ensure `avx2` is available
+#[cfg(target_arch = "x86_64")]
+#[inline]
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn find_control_x86_64_avx2(input: &[u8]) -> Option<usize> {
+    find_control_simd::<x86_64::AVX2>(input)
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(super) const X86_64_AVX2_VECTORIZATION_THRESHOLD: usize = x86_64::AVX2::BLOCK_SIZE;
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64;
+
+// SAFETY: Callers must ensure `neon` is available
+#[cfg(target_arch = "aarch64")]
+#[inline]
+#[target_feature(enable = "neon")]
+pub(super) unsafe fn escape_aarch64_neon(buf: &mut Vec<u8>, input: &[u8]) {
+    escape_simd::<aarch64::Neon>(buf, input)
+}
+
+// SAFETY: Callers must ensure `neon` is available
+#[cfg(target_arch = "aarch64")]
+#[inline]
+#[target_feature(enable = "neon")]
+pub(super) unsafe fn find_control_aarch64_neon(input: &[u8]) -> Option<usize> {
+    find_control_simd::<aarch64::Neon>(input)
+}
+
+#[cfg(target_arch = "aarch64")]
+pub(super) const AARCH64_NEON_VECTORIZATION_THRESHOLD: usize = aarch64::Neon::BLOCK_SIZE;
+
+#[inline(always)]
+unsafe fn escape_simd<V>(buf: &mut Vec<u8>, input: &[u8])
+where
+    V: EscapeSimd,
+{
+    test_assert!(V::BLOCK_SIZE <= MAX_BLOCK_SIZE);
+    test_assert!(input.len() >= V::BLOCK_SIZE);
+
+    // HEURISTIC: we're escaping the input once, so we just do unaligned loads
+
+    let last_block_start = input.len() - V::BLOCK_SIZE;
+
+    let mut flushed = 0;
+    let mut input_offset = 0;
+
+    while input_offset <= last_block_start {
+        test_assert!(input_offset + V::BLOCK_SIZE <= input.len());
+
+        // we explicitly perform an unaligned load
+        let i = V::load_block_unaligned(input.as_ptr().add(input_offset));
+
+        // find characters that need escaping in the input
+        let mut mask_escape = V::mask_escape(i) as u32;
+
+        while mask_escape != 0 {
+            let block_offset = mask_escape.trailing_zeros() as usize;
+
+            flushed = escape_at(buf, input, flushed, input_offset + block_offset);
+
+            // clear the lowest set bit in the mask
+            mask_escape &= mask_escape - 1;
+        }
+
+        input_offset += V::BLOCK_SIZE;
+    }
+
+    test_assert!(input.len() - input_offset < V::BLOCK_SIZE);
+
+    // finish the input byte-by-byte
+    escape_fallback(buf, input, flushed, input_offset);
+}
+
+#[inline(always)]
+unsafe fn find_control_simd<V>(input: &[u8]) -> Option<usize>
+where
+    V: EscapeSimd,
+{
+    test_assert!(V::BLOCK_SIZE <= MAX_BLOCK_SIZE);
+    test_assert!(input.len() >= V::BLOCK_SIZE);
+
+    let last_block_start = input.len() - V::BLOCK_SIZE;
+
+    let mut input_offset = 0;
+
+    while input_offset <= last_block_start {
+        test_assert!(input_offset + V::BLOCK_SIZE <= input.len());
+
+        // we explicitly perform an unaligned load
+        let i = V::load_block_unaligned(input.as_ptr().add(input_offset));
+
+        let mask_control = V::mask_control(i);
+
+        if mask_control != 0 {
+            return Some(input_offset + mask_control.trailing_zeros() as usize);
+        }
+
+        input_offset += V::BLOCK_SIZE;
+    }
+
+    // finish the input byte-by-byte
+    find_control_fallback(input, input_offset)
+}
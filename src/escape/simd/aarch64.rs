@@ -0,0 +1,59 @@
+use super::*;
+
+use crate::std_ext::arch::aarch64::*;
+use std::arch::aarch64::*;
+
+pub(super) struct Neon;
+impl EscapeSimd for Neon {
+    type Block = uint8x8_t;
+
+    #[inline(always)]
+    fn load_block_unaligned(ptr: *const u8) -> Self::Block {
+        // SAFETY: In this module, Neon is always available
+        unsafe { vld1_u8(ptr) }
+    }
+
+    #[inline(always)]
+    fn mask_escape(block: Self::Block) -> i32 {
+        // SAFETY: In this module, Neon is always available
+        unsafe {
+            let match_quote = vceq_u8(
+                block,
+                splat([b'"', b'"', b'"', b'"', b'"', b'"', b'"', b'"']),
+            );
+            let match_escape = vceq_u8(
+                block,
+                splat([b'\\', b'\\', b'\\', b'\\', b'\\', b'\\', b'\\', b'\\']),
+            );
+
+            vmovemask_u8(vorr_u8(
+                vorr_u8(match_quote, match_escape),
+                match_control(block),
+            )) as i32
+        }
+    }
+
+    #[inline(always)]
+    fn mask_control(block: Self::Block) -> i32 {
+        // SAFETY: In this module, Neon is always available
+        unsafe { vmovemask_u8(match_control(block)) as i32 }
+    }
+}
+
+#[inline(always)]
+unsafe fn match_control(block: uint8x8_t) -> uint8x8_t {
+    vcle_u8(
+        block,
+        splat([0x1f, 0x1f, 0x1f, 0x1f, 0x1f, 0x1f, 0x1f, 0x1f]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_offset_is_8_bytes() {
+        assert_eq!(8, Neon::BLOCK_SIZE);
+    }
+}
@@ -0,0 +1,47 @@
+use super::*;
+
+use std::arch::x86_64::*;
+
+pub(super) struct AVX2;
+impl EscapeSimd for AVX2 {
+    type Block = __m256i;
+
+    #[inline(always)]
+    fn load_block_unaligned(ptr: *const u8) -> Self::Block {
+        unsafe { _mm256_loadu_si256(ptr as *const _) }
+    }
+
+    #[inline(always)]
+    fn mask_escape(block: Self::Block) -> i32 {
+        unsafe {
+            let match_quote = _mm256_cmpeq_epi8(block, _mm256_set1_epi8(b'"' as i8));
+            let match_escape = _mm256_cmpeq_epi8(block, _mm256_set1_epi8(b'\\' as i8));
+
+            _mm256_movemask_epi8(_mm256_or_si256(
+                _mm256_or_si256(match_quote, match_escape),
+                match_control(block),
+            ))
+        }
+    }
+
+    #[inline(always)]
+    fn mask_control(block: Self::Block) -> i32 {
+        unsafe { _mm256_movemask_epi8(match_control(block)) }
+    }
+}
+
+#[inline(always)]
+unsafe fn match_control(block: __m256i) -> __m256i {
+    // control characters are the ones that are unchanged by taking their minimum with 0x1f
+    _mm256_cmpeq_epi8(_mm256_min_epu8(block, _mm256_set1_epi8(0x1f)), block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_offset_is_32_bytes() {
+        assert_eq!(32, AVX2::BLOCK_SIZE);
+    }
+}
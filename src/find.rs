@@ -0,0 +1,79 @@
+/*!
+Substring search for raw needles in input buffers.
+
+This module contains a vectorized implementation for finding a needle in an input.
+
+Needles are things like quoted keys, so their first and last bytes are usually structural
+characters. The vectorized implementation compares blocks of the input against the first
+and last bytes of the needle at the same time, and only compares the whole needle at offsets
+where both of them match.
+
+This implementation follows the same basic design as `unescape` for supporting a vectorized
+and fallback implementation.
+*/
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+mod simd;
+
+/**
+Find the offset of the first occurrence of a needle in the input, starting from an offset.
+
+An empty needle is found at the starting offset.
+*/
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[inline]
+pub(crate) fn find(input: &[u8], needle: &[u8], input_offset: usize) -> Option<usize> {
+    if needle.is_empty() || input_offset >= input.len() {
+        return find_fallback(input, needle, input_offset);
+    }
+
+    // the block for the last byte of the needle is loaded from past the block for its first
+    let remaining = (input.len() - input_offset).saturating_sub(needle.len() - 1);
+
+    // when SIMD is available, we can vectorize
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") && remaining > simd::X86_64_AVX2_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: avx2 is available
+            return unsafe { simd::find_x86_64_avx2(input, needle, input_offset) };
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon")
+            && remaining > simd::AARCH64_NEON_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: neon is available
+            return unsafe { simd::find_aarch64_neon(input, needle, input_offset) };
+        }
+    }
+
+    // when SIMD is not available, we need to fallback
+    find_fallback(input, needle, input_offset)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[inline]
+pub(crate) fn find(input: &[u8], needle: &[u8], input_offset: usize) -> Option<usize> {
+    find_fallback(input, needle, input_offset)
+}
+
+/**
+Find a needle in the input byte-by-byte, starting from an offset.
+*/
+#[inline]
+fn find_fallback(input: &[u8], needle: &[u8], input_offset: usize) -> Option<usize> {
+    if input_offset > input.len() {
+        return None;
+    }
+
+    if needle.is_empty() {
+        return Some(input_offset);
+    }
+
+    get_unchecked!(input, input_offset..)
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|i| input_offset + i)
+}
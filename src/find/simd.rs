@@ -0,0 +1,95 @@
+use std::mem;
+
+use super::*;
+
+#[cfg(test)]
+const MAX_BLOCK_SIZE: usize = 32;
+
+trait FindSimd {
+    type Block: Sized + Clone + Copy;
+    const BLOCK_SIZE: usize = mem::size_of::<Self::Block>();
+
+    fn load_block_unaligned(ptr: *const u8) -> Self::Block;
+    fn mask_eq(block: Self::Block, b: u8) -> i32;
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64;
+
+// SAFETY: Callers must ensure `avx2` is available
+#[cfg(target_arch = "x86_64")]
+#[inline]
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn find_x86_64_avx2(
+    input: &[u8],
+    needle: &[u8],
+    input_offset: usize,
+) -> Option<usize> {
+    find_simd::<x86_64::AVX2>(input, needle, input_offset)
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(super) const X86_64_AVX2_VECTORIZATION_THRESHOLD: usize = x86_64::AVX2::BLOCK_SIZE;
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64;
+
+// SAFETY: Callers must ensure `neon` is available
+#[cfg(target_arch = "aarch64")]
+#[inline]
+#[target_feature(enable = "neon")]
+pub(super) unsafe fn find_aarch64_neon(
+    input: &[u8],
+    needle: &[u8],
+    input_offset: usize,
+) -> Option<usize> {
+    find_simd::<aarch64::Neon>(input, needle, input_offset)
+}
+
+#[cfg(target_arch = "aarch64")]
+pub(super) const AARCH64_NEON_VECTORIZATION_THRESHOLD: usize = aarch64::Neon::BLOCK_SIZE;
+
+#[inline(always)]
+unsafe fn find_simd<V>(input: &[u8], needle: &[u8], mut input_offset: usize) -> Option<usize>
+where
+    V: FindSimd,
+{
+    test_assert!(V::BLOCK_SIZE <= MAX_BLOCK_SIZE);
+    test_assert!(!needle.is_empty());
+    test_assert!(input.len() >= input_offset + needle.len() - 1 + V::BLOCK_SIZE);
+
+    // HEURISTIC: we're searching the input once, so we just do unaligned loads
+
+    let first = *get_unchecked!(needle, 0);
+    let last_offset = needle.len() - 1;
+    let last = *get_unchecked!(needle, last_offset);
+
+    let last_block_start = input.len() - last_offset - V::BLOCK_SIZE;
+
+    while input_offset <= last_block_start {
+        test_assert!(input_offset + last_offset + V::BLOCK_SIZE <= input.len());
+
+        // we explicitly perform an unaligned load
+        let i_first = V::load_block_unaligned(input.as_ptr().add(input_offset));
+        let i_last = V::load_block_unaligned(input.as_ptr().add(input_offset + last_offset));
+
+        // find offsets where both the first and last bytes of the needle match
+        let mut mask_candidate = (V::mask_eq(i_first, first) & V::mask_eq(i_last, last)) as u32;
+
+        while mask_candidate != 0 {
+            let candidate = input_offset + mask_candidate.trailing_zeros() as usize;
+
+            if get_unchecked!(input, candidate..candidate + needle.len()) == needle {
+                return Some(candidate);
+            }
+
+            // clear the lowest set bit in the mask
+            mask_candidate &= mask_candidate - 1;
+        }
+
+        input_offset += V::BLOCK_SIZE;
+    }
+
+    // finish the input byte-by-byte
+    find_fallback(input, needle, input_offset)
+}
@@ -0,0 +1,31 @@
+use super::*;
+
+use crate::std_ext::arch::aarch64::*;
+use std::arch::aarch64::*;
+
+pub(super) struct Neon;
+impl FindSimd for Neon {
+    type Block = uint8x8_t;
+
+    #[inline(always)]
+    fn load_block_unaligned(ptr: *const u8) -> Self::Block {
+        // SAFETY: In this module, Neon is always available
+        unsafe { vld1_u8(ptr) }
+    }
+
+    #[inline(always)]
+    fn mask_eq(block: Self::Block, b: u8) -> i32 {
+        // SAFETY: In this module, Neon is always available
+        unsafe { vmovemask_u8(vceq_u8(block, splat([b; 8]))) as i32 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_offset_is_8_bytes() {
+        assert_eq!(8, Neon::BLOCK_SIZE);
+    }
+}
@@ -0,0 +1,28 @@
+use super::*;
+
+use std::arch::x86_64::*;
+
+pub(super) struct AVX2;
+impl FindSimd for AVX2 {
+    type Block = __m256i;
+
+    #[inline(always)]
+    fn load_block_unaligned(ptr: *const u8) -> Self::Block {
+        unsafe { _mm256_loadu_si256(ptr as *const _) }
+    }
+
+    #[inline(always)]
+    fn mask_eq(block: Self::Block, b: u8) -> i32 {
+        unsafe { _mm256_movemask_epi8(_mm256_cmpeq_epi8(block, _mm256_set1_epi8(b as i8))) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_offset_is_32_bytes() {
+        assert_eq!(32, AVX2::BLOCK_SIZE);
+    }
+}
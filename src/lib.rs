@@ -37,9 +37,35 @@ mod macros;
 
 mod std_ext;
 
+pub mod batch;
 pub mod de;
+pub mod diagnostics;
+mod escape;
+mod find;
+pub mod prefilter;
+pub mod ser;
+pub mod stream;
 mod unescape;
-pub use de::Document;
+mod utf16;
+mod utf8;
+pub use de::{CowDocument, Document, OwnedDocument};
+pub use unescape::{
+    unescape_json_str, unescape_json_str_with, UnescapeError, UnescapePolicy, UnescapeScratch,
+};
+pub use utf16::transcode_utf16_to_utf8;
+
+#[cfg(any(test, feature = "test-support"))]
+pub mod testing;
+
+#[cfg(any(test, feature = "metrics"))]
+pub mod metrics;
+
+#[cfg(any(test, feature = "metrics"))]
+pub use metrics::metrics_snapshot;
+
+// the derive refers to this crate by name, so it needs to be available within its own tests
+#[cfg(test)]
+extern crate self as squirrel_json;
 
 #[cfg(test)]
 mod tests;
@@ -83,24 +83,6 @@ macro_rules! from_utf8_unchecked {
     }};
 }
 
-macro_rules! owned_from_utf8_unchecked {
-    ($str:expr) => {{
-        #[cfg(any(all(test, debug), checked))]
-        {
-            String::from_utf8($str).expect("invalid utf8")
-        }
-
-        #[cfg(not(any(all(test, debug), checked)))]
-        {
-            // SAFETY: the input must always be valid UTF8
-            #[allow(unused_unsafe)]
-            unsafe {
-                String::from_utf8_unchecked($str)
-            }
-        }
-    }};
-}
-
 macro_rules! offset_from_raw_parts {
     ($base_ptr:expr, $base_len:expr, $offset:expr, $len:expr) => {{
         #[cfg(any(all(test, debug), checked))]
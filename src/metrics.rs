@@ -0,0 +1,104 @@
+/*!
+Process-wide counters for the work done by the scanner.
+
+Counters are accumulated locally while a document is scanned and only published to the
+process-wide totals once it's finished, so the scanner itself doesn't touch any shared state.
+They're useful for getting a sense of how often documents fall back to the byte-by-byte
+implementation in production.
+*/
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static DOCUMENTS_SCANNED: AtomicU64 = AtomicU64::new(0);
+static DOCUMENTS_ERRORED: AtomicU64 = AtomicU64::new(0);
+static BYTES_SCANNED: AtomicU64 = AtomicU64::new(0);
+static VECTORIZED_BYTES: AtomicU64 = AtomicU64::new(0);
+static FALLBACK_BYTES: AtomicU64 = AtomicU64::new(0);
+static ESCAPES: AtomicU64 = AtomicU64::new(0);
+
+/**
+A snapshot of the counters accumulated since the process started.
+
+Counters only ever increase, so the work done over some period can be found
+by taking the difference between two snapshots.
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MetricsSnapshot {
+    /**
+    The number of documents scanned.
+    */
+    pub documents_scanned: u64,
+    /**
+    The number of documents scanned that were erroneous.
+    */
+    pub documents_errored: u64,
+    /**
+    The total length of the inputs of documents scanned.
+    */
+    pub bytes_scanned: u64,
+    /**
+    The number of bytes scanned in blocks by the vectorized implementation.
+    */
+    pub vectorized_bytes: u64,
+    /**
+    The number of bytes scanned byte-by-byte by the fallback implementation.
+
+    This includes the leading and trailing parts of inputs that are vectorized,
+    along with the whole of inputs that are too small to vectorize.
+    */
+    pub fallback_bytes: u64,
+    /**
+    The number of escape sequences encountered in strings.
+    */
+    pub escapes: u64,
+}
+
+/**
+Take a snapshot of the counters accumulated since the process started.
+*/
+pub fn metrics_snapshot() -> MetricsSnapshot {
+    MetricsSnapshot {
+        documents_scanned: DOCUMENTS_SCANNED.load(Ordering::Relaxed),
+        documents_errored: DOCUMENTS_ERRORED.load(Ordering::Relaxed),
+        bytes_scanned: BYTES_SCANNED.load(Ordering::Relaxed),
+        vectorized_bytes: VECTORIZED_BYTES.load(Ordering::Relaxed),
+        fallback_bytes: FALLBACK_BYTES.load(Ordering::Relaxed),
+        escapes: ESCAPES.load(Ordering::Relaxed),
+    }
+}
+
+/**
+The counters accumulated while scanning a single document.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ScanMetrics {
+    pub(crate) vectorized_bytes: usize,
+    pub(crate) fallback_bytes: usize,
+    pub(crate) escapes: usize,
+}
+
+/**
+Publish the counters for a scanned document to the process-wide totals.
+*/
+#[inline]
+pub(crate) fn record(input_len: usize, metrics: &ScanMetrics, errored: bool) {
+    DOCUMENTS_SCANNED.fetch_add(1, Ordering::Relaxed);
+    BYTES_SCANNED.fetch_add(input_len as u64, Ordering::Relaxed);
+
+    if errored {
+        DOCUMENTS_ERRORED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    if metrics.vectorized_bytes > 0 {
+        VECTORIZED_BYTES.fetch_add(metrics.vectorized_bytes as u64, Ordering::Relaxed);
+    }
+
+    if metrics.fallback_bytes > 0 {
+        FALLBACK_BYTES.fetch_add(metrics.fallback_bytes as u64, Ordering::Relaxed);
+    }
+
+    if metrics.escapes > 0 {
+        ESCAPES.fetch_add(metrics.escapes as u64, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,115 @@
+/*!
+Cheaply rule out documents that can't match a filter before scanning them.
+
+Filters over batches of events usually only match a small fraction of them. A prefilter
+searches the raw input of each document for a set of needles, like quoted keys or literal
+values, without scanning it. Documents that don't contain the needles can't match, so only
+the remaining candidates need to be scanned to check the filter properly.
+
+A prefilter can produce false positives, like a needle for a key that's found as a value,
+but never false negatives for needles that are written the same way in the input.
+*/
+
+use crate::{escape::escape_str, find::find};
+
+/**
+A set of needles to search documents for.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct Prefilter {
+    needles: Vec<Box<[u8]>>,
+    require_all: bool,
+}
+
+impl Prefilter {
+    /**
+    Create a prefilter without any needles.
+
+    A prefilter without any needles treats every document as a candidate.
+    */
+    #[inline]
+    pub fn new() -> Self {
+        Prefilter::default()
+    }
+
+    /**
+    Search for a key, as a quoted string followed by a `:`.
+
+    The key is escaped the same way a [`Writer`](crate::ser::Writer) escapes it.
+    */
+    pub fn key(self, key: &str) -> Self {
+        let mut needle = Vec::with_capacity(key.len() + 3);
+        escape_str(&mut needle, key);
+        needle.push(b':');
+
+        self.needle(needle)
+    }
+
+    /**
+    Search for a string value, as a quoted string.
+
+    The string is escaped the same way a [`Writer`](crate::ser::Writer) escapes it.
+    */
+    pub fn str(self, s: &str) -> Self {
+        let mut needle = Vec::with_capacity(s.len() + 2);
+        escape_str(&mut needle, s);
+
+        self.needle(needle)
+    }
+
+    /**
+    Search for some raw bytes, like a number or part of a string.
+
+    Empty needles are found in every document.
+    */
+    #[inline]
+    pub fn needle(mut self, needle: impl Into<Vec<u8>>) -> Self {
+        self.needles.push(needle.into().into_boxed_slice());
+        self
+    }
+
+    /**
+    Only treat documents as candidates if they contain every needle, instead of any of them.
+    */
+    #[inline]
+    pub fn require_all(mut self, require_all: bool) -> Self {
+        self.require_all = require_all;
+        self
+    }
+
+    /**
+    Whether or not a document could match.
+
+    The document is searched for each needle in turn, stopping as soon as the result is known.
+    */
+    pub fn could_match(&self, input: &[u8]) -> bool {
+        if self.needles.is_empty() {
+            return true;
+        }
+
+        let mut needles = self.needles.iter().map(|needle| &**needle);
+        let found = |needle: &[u8]| find(input, needle, 0).is_some();
+
+        if self.require_all {
+            needles.all(found)
+        } else {
+            needles.any(found)
+        }
+    }
+
+    /**
+    Classify a batch of documents, returning the indexes of the ones that could match.
+    */
+    pub fn candidates<I>(&self, batch: I) -> Vec<usize>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        batch
+            .into_iter()
+            .enumerate()
+            .filter(|(_, input)| self.could_match(input.as_ref()))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
@@ -0,0 +1,300 @@
+/*!
+Serialization for minified JSON objects.
+
+This module contains a writer that produces minified JSON maps that can always
+be scanned again by [`Document::scan_trusted`](crate::Document::scan_trusted).
+The writer never emits whitespace, and tracks the structure of the document
+so it can't produce keys without values or unbalanced maps and arrays.
+
+Misusing the writer is considered a bug in the caller, so it panics instead
+of returning errors.
+*/
+
+use std::{fmt, io::Write as _};
+
+use crate::escape::escape_str;
+
+/**
+A writer for a minified JSON map.
+
+The root map is begun when the writer is created and finished by [`Writer::finish`].
+*/
+#[derive(Debug, Clone)]
+pub struct Writer {
+    buf: Vec<u8>,
+    stack: Vec<Frame>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    kind: FrameKind,
+    first: bool,
+    expect_value: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FrameKind {
+    Map,
+    Arr,
+}
+
+/**
+A number that can be written by a [`Writer`].
+*/
+pub trait Num: private::Sealed {
+    #[doc(hidden)]
+    fn write(&self, buf: &mut Vec<u8>);
+}
+
+macro_rules! impl_num_int {
+    ($($ty:ty),*) => {
+        $(
+            impl private::Sealed for $ty {}
+
+            impl Num for $ty {
+                #[inline]
+                fn write(&self, buf: &mut Vec<u8>) {
+                    write_display(buf, self);
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_num_float {
+    ($($ty:ty),*) => {
+        $(
+            impl private::Sealed for $ty {}
+
+            impl Num for $ty {
+                #[inline]
+                fn write(&self, buf: &mut Vec<u8>) {
+                    // JSON doesn't have a representation for non-finite numbers
+                    if self.is_finite() {
+                        write_display(buf, self);
+                    } else {
+                        buf.extend_from_slice(b"null");
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_num_int!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+impl_num_float!(f32, f64);
+
+mod private {
+    pub trait Sealed {}
+}
+
+impl Default for Writer {
+    #[inline]
+    fn default() -> Self {
+        Writer::new()
+    }
+}
+
+impl Writer {
+    /**
+    The maximum depth of maps and arrays that can be scanned.
+    */
+    const MAX_DEPTH: usize = 96;
+
+    /**
+    Create a writer for a new map.
+    */
+    #[inline]
+    pub fn new() -> Self {
+        Writer::with_capacity(0)
+    }
+
+    /**
+    Create a writer for a new map with some preallocated capacity for its output.
+    */
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut buf = Vec::with_capacity(capacity.max(2));
+        buf.push(b'{');
+
+        Writer {
+            buf,
+            stack: vec![Frame {
+                kind: FrameKind::Map,
+                first: true,
+                expect_value: false,
+            }],
+        }
+    }
+
+    /**
+    Write the key for the next entry in a map.
+    */
+    pub fn key(&mut self, key: &str) -> &mut Self {
+        let frame = top(&mut self.stack);
+
+        assert!(
+            frame.kind == FrameKind::Map && !frame.expect_value,
+            "attempt to write a key outside of a map, or twice for the same entry"
+        );
+
+        if !std::mem::take(&mut frame.first) {
+            self.buf.push(b',');
+        }
+        frame.expect_value = true;
+
+        escape_str(&mut self.buf, key);
+        self.buf.push(b':');
+
+        self
+    }
+
+    /**
+    Write a string value.
+    */
+    pub fn str(&mut self, value: &str) -> &mut Self {
+        self.value();
+        escape_str(&mut self.buf, value);
+
+        self
+    }
+
+    /**
+    Write a number value.
+
+    Floating point numbers that aren't finite are written as `null`.
+    */
+    pub fn num(&mut self, value: impl Num) -> &mut Self {
+        self.value();
+        value.write(&mut self.buf);
+
+        self
+    }
+
+    /**
+    Write a boolean value.
+    */
+    pub fn bool(&mut self, value: bool) -> &mut Self {
+        self.value();
+        self.buf
+            .extend_from_slice(if value { b"true" } else { b"false" });
+
+        self
+    }
+
+    /**
+    Write a null value.
+    */
+    pub fn null(&mut self) -> &mut Self {
+        self.value();
+        self.buf.extend_from_slice(b"null");
+
+        self
+    }
+
+    /**
+    Begin a map value.
+    */
+    pub fn map_begin(&mut self) -> &mut Self {
+        self.begin(FrameKind::Map, b'{')
+    }
+
+    /**
+    End the current map.
+    */
+    pub fn map_end(&mut self) -> &mut Self {
+        self.end(FrameKind::Map, b'}')
+    }
+
+    /**
+    Begin an array value.
+    */
+    pub fn arr_begin(&mut self) -> &mut Self {
+        self.begin(FrameKind::Arr, b'[')
+    }
+
+    /**
+    End the current array.
+    */
+    pub fn arr_end(&mut self) -> &mut Self {
+        self.end(FrameKind::Arr, b']')
+    }
+
+    /**
+    Finish the root map, returning the written JSON.
+    */
+    pub fn finish(mut self) -> Vec<u8> {
+        assert!(
+            self.stack.len() == 1 && !top(&mut self.stack).expect_value,
+            "attempt to finish a writer with unterminated maps, arrays, or keys"
+        );
+
+        self.buf.push(b'}');
+        self.buf
+    }
+
+    #[inline]
+    fn value(&mut self) {
+        let frame = top(&mut self.stack);
+
+        match frame.kind {
+            FrameKind::Map => {
+                assert!(
+                    frame.expect_value,
+                    "attempt to write a value in a map without a key"
+                );
+
+                frame.expect_value = false;
+            }
+            FrameKind::Arr => {
+                if !std::mem::take(&mut frame.first) {
+                    self.buf.push(b',');
+                }
+            }
+        }
+    }
+
+    fn begin(&mut self, kind: FrameKind, open: u8) -> &mut Self {
+        assert!(
+            self.stack.len() <= Self::MAX_DEPTH,
+            "attempt to write maps or arrays nested too deeply to scan"
+        );
+
+        self.value();
+        self.buf.push(open);
+
+        self.stack.push(Frame {
+            kind,
+            first: true,
+            expect_value: false,
+        });
+
+        self
+    }
+
+    fn end(&mut self, kind: FrameKind, close: u8) -> &mut Self {
+        let frame = *top(&mut self.stack);
+
+        assert!(
+            self.stack.len() > 1 && frame.kind == kind && !frame.expect_value,
+            "attempt to end a map or array that isn't the current one"
+        );
+
+        self.stack.pop();
+        self.buf.push(close);
+
+        self
+    }
+}
+
+#[inline]
+fn top(stack: &mut [Frame]) -> &mut Frame {
+    // the root map is only popped by `finish`, which consumes the writer
+    stack.last_mut().expect("missing root map")
+}
+
+#[inline]
+fn write_display(buf: &mut Vec<u8>, value: impl fmt::Display) {
+    // writing to a `Vec` can't fail
+    let _ = write!(buf, "{}", value);
+}
@@ -0,0 +1,147 @@
+/*!
+Scan newline-delimited documents straight out of a reader's buffer.
+
+Tailing a file of events usually means reading each line into its own buffer before scanning
+it. A [`BufRead`] already has its own buffer though, so lines that fit entirely within it are
+scanned from there without copying them. Only lines that straddle the end of the buffer are
+copied into a separate one, so they can be scanned once the rest of them has been read.
+*/
+
+use std::io::{self, BufRead};
+
+use crate::{
+    de::{Document, ScanOptions},
+    find::find,
+};
+
+/**
+Scan newline-delimited documents from a reader.
+
+Blank lines between documents are skipped. A final document doesn't need to end with a newline.
+*/
+#[inline]
+pub fn from_buf_read<R: BufRead>(reader: R) -> BufReadStream<R> {
+    BufReadStream {
+        reader,
+        options: ScanOptions::new(),
+        straddled: Vec::new(),
+        consume: 0,
+    }
+}
+
+/**
+A stream of newline-delimited documents scanned from a reader.
+
+Each document borrows from the stream, so it needs to be dropped before the next one
+is scanned.
+*/
+#[derive(Debug)]
+pub struct BufReadStream<R> {
+    reader: R,
+    options: ScanOptions,
+    /**
+    A line that straddled the end of the reader's buffer.
+    */
+    straddled: Vec<u8>,
+    /**
+    The length of the last line that was scanned from the reader's buffer.
+
+    The line can't be consumed until the document borrowing it is dropped.
+    */
+    consume: usize,
+}
+
+enum Line {
+    Buffered(usize),
+    Straddled,
+}
+
+impl<R: BufRead> BufReadStream<R> {
+    /**
+    Scan documents using the given options.
+    */
+    #[inline]
+    pub fn with_options(mut self, options: ScanOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /**
+    Get the underlying reader.
+    */
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /**
+    Scan the next document from the reader.
+
+    If the reader is finished then this method returns `Ok(None)`.
+    This method has the same guarantees as [`Document::scan_trusted`].
+    */
+    pub fn next_document(&mut self) -> io::Result<Option<Document<'_>>> {
+        // the previous document has been dropped, so its line can be consumed
+        self.reader.consume(self.consume);
+        self.consume = 0;
+        self.straddled.clear();
+
+        let line = loop {
+            let buf = self.reader.fill_buf()?;
+
+            if buf.is_empty() {
+                if is_blank(&self.straddled) {
+                    return Ok(None);
+                }
+
+                break Line::Straddled;
+            }
+
+            match find(buf, b"\n", 0) {
+                // the line is entirely within the buffer, so it can be scanned from there
+                Some(end) if self.straddled.is_empty() => {
+                    if is_blank(get_unchecked!(buf, ..end)) {
+                        self.reader.consume(end + 1);
+                        continue;
+                    }
+
+                    self.consume = end + 1;
+                    break Line::Buffered(end);
+                }
+                // the rest of a line that started in a previous buffer
+                Some(end) => {
+                    self.straddled.extend_from_slice(get_unchecked!(buf, ..end));
+                    self.reader.consume(end + 1);
+
+                    if is_blank(&self.straddled) {
+                        self.straddled.clear();
+                        continue;
+                    }
+
+                    break Line::Straddled;
+                }
+                // the line continues past the end of the buffer
+                None => {
+                    let len = buf.len();
+
+                    self.straddled.extend_from_slice(buf);
+                    self.reader.consume(len);
+                }
+            }
+        };
+
+        let input = match line {
+            // the buffer hasn't been consumed, so this doesn't read from the reader again
+            // it's still indexed with a bounds check, since that's up to the reader
+            Line::Buffered(end) => &self.reader.fill_buf()?[..end],
+            Line::Straddled => &self.straddled,
+        };
+
+        Ok(Some(Document::scan_trusted_with(input, &self.options)))
+    }
+}
+
+#[inline]
+fn is_blank(line: &[u8]) -> bool {
+    line.iter().all(u8::is_ascii_whitespace)
+}
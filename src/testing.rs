@@ -0,0 +1,424 @@
+/*!
+Utilities for testing code that uses documents.
+
+This module is only available when the `test-support` feature is enabled.
+It contains a generator of random valid minified JSON objects that can be used
+to run property tests against the parser, along with adversarial generators that
+target the limits of the parser, like deeply nested containers, long runs of escapes,
+and documents right at the limit of the number of elements that can be indexed.
+*/
+
+use rand::Rng;
+use std::fmt::Write;
+
+/**
+Generate a random minified JSON object.
+
+Maps and arrays will be nested up to `depth` levels deep. The generator only
+uses the given `rng` so a seeded RNG will always produce the same object.
+*/
+pub fn arbitrary_object<R: Rng + ?Sized>(rng: &mut R, depth: usize) -> String {
+    let mut gen = Gen {
+        s: String::new(),
+        d: 0,
+        depth,
+        rng,
+    };
+
+    gen.write_object();
+
+    gen.s
+}
+
+/**
+Generate a minified JSON object with maps and arrays nested exactly `depth` levels deep.
+
+Each level is randomly a map or an array, and may have random values alongside the
+nested one. The scanner limits how deeply documents can be nested, so this can be used
+to generate objects right at that limit.
+*/
+pub fn nested_object<R: Rng + ?Sized>(rng: &mut R, depth: usize) -> String {
+    let mut gen = Gen {
+        s: String::new(),
+        d: 0,
+        depth: 0,
+        rng,
+    };
+
+    // the levels are opened first, then closed in reverse
+    let mut closes = Vec::with_capacity(depth);
+
+    gen.s.push('{');
+    gen.write_string();
+    gen.s.push(':');
+
+    for _ in 0..depth {
+        let map = gen.rng.gen();
+
+        if map {
+            gen.s.push('{');
+            gen.write_string();
+            gen.s.push(':');
+        } else {
+            gen.s.push('[');
+        }
+
+        closes.push(map);
+    }
+
+    gen.write_any();
+
+    for map in closes.into_iter().rev() {
+        // maybe add a value after the nested one, so the levels aren't all closed together
+        if gen.rng.gen() {
+            gen.s.push(',');
+
+            if map {
+                gen.write_string();
+                gen.s.push(':');
+            }
+
+            gen.write_any();
+        }
+
+        gen.s.push(if map { '}' } else { ']' });
+    }
+
+    gen.s.push('}');
+
+    gen.s
+}
+
+/**
+Generate a minified JSON object with strings that contain long runs of backslashes.
+
+Each run is up to `len` escaped backslashes long, and may be followed by an escaped quote,
+so the scanner has to count through the whole run to know whether the string has ended.
+Runs are placed in both keys and values.
+*/
+pub fn backslash_object<R: Rng + ?Sized>(rng: &mut R, len: usize) -> String {
+    let mut gen = Gen {
+        s: String::new(),
+        d: 0,
+        depth: 0,
+        rng,
+    };
+
+    gen.s.push('{');
+
+    for i in 0..gen.rng(4) + 1 {
+        if i > 0 {
+            gen.s.push(',');
+        }
+
+        gen.write_backslashes(len);
+        gen.s.push(':');
+        gen.write_backslashes(len);
+    }
+
+    gen.s.push('}');
+
+    gen.s
+}
+
+/**
+Generate a minified JSON object with strings that contain nothing but escapes.
+
+Each string has up to `len` escapes in it, including escaped quotes, control characters,
+unicode escapes, and escaped surrogate pairs. Strings are placed in both keys and values,
+and in arrays.
+*/
+pub fn escaped_object<R: Rng + ?Sized>(rng: &mut R, len: usize) -> String {
+    let mut gen = Gen {
+        s: String::new(),
+        d: 0,
+        depth: 0,
+        rng,
+    };
+
+    gen.s.push('{');
+
+    for i in 0..gen.rng(4) + 1 {
+        if i > 0 {
+            gen.s.push(',');
+        }
+
+        gen.write_escapes(len);
+        gen.s.push(':');
+
+        if gen.rng.gen() {
+            gen.write_escapes(len);
+        } else {
+            gen.s.push('[');
+            gen.write_escapes(len);
+            gen.s.push(',');
+            gen.write_escapes(len);
+            gen.s.push(']');
+        }
+    }
+
+    gen.s.push('}');
+
+    gen.s
+}
+
+/**
+Generate a minified JSON object with exactly `elements` elements in it.
+
+Each key and value in a map, and each element in an array, counts as an element.
+The elements are random values in a mix of maps and arrays at the root of the object.
+This can be used to generate objects right at the limit of [`Document::MAX_ELEMENTS`],
+and just over it. An object can't have a single element, so an `elements` of `1`
+generates an object with two.
+
+[`Document::MAX_ELEMENTS`]: crate::Document::MAX_ELEMENTS
+*/
+pub fn wide_object<R: Rng + ?Sized>(rng: &mut R, elements: usize) -> String {
+    let mut gen = Gen {
+        s: String::new(),
+        d: 0,
+        depth: 0,
+        rng,
+    };
+
+    let mut remaining = elements;
+
+    gen.s.push('{');
+
+    while remaining > 0 {
+        if remaining < elements {
+            gen.s.push(',');
+        }
+
+        gen.write_string();
+        gen.s.push(':');
+
+        // an entry needs at least 2 elements, so there can't be a single one left over
+        let mut len = gen.rng(100).min(remaining.saturating_sub(2));
+        if remaining.saturating_sub(2 + len) == 1 {
+            len += 1;
+        }
+
+        remaining = remaining.saturating_sub(2 + len);
+
+        if len == 0 {
+            gen.write_any();
+            continue;
+        }
+
+        gen.s.push('[');
+
+        for i in 0..len {
+            if i > 0 {
+                gen.s.push(',');
+            }
+
+            gen.write_any();
+        }
+
+        gen.s.push(']');
+    }
+
+    gen.s.push('}');
+
+    gen.s
+}
+
+struct Gen<'a, R: ?Sized> {
+    s: String,
+    d: usize,
+    depth: usize,
+    rng: &'a mut R,
+}
+
+impl<'a, R: Rng + ?Sized> Gen<'a, R> {
+    fn write_any(&mut self) {
+        if self.d < self.depth {
+            match self.rng(6) {
+                0 => self.write_object(),
+                1 => self.write_array(),
+                2 => self.write_bool(),
+                3 => self.write_number(),
+                4 => self.write_null(),
+                5 => self.write_string(),
+                _ => unreachable!(),
+            }
+        } else {
+            match self.rng(4) {
+                0 => self.write_bool(),
+                1 => self.write_number(),
+                2 => self.write_null(),
+                3 => self.write_string(),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn write_object(&mut self) {
+        self.d += 1;
+        self.s.push('{');
+
+        let mut first = true;
+        for _ in 0..self.rng(10) {
+            if !first {
+                self.s.push(',');
+            }
+            first = false;
+
+            self.write_string();
+            self.s.push(':');
+            self.write_any();
+        }
+
+        self.s.push('}');
+        self.d -= 1;
+    }
+
+    fn write_array(&mut self) {
+        self.d += 1;
+        self.s.push('[');
+
+        let mut first = true;
+        for _ in 0..self.rng(10) {
+            if !first {
+                self.s.push(',');
+            }
+            first = false;
+
+            self.write_any();
+        }
+
+        self.s.push(']');
+        self.d -= 1;
+    }
+
+    fn write_null(&mut self) {
+        self.s.push_str("null");
+    }
+
+    fn write_bool(&mut self) {
+        if self.rng.gen() {
+            self.s.push_str("true");
+        } else {
+            self.s.push_str("false");
+        }
+    }
+
+    fn write_string(&mut self) {
+        self.s.push('"');
+
+        for _ in 0..self.rng(10) {
+            match self.rng(95) {
+                0..=50 => {
+                    let i = self.rng(STR_1.len());
+                    self.s.push_str(&STR_1[i..i + 1]);
+                }
+                51..=60 => self.s.push_str(STR_2),
+                61..=70 => self.s.push_str(STR_3),
+                71..=80 => self.s.push_str(STR_4),
+                81..=90 => self.s.push_str(STR_5),
+                _ => {
+                    let i = self.rng(STR_0.len());
+                    self.s.push_str(&STR_0[0..i]);
+                }
+            }
+        }
+
+        self.s.push('"');
+    }
+
+    fn write_backslashes(&mut self, len: usize) {
+        self.s.push('"');
+
+        for _ in 0..self.rng(len + 1) {
+            self.s.push_str("\\\\");
+        }
+
+        if self.rng.gen() {
+            self.s.push_str("\\\"");
+        }
+
+        self.s.push('"');
+    }
+
+    fn write_escapes(&mut self, len: usize) {
+        self.s.push('"');
+
+        for _ in 0..self.rng(len + 1) {
+            match self.rng(5) {
+                0 => self.s.push_str(STR_2),
+                1 => self.s.push_str(STR_3),
+                2 => {
+                    let i = self.rng(ESCAPES.len());
+                    self.s.push_str(ESCAPES[i]);
+                }
+                3 => self.s.push_str(STR_6),
+                4 => self.s.push_str("\\u0000"),
+                _ => unreachable!(),
+            }
+        }
+
+        self.s.push('"');
+    }
+
+    fn write_number(&mut self) {
+        if self.rng.gen() {
+            self.s.push('-');
+        }
+
+        match self.rng(3) {
+            0 => self.write_integer(),
+            1 => self.write_decimal(),
+            2 => self.write_scientific(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn write_integer(&mut self) {
+        let n: u32 = self.rng.gen();
+        write!(self.s, "{}", n).unwrap();
+    }
+
+    fn write_decimal(&mut self) {
+        // Keep precision low enough that floats can roundtrip
+        let (n, d): (u32, usize) = (self.rng.gen(), self.rng(300));
+        write!(self.s, "{}.{}", n, d).unwrap();
+    }
+
+    fn write_scientific(&mut self) {
+        let e = match self.rng(4) {
+            0 => "e",
+            1 => "e-",
+            2 => "E",
+            3 => "E-",
+            _ => unreachable!(),
+        };
+
+        // Try not to get too overboard with scientific numbers
+        // They could easily overflow f64 or u64
+        let (n, d, x) = (self.rng(10), self.rng(300), self.rng(7));
+        write!(self.s, "{}.{}{}{}", n, d, e, x).unwrap();
+    }
+
+    fn rng(&mut self, to: usize) -> usize {
+        self.rng.gen_range(0..to)
+    }
+}
+
+// It's public domain, ok
+const STR_0: &str =
+    "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.";
+
+const STR_1: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+const STR_2: &str = "\\\"";
+
+const STR_3: &str = "\\u58c1";
+
+const STR_4: &str = "壁";
+
+const STR_5: &str = "😄";
+
+const STR_6: &str = "\\ud83d\\ude04";
+
+const ESCAPES: [&str; 8] = ["\\\\", "\\/", "\\b", "\\f", "\\n", "\\r", "\\t", "\\\""];
@@ -3,8 +3,6 @@
 
 use std::{iter, panic, panic::AssertUnwindSafe};
 
-mod some;
-
 macro_rules! assert_test_panics {
     ($e:expr) => {{
         #[cfg(debug)]
@@ -15,7 +15,17 @@ There are two kinds of tests here:
 Many of these cases come from fuzz testing the parser and deciding on semantics when things break.
 */
 
-use crate::{unescape::unescape_trusted, Document};
+use std::{panic, str};
+
+use crate::{
+    de::{Backend, Kind, ProblemKind, RootError, ScanErrorKind, ScanOptions, WarningKind},
+    metrics_snapshot,
+    ser::Writer,
+    unescape::unescape_trusted,
+    unescape_json_str, unescape_json_str_with, utf8, Document, OwnedDocument, UnescapePolicy,
+};
+
+use serde_json::json;
 
 #[test]
 fn err_internal_whitespace() {
@@ -62,6 +72,752 @@ fn err_root_level_arr_terminate() {
     assert!(document.is_err());
 }
 
+#[test]
+fn err_invalid_utf8() {
+    // invalid UTF8 anywhere in the input is detected
+    let valid = "{\"a\":\"a string with some multibyte characters 壁 in it 😄 that's longer than a few blocks\"}";
+
+    for i in 0..valid.len() {
+        for invalid in [0xff, 0x80] {
+            let mut input = valid.as_bytes().to_vec();
+            input[i] = invalid;
+
+            // replacing a continuation byte with another may still be valid
+            let expected = std::str::from_utf8(&input);
+
+            assert_eq!(expected, utf8::from_utf8(&input));
+
+            if expected.is_err() {
+                assert!(Document::scan_trusted(&input).is_err());
+            }
+        }
+    }
+}
+
+#[test]
+fn err_truncated_valid_up_to() {
+    // truncated documents are detected up to the end of the input
+    let input = b"{\"a\":\"b\",\"c\":\"trunc";
+
+    let document = Document::scan_trusted(input);
+
+    assert!(document.is_err());
+    assert_eq!(input.len(), document.valid_up_to());
+    assert_eq!(json!({}), document.to_value());
+}
+
+#[test]
+fn err_truncated_raw_value() {
+    // maps and arrays in erroneous documents can't be borrowed as raw values
+    let input = b"{\"a\":\"b\",\"c\":[1],\"d\":{\"e\":[1,2";
+
+    let document = Document::scan_trusted_with(input, &ScanOptions::new().keep_partial(true));
+    let map = document.as_map();
+
+    assert!(document.is_err());
+    assert_eq!("\"b\"", map.get("a").unwrap().to_raw_value().unwrap().get());
+    assert!(map.get("c").unwrap().to_raw_value().is_none());
+    assert!(map.get("d").unwrap().to_raw_value().is_none());
+}
+
+#[test]
+fn err_truncated_keep_partial() {
+    // truncated documents can be partially kept
+    for (input, expected, valid_up_to) in [
+        (
+            b"{\"a\":\"b\",\"c\":\"trunc" as &[u8],
+            json!({"a": "b"}),
+            13,
+        ),
+        (b"{\"a\":1,\"b\":12", json!({"a": 1}), 11),
+        (
+            b"{\"a\":{\"b\":1},\"c\":{\"d\":[1,2",
+            json!({"a": {"b": 1}, "c": {}}),
+            25,
+        ),
+    ] {
+        for backend in [Backend::Scalar, Backend::Auto] {
+            let document = Document::scan_trusted_with(
+                input,
+                &ScanOptions::new().backend(backend).keep_partial(true),
+            );
+
+            assert!(document.is_err());
+            assert_eq!(valid_up_to, document.valid_up_to());
+            assert_eq!(expected, document.to_value());
+        }
+    }
+}
+
+#[test]
+fn err_invalid_utf8_keep_partial() {
+    // documents with invalid UTF8 can be partially kept up to the invalid byte
+    let input = b"{\"a\":\"b\",\"c\":\"\xff\"}";
+
+    let document = Document::scan_trusted(input);
+
+    assert!(document.is_err());
+    assert_eq!(14, document.valid_up_to());
+
+    let document = Document::scan_trusted_with(input, &ScanOptions::new().keep_partial(true));
+
+    assert!(document.is_err());
+    assert_eq!(14, document.valid_up_to());
+    assert_eq!(json!({"a": "b"}), document.to_value());
+}
+
+#[test]
+fn err_invalid_utf8_interleave_utf8() {
+    // documents with invalid UTF8 are the same whether or not it's validated while scanning
+    let mut input = b"{\"a\":\"b\",\"c\":\"".to_vec();
+    input.extend([b'x'; 200]);
+    input.extend(b"\xff\",\"d\":\"");
+    input.extend([b'x'; 200]);
+    input.extend(b"\"}");
+
+    for keep_partial in [false, true] {
+        let options = ScanOptions::new().keep_partial(keep_partial);
+
+        let expected = Document::scan_trusted_with(&input, &options);
+        let document = Document::scan_trusted_with(&input, &options.interleave_utf8(true));
+
+        assert!(document.is_err());
+        assert_eq!(expected.valid_up_to(), document.valid_up_to());
+        assert_eq!(expected.to_value(), document.to_value());
+    }
+}
+
+#[test]
+fn err_invalid_utf8_interleave_utf8_capture() {
+    use std::sync::{Arc, Mutex};
+
+    // captures are never invoked with invalid UTF8, even when it's validated while scanning
+    let mut input = b"{\"b\":\"".to_vec();
+    input.extend([b'x'; 200]);
+    input.extend(b"\",\"a\":\"\xff\xfe\",\"c\":\"");
+    input.extend([b'x'; 200]);
+    input.extend(b"\"}");
+
+    let captured = Arc::new(Mutex::new(Vec::new()));
+
+    let options = ScanOptions::new()
+        .interleave_utf8(true)
+        .capture(["a", "b"], {
+            let captured = captured.clone();
+            move |key, _| captured.lock().unwrap().push(key.to_owned())
+        });
+
+    let document = Document::scan_trusted_with(&input, &options);
+
+    assert!(document.is_err());
+    assert!(!captured.lock().unwrap().iter().any(|key| key == "a"));
+}
+
+#[test]
+fn err_metrics() {
+    // other tests may be scanning documents at the same time
+    let before = metrics_snapshot();
+    let document = Document::scan_trusted(b"{\"a\":\"trunc");
+    let after = metrics_snapshot();
+
+    assert!(document.is_err());
+
+    assert!(after.documents_errored > before.documents_errored);
+    assert!(after.documents_scanned > before.documents_scanned);
+}
+
+#[test]
+fn err_truncated_recover() {
+    // truncated documents can be recovered by closing their open containers
+    for (input, expected, valid_up_to) in [
+        (
+            b"{\"a\":{\"b\":[1,2,\"tr" as &[u8],
+            json!({"a": {"b": [1, 2]}}),
+            15,
+        ),
+        (b"{\"a\":[1,2", json!({"a": [1]}), 8),
+        (b"{\"a\":[1,2,", json!({"a": [1]}), 8),
+        (
+            b"{\"a\":\"b\",\"c\":{\"d\":true",
+            json!({"a": "b", "c": {"d": true}}),
+            22,
+        ),
+    ] {
+        for backend in [Backend::Scalar, Backend::Auto] {
+            let document = Document::scan_trusted_with(
+                input,
+                &ScanOptions::new().backend(backend).recover(true),
+            );
+
+            assert!(document.is_err());
+            assert!(document.is_partial());
+            assert_eq!(valid_up_to, document.valid_up_to());
+            assert_eq!(expected, document.to_value());
+        }
+    }
+}
+
+#[test]
+fn err_mismatched_terminator_recover() {
+    // documents with mismatched terminators can be recovered up to the terminator
+    let input = b"{\"a\":[1,{\"b\":2}}],\"c\":3}";
+
+    let document: Document = assert_test_panics!(Document::scan_trusted_with(
+        input,
+        &ScanOptions::new().backend(Backend::Scalar).recover(true)
+    ));
+
+    assert!(document.is_err());
+    assert!(document.is_partial());
+    assert_eq!(json!({"a": [1, {"b": 2}]}), document.to_value());
+}
+
+#[test]
+fn err_rev_partial() {
+    // partial documents can be iterated in reverse over the parts that were kept
+    fn check(kind: &Kind) {
+        match kind {
+            Kind::Map(map) => {
+                let mut reverse = map.entries_rev().collect::<Vec<_>>();
+                reverse.reverse();
+
+                assert_eq!(map.entries().count(), reverse.len());
+
+                for ((fk, fv), (rk, rv)) in map.entries().zip(reverse) {
+                    assert_eq!(fk.as_raw(), rk.as_raw());
+                    assert_eq!(fv.as_json(), rv.as_json());
+
+                    check(&fv);
+                }
+            }
+            Kind::Arr(arr) => {
+                let mut reverse = arr.iter_rev().collect::<Vec<_>>();
+                reverse.reverse();
+
+                assert_eq!(arr.iter().count(), reverse.len());
+
+                for (f, r) in arr.iter().zip(reverse) {
+                    assert_eq!(f.as_json(), r.as_json());
+
+                    check(&f);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    for input in [
+        b"{\"a\":{\"b\":[1,2,\"tr" as &[u8],
+        b"{\"a\":{\"b\":1},\"c\":{\"d\":[1,2",
+        b"{\"a\":\"b\",\"c\":{\"d\":true",
+        b"{\"a\":[1,{\"b\":2,\"c\"",
+    ] {
+        for options in [
+            ScanOptions::new().keep_partial(true),
+            ScanOptions::new().recover(true),
+        ] {
+            for backend in [Backend::Scalar, Backend::Auto] {
+                let document =
+                    Document::scan_trusted_with(input, &options.clone().backend(backend));
+
+                assert!(document.is_err());
+                check(&Kind::Map(document.as_map()));
+            }
+        }
+    }
+}
+
+#[test]
+fn err_repair() {
+    let input = b"{\"a\":[1,{\"b\":1.,\"c\"}],\"d\":\"e";
+
+    // there's nothing to repair an erroneous document from unless its offsets are kept
+    assert_eq!(None, Document::scan_trusted(input).repair());
+
+    let document = Document::scan_trusted_with(input, &ScanOptions::new().recover(true));
+
+    let repaired = document.repair().unwrap();
+
+    assert_eq!(
+        "{\"a\":[1,{\"b\":\"1.\"}]}",
+        str::from_utf8(&repaired).unwrap()
+    );
+    assert!(!Document::scan_trusted(&repaired).is_err());
+
+    // mismatched terminators and invalid numbers are fixed even if the document isn't erroneous
+    let document = Document::scan_trusted_fallback(b"{\"a\":{\"b\":12abc],\"c\":-,\"d\":-0.5e+1}");
+
+    assert!(!document.is_err());
+    assert_eq!(
+        "{\"a\":[\"b\"],\"c\":\"-\",\"d\":-0.5e+1}",
+        str::from_utf8(&document.repair().unwrap()).unwrap()
+    );
+}
+
+#[test]
+fn err_conversions() {
+    let input = b"{\"a\":[1,{\"b\":2";
+
+    let err = Document::try_from(input as &[u8]).unwrap_err();
+    assert_eq!(
+        Document::scan_trusted(input).valid_up_to(),
+        err.valid_up_to()
+    );
+
+    let err = str::from_utf8(input)
+        .unwrap()
+        .parse::<OwnedDocument>()
+        .unwrap_err();
+    assert_eq!(
+        Document::scan_trusted(input).valid_up_to(),
+        err.valid_up_to()
+    );
+}
+
+#[test]
+fn err_collect_problems() {
+    let problems = |input: &[u8], options: ScanOptions| {
+        let mut collected = None;
+
+        for backend in [Backend::Scalar, Backend::Auto] {
+            let document = Document::scan_trusted_with(input, &options.clone().backend(backend));
+            assert!(document.is_err());
+
+            let problems = document
+                .problems()
+                .iter()
+                .map(|problem| (problem.kind(), problem.input_offset()))
+                .collect::<Vec<_>>();
+
+            if let Some(ref collected) = collected {
+                assert_eq!(collected, &problems);
+            }
+
+            collected = Some(problems);
+        }
+
+        collected.unwrap()
+    };
+
+    let options = ScanOptions::new().verify_atoms(true).collect_problems(8);
+
+    // the scanner keeps going after the first problem it finds
+    assert_eq!(
+        vec![
+            (ProblemKind::InvalidAtom, 5),
+            (ProblemKind::InvalidAtom, 13)
+        ],
+        problems(b"{\"a\":tru,\"b\":nul,\"c\":true}", options.clone())
+    );
+    assert_eq!(
+        vec![(ProblemKind::InvalidAtom, 5), (ProblemKind::Truncated, 14)],
+        problems(b"{\"a\":tru,\"b\":1", options.clone().keep_partial(true))
+    );
+    assert_eq!(
+        vec![(ProblemKind::NotAnObject, 0)],
+        problems(b"[1]", options.clone())
+    );
+    assert_eq!(
+        vec![(ProblemKind::InvalidUtf8, 6)],
+        problems(b"{\"a\":\"\xff\"}", options.clone())
+    );
+
+    // problems past the maximum are counted, but not kept
+    let document = Document::scan_trusted_with(
+        b"{\"a\":tru,\"b\":nul,\"c\":fals}",
+        &options.clone().collect_problems(1),
+    );
+    assert_eq!(1, document.problems().len());
+    assert_eq!(2, document.problems().dropped());
+
+    // problems aren't collected by default
+    let document = Document::scan_trusted_with(
+        b"{\"a\":tru,\"b\":nul}",
+        &ScanOptions::new().verify_atoms(true),
+    );
+    assert!(document.is_err());
+    assert!(document.problems().is_empty());
+    assert_eq!(0, document.problems().dropped());
+
+    // valid documents don't have any problems
+    let document = Document::scan_trusted_with(b"{\"a\":true}", &options);
+    assert!(!document.is_err());
+    assert!(document.problems().is_empty());
+}
+
+#[test]
+fn err_too_many_elements() {
+    let elems = |count: usize| {
+        let mut input = String::from("{\"a\":[");
+        input.push_str(&vec!["0"; count].join(","));
+        input.push_str("]}");
+        input
+    };
+
+    // the key and array count towards the limit
+    let max = elems(Document::MAX_ELEMENTS - 2);
+    let doc = Document::scan_trusted(max.as_bytes());
+    assert!(doc.scan_error().is_none());
+    assert_eq!(
+        Document::MAX_ELEMENTS - 2,
+        doc.as_map().get_arr("a").unwrap().len()
+    );
+
+    // documents that are too large can't be kept, even partially
+    let over = elems(Document::MAX_ELEMENTS - 1);
+    for backend in [Backend::Scalar, Backend::Auto] {
+        for options in [
+            ScanOptions::new(),
+            ScanOptions::new().keep_partial(true),
+            ScanOptions::new().recover(true),
+        ] {
+            let doc = Document::scan_trusted_with(over.as_bytes(), &options.backend(backend));
+
+            assert!(doc.is_err());
+            assert_eq!(0, doc.as_map().len());
+            assert_eq!(
+                ScanErrorKind::TooManyElements {
+                    count: Document::MAX_ELEMENTS + 1
+                },
+                doc.scan_error().unwrap().kind()
+            );
+        }
+    }
+
+    let err = Document::try_from(over.as_bytes()).unwrap_err();
+    assert_eq!(
+        ScanErrorKind::TooManyElements {
+            count: Document::MAX_ELEMENTS + 1
+        },
+        err.kind()
+    );
+}
+
+#[test]
+fn err_flat_too_deep() {
+    // documents that aren't flat are invalid for the flat profile
+    let input = b"{\"a\":{\"b\":{\"c\":1}}}";
+
+    let document: Document = assert_test_panics!(Document::scan_trusted_flat(input));
+
+    assert!(document.is_err());
+    assert_eq!(10, document.valid_up_to());
+}
+
+#[test]
+fn err_flat_too_deep_vectorized() {
+    let input = format!(
+        "{{\"a\":[{}]}}",
+        str::from_utf8(include_bytes!("../../cases/10kb_event_stacktrace.json"))
+            .unwrap()
+            .trim()
+    );
+
+    let document: Document = assert_test_panics!(Document::scan_trusted_flat(input.as_bytes()));
+
+    assert!(document.is_err());
+    assert!(!Document::scan_trusted(input.as_bytes()).is_err());
+}
+
+#[test]
+fn err_root() {
+    for (input, expected) in [
+        (b"" as &[u8], RootError::Empty),
+        (b" \r\n", RootError::Empty),
+        (b"not json", RootError::NotAnObject),
+        (b" [1,2]", RootError::NotAnObject),
+        (b"\xff{\"a\":1}", RootError::InvalidUtf8),
+        (b" {\xff\"a\":1}", RootError::InvalidUtf8),
+    ] {
+        for backend in [Backend::Scalar, Backend::Auto] {
+            let doc = Document::scan_trusted_with(input, &ScanOptions::new().backend(backend));
+
+            assert!(doc.is_err());
+            assert_eq!(
+                ScanErrorKind::Root(expected),
+                doc.scan_error().unwrap().kind(),
+                "{:?}",
+                input
+            );
+        }
+
+        assert_eq!(
+            ScanErrorKind::Root(expected),
+            Document::scan_keys(input).unwrap_err().kind()
+        );
+    }
+
+    // invalid UTF8 within the root map isn't a problem with the root itself
+    for input in [b"{\"a\":\"\xff\"}" as &[u8], b"[\xff]"] {
+        let doc = Document::scan_trusted(input);
+
+        assert!(doc.is_err());
+        assert!(!matches!(
+            doc.scan_error().unwrap().kind(),
+            ScanErrorKind::Root(RootError::InvalidUtf8)
+        ));
+    }
+}
+
+#[test]
+fn err_unescape_json_str() {
+    // strings that end with a `\` that isn't escaped can't be unescaped
+    for (input, input_offset) in [("\\", 0), ("abc\\", 3), ("a\\\\\\", 3)] {
+        assert_eq!(
+            input_offset,
+            unescape_json_str(input).unwrap_err().input_offset()
+        );
+    }
+}
+
+#[test]
+fn err_unescape_json_str_policy() {
+    // escapes that aren't valid JSON are errors when the policy asks for them
+    for (input, input_offset) in [
+        ("a\\xb", 1),
+        ("\\\\\\q", 2),
+        ("ok\\ud83d", 2),
+        ("\\ude04\\ud83d\\ude04", 0),
+        ("\\u12", 0),
+        ("\\é", 0),
+    ] {
+        assert_eq!(
+            input_offset,
+            unescape_json_str_with(input, UnescapePolicy::Error)
+                .unwrap_err()
+                .input_offset(),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn err_project() {
+    use squirrel_json_derive::FromDocument;
+
+    #[derive(Debug, FromDocument)]
+    struct Event<'a> {
+        #[sqj(key = "@m")]
+        _message: &'a str,
+    }
+
+    // missing keys, values of the wrong kind, and strings that can't be borrowed are errors
+    for input in [
+        b"{\"@x\":\"message\"}" as &[u8],
+        b"{\"@m\":1}",
+        b"{\"@m\":\"a\\nb\"}",
+    ] {
+        let document = Document::scan_trusted(input);
+
+        assert_eq!("@m", document.project::<Event>().unwrap_err().key());
+    }
+}
+
+#[test]
+fn err_scan_keys() {
+    for (input, valid_up_to) in [
+        (b"{\"a\":1" as &[u8], 6),
+        (b"{\"a\":\"b}", 5),
+        (b"{\"a\":[1}", 7),
+        (b"{\"a\":1]}", 6),
+        (b"{\"a\":\"b\\\"}", 5),
+        (b"[\"a\"]", 0),
+        (b"{\"a\":\"\xff\"}", 6),
+    ] {
+        assert_eq!(
+            valid_up_to,
+            Document::scan_keys(input).unwrap_err().valid_up_to(),
+            "{:?}",
+            input
+        );
+    }
+
+    // long enough inputs are scanned in vectorized blocks
+    let long = "a\\\"".repeat(100);
+    for (input, valid_up_to) in [
+        (format!("{{\"a\":\"{}}}", long), 5),
+        (format!("{{\"a\":\"{}\",\"b\":[1}}", long), long.len() + 14),
+        (format!("{{\"a\":\"{}\",\"b\":1]}}", long), long.len() + 13),
+        (format!("{{\"a\":[{}1]]}}", "1,".repeat(100)), 208),
+    ] {
+        assert_eq!(
+            valid_up_to,
+            Document::scan_keys(input.as_bytes())
+                .unwrap_err()
+                .valid_up_to(),
+            "{:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn err_check_scannable() {
+    let nested = |depth| format!("{{\"a\":{}{}}}", "[".repeat(depth), "]".repeat(depth));
+    let too_deep = nested(100);
+
+    for (input, kind, input_offset) in [
+        (b"{\"a\": 1}" as &[u8], ProblemKind::Unexpected, 5),
+        (b"{\"a\":[1,\n2]}", ProblemKind::Unexpected, 8),
+        (b" [\"a\"]", ProblemKind::NotAnObject, 1),
+        (b"[\"a\"]", ProblemKind::NotAnObject, 0),
+        (b"{\"a\":1", ProblemKind::Truncated, 6),
+        (b"{\"a\":\"b}", ProblemKind::Truncated, 5),
+        (b"{\"a\":[1}", ProblemKind::Truncated, 7),
+        (b"{\"a\":1]}", ProblemKind::Unbalanced, 6),
+        (b"{\"a\":1}{\"b\":2}", ProblemKind::Unbalanced, 6),
+        (b"{\"a\":\"\xff\"}", ProblemKind::InvalidUtf8, 6),
+        (too_deep.as_bytes(), ProblemKind::TooDeep, 102),
+    ] {
+        let problem = Document::check_scannable(input).unwrap_err();
+
+        assert_eq!(
+            (kind, input_offset),
+            (problem.kind(), problem.input_offset()),
+            "{:?}",
+            input
+        );
+        assert!(!Document::is_minified_object(input));
+    }
+
+    // whitespace within strings is fine
+    assert!(Document::is_minified_object(b"{\"a b\":\" \\\" \"}"));
+
+    // whitespace around the document is fine
+    assert!(Document::is_minified_object(b" \r\n{\"a\":1}\n"));
+
+    // the depth limit is the same as the scanner's
+    let deepest = nested(97);
+
+    assert!(!Document::scan_trusted(deepest.as_bytes()).is_err());
+    assert!(Document::is_minified_object(deepest.as_bytes()));
+}
+
+#[test]
+fn err_verify_atoms() {
+    for (input, valid_up_to) in [
+        (b"{\"a\":[true,false,null],\"b\":nool}" as &[u8], 27),
+        (b"{\"a\":tru,\"b\":1}", 5),
+        (b"{\"a\":[1,falsey]}", 8),
+        (b"{\"a\":nulll}", 5),
+        (b"{\"a\":t}", 5),
+    ] {
+        for backend in [Backend::Scalar, Backend::Simd] {
+            // atoms that don't match are only detected if they're verified
+            let document = Document::scan_trusted_backend(input, backend);
+            assert!(!document.is_err(), "{:?}", input);
+
+            let document = Document::scan_trusted_with(
+                input,
+                &ScanOptions::new().backend(backend).verify_atoms(true),
+            );
+
+            assert!(document.is_err(), "{:?}", input);
+            assert_eq!(valid_up_to, document.valid_up_to(), "{:?}", input);
+        }
+    }
+
+    // documents with valid atoms aren't affected
+    let document = Document::scan_trusted_with(
+        b"{\"a\":[true,false,null],\"b\":{\"c\":true}}",
+        &ScanOptions::new().verify_atoms(true),
+    );
+
+    assert!(!document.is_err());
+    assert_eq!(
+        json!({"a": [true, false, null], "b": {"c": true}}),
+        document.to_value()
+    );
+}
+
+#[test]
+fn err_trailing_comma() {
+    let key = "a".repeat(64);
+    let long = format!("{{\"{}\":[1,2],\"b\":{{\"c\":\"d\",}}}}", key);
+
+    for (input, valid_up_to, recovered) in [
+        (b"{\"a\":1,}" as &[u8], 6, json!({"a": 1})),
+        (b"{\"a\":[1,2,]}", 9, json!({"a": [1, 2]})),
+        (b"{\"a\":[\"b\",],\"c\":1}", 9, json!({"a": ["b"]})),
+        (b"{\"a\":{\"b\":true,}}", 14, json!({"a": {"b": true}})),
+        (
+            long.as_bytes(),
+            86,
+            json!({key.clone(): [1, 2], "b": {"c": "d"}}),
+        ),
+    ] {
+        for backend in [Backend::Scalar, Backend::Simd] {
+            let options = ScanOptions::new()
+                .backend(backend)
+                .allow_trailing_commas(false)
+                .collect_problems(8);
+
+            let document = Document::scan_trusted_with(input, &options);
+
+            assert!(document.is_err(), "{:?}", input);
+            assert_eq!(valid_up_to, document.valid_up_to(), "{:?}", input);
+            assert_eq!(
+                vec![(ProblemKind::TrailingComma, valid_up_to)],
+                document
+                    .problems()
+                    .iter()
+                    .map(|problem| (problem.kind(), problem.input_offset()))
+                    .collect::<Vec<_>>(),
+                "{:?}",
+                input
+            );
+
+            // recovering keeps everything before the trailing comma
+            let document = Document::scan_trusted_with(input, &options.recover(true));
+
+            assert!(document.is_err(), "{:?}", input);
+            assert_eq!(recovered, document.to_value(), "{:?}", input);
+        }
+    }
+}
+
+#[test]
+fn err_writer_misuse() {
+    // misusing the writer panics instead of producing unscannable JSON
+    fn panics(f: impl FnOnce(&mut Writer)) -> bool {
+        panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            let mut writer = Writer::new();
+            f(&mut writer);
+            writer.finish()
+        }))
+        .is_err()
+    }
+
+    assert!(panics(|w| {
+        w.str("no key");
+    }));
+    assert!(panics(|w| {
+        w.key("a").key("b");
+    }));
+    assert!(panics(|w| {
+        w.key("a");
+    }));
+    assert!(panics(|w| {
+        w.key("a").arr_begin().key("b");
+    }));
+    assert!(panics(|w| {
+        w.key("a").map_begin().arr_end();
+    }));
+    assert!(panics(|w| {
+        w.map_end();
+    }));
+    assert!(panics(|w| {
+        w.key("a").arr_begin();
+    }));
+    assert!(panics(|w| {
+        w.key("a");
+        for _ in 0..100 {
+            w.arr_begin();
+        }
+    }));
+}
+
 #[test]
 fn invalid_escape() {
     // unknown escape sequences are passed through
@@ -79,6 +835,17 @@ fn invalid_map_terminated_as_arr() {
     drop(document.to_value());
 }
 
+#[test]
+fn invalid_map_terminated_as_arr_minified() {
+    // re-emitting a document always closes containers with the terminator of their kind
+    let document = Document::scan_trusted_fallback(b"{\"a\":{\"b\":1],\"c\":[1}}");
+
+    assert_eq!(
+        "{\"a\":[\"b\"],\"c\":{}}",
+        str::from_utf8(&document.to_minified_bytes()).unwrap()
+    );
+}
+
 #[test]
 fn invalid_arr_terminated_as_map() {
     // arrays that are terminated with a `}` instead of a `]` are not detected
@@ -111,6 +878,99 @@ fn invalid_map_with_missing_key() {
     drop(document.to_value());
 }
 
+#[test]
+fn invalid_collect_warnings() {
+    // oddities that aren't detected as errors can be collected as warnings
+    let input = b"{\"a\":\"\\j\",\"b\":\"\\ud83d\",\"c\":{\"d\":1],\"e\":[1,2},\"f\":tru,\"g\":nil,\"h\":[true,false,null],\"i\":\"\\ud83d\\ude04\\n\"}";
+
+    for backend in [Backend::Scalar, Backend::Auto] {
+        let document = Document::scan_trusted_with(
+            input,
+            &ScanOptions::new().backend(backend).collect_warnings(true),
+        );
+
+        assert!(!document.is_err());
+
+        let warnings: Vec<_> = document
+            .warnings()
+            .iter()
+            .map(|warning| (warning.kind(), warning.input_offset()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (WarningKind::InvalidEscape, 6),
+                (WarningKind::LoneSurrogate, 15),
+                (WarningKind::MapTerminatedAsArr, 27),
+                (WarningKind::ArrTerminatedAsMap, 39),
+                (WarningKind::InvalidAtom, 49),
+                (WarningKind::InvalidAtom, 57),
+            ],
+            warnings
+        );
+
+        // warnings aren't collected unless they're asked for
+        let document = Document::scan_trusted_with(input, &ScanOptions::new().backend(backend));
+
+        assert!(document.warnings().is_empty());
+    }
+}
+
+#[test]
+fn invalid_collect_unescaped_controls() {
+    // control characters in strings aren't detected as errors, but can be collected as warnings
+    let long = format!("{}\0", "a".repeat(70));
+    let input = format!(
+        "{{\"a\":\"b\tc\",\"d\\n\":\"{}\",\"e\\u0000\":\"\\n\x1f\"}}",
+        long
+    );
+
+    for backend in [Backend::Scalar, Backend::Auto] {
+        let document = Document::scan_trusted_with(
+            input.as_bytes(),
+            &ScanOptions::new().backend(backend).collect_warnings(true),
+        );
+
+        assert!(!document.is_err());
+
+        let warnings: Vec<_> = document
+            .warnings()
+            .iter()
+            .map(|warning| (warning.kind(), warning.input_offset()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (WarningKind::UnescapedControl, 7),
+                (WarningKind::UnescapedControl, 88),
+                (WarningKind::UnescapedControl, 104),
+            ],
+            warnings
+        );
+    }
+}
+
+#[test]
+fn invalid_map_with_dangling_key() {
+    // documents with a trailing key without a value are not detected
+    // the key is dropped, but the map knows it was there
+    let document = Document::scan_trusted_fallback(b"{\"a\":1,\"b\":{\"c\"},\"d\"}");
+
+    let map = document.as_map();
+    assert_eq!(2, map.len());
+    assert!(map.has_dangling_key());
+
+    match map.entries().nth(1) {
+        Some((_, Kind::Map(b))) => {
+            assert_eq!(0, b.len());
+            assert!(b.has_dangling_key());
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    assert_eq!(json!({"a": 1, "b": {}}), document.to_value());
+}
+
 #[test]
 fn invalid_unescape_unknown() {
     drop(unsafe { unescape_trusted("\\j") });
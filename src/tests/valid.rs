@@ -1,8 +1,25 @@
 use super::*;
 
-use std::str;
+use std::{borrow::Cow, str};
 
-use crate::{tests::some, unescape::unescape_trusted, Document};
+use crate::{
+    batch::{self, KeyHistogram, Predicate},
+    de::{
+        self, ArchivedOffsets, Backend, BinaryFormat, DocumentPool, DuplicateKeys, KeySet, Kind,
+        KindTag, MergeStrategy, Numbers, OffsetsCache, RawKind, RawPosition, ScanOptions, Scanner,
+        StringPool, SummaryLimits,
+    },
+    diagnostics,
+    escape::escape_str,
+    find::find,
+    metrics_snapshot,
+    prefilter::Prefilter,
+    ser::Writer,
+    stream, testing, transcode_utf16_to_utf8,
+    unescape::unescape_trusted,
+    unescape_json_str, unescape_json_str_with, utf8, CowDocument, Document, OwnedDocument,
+    UnescapePolicy, UnescapeScratch,
+};
 
 use serde_json::json;
 
@@ -35,6 +52,309 @@ fn read_cases() {
     }
 }
 
+#[test]
+fn read_leading_whitespace() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        b"{\"a\":1}",
+    ] {
+        let expected = Document::scan_trusted(input);
+        assert!(!expected.is_err());
+
+        for leading in ["\n", " \r\n\t", &" ".repeat(40)] {
+            let mut padded = leading.as_bytes().to_vec();
+            padded.extend_from_slice(input);
+
+            test_alignment(&padded, 32, |padded| {
+                for options in [
+                    ScanOptions::new().backend(Backend::Auto),
+                    ScanOptions::new().backend(Backend::Scalar),
+                    ScanOptions::new().backend(Backend::Simd),
+                    ScanOptions::new().interleave_utf8(true),
+                ] {
+                    let document = Document::scan_trusted_with(padded, &options);
+
+                    assert!(!document.is_err(), "{:?}", options);
+                    assert_eq!(expected.to_value(), document.to_value(), "{:?}", options);
+                    assert_eq!(
+                        expected.as_map().as_json(),
+                        document.as_map().as_json(),
+                        "{:?}",
+                        options
+                    );
+                }
+            });
+
+            assert!(Document::is_minified_object(&padded));
+            assert!(Document::scan_keys(input)
+                .unwrap()
+                .iter()
+                .map(|key| key.as_raw())
+                .eq(Document::scan_keys(&padded)
+                    .unwrap()
+                    .iter()
+                    .map(|key| key.as_raw())));
+        }
+    }
+
+    // the raw walkers skip leading whitespace too
+    let input = b"\r\n{\"a\":{\"b\":\"c\"}}";
+
+    assert!(Document::contains_key_raw(input, "a"));
+    assert_eq!(
+        1,
+        batch::filter([input], "a.b", Predicate::StrEq("c")).count()
+    );
+
+    // truncated documents with leading whitespace can be recovered
+    let input = b"{\"a\":[1,2],\"b\":{\"c\":\"d";
+    let options = ScanOptions::new().recover(true);
+
+    let mut padded = b" \n".to_vec();
+    padded.extend_from_slice(input);
+
+    let document = Document::scan_trusted_with(&padded, &options);
+
+    assert!(document.is_err());
+    assert_eq!(json!({"a": [1, 2], "b": {}}), document.to_value());
+    assert_eq!(
+        Document::scan_trusted_with(input, &options).to_value(),
+        document.to_value()
+    );
+}
+
+#[test]
+fn read_trailing_commas() {
+    let key = "a".repeat(64);
+    let long = format!(
+        "{{\"{}\":[1,2,],\"b\":{{\"c\":\"d\",\"e\":[{{}},[],],}},\"f\":true,}}",
+        key
+    );
+
+    for (input, expected) in [
+        (b"{\"a\":1,}" as &[u8], json!({"a": 1})),
+        (b"{\"a\":[1,2,]}", json!({"a": [1, 2]})),
+        (
+            b"{\"a\":[\"b\",],\"c\":{\"d\":null,}}",
+            json!({"a": ["b"], "c": {"d": null}}),
+        ),
+        (
+            b"{\"a\":{\"b\":1,},\"c\":2}",
+            json!({"a": {"b": 1}, "c": 2}),
+        ),
+        (
+            long.as_bytes(),
+            json!({key.clone(): [1, 2], "b": {"c": "d", "e": [{}, []]}, "f": true}),
+        ),
+    ] {
+        for backend in [Backend::Scalar, Backend::Simd] {
+            let document = Document::scan_trusted_with(
+                input,
+                &ScanOptions::new()
+                    .backend(backend)
+                    .allow_trailing_commas(true),
+            );
+
+            assert!(!document.is_err(), "{:?}", input);
+            assert_eq!(expected, document.to_value(), "{:?}", input);
+        }
+
+        // trailing commas are accepted by default
+        let document = Document::scan_trusted(input);
+        assert!(!document.is_err(), "{:?}", input);
+        assert_eq!(expected, document.to_value(), "{:?}", input);
+
+        let document = Document::try_from(input).unwrap();
+        assert_eq!(expected, document.to_value(), "{:?}", input);
+    }
+}
+
+#[test]
+fn read_lazy_depth() {
+    let input = b"{\"a\":{\"b\":[1,{\"c\":2}],\"d\":3},\"e\":[[4]],\"f\":5}";
+    let expected: serde_json::Value = serde_json::from_slice(input).unwrap();
+
+    let full = Document::scan_trusted(input);
+
+    for (depth, len) in [(0, 6), (1, 11), (2, 14), (3, 16)] {
+        for backend in [Backend::Scalar, Backend::Simd] {
+            let document = Document::scan_trusted_with(
+                input,
+                &ScanOptions::new().backend(backend).lazy_depth(depth),
+            );
+
+            assert!(!document.is_err());
+            assert_eq!(len, document.offsets().len(), "{}", depth);
+
+            assert_eq!(expected, document.to_value());
+            assert_eq!(
+                expected,
+                document.deserialize::<serde_json::Value>().unwrap()
+            );
+            assert_eq!(
+                full.transcode(BinaryFormat::Cbor),
+                document.transcode(BinaryFormat::Cbor)
+            );
+        }
+    }
+
+    let document = Document::scan_trusted_with(input, &ScanOptions::new().lazy_depth(0));
+
+    let a = document.as_map().get("a").unwrap();
+    match a {
+        Kind::Lazy(lazy) => {
+            assert_eq!(KindTag::Map, lazy.kind());
+            assert_eq!("{\"b\":[1,{\"c\":2}],\"d\":3}", lazy.as_json());
+        }
+        a => panic!("unexpected {:?}", a),
+    }
+
+    let nested = a.scan_nested().unwrap();
+    match nested.as_kind() {
+        Kind::Map(map) => {
+            assert_eq!(2, map.len());
+            assert_eq!("[1,{\"c\":2}]", map.get("b").unwrap().as_json());
+        }
+        kind => panic!("unexpected {:?}", kind),
+    }
+
+    assert_eq!(
+        std::cmp::Ordering::Equal,
+        a.json_cmp(&full.as_map().get("a").unwrap())
+    );
+    assert_eq!(
+        RawKind::Lazy { map: false },
+        document.offsets().get(3).unwrap().kind()
+    );
+    assert!(document.as_map().get("f").unwrap().scan_nested().is_none());
+}
+
+#[test]
+fn read_element_count_max_depth() {
+    let input = b"{\"a\":{\"b\":[1,{\"c\":2}],\"d\":3},\"e\":[[4]],\"f\":5}";
+
+    for options in [
+        ScanOptions::new().backend(Backend::Scalar),
+        ScanOptions::new().backend(Backend::Simd),
+        ScanOptions::new().lazy_depth(0),
+        ScanOptions::new().lazy_depth(1),
+    ] {
+        let document = Document::scan_trusted_with(input, &options);
+
+        assert_eq!(16, document.element_count());
+        assert_eq!(3, document.max_depth());
+    }
+
+    for (input, count, depth) in [
+        (b"{}" as &[u8], 0, 0),
+        (b"{\"a\":1}", 2, 0),
+        (b"{\"a\":[]}", 2, 1),
+        (b"{\"a\":[{},[]],\"b\":{\"c\":null}}", 8, 2),
+    ] {
+        let document = Document::scan_trusted(input);
+
+        assert_eq!(count, document.element_count(), "{:?}", input);
+        assert_eq!(depth, document.max_depth(), "{:?}", input);
+    }
+
+    // nested maps treated as documents only count their own elements
+    for options in [
+        ScanOptions::new(),
+        ScanOptions::new().lazy_depth(1),
+        ScanOptions::new().lazy_depth(2),
+    ] {
+        let document = Document::scan_trusted_with(input, &options);
+        let nested = document.as_map().get_map("a").unwrap().as_document();
+
+        assert_eq!(8, nested.element_count(), "{:?}", options);
+        assert_eq!(2, nested.max_depth(), "{:?}", options);
+    }
+
+    let document = Document::scan_trusted(b"{\"a\":{\"x\":1},\"b\":2}");
+    let nested = document.as_map().get_map("a").unwrap().as_document();
+
+    assert_eq!(2, nested.element_count());
+    assert_eq!(0, nested.max_depth());
+}
+
+#[test]
+fn read_presize() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        b"{\"a\":[],\"b\":[{}],\"c\":\":,[\"}",
+        b"{\"a\":[1,{\"b\":2",
+    ] {
+        let expected = Document::scan_trusted(input);
+
+        for backend in [Backend::Scalar, Backend::Simd] {
+            let document = Document::scan_trusted_with(
+                input,
+                &ScanOptions::new().backend(backend).presize(true),
+            );
+
+            assert_eq!(expected.is_err(), document.is_err());
+            assert_eq!(expected.to_value(), document.to_value());
+        }
+    }
+}
+
+#[test]
+fn read_flat() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        b"{}",
+        b"{\"a\":[],\"b\":{},\"c\":[1,\"2\",null],\"d\":{\"e\":true}}",
+    ] {
+        let expected = Document::scan_trusted(input);
+        let document = Document::scan_trusted_flat(input);
+
+        assert!(!document.is_err());
+        assert_eq!(expected.to_value(), document.to_value());
+        assert_eq!(expected.element_count(), document.element_count());
+        assert_eq!(expected.max_depth(), document.max_depth());
+    }
+}
+
+#[test]
+fn read_backends() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+    ] {
+        let expected: serde_json::Value = serde_json::from_slice(input).unwrap();
+
+        for backend in [Backend::Auto, Backend::Scalar, Backend::Simd] {
+            let document = Document::scan_trusted_backend(input, backend);
+
+            assert_eq!(expected, document.to_value(), "{:?}", backend);
+        }
+
+        assert_eq!(expected, Document::scan_trusted_scalar(input).to_value());
+    }
+}
+
+#[test]
+fn read_simd_small() {
+    // the vectorized backend is used for inputs that are too small for the heuristic
+    for input in [
+        b"{\"a\":42}" as &[u8],
+        b"{\"a\":\"a string that's just long enough to be vectorized\",\"b\":[true,false,null]}",
+        b"{\"a\":\"a string that's long enough to be vectorized\",\"b\":[true,false,null],\"c\":{\"d\":1.5}}",
+    ] {
+        let expected: serde_json::Value = serde_json::from_slice(input).unwrap();
+
+        test_alignment(input, 32, |input| {
+            let document = Document::scan_trusted_simd(input);
+
+            assert_eq!(expected, document.to_value());
+        });
+    }
+}
+
 #[test]
 fn read_generated() {
     // debug builds are slow, so just run a handful of cases
@@ -44,94 +364,2905 @@ fn read_generated() {
             100
         }
 
-        #[cfg(not(debug))]
-        {
-            2000
-        }
+        #[cfg(not(debug))]
+        {
+            2000
+        }
+    };
+
+    for _ in 0..iterations {
+        // Check the parser against some randomly generated JSON data
+        // Fuzzing is good at finding bizarre and invalid almost-JSON
+        // but doesn't discover valid JSON very often. This approach
+        // stampedes with a bunch of valid combinations of JSON objects
+        // to ensure the parser is always correct for all correct JSON
+        // objects without internal whitespace
+        let input = testing::arbitrary_object(&mut rand::thread_rng(), 10);
+
+        let expected: serde_json::Value = match serde_json::from_str(&input) {
+            Ok(v) => v,
+            Err(e) => {
+                panic!("parsing `{}`: {}", input, e);
+            }
+        };
+
+        let document = Document::scan_trusted_fallback(input.as_bytes());
+
+        assert_eq!(expected, document.to_value());
+
+        // Check the document using the vectorized parser
+        test_alignment(input.as_bytes(), 32, |input| {
+            let document = Document::scan_trusted(input);
+
+            assert_eq!(expected, document.to_value());
+
+            let offsets = document.into_offsets();
+            let document = unsafe { offsets.to_document_unchecked(input) };
+
+            assert_eq!(expected, document.to_value());
+        });
+    }
+}
+
+#[test]
+fn read_generated_seeded() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // the same seed always generates the same object
+    let a = testing::arbitrary_object(&mut StdRng::seed_from_u64(42), 3);
+    let b = testing::arbitrary_object(&mut StdRng::seed_from_u64(42), 3);
+
+    assert_eq!(a, b);
+
+    let expected: serde_json::Value = serde_json::from_str(&a).unwrap();
+
+    assert_eq!(expected, Document::scan_trusted(a.as_bytes()).to_value());
+}
+
+#[test]
+fn read_generated_adversarial() {
+    let check = |input: &str| {
+        let expected: serde_json::Value = match serde_json::from_str(input) {
+            Ok(v) => v,
+            Err(e) => {
+                panic!("parsing `{}`: {}", input, e);
+            }
+        };
+
+        let document = Document::scan_trusted_fallback(input.as_bytes());
+        assert_eq!(expected, document.to_value());
+
+        test_alignment(input.as_bytes(), 32, |input| {
+            let document = Document::scan_trusted(input);
+            assert_eq!(expected, document.to_value());
+
+            let document = Document::scan_trusted_simd(input);
+            assert_eq!(expected, document.to_value());
+        });
+    };
+
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..10 {
+        // maps and arrays can be nested up to 97 levels below the root
+        let input = testing::nested_object(&mut rng, 97);
+        check(&input);
+        assert_eq!(97, Document::scan_trusted(input.as_bytes()).max_depth());
+
+        check(&testing::backslash_object(&mut rng, 200));
+        check(&testing::escaped_object(&mut rng, 200));
+    }
+
+    // an object can't have a single element, so it gets two instead
+    for (elements, expected) in [(0, 0), (1, 2), (2, 2), (3, 3), (100, 100)] {
+        let input = testing::wide_object(&mut rng, elements);
+        let document = Document::scan_trusted(input.as_bytes());
+
+        assert_eq!(expected, document.element_count(), "{}", input);
+    }
+
+    // documents right at the limit of the number of elements can be indexed
+    let max = testing::wide_object(&mut rng, Document::MAX_ELEMENTS);
+    let document = Document::scan_trusted(max.as_bytes());
+
+    assert!(!document.is_err());
+    assert_eq!(Document::MAX_ELEMENTS, document.element_count());
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&max).unwrap(),
+        document.to_value()
+    );
+
+    // documents just over the limit can't be indexed
+    let over = testing::wide_object(&mut rng, Document::MAX_ELEMENTS + 1);
+
+    for backend in [Backend::Scalar, Backend::Simd] {
+        let document =
+            Document::scan_trusted_with(over.as_bytes(), &ScanOptions::new().backend(backend));
+
+        assert_eq!(
+            de::ScanErrorKind::TooManyElements {
+                count: Document::MAX_ELEMENTS + 1
+            },
+            document.scan_error().unwrap().kind()
+        );
+    }
+}
+
+#[test]
+fn compare_backends() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+    ] {
+        test_alignment(input, 32, |input| {
+            assert!(diagnostics::compare(input).is_none());
+        });
+    }
+
+    for _ in 0..10 {
+        let input = testing::arbitrary_object(&mut rand::thread_rng(), 10);
+
+        assert!(diagnostics::compare(input.as_bytes()).is_none());
+    }
+}
+
+#[test]
+fn assert_backends_agree() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        include_bytes!("../../cases/600b_event_healthcheck_no_escape.json") as &[u8],
+        b"{}",
+        b"{\"a\":[1,true,null,{\"b\":\"c\\n\"}],\"d\":{},\"e\":[],\"f\":\"\"}",
+    ] {
+        diagnostics::assert_backends_agree(input);
+    }
+
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..5 {
+        diagnostics::assert_backends_agree(testing::arbitrary_object(&mut rng, 10).as_bytes());
+        diagnostics::assert_backends_agree(testing::backslash_object(&mut rng, 100).as_bytes());
+        diagnostics::assert_backends_agree(testing::escaped_object(&mut rng, 100).as_bytes());
+    }
+}
+
+#[test]
+fn read_scanner() {
+    let inputs = [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        b"{\"a\":[1,{\"b\":2",
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        b"{}",
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+    ];
+
+    for backend in [Backend::Auto, Backend::Scalar] {
+        let mut scanner = Scanner::with_options(ScanOptions::new().backend(backend));
+
+        for input in inputs.iter().chain(inputs.iter()) {
+            let expected = Document::scan_trusted(input);
+            let document = scanner.scan(input);
+
+            assert_eq!(expected.is_err(), document.is_err());
+            assert_eq!(expected.valid_up_to(), document.valid_up_to());
+            assert_eq!(expected.to_value(), document.to_value());
+        }
+    }
+}
+
+#[test]
+fn read_document_pool() {
+    let inputs = [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        b"{\"a\":[1,{\"b\":2",
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        b"{}",
+    ];
+
+    for backend in [Backend::Auto, Backend::Scalar] {
+        let mut pool = DocumentPool::with_options(ScanOptions::new().backend(backend));
+
+        for _ in 0..2 {
+            // documents scanned from the pool can be alive at the same time
+            let documents = inputs
+                .iter()
+                .map(|input| pool.scan(input))
+                .collect::<Vec<_>>();
+            assert!(pool.is_empty());
+
+            for (input, document) in inputs.iter().zip(&documents) {
+                let expected = Document::scan_trusted(input);
+
+                assert_eq!(expected.is_err(), document.is_err());
+                assert_eq!(expected.to_value(), document.to_value());
+            }
+
+            for document in documents {
+                pool.release(document);
+            }
+            assert_eq!(inputs.len(), pool.len());
+        }
+    }
+}
+
+#[test]
+fn read_stream() {
+    let events = [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        b"{\"a\":1}",
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+        b"{\"b\":[true,null]}",
+    ];
+
+    let mut input = Vec::new();
+    for event in events {
+        input.extend_from_slice(event);
+        input.extend_from_slice(b"\n\r\n");
+    }
+    // the last document doesn't need to end with a newline
+    input.extend_from_slice(b"{\"c\":\"d\"}");
+
+    let expected = events
+        .iter()
+        .copied()
+        .chain(Some(b"{\"c\":\"d\"}" as &[u8]))
+        .map(|event| Document::scan_trusted(event).to_value())
+        .collect::<Vec<_>>();
+
+    // small buffers split documents across them
+    for capacity in [1, 7, 64, 8 * 1024] {
+        let reader = std::io::BufReader::with_capacity(capacity, &*input);
+        let mut stream = stream::from_buf_read(reader);
+
+        let mut actual = Vec::new();
+        while let Some(document) = stream.next_document().unwrap() {
+            assert!(!document.is_err());
+            actual.push(document.to_value());
+        }
+
+        assert_eq!(expected, actual, "{}", capacity);
+        assert!(stream.next_document().unwrap().is_none());
+    }
+
+    let mut stream = stream::from_buf_read(&b"\n \n"[..]);
+    assert!(stream.next_document().unwrap().is_none());
+}
+
+#[test]
+fn read_offsets_cache() {
+    let inputs = [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        b"{\"a\":[1,{\"b\":2",
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+        b"{\"a\":1}",
+    ];
+
+    let mut cache = OffsetsCache::new(8192);
+
+    for input in inputs.iter().chain(inputs.iter()) {
+        let expected = Document::scan_trusted(input);
+        let document = cache.scan_or_attach(input);
+
+        assert_eq!(expected.is_err(), document.is_err());
+        assert_eq!(expected.to_value(), document.to_value());
+    }
+
+    // erroneous documents aren't cached
+    assert_eq!(3, cache.len());
+    assert!(cache.size() <= 8192);
+
+    // the least recently used entries are evicted to make room for new ones
+    let size =
+        |input: &[u8]| input.len() + Document::scan_trusted(input).offsets().approximate_size();
+    let mut cache = OffsetsCache::new(size(inputs[0]).max(size(inputs[2])));
+
+    cache.scan_or_attach(inputs[0]);
+    cache.scan_or_attach(inputs[2]);
+    assert_eq!(1, cache.len());
+
+    assert_eq!(
+        Document::scan_trusted(inputs[0]).to_value(),
+        cache.scan_or_attach(inputs[0]).to_value()
+    );
+    assert_eq!(1, cache.len());
+}
+
+#[test]
+fn read_raw_offsets() {
+    let input = b"{\"a\":[1,true],\"b\\n\":{\"c\":null}}";
+
+    let document = Document::scan_trusted(input);
+    let offsets = document.offsets();
+
+    let raw = offsets
+        .iter()
+        .map(|offset| {
+            (
+                offset.kind(),
+                offset.position(),
+                offset.input_range(),
+                offset.next(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        vec![
+            (
+                RawKind::Str { escaped: false },
+                Some(RawPosition::Key),
+                Some(2..3),
+                Some(4)
+            ),
+            (
+                RawKind::Arr { len: 2 },
+                Some(RawPosition::Value),
+                Some(5..13),
+                Some(5)
+            ),
+            (RawKind::Num, Some(RawPosition::Elem), Some(6..7), Some(3)),
+            (RawKind::Bool(true), Some(RawPosition::Elem), None, None),
+            (
+                RawKind::Str { escaped: true },
+                Some(RawPosition::Key),
+                Some(15..18),
+                None
+            ),
+            (
+                RawKind::Map { entries: 1 },
+                Some(RawPosition::Value),
+                Some(20..30),
+                None
+            ),
+            (
+                RawKind::Str { escaped: false },
+                Some(RawPosition::Key),
+                Some(22..23),
+                None
+            ),
+            (RawKind::Null, Some(RawPosition::Value), None, None),
+        ],
+        raw
+    );
+
+    assert_eq!(raw.len(), offsets.len());
+    assert_eq!(
+        Some(RawKind::Null),
+        offsets.get(7).map(|offset| offset.kind())
+    );
+    assert_eq!(None, offsets.get(8));
+}
+
+#[test]
+fn read_value_span() {
+    let mut input = b"{\"token\":\"s3cr3t\",\"n\":-1.5,\"a\":[1,{}],\"t\":true,\"f\":false,\"z\":null,\"m\":{\"token\":\"x\"}}".to_vec();
+
+    let document = Document::scan_trusted(&input);
+    let map = document.as_map();
+
+    for (key, expected) in [
+        ("token", "\"s3cr3t\""),
+        ("n", "-1.5"),
+        ("a", "[1,{}]"),
+        ("t", "true"),
+        ("f", "false"),
+        ("z", "null"),
+        ("m", "{\"token\":\"x\"}"),
+    ] {
+        let span = map.value_span(key).unwrap();
+
+        assert_eq!(expected.as_bytes(), &input[span], "{}", key);
+    }
+
+    assert!(map.value_span("missing").is_none());
+
+    let span = map.value_span("token").unwrap();
+    drop(document);
+
+    // mask the contents of the string, leaving its quotes
+    input[span.start + 1..span.end - 1].fill(b'*');
+
+    let document = Document::scan_trusted(&input);
+    assert_eq!(
+        "******",
+        document.as_map().get_str("token").unwrap().as_raw()
+    );
+}
+
+#[test]
+fn read_capture() {
+    use std::sync::{Arc, Mutex};
+
+    let input = b"{\"level\":\"warn\",\"m\":{\"level\":\"info\"},\"tags\":[1,{}],\"l\\u0065vel\":\"debug\",\"n\":-1.5}";
+
+    for backend in [Backend::Scalar, Backend::Simd] {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+
+        let options = ScanOptions::new()
+            .backend(backend)
+            .capture(["level", "tags", "n"], {
+                let captured = captured.clone();
+                move |key, value| {
+                    captured
+                        .lock()
+                        .unwrap()
+                        .push((key.to_owned(), value.as_json().to_owned()))
+                }
+            });
+
+        let document = Document::scan_trusted_with(input, &options);
+        assert!(!document.is_err());
+
+        assert_eq!(
+            vec![
+                ("level".to_owned(), "\"warn\"".to_owned()),
+                ("tags".to_owned(), "[1,{}]".to_owned()),
+                ("level".to_owned(), "\"debug\"".to_owned()),
+                ("n".to_owned(), "-1.5".to_owned()),
+            ],
+            *captured.lock().unwrap(),
+            "{:?}",
+            backend
+        );
+
+        // nothing is captured after the document becomes erroneous
+        captured.lock().unwrap().clear();
+
+        let document =
+            Document::scan_trusted_with(b"{\"level\":\"warn\",\"a\":\"\xff\",\"n\":1}", &options);
+        assert!(document.is_err());
+
+        assert!(
+            !captured.lock().unwrap().iter().any(|(key, _)| key == "n"),
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn read_schema_fingerprint() {
+    let fingerprint = |input: &[u8]| Document::scan_trusted(input).schema_fingerprint();
+
+    let expected = fingerprint(b"{\"a\":\"b\",\"c\":{\"d\":1,\"e\":[true,false]},\"f\":null}");
+
+    // values and array lengths don't change the shape
+    for input in [
+        b"{\"a\":\"x\",\"c\":{\"d\":-2.5,\"e\":[false]},\"f\":null}" as &[u8],
+        b"{\"\\u0061\":\"\",\"c\":{\"d\":0,\"e\":[true,true,false]},\"f\":null}",
+    ] {
+        assert_eq!(
+            expected,
+            fingerprint(input),
+            "{}",
+            String::from_utf8_lossy(input)
+        );
+    }
+
+    // keys, kinds, nesting, and order do
+    for input in [
+        b"{\"a\":\"b\",\"c\":{\"d\":\"1\",\"e\":[true,false]},\"f\":null}" as &[u8],
+        b"{\"a\":\"b\",\"c\":{\"d\":1,\"e\":[true,1]},\"f\":null}",
+        b"{\"a\":\"b\",\"c\":{\"d\":1,\"e\":[true,false]}}",
+        b"{\"a\":\"b\",\"c\":{\"d\":1},\"e\":[true,false],\"f\":null}",
+        b"{\"c\":{\"d\":1,\"e\":[true,false]},\"a\":\"b\",\"f\":null}",
+        b"{\"a\":\"b\",\"c\":{\"d\":1,\"e\":[[true,false]]},\"f\":null}",
+    ] {
+        assert_ne!(
+            expected,
+            fingerprint(input),
+            "{}",
+            String::from_utf8_lossy(input)
+        );
+    }
+
+    assert_ne!(fingerprint(b"{}"), fingerprint(b"{\"a\":{}}"));
+    assert_ne!(fingerprint(b"{\"a\":[]}"), fingerprint(b"{\"a\":{}}"));
+}
+
+#[test]
+fn read_raw_chain() {
+    let input = b"{\"a\":[1,true,{}],\"b\":{\"c\":null,\"d\":[]},\"e\":\"f\"}";
+
+    let document = Document::scan_trusted(input);
+    let offsets = document.offsets();
+
+    let key = |index: usize| {
+        let range = offsets.get(index).unwrap().input_range().unwrap();
+        str::from_utf8(&input[range]).unwrap()
+    };
+
+    let root = offsets
+        .chain(offsets.root_first().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec!["a", "b", "e"],
+        root.iter().map(|i| key(*i)).collect::<Vec<_>>()
+    );
+
+    // values are at the index after their key
+    let a = offsets
+        .chain(offsets.first_child(root[0] + 1).unwrap())
+        .map(|i| offsets.get(i).unwrap().kind())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            RawKind::Num,
+            RawKind::Bool(true),
+            RawKind::Map { entries: 0 }
+        ],
+        a
+    );
+
+    let b = offsets
+        .chain(offsets.first_child(root[1] + 1).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec!["c", "d"],
+        b.iter().map(|i| key(*i)).collect::<Vec<_>>()
+    );
+    assert_eq!(Some(b[1]), offsets.next(b[0]));
+    assert_eq!(None, offsets.next(b[1]));
+
+    // empty containers and other elements don't have children
+    assert_eq!(None, offsets.first_child(b[1] + 1));
+    assert_eq!(None, offsets.first_child(root[2] + 1));
+    assert_eq!(None, offsets.first_child(offsets.len()));
+
+    assert_eq!(None, Document::scan_trusted(b"{}").offsets().root_first());
+    assert_eq!(0, offsets.chain(offsets.len()).count());
+}
+
+#[test]
+fn read_attach_verified() {
+    let input = include_bytes!("../../cases/serilog_embedded.json") as &[u8];
+
+    let document = Document::scan_trusted(input);
+    let expected = document.to_value();
+
+    let offsets = document.into_bound_offsets();
+    assert!(offsets.is_bound());
+
+    assert_eq!(
+        expected,
+        unsafe { offsets.attach_verified(input) }
+            .unwrap()
+            .to_value()
+    );
+
+    // inputs with a different length or content aren't attached
+    let mut changed = input.to_vec();
+    assert!(unsafe { offsets.attach_verified(&changed[1..]) }.is_none());
+
+    changed[10] = b'x';
+    assert!(unsafe { offsets.attach_verified(&changed) }.is_none());
+
+    // offsets that aren't bound can't be verified
+    let offsets = Document::scan_trusted(input).into_offsets().into_owned();
+    assert!(!offsets.is_bound());
+    assert!(unsafe { offsets.attach_verified(input) }.is_none());
+}
+
+#[test]
+fn read_memory_usage() {
+    let large = include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8];
+    let small = b"{\"a\":1}";
+
+    let detached = Document::scan_trusted(large).detach();
+    let large_usage = detached.memory_usage();
+
+    // scanning a small document into the same allocation keeps its capacity
+    let mut offsets = Document::scan_trusted_attach(small, detached)
+        .into_offsets()
+        .into_owned();
+    assert_eq!(
+        Document::scan_trusted(small).to_value(),
+        unsafe { offsets.to_document_unchecked(small) }.to_value()
+    );
+
+    let usage = offsets.memory_usage();
+    assert!(usage > offsets.approximate_size());
+
+    // the small document fits inline once it's shrunk
+    offsets.shrink_to_fit();
+    assert!(offsets.memory_usage() < usage);
+    assert_eq!(std::mem::size_of::<de::Offsets>(), offsets.memory_usage());
+
+    let mut detached = Document::scan_trusted(large).detach();
+    assert_eq!(large_usage, detached.memory_usage());
+
+    detached.shrink_to_fit();
+    assert!(detached.memory_usage() < large_usage);
+}
+
+#[test]
+fn read_inline_offsets() {
+    let small = b"{\"@t\":\"2021-01-01T00:00:00Z\",\"@mt\":\"A small event\",\"n\":42}" as &[u8];
+    let large = include_bytes!("../../cases/600b_event_no_escape.json") as &[u8];
+
+    // small documents keep their offsets inline
+    let document = Document::scan_trusted(small);
+    assert!(document.offsets().len() <= 8);
+    assert_eq!(
+        std::mem::size_of::<de::Offsets>(),
+        document.offsets().memory_usage()
+    );
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(small).unwrap(),
+        document.to_value()
+    );
+
+    // larger documents move them into their own allocation
+    let document = Document::scan_trusted(large);
+    assert!(document.offsets().memory_usage() > std::mem::size_of::<de::Offsets>());
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(large).unwrap(),
+        document.to_value()
+    );
+
+    // a detached small document can be reused for a larger one
+    let document = Document::scan_trusted_attach(large, Document::scan_trusted(small).detach());
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(large).unwrap(),
+        document.to_value()
+    );
+}
+
+#[test]
+fn read_archived_offsets() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        b"{}",
+    ] {
+        let document = Document::scan_trusted(input);
+        let expected = document.to_value();
+
+        let offsets = document.into_bound_offsets();
+        let archived = offsets.archive();
+
+        let loaded = ArchivedOffsets::from_bytes(&archived).unwrap();
+        assert_eq!(offsets.len(), loaded.len());
+        assert!(!loaded.is_err());
+
+        let document = unsafe { loaded.to_document_unchecked(input) };
+        assert_eq!(expected, document.to_value());
+
+        // the binding to the input is kept in the archive
+        assert!(loaded.is_bound());
+        assert_eq!(
+            expected,
+            unsafe { loaded.attach_verified(input) }.unwrap().to_value()
+        );
+        assert!(unsafe { loaded.attach_verified(b"{\"a\":1}") }.is_none());
+
+        let offsets = loaded.to_offsets();
+        assert_eq!(
+            expected,
+            unsafe { offsets.attach_verified(input) }
+                .unwrap()
+                .to_value()
+        );
+    }
+
+    // offsets that aren't bound can't be attached safely
+    let archived = Document::scan_trusted(b"{}").offsets().to_owned().archive();
+    let loaded = ArchivedOffsets::from_bytes(&archived).unwrap();
+    assert!(!loaded.is_bound());
+    assert!(unsafe { loaded.attach_verified(b"{}") }.is_none());
+
+    assert!(ArchivedOffsets::from_bytes(&[0xff; 3]).is_none());
+}
+
+#[test]
+fn read_scan_keys() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+        b"{\"a\\\"\":\"b,\\\"c\\\":\",\"d\":[{\"e\":1},\"]\"],\"f\":{}}",
+        b"{}",
+    ] {
+        let document = Document::scan_trusted(input);
+
+        let expected = document
+            .as_map()
+            .entries()
+            .map(|(k, _)| k.as_json())
+            .collect::<Vec<_>>();
+
+        let keys = Document::scan_keys(input).unwrap();
+
+        assert_eq!(
+            expected,
+            keys.iter().map(|k| k.as_json()).collect::<Vec<_>>()
+        );
+    }
+
+    let keys = Document::scan_keys(b"{\"a\\n\":1,\"b\":2}").unwrap();
+    assert_eq!("a\n", keys[0].to_unescaped());
+    assert_eq!("b", keys[1].to_unescaped());
+}
+
+#[test]
+fn read_contains_key_raw() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+    ] {
+        let document = Document::scan_trusted(input);
+
+        for (k, v) in document.as_map().entries() {
+            assert!(Document::contains_key_raw(input, &k.to_unescaped()));
+
+            // nested keys aren't at the root
+            if let Kind::Map(map) = v {
+                for (k, _) in map.entries() {
+                    let k = k.to_unescaped();
+
+                    if document.as_map().get(&k).is_none() {
+                        assert!(!Document::contains_key_raw(input, &k), "{}", k);
+                    }
+                }
+            }
+        }
+
+        assert!(!Document::contains_key_raw(input, "not a key"));
+    }
+
+    for (input, key, expected) in [
+        (b"{\"a\":1,\"b\":2}" as &[u8], "b", true),
+        (b"{\"a\":{\"b\":2}}", "b", false),
+        (b"{\"a\":[{\"b\":2}],\"b\":3}", "b", true),
+        (b"{\"a\":\"\\\"b\\\":1\"}", "b", false),
+        (b"{\"a\":\"\\\\\",\"b\":1}", "b", true),
+        (b"{\"a\":\"}\",\"b\":1}", "b", true),
+        (b"{\"a\\\"b\":1}", "a\"b", true),
+        (b"{\"a\":\"b\"}", "b", false),
+        (b"[\"a\":1]", "a", false),
+        (b"", "a", false),
+    ] {
+        assert_eq!(
+            expected,
+            Document::contains_key_raw(input, key),
+            "{:?} {}",
+            str::from_utf8(input).unwrap(),
+            key
+        );
+    }
+}
+
+#[test]
+fn read_check_scannable() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+        b"{\"a \\\"b\\\"\":\"c, d\\\\\",\"e\":[{\"f\":\"}\"}]}",
+        b"{}\n",
+    ] {
+        assert_eq!(Ok(()), Document::check_scannable(input));
+        assert!(Document::is_minified_object(input));
+        assert!(!Document::scan_trusted(input).is_err());
+    }
+}
+
+#[test]
+fn read_str() {
+    let input = include_str!("../../cases/10kb_event_stacktrace.json");
+    let expected: serde_json::Value = serde_json::from_str(input).unwrap();
+
+    let document = Document::scan_trusted_str(input);
+
+    assert_eq!(expected, document.to_value());
+
+    let document = unsafe { Document::scan_trusted_utf8_unchecked(input.as_bytes()) };
+
+    assert_eq!(expected, document.to_value());
+}
+
+#[test]
+fn read_raw_json() {
+    fn check(input: &[u8]) {
+        let document = Document::scan_trusted(input);
+
+        let root = document.as_map();
+        assert_eq!(str::from_utf8(input).unwrap().trim_end(), root.as_json());
+
+        // embed each raw value into a new map and make sure it's the same as the original
+        let mut raw = serde_json::Map::new();
+        for (k, v) in root.entries() {
+            raw.insert(
+                k.to_unescaped().into_owned(),
+                serde_json::to_value(v.to_raw_value().unwrap()).unwrap(),
+            );
+        }
+
+        assert_eq!(document.to_value(), serde_json::Value::Object(raw));
+    }
+
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+        b"{\"a\":[1,true,null,{\"b\":\"c\\n\"}],\"d\":{},\"e\":[],\"f\":\"\"}",
+    ] {
+        check(input);
+    }
+
+    for _ in 0..10 {
+        check(testing::arbitrary_object(&mut rand::thread_rng(), 10).as_bytes());
+    }
+}
+
+#[test]
+fn read_to_value_into() {
+    let inputs = [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        b"{\"a\":[1,\"b\",{\"c\":\"d\\n\"}],\"e\":{},\"f\":\"\"}",
+        b"{}",
+    ];
+
+    let mut map = serde_json::Map::new();
+    let mut pooled = serde_json::Map::new();
+    let mut pool = StringPool::new();
+
+    for input in inputs.iter().chain(inputs.iter()) {
+        let document = Document::scan_trusted(input);
+        let expected = document.to_value();
+
+        document.to_value_into(&mut map);
+        assert_eq!(expected, serde_json::Value::Object(map.clone()));
+
+        document.to_value_into_pooled(&mut pooled, &mut pool);
+        assert_eq!(expected, serde_json::Value::Object(pooled.clone()));
+    }
+
+    // strings from the last document are returned to the pool
+    Document::scan_trusted(b"{\"a\":\"b\",\"c\":1}").to_value_into_pooled(&mut pooled, &mut pool);
+    let len = pool.len();
+
+    Document::scan_trusted(b"{}").to_value_into_pooled(&mut pooled, &mut pool);
+    assert!(pooled.is_empty());
+    assert_eq!(len + 3, pool.len());
+}
+
+#[test]
+fn read_conversions() {
+    let input = "{\"a\":[1,\"b\",{\"c\":\"d\\n\"}],\"e\":{}}";
+    let expected = json!({"a": [1, "b", {"c": "d\n"}], "e": {}});
+
+    let document = Document::try_from(input.as_bytes()).unwrap();
+    assert_eq!(expected, serde_json::Value::from(&document));
+
+    let owned = input.parse::<OwnedDocument>().unwrap();
+    assert_eq!(input.as_bytes(), owned.input());
+    assert_eq!(expected, owned.as_document().to_value());
+
+    // owned documents can be moved without needing to scan them again
+    let owned = Box::new(owned);
+    assert_eq!(expected, owned.clone().as_document().to_value());
+}
+
+#[test]
+fn read_owned_document_static() {
+    fn assert_static<T: Send + Sync + 'static>(_: &T) {}
+
+    let owned = "{\"a\":1}".parse::<OwnedDocument>().unwrap();
+    assert_static(&owned);
+
+    // owned documents can be stored and sent without their original buffer
+    let mut documents = std::collections::HashMap::new();
+    documents.insert("a", owned);
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || tx.send(documents.remove("a").unwrap()).unwrap())
+        .join()
+        .unwrap();
+
+    assert_eq!(json!({"a": 1}), rx.recv().unwrap().as_document().to_value());
+}
+
+#[test]
+fn read_str_eq() {
+    let input = b"{\"@m\":1,\"a\\nb\":2,\"\\u58c1\\ud83d\\ude04\\/\":3,\"\\q\":4}";
+    let document = Document::scan_trusted(input);
+
+    let keys: Vec<_> = document.as_map().entries().map(|(k, _)| k).collect();
+
+    assert!(keys[0] == "@m");
+    assert!("@m" == keys[0]);
+    assert_eq!("@m", &*keys[0]);
+    assert_eq!("@m", keys[0].as_ref());
+    assert!(keys[0] != "@m2");
+
+    // escaped strings are compared and displayed unescaped, but deref to their raw form
+    assert!(keys[1] == "a\nb");
+    assert!(keys[1] != "a\nbc");
+    assert!(keys[1] != "a\n");
+    assert_eq!("a\\nb", &*keys[1]);
+    assert_eq!("a\nb", keys[1].to_string());
+
+    assert!(keys[2].eq_unescaped("壁😄/"));
+    assert!(!keys[2].eq_unescaped("壁😄"));
+
+    // invalid escapes are compared the same way they're unescaped
+    assert!(keys[3].eq_unescaped(&keys[3].to_unescaped()));
+}
+
+#[test]
+fn read_json_cmp() {
+    use std::cmp::Ordering;
+
+    let input = b"{\"a\":[null,false,true,-1e2,-3,0,-0.0,1,1.0,1.5,170141183460469231731687303715884105727,1e300,\"\",\"a\",\"a\\u0062\",\"ac\",[],[1],[1,2],[2],{},{\"a\":1},{\"a\":2},{\"a\":2,\"b\":1},{\"b\":0}]}";
+    let document = Document::scan_trusted(input);
+
+    let elems: Vec<_> = match document.as_map().entries().next() {
+        Some((_, Kind::Arr(arr))) => arr.iter().collect(),
+        other => panic!("unexpected {:?}", other),
+    };
+
+    // each element is ordered after the ones before it, except numerically equal numbers
+    let equal = [(5, 6), (7, 8)];
+    for (i, a) in elems.iter().enumerate() {
+        for (j, b) in elems.iter().enumerate() {
+            let expected = if equal.contains(&(i, j)) || equal.contains(&(j, i)) {
+                Ordering::Equal
+            } else {
+                i.cmp(&j)
+            };
+
+            assert_eq!(
+                expected,
+                a.json_cmp(b),
+                "{} cmp {}",
+                a.as_json(),
+                b.as_json()
+            );
+        }
+    }
+}
+
+#[test]
+fn read_map_get() {
+    let input = b"{\"s\":\"a\\nb\",\"i\":-42,\"f\":1.5e1,\"b\":true,\"m\":{\"a\":1},\"a\":[1,2],\"n\":null,\"\\u0073\":\"dup\"}";
+    let document = Document::scan_trusted(input);
+    let map = document.as_map();
+
+    assert!(matches!(map.get("n"), Some(Kind::Null)));
+    assert!(map.get("missing").is_none());
+
+    // the first entry with a key is used, and keys are compared unescaped
+    assert_eq!("a\nb", map.get_str("s").unwrap().to_unescaped());
+
+    assert_eq!(Some(-42), map.get_i64("i"));
+    assert_eq!(Some(-42.0), map.get_f64("i"));
+    assert_eq!(Some(15.0), map.get_f64("f"));
+    assert_eq!(None, map.get_i64("f"));
+    assert_eq!(Some(true), map.get_bool("b"));
+    assert_eq!(Some(1), map.get_map("m").unwrap().get_i64("a"));
+    assert_eq!(2, map.get_arr("a").unwrap().len());
+
+    // values of the wrong kind aren't returned
+    assert!(map.get_str("i").is_none());
+    assert!(map.get_bool("s").is_none());
+    assert!(map.get_map("a").is_none());
+    assert!(map.get_arr("m").is_none());
+}
+
+#[test]
+fn read_map_extract() {
+    let input = b"{\"@t\":\"2021-01-01\",\"n\":42,\"@\\u006c\":\"Warning\",\"n\":43}";
+    let document = Document::scan_trusted(input);
+
+    let [t, l, n, missing] = document.as_map().extract(["@t", "@l", "n", "missing"]);
+
+    assert_eq!("2021-01-01", t.and_then(|t| t.as_str()).unwrap().as_raw());
+    assert_eq!("Warning", l.and_then(|l| l.as_str()).unwrap().as_raw());
+    assert_eq!("42", n.unwrap().as_json());
+    assert!(missing.is_none());
+
+    let [] = document.as_map().extract([]);
+}
+
+#[test]
+fn read_project() {
+    use crate::de::{FromDocument as _, Str};
+    use squirrel_json_derive::FromDocument;
+
+    #[derive(Debug, FromDocument)]
+    struct Event<'a> {
+        #[sqj(key = "@t")]
+        timestamp: &'a str,
+        #[sqj(key = "@m")]
+        message: Cow<'a, str>,
+        #[sqj(key = "@l")]
+        level: Option<Str<'a>>,
+        count: u64,
+        elapsed: f64,
+        ok: bool,
+        missing: Option<i32>,
+    }
+
+    #[derive(Debug, PartialEq, FromDocument)]
+    struct Owned {
+        #[sqj(key = "@m")]
+        message: String,
+    }
+
+    let document = Document::scan_trusted(b"{\"@t\":\"2021-01-01\",\"@m\":\"a\\nb\",\"count\":42,\"elapsed\":1.5,\"ok\":true,\"extra\":[1]}");
+
+    let event: Event = document.project().unwrap();
+
+    assert_eq!("2021-01-01", event.timestamp);
+    assert_eq!("a\nb", event.message);
+    assert!(event.level.is_none());
+    assert_eq!(42, event.count);
+    assert_eq!(1.5, event.elapsed);
+    assert!(event.ok);
+    assert_eq!(None, event.missing);
+
+    // structs without a lifetime don't borrow from the document
+    let owned = {
+        let document = Document::scan_trusted(b"{\"@m\":\"message\"}");
+
+        Owned::from_map(&document.as_map()).unwrap()
+    };
+
+    assert_eq!(
+        Owned {
+            message: "message".to_owned()
+        },
+        owned
+    );
+}
+
+#[test]
+fn read_try_from_kind() {
+    use crate::de::{ConversionError, KindTag, Map};
+
+    fn get<'a, 'b, T>(map: &Map<'a, 'b>, key: &str) -> Result<T, ConversionError>
+    where
+        T: TryFrom<Kind<'a, 'b>, Error = ConversionError>,
+    {
+        map.get(key).unwrap().try_into()
+    }
+
+    let document = Document::scan_trusted(
+        b"{\"i\":-42,\"u\":18446744073709551615,\"f\":1.5,\"b\":true,\"s\":\"a\",\"e\":\"a\\nb\",\"m\":{}}",
+    );
+    let map = document.as_map();
+
+    assert_eq!(-42, get::<i64>(&map, "i").unwrap());
+    assert_eq!(u64::MAX, get::<u64>(&map, "u").unwrap());
+    assert_eq!(1.5, get::<f64>(&map, "f").unwrap());
+    assert!(get::<bool>(&map, "b").unwrap());
+    assert_eq!("a", get::<&str>(&map, "s").unwrap());
+    assert_eq!("a\nb", get::<String>(&map, "e").unwrap());
+
+    // escaped strings can't be borrowed
+    assert_eq!(KindTag::Str, get::<&str>(&map, "e").unwrap_err().kind());
+
+    assert_eq!(KindTag::Num, get::<i64>(&map, "u").unwrap_err().kind());
+    assert_eq!(KindTag::Num, get::<bool>(&map, "i").unwrap_err().kind());
+    assert_eq!(KindTag::Map, get::<String>(&map, "m").unwrap_err().kind());
+}
+
+#[test]
+fn read_cow_document() {
+    let input = "{\"a\":[1,\"b\",{\"c\":\"d\\n\"}],\"e\":{}}";
+    let expected = json!({"a": [1, "b", {"c": "d\n"}], "e": {}});
+
+    let borrowed = CowDocument::scan_trusted(input.as_bytes());
+    assert!(borrowed.is_borrowed());
+    assert_eq!(expected, borrowed.as_document().to_value());
+
+    // detaching a borrowed document copies its input without scanning it again
+    let detached: CowDocument<'static> = borrowed.into_owned();
+    assert!(!detached.is_borrowed());
+    assert_eq!(input.as_bytes(), detached.input());
+    assert_eq!(expected, detached.as_document().to_value());
+
+    let owned = CowDocument::scan_trusted(input.as_bytes().to_vec());
+    assert!(!owned.is_borrowed());
+    assert_eq!(expected, owned.as_document().to_value());
+
+    let owned = CowDocument::from(input.parse::<OwnedDocument>().unwrap());
+    assert!(!owned.is_borrowed());
+    assert_eq!(expected, owned.as_document().to_value());
+}
+
+#[test]
+fn read_eq_value() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        b"{\"a\":[1,\"b\",{\"c\":\"d\\n\"}],\"e\":{},\"f\":1.5}",
+        b"{\"a\":1,\"a\":2}",
+        b"{}",
+    ] {
+        let document = Document::scan_trusted(input);
+        let value = document.to_value();
+
+        assert_eq!(document, value);
+        assert_eq!(value, document);
+    }
+
+    let document = Document::scan_trusted(b"{\"a\":[1,\"b\",{\"c\":null}],\"d\":true}");
+
+    assert_eq!(document, json!({"a": [1, "b", {"c": null}], "d": true}));
+    assert_ne!(document, json!({"a": [1, "b", {"c": null}]}));
+    assert_ne!(
+        document,
+        json!({"a": [1, "b", {"c": null}], "d": true, "e": 1})
+    );
+    assert_ne!(document, json!({"a": [1, "b", {"c": false}], "d": true}));
+    assert_ne!(document, json!({"a": [1.0, "b", {"c": null}], "d": true}));
+    assert_ne!(document, json!({"a": [1, "b"], "d": true}));
+    assert_ne!(document, json!([]));
+
+    // duplicate keys are compared using their last value
+    let document = Document::scan_trusted(b"{\"a\":1,\"a\":2}");
+
+    assert_eq!(document, json!({"a": 2}));
+    assert_ne!(document, json!({"a": 1}));
+    assert_ne!(document, json!({"a": 2, "b": 1}));
+}
+
+#[test]
+fn read_raw_entries() {
+    let input =
+        b"{\"a\":\"b\\n\",\"c\":-1.5e3,\"d\":[1,{\"e\":null}],\"f\":{},\"g\":true,\"h\":null}";
+
+    let document = Document::scan_trusted(input);
+
+    let root = document.as_map();
+    let entries = root
+        .raw_entries()
+        .map(|(k, v)| (k.as_raw(), v))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        vec![
+            ("a", "\"b\\n\""),
+            ("c", "-1.5e3"),
+            ("d", "[1,{\"e\":null}]"),
+            ("f", "{}"),
+            ("g", "true"),
+            ("h", "null"),
+        ],
+        entries
+    );
+}
+
+#[test]
+fn read_raw_json_err() {
+    let document = Document::scan_trusted(b"{\"a\":");
+
+    assert_eq!("{}", document.as_map().as_json());
+}
+
+#[test]
+fn read_display() {
+    let input = b"{\"a\":[1,true,null,{\"b\":\"c\\n\"}],\"d\":{},\"e\":-1.5,\"f\":\"\"}";
+    let document = Document::scan_trusted(input);
+
+    for (expected, key) in [
+        ("[1,true,null,{\"b\":\"c\\n\"}]", "a"),
+        ("{}", "d"),
+        ("-1.5", "e"),
+        ("\"\"", "f"),
+    ] {
+        assert_eq!(expected, document.as_map().get(key).unwrap().to_string());
+    }
+
+    assert_eq!(
+        str::from_utf8(input).unwrap(),
+        document.as_map().to_string()
+    );
+
+    // maps and arrays without a raw span are written from their contents
+    let document = Document::scan_trusted_with(
+        b"{\"a\":[1,{\"b\":\"c\\n\",\"d\":[true",
+        &ScanOptions::new().recover(true),
+    );
+    assert_eq!(
+        "[1,{\"b\":\"c\\n\",\"d\":[true]}]",
+        document.as_map().get("a").unwrap().to_string()
+    );
+}
+
+#[test]
+fn read_dump() {
+    let input = b"{\"a\":[1,true,null,{\"b\":\"c\\n\"}],\"d\":{},\"e\":[],\"f\":{\"g\":[2]}}";
+
+    assert_eq!(
+        r#"{
+  "a": [
+    1,
+    true,
+    null,
+    {
+      "b": "c\n"
+    }
+  ],
+  "d": {},
+  "e": [],
+  "f": {
+    "g": [
+      2
+    ]
+  }
+}"#,
+        Document::scan_trusted(input).dump()
+    );
+
+    assert_eq!(
+        r#"{
+  "a": [1,true,null,{"b":"c\n"}] /* lazy */,
+  "d": {},
+  "e": [],
+  "f": {"g":[2]} /* lazy */
+}"#,
+        Document::scan_trusted_with(input, &ScanOptions::new().lazy_depth(0)).dump()
+    );
+
+    assert_eq!(
+        "/* erroneous */\n{}",
+        Document::scan_trusted(b"{\"a\":").dump()
+    );
+    assert_eq!(
+        "/* erroneous, partial */\n{\n  \"a\": 1\n}",
+        Document::scan_trusted_with(b"{\"a\":1,\"b\":", &ScanOptions::new().keep_partial(true))
+            .dump()
+    );
+}
+
+#[test]
+fn deserialize_value() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+    ] {
+        let document = Document::scan_trusted(input);
+
+        let value: serde_json::Value = document.deserialize().unwrap();
+
+        assert_eq!(document.to_value(), value);
+    }
+}
+
+#[test]
+fn deserialize_struct() {
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Event<'a> {
+        #[serde(rename = "@t")]
+        timestamp: &'a str,
+        #[serde(rename = "@m")]
+        message: String,
+        level: Option<Level>,
+        count: u8,
+        elapsed: f64,
+        offset: i32,
+        tags: Vec<&'a str>,
+        props: Props,
+        missing: Option<u64>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Level {
+        Warning,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Props {
+        Http { status: u16 },
+    }
+
+    let input = b"{\"@t\":\"2021-01-01\",\"@m\":\"a \\\"message\\\"\",\"level\":\"Warning\",\"count\":42,\"elapsed\":1.5e3,\"offset\":-1,\"tags\":[\"a\",\"b\"],\"props\":{\"Http\":{\"status\":200}},\"missing\":null,\"ignored\":[{}]}";
+
+    let document = Document::scan_trusted(input);
+
+    let event: Event = document.deserialize().unwrap();
+
+    assert_eq!(
+        Event {
+            timestamp: "2021-01-01",
+            message: "a \"message\"".to_owned(),
+            level: Some(Level::Warning),
+            count: 42,
+            elapsed: 1500.0,
+            offset: -1,
+            tags: vec!["a", "b"],
+            props: Props::Http { status: 200 },
+            missing: None,
+        },
+        event
+    );
+}
+
+#[test]
+fn deserialize_escaped_str() {
+    use serde::{de, Deserialize};
+
+    // a type that only needs its string by reference
+    #[derive(Debug, PartialEq)]
+    struct Len(usize);
+
+    impl<'de> Deserialize<'de> for Len {
+        fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct Visitor;
+
+            impl<'de> de::Visitor<'de> for Visitor {
+                type Value = Len;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("a string")
+                }
+
+                fn visit_str<E: de::Error>(self, v: &str) -> Result<Len, E> {
+                    Ok(Len(v.len()))
+                }
+            }
+
+            deserializer.deserialize_str(Visitor)
+        }
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Event {
+        #[serde(rename = "@m\n")]
+        message: String,
+        short: Len,
+        long: Len,
+        level: Level,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Level {
+        #[serde(rename = "\"Warning\"")]
+        Warning,
+    }
+
+    let long = "\\n".repeat(20 * 1024);
+    let input = format!(
+        "{{\"@m\\n\":\"a\\tb\",\"\\u0073hort\":\"c\\td\",\"long\":\"{}\",\"level\":\"\\\"Warning\\\"\"}}",
+        long
+    );
+
+    let document = Document::scan_trusted(input.as_bytes());
+
+    let event: Event = document.deserialize().unwrap();
+
+    assert_eq!(
+        Event {
+            message: "a\tb".to_owned(),
+            short: Len(3),
+            long: Len(20 * 1024),
+            level: Level::Warning,
+        },
+        event
+    );
+}
+
+#[test]
+fn deserialize_seed() {
+    use serde::de::{Deserialize, DeserializeSeed, Deserializer};
+    use std::marker::PhantomData;
+
+    // a seed that sums into a shared total rather than allocating
+    #[derive(Clone, Copy)]
+    struct Sum<'a>(&'a std::cell::Cell<u64>);
+
+    impl<'a, 'de> DeserializeSeed<'de> for Sum<'a> {
+        type Value = ();
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+            let n = u64::deserialize(deserializer)?;
+            self.0.set(self.0.get() + n);
+
+            Ok(())
+        }
+    }
+
+    let document = Document::scan_trusted(b"{\"a\":[1,2,3,4],\"b\":\"value\"}");
+    let map = document.as_map();
+
+    let total = std::cell::Cell::new(0);
+    for (_, v) in map.entries() {
+        if let Kind::Arr(arr) = v {
+            for r in arr.iter_seed(Sum(&total)) {
+                r.unwrap();
+            }
+        }
+    }
+    assert_eq!(10, total.get());
+
+    assert_eq!(
+        Some("value"),
+        map.deserialize_entry_seed("b", PhantomData::<&str>)
+            .unwrap()
+    );
+    assert_eq!(
+        None,
+        map.deserialize_entry_seed("c", PhantomData::<&str>)
+            .unwrap()
+    );
+}
+
+#[test]
+fn serialize() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+    ] {
+        let document = Document::scan_trusted(input);
+
+        let pretty = serde_json::to_string_pretty(&document).unwrap();
+
+        assert_eq!(
+            document.to_value(),
+            serde_json::from_str::<serde_json::Value>(&pretty).unwrap()
+        );
+    }
+
+    // entries are serialized in the order they appear in the document
+    let document = Document::scan_trusted(b"{\"b\":[1,-1,1.5],\"a\":{\"d\\n\":null,\"c\":true}}");
+
+    assert_eq!(
+        "{\"b\":[1,-1,1.5],\"a\":{\"d\\n\":null,\"c\":true}}",
+        serde_json::to_string(&document).unwrap()
+    );
+}
+
+#[test]
+fn deserialize_into_deserializer() {
+    use serde::de::{Deserialize, IntoDeserializer};
+
+    // generic code that only knows about `IntoDeserializer`
+    fn from<'de, T: Deserialize<'de>>(v: impl IntoDeserializer<'de, serde::de::value::Error>) -> T {
+        T::deserialize(v.into_deserializer()).unwrap()
+    }
+
+    let document = Document::scan_trusted(b"{\"a\":[1,2,3],\"b\":\"value\"}");
+
+    let value: serde_json::Value = from(&document);
+    assert_eq!(document.to_value(), value);
+
+    let (_, a) = document.as_map().entries().next().unwrap();
+    let a: Vec<u8> = from(a);
+    assert_eq!(vec![1, 2, 3], a);
+
+    let b: &str = from(document.as_map().entries().nth(1).unwrap().1);
+    assert_eq!("value", b);
+}
+
+#[test]
+fn to_value_duplicate_keys() {
+    let document = Document::scan_trusted(
+        b"{\"a\":1,\"b\":[1],\"a\":2,\"c\":{\"d\":true,\"d\":false},\"b\":[2],\"a\":3}",
+    );
+
+    assert_eq!(
+        json!({"a": 1, "b": [1], "c": {"d": true}}),
+        document.to_value_with(DuplicateKeys::FirstWins).unwrap()
+    );
+    assert_eq!(
+        document.to_value(),
+        document.to_value_with(DuplicateKeys::LastWins).unwrap()
+    );
+    assert_eq!(
+        json!({"a": [1, 2, 3], "b": [[1], [2]], "c": {"d": [true, false]}}),
+        document
+            .to_value_with(DuplicateKeys::CollectIntoArray)
+            .unwrap()
+    );
+    assert_eq!(
+        "a",
+        document
+            .to_value_with(DuplicateKeys::Error)
+            .unwrap_err()
+            .key()
+    );
+
+    // documents without duplicates are the same for any policy
+    let document = Document::scan_trusted(b"{\"a\":[1],\"b\":{\"c\":null}}");
+
+    for duplicates in [
+        DuplicateKeys::FirstWins,
+        DuplicateKeys::LastWins,
+        DuplicateKeys::CollectIntoArray,
+        DuplicateKeys::Error,
+    ] {
+        assert_eq!(
+            document.to_value(),
+            document.to_value_with(duplicates).unwrap()
+        );
+    }
+}
+
+#[test]
+#[cfg(not(feature = "arbitrary_precision"))]
+fn to_value_numbers() {
+    let document = Document::scan_trusted(
+        b"{\"a\":1,\"b\":-2.5,\"c\":12345678901234567890123,\"d\":0.1234567890123456789,\"e\":1e400,\"f\":1.50e-3,\"g\":[18446744073709551615,1e-320]}",
+    );
+
+    assert_eq!(
+        document.to_value(),
+        document.to_value_with_numbers(Numbers::Lossy)
+    );
+    assert_eq!(
+        json!({"a": 1, "b": -2.5, "c": 1.2345678901234568e22, "d": 0.12345678901234568, "e": "1e400", "f": 0.0015, "g": [18446744073709551615u64, 1e-320]}),
+        document.to_value_with_numbers(Numbers::Lossy)
+    );
+
+    assert_eq!(
+        json!({"a": 1, "b": -2.5, "c": "12345678901234567890123", "d": "0.1234567890123456789", "e": "1e400", "f": 0.0015, "g": [18446744073709551615u64, "1e-320"]}),
+        document.to_value_with_numbers(Numbers::RawString)
+    );
+}
+
+#[test]
+#[cfg(feature = "arbitrary_precision")]
+fn to_value_numbers_arbitrary_precision() {
+    let input = "{\"a\":1,\"c\":12345678901234567890123,\"d\":0.1234567890123456789,\"e\":1e+400}";
+    let document = Document::scan_trusted(input.as_bytes());
+
+    assert_eq!(
+        input,
+        document
+            .to_value_with_numbers(Numbers::ArbitraryPrecision)
+            .to_string()
+    );
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn to_value_preserve_order() {
+    let document = Document::scan_trusted(
+        b"{\"c\":1,\"a\":{\"z\":true,\"y\":null},\"b\":[{\"2\":2,\"1\":1}],\"a\":2}",
+    );
+
+    let value = document.to_value();
+
+    assert_eq!(
+        "{\"c\":1,\"a\":2,\"b\":[{\"2\":2,\"1\":1}]}",
+        serde_json::to_string(&value).unwrap()
+    );
+}
+
+#[test]
+fn transcode() {
+    let input = b"{\"a\":1,\"b\":-1,\"c\":300,\"d\":-200,\"e\":1.5,\"f\":[true,false,null],\"g\":{\"h\":\"\\u00e9\"}}";
+
+    let document = Document::scan_trusted(input);
+
+    assert_eq!(
+        vec![
+            0x87, 0xa1, b'a', 0x01, 0xa1, b'b', 0xff, 0xa1, b'c', 0xcd, 0x01, 0x2c, 0xa1, b'd',
+            0xd1, 0xff, 0x38, 0xa1, b'e', 0xcb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0, 0xa1, b'f', 0x93,
+            0xc3, 0xc2, 0xc0, 0xa1, b'g', 0x81, 0xa1, b'h', 0xa2, 0xc3, 0xa9,
+        ],
+        document.transcode(BinaryFormat::MessagePack)
+    );
+
+    assert_eq!(
+        vec![
+            0xa7, 0x61, b'a', 0x01, 0x61, b'b', 0x20, 0x61, b'c', 0x19, 0x01, 0x2c, 0x61, b'd',
+            0x38, 0xc7, 0x61, b'e', 0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0, 0x61, b'f', 0x83, 0xf5,
+            0xf4, 0xf6, 0x61, b'g', 0xa1, 0x61, b'h', 0x62, 0xc3, 0xa9,
+        ],
+        document.transcode(BinaryFormat::Cbor)
+    );
+}
+
+#[test]
+fn transcode_lengths() {
+    // lengths that don't fit in the type byte are written after it
+    let long = "a".repeat(40);
+    let input = format!("{{\"{}\":[{}]}}", long, vec!["null"; 300].join(","));
+
+    let document = Document::scan_trusted(input.as_bytes());
+
+    let msgpack = document.transcode(BinaryFormat::MessagePack);
+    assert_eq!([0x81, 0xd9, 40], msgpack[..3]);
+    assert_eq!([0xdc, 0x01, 0x2c], msgpack[43..46]);
+    assert_eq!(46 + 300, msgpack.len());
+
+    let cbor = document.transcode(BinaryFormat::Cbor);
+    assert_eq!([0xa1, 0x78, 40], cbor[..3]);
+    assert_eq!([0x99, 0x01, 0x2c], cbor[43..46]);
+    assert_eq!(46 + 300, cbor.len());
+}
+
+#[test]
+fn read_empty() {
+    let document = Document::scan_trusted_fallback(b"");
+
+    assert_eq!(json!({}), document.to_value());
+}
+
+#[test]
+fn read_empty_map() {
+    let document = Document::scan_trusted_fallback(b"{}");
+
+    assert_eq!(json!({}), document.to_value());
+}
+
+#[test]
+fn read_map_with_trailing_num() {
+    let expected = json!({
+        "a": 123
+    });
+
+    let document = Document::scan_trusted_fallback(b"{\"a\":123}");
+
+    assert_eq!(expected, document.to_value());
+}
+
+#[test]
+fn read_arr_of_empty_maps() {
+    let expected = json!({
+        "a": [{},{},{}]
+    });
+
+    let document = Document::scan_trusted_fallback(b"{\"a\":[{},{},{}]}");
+
+    assert_eq!(expected, document.to_value());
+}
+
+#[test]
+fn read_arr_of_numbers() {
+    let expected = json!({
+        "a": [
+            34785u64,
+            78234.2f64,
+        ]
+    });
+
+    let document = Document::scan_trusted_fallback(b"{\"a\":[34785,78234.2]}");
+
+    assert_eq!(expected, document.to_value());
+}
+
+#[test]
+fn read_long_arr_of_numbers() {
+    let mut input = String::from("{\"a\":[");
+    for i in 0..2000 {
+        if i > 0 {
+            input.push(',');
+        }
+
+        input.push_str(&(i as f64 * -1234.5678).to_string());
+    }
+    input.push_str("]}");
+
+    let expected: serde_json::Value = serde_json::from_str(&input).unwrap();
+
+    for backend in [Backend::Auto, Backend::Scalar, Backend::Simd] {
+        let document = Document::scan_trusted_backend(input.as_bytes(), backend);
+
+        assert_eq!(expected, document.to_value(), "{:?}", backend);
+    }
+}
+
+#[test]
+fn read_lens() {
+    let document = Document::scan_trusted(b"{\"a\":{\"b\":1,\"c\":{}},\"d\":[1,[],{\"e\":null}]}");
+
+    let map = document.as_map();
+    assert_eq!(2, map.len());
+    assert!(!map.has_dangling_key());
+
+    let mut entries = map.entries();
+
+    match entries.next() {
+        Some((_, Kind::Map(a))) => {
+            assert_eq!(2, a.len());
+            assert!(!a.has_dangling_key());
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    match entries.next() {
+        Some((_, Kind::Arr(d))) => {
+            assert_eq!(3, d.len());
+
+            let lens: Vec<_> = d
+                .iter()
+                .map(|elem| match elem {
+                    Kind::Map(map) => map.len(),
+                    Kind::Arr(arr) => arr.len(),
+                    _ => 0,
+                })
+                .collect();
+
+            assert_eq!(vec![0, 0, 1], lens);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn read_find_all() {
+    let input = b"{\"RequestId\":1,\"a\":{\"RequestId\":\"b\",\"c/d~\":[{\"RequestId\":[]},{\"Request\\u0049d\":null}]}}";
+    let expected: serde_json::Value = serde_json::from_slice(input).unwrap();
+
+    let document = Document::scan_trusted(input);
+
+    let found: Vec<_> = document
+        .find_all("RequestId")
+        .map(|(path, value)| (path.to_string(), value.as_json()))
+        .collect();
+
+    assert_eq!(
+        vec![
+            ("/RequestId".to_owned(), "1"),
+            ("/a/RequestId".to_owned(), "\"b\""),
+            ("/a/c~1d~0/0/RequestId".to_owned(), "[]"),
+            ("/a/c~1d~0/1/RequestId".to_owned(), "null"),
+        ],
+        found
+    );
+
+    for (path, _) in found {
+        assert!(expected.pointer(&path).is_some());
+    }
+
+    let joined: Vec<_> = document
+        .find_all("RequestId")
+        .map(|(path, _)| path.join("."))
+        .collect();
+
+    assert_eq!("a.c/d~.0.RequestId", joined[2]);
+}
+
+#[test]
+fn read_kind_of() {
+    let input = b"{\"a\":{\"b\":[1,\"c\",{\"d\\n\":null}],\"e\":{}},\"f\":true,\"a\":1,\"g\":[]}";
+
+    let document = Document::scan_trusted(input);
+
+    for (path, expected) in [
+        ("", Some(KindTag::Map)),
+        ("a", Some(KindTag::Map)),
+        ("a.b", Some(KindTag::Arr)),
+        ("a.b.0", Some(KindTag::Num)),
+        ("a.b.1", Some(KindTag::Str)),
+        ("a.b.2", Some(KindTag::Map)),
+        ("a.b.2.d\n", Some(KindTag::Null)),
+        ("a.e", Some(KindTag::Map)),
+        ("f", Some(KindTag::Bool)),
+        ("g", Some(KindTag::Arr)),
+        ("a.b.3", None),
+        ("a.b.x", None),
+        ("a.e.x", None),
+        ("g.0", None),
+        ("f.x", None),
+        ("x", None),
+        ("a.b.2.d", None),
+    ] {
+        assert_eq!(expected, document.kind_of(path), "{}", path);
+    }
+}
+
+#[test]
+fn read_select() {
+    let input = b"{\"a\":{\"b\":[1,\"c\",{\"d\\n\":null}],\"e\":{}},\"f\":true,\"a\":1,\"g\":[]}";
+
+    let document = Document::scan_trusted(input);
+
+    let paths = [
+        "a.b.2.d\n",
+        "f",
+        "",
+        "a.b.0",
+        "a.b.01",
+        "a",
+        "a.b.3",
+        "a.e",
+        "x",
+        "a.b.1",
+        "f.x",
+        "a.b.0",
+        "g.0",
+    ];
+
+    let selected = document.select(&paths.map(de::Path::new));
+
+    assert_eq!(paths.len(), selected.len());
+
+    for (path, selected) in paths.iter().zip(selected) {
+        assert_eq!(
+            document.kind_of(path).is_some(),
+            selected.is_some(),
+            "{}",
+            path
+        );
+    }
+
+    let json: Vec<_> = document
+        .select(&paths.map(de::Path::new))
+        .into_iter()
+        .map(|selected| selected.map(|selected| selected.as_json()))
+        .collect();
+
+    assert_eq!(
+        vec![
+            Some("null"),
+            Some("true"),
+            Some(str::from_utf8(input).unwrap()),
+            Some("1"),
+            Some("\"c\""),
+            Some("{\"b\":[1,\"c\",{\"d\\n\":null}],\"e\":{}}"),
+            None,
+            Some("{}"),
+            None,
+            Some("\"c\""),
+            None,
+            Some("1"),
+            None,
+        ],
+        json
+    );
+
+    assert!(document.select(&[]).is_empty());
+}
+
+#[test]
+fn read_flatten() {
+    let input = b"{\"a\":{\"b\":1,\"c\":[true,{\"d\":null},[]]},\"e\":{},\"f\":\"g\"}";
+
+    let document = Document::scan_trusted(input);
+
+    let flattened: Vec<_> = document
+        .flatten()
+        .map(|(path, value)| (path.to_string(), value.as_json()))
+        .collect();
+
+    assert_eq!(
+        vec![
+            ("/a/b".to_owned(), "1"),
+            ("/a/c/0".to_owned(), "true"),
+            ("/a/c/1/d".to_owned(), "null"),
+            ("/a/c/2".to_owned(), "[]"),
+            ("/e".to_owned(), "{}"),
+            ("/f".to_owned(), "\"g\""),
+        ],
+        flattened
+    );
+
+    let joined: Vec<_> = document.flatten_joined(".").map(|(path, _)| path).collect();
+
+    assert_eq!(vec!["a.b", "a.c.0", "a.c.1.d", "a.c.2", "e", "f"], joined);
+}
+
+#[test]
+fn read_cursor() {
+    let input = b"{\"@m\":\"failed\",\"@x\":{\"frames\":[{\"method\":\"a\",\"line\":1},{\"method\":\"b::fail\",\"line\":2}]}}";
+
+    let document = Document::scan_trusted(input);
+
+    let mut cursor = document.cursor();
+    assert!(cursor.is_root());
+    assert!(!cursor.to_parent());
+    assert!(!cursor.to_next_sibling());
+
+    // find the frame with a method containing `fail`
+    assert!(cursor.to_first_child());
+    assert_eq!("@m", cursor.key().unwrap().as_raw());
+    assert!(cursor.to_next_sibling());
+    assert_eq!("@x", cursor.key().unwrap().as_raw());
+    assert!(!cursor.to_next_sibling());
+    assert!(cursor.to_first_child());
+    assert_eq!("frames", cursor.key().unwrap().as_raw());
+    assert!(cursor.to_first_child());
+    assert_eq!(3, cursor.depth());
+    assert!(cursor.key().is_none());
+
+    let frame = loop {
+        assert!(cursor.to_first_child());
+
+        let found = loop {
+            if cursor.key().unwrap().as_raw() == "method"
+                && cursor.kind().as_json().contains("fail")
+            {
+                break true;
+            }
+
+            if !cursor.to_next_sibling() {
+                break false;
+            }
+        };
+
+        assert!(cursor.to_parent());
+
+        if found {
+            break cursor.kind().as_json();
+        }
+
+        assert!(cursor.to_next_sibling());
+    };
+
+    assert_eq!("{\"method\":\"b::fail\",\"line\":2}", frame);
+
+    assert!(cursor.to_parent());
+    assert!(cursor.to_parent());
+    assert!(cursor.to_parent());
+    assert!(cursor.is_root());
+    assert_eq!(input as &[u8], cursor.kind().as_json().as_bytes());
+}
+
+#[test]
+fn read_by_id() {
+    let input = b"{\"a\":{\"b\":1,\"c\":[true,{\"d\":null}]},\"e\":\"f\"}";
+
+    let document = Document::scan_trusted(input);
+
+    // remember some ids while walking the document
+    let mut ids = Vec::new();
+    for (id, key, value) in document.as_map().entries_with_ids() {
+        ids.push((id, value.as_json()));
+
+        if key.as_raw() == "a" {
+            if let Kind::Map(a) = value {
+                for (id, _, value) in a.entries_with_ids() {
+                    ids.push((id, value.as_json()));
+
+                    if let Kind::Arr(c) = value {
+                        for (id, value) in c.iter_with_ids() {
+                            ids.push((id, value.as_json()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    assert_eq!(6, ids.len());
+
+    // revisit them
+    for (id, json) in ids {
+        assert_eq!(json, document.get_by_id(id).unwrap().as_json());
+    }
+
+    let mut cursor = document.cursor();
+    assert!(cursor.id().is_none());
+    assert!(cursor.to_first_child());
+
+    let id = cursor.id().unwrap();
+    assert_eq!(
+        cursor.kind().as_json(),
+        document.get_by_id(id).unwrap().as_json()
+    );
+}
+
+#[test]
+fn read_element_positions() {
+    let input = b"{\"a\":[1,true],\"b\":{\"c\":null}}";
+
+    let document = Document::scan_trusted(input);
+
+    let mut cursor = document.cursor();
+    assert_eq!(None, cursor.position());
+
+    assert!(cursor.to_first_child());
+    assert_eq!(Some(RawPosition::Value), cursor.position());
+
+    assert!(cursor.to_first_child());
+    assert_eq!(Some(RawPosition::Elem), cursor.position());
+
+    // ids line up with the table of offsets
+    let map = document.as_map();
+
+    for (id, _, _) in map.entries_with_ids() {
+        assert_eq!(Some(RawPosition::Value), document.position_of(id));
+        assert_eq!(
+            Some(RawPosition::Value),
+            document.offsets().get(id.index()).unwrap().position()
+        );
+
+        // keys always immediately precede their values
+        assert_eq!(
+            Some(RawPosition::Key),
+            document.offsets().get(id.index() - 1).unwrap().position()
+        );
+    }
+
+    for (id, _) in map.get_arr("a").unwrap().iter_with_ids() {
+        assert_eq!(Some(RawPosition::Elem), document.position_of(id));
+    }
+}
+
+#[test]
+fn read_rev() {
+    fn check_map(map: &de::Map) {
+        let forward = map
+            .entries()
+            .map(|(k, v)| (k.as_raw(), v.as_json()))
+            .collect::<Vec<_>>();
+        let mut reverse = map
+            .entries_rev()
+            .map(|(k, v)| (k.as_raw(), v.as_json()))
+            .collect::<Vec<_>>();
+        reverse.reverse();
+
+        assert_eq!(forward, reverse);
+        assert_eq!(
+            forward.last().copied(),
+            map.last_entry().map(|(k, v)| (k.as_raw(), v.as_json()))
+        );
+
+        for (_, v) in map.entries() {
+            check_kind(&v);
+        }
+    }
+
+    fn check_kind(kind: &Kind) {
+        match kind {
+            Kind::Map(map) => check_map(map),
+            Kind::Arr(arr) => {
+                let forward = arr.iter().map(|v| v.as_json()).collect::<Vec<_>>();
+                let mut reverse = arr.iter_rev().map(|v| v.as_json()).collect::<Vec<_>>();
+                reverse.reverse();
+
+                assert_eq!(forward, reverse);
+
+                for v in arr.iter() {
+                    check_kind(&v);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        b"{}",
+        b"{\"a\":{},\"b\":[],\"c\":[1],\"d\":{\"e\":[[{\"f\":1}],2]}}",
+    ] {
+        for backend in [Backend::Scalar, Backend::Auto] {
+            let document = Document::scan_trusted_backend(input, backend);
+
+            check_map(&document.as_map());
+
+            let offsets = document.into_offsets();
+            let document = unsafe { offsets.to_document_unchecked(input) };
+
+            check_map(&document.as_map());
+        }
+    }
+
+    // maps with a trailing key without a value only have the entries with values
+    let document = Document::scan_trusted(b"{\"a\":1,\"b\":{\"c\":2,\"d\"},\"e\"}");
+
+    let map = document.as_map();
+    assert_eq!("b", map.last_entry().unwrap().0.as_raw());
+    assert_eq!(
+        vec!["c"],
+        map.get_map("b")
+            .unwrap()
+            .entries_rev()
+            .map(|(k, _)| k.as_raw())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn read_prefix() {
+    let input = "{\"a\":\"hello\",\"b\":{\"c\":[1,2,3],\"d\":true},\"e\":\"ö\"}";
+
+    for (max_bytes, expected) in [
+        (
+            1000,
+            json!({"a": "hello", "b": {"c": [1, 2, 3], "d": true}, "e": "ö"}),
+        ),
+        (
+            input.len(),
+            json!({"a": "hello", "b": {"c": [1, 2, 3], "d": true}, "e": "ö"}),
+        ),
+        (10, json!({})),
+        (13, json!({"a": "hello"})),
+        // the last byte isn't scanned, so a value before it may have been cut short
+        (27, json!({"a": "hello", "b": {"c": [1]}})),
+        (29, json!({"a": "hello", "b": {"c": [1, 2]}})),
+        (30, json!({"a": "hello", "b": {"c": [1, 2, 3]}})),
+        (39, json!({"a": "hello", "b": {"c": [1, 2, 3], "d": true}})),
+        // the cut-off point is in the middle of the `ö`
+        (
+            input.find('ö').unwrap() + 1,
+            json!({"a": "hello", "b": {"c": [1, 2, 3], "d": true}}),
+        ),
+    ] {
+        let document = Document::scan_prefix(input.as_bytes(), max_bytes);
+
+        assert_eq!(max_bytes < input.len(), document.is_truncated());
+        assert_eq!(document.is_truncated(), document.is_err());
+        assert_eq!(expected, document.to_value(), "{}", max_bytes);
+    }
+
+    // trailing whitespace doesn't need to fit
+    let document = Document::scan_prefix(b"{\"a\":1}\n", 7);
+    assert!(!document.is_truncated());
+    assert!(!document.is_err());
+
+    // every prefix of a document can be previewed
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+    ] {
+        let full = Document::scan_trusted(input).to_value();
+
+        let len = str::from_utf8(input).unwrap().trim_end().len();
+
+        for max_bytes in 0..len {
+            let document = Document::scan_prefix(input, max_bytes);
+
+            assert!(document.is_truncated());
+
+            // a lone `{` can't be scanned
+            if max_bytes > 1 {
+                assert!(document.is_partial());
+
+                // previews can always be repaired into valid documents
+                let repaired = document.repair().unwrap();
+                let preview = Document::scan_trusted(&repaired);
+
+                assert!(!preview.is_err());
+                assert!(repaired.len() <= input.len());
+            }
+        }
+
+        assert_eq!(full, Document::scan_prefix(input, len).to_value());
+    }
+}
+
+#[test]
+fn read_sub_document() {
+    let input =
+        b"{\"@m\":\"failed\",\"@x\":{\"type\":\"Error\",\"frames\":[{\"line\":1}]},\"e\":{}}";
+
+    let document = Document::scan_trusted(input);
+
+    let root = document.as_map();
+    let mut entries = root.entries();
+    entries.next();
+
+    let x = entries.next().unwrap().1.into_document().unwrap();
+
+    assert_eq!(
+        json!({"type": "Error", "frames": [{"line": 1}]}),
+        x.to_value()
+    );
+    assert_eq!(
+        "{\"type\":\"Error\",\"frames\":[{\"line\":1}]}",
+        x.as_map().as_json()
+    );
+    assert_eq!(1, x.find_all("line").count());
+
+    let mut cursor = x.cursor();
+    assert!(cursor.to_first_child());
+    assert_eq!("type", cursor.key().unwrap().as_raw());
+
+    let e = entries.next().unwrap().1.into_document().unwrap();
+
+    assert_eq!(json!({}), e.to_value());
+    assert_eq!("{}", e.as_map().as_json());
+
+    // documents can be re-rooted more than once
+    let frames = x.as_map().entries().nth(1).unwrap().1;
+    let frame = match frames {
+        Kind::Arr(frames) => frames.iter().next().unwrap(),
+        _ => panic!("expected an array"),
     };
 
-    for _ in 0..iterations {
-        // Check the parser against some randomly generated JSON data
-        // Fuzzing is good at finding bizarre and invalid almost-JSON
-        // but doesn't discover valid JSON very often. This approach
-        // stampedes with a bunch of valid combinations of JSON objects
-        // to ensure the parser is always correct for all correct JSON
-        // objects without internal whitespace
-        let input = some::json_object();
+    assert_eq!(
+        json!({"line": 1}),
+        frame.into_document().unwrap().to_value()
+    );
+}
 
-        let expected: serde_json::Value = match serde_json::from_str(&input) {
-            Ok(v) => v,
-            Err(e) => {
-                panic!("parsing `{}`: {}", input, e);
-            }
-        };
+#[test]
+fn rewrite_redact() {
+    let input = b"{\"user\":{\"email\":\"a@b.c\",\"name\":\"a\"},\"events\":[{\"password\":\"x\",\"n\":1},[]],\"pass\\u0077ord\":{\"nested\":true},\"email\":null}";
 
-        let document = Document::scan_trusted_fallback(input.as_bytes());
+    let document = Document::scan_trusted(input);
 
-        assert_eq!(expected, document.to_value());
+    let keys: KeySet = ["email", "password"].into_iter().collect();
 
-        // Check the document using the vectorized parser
-        test_alignment(input.as_bytes(), 32, |input| {
-            let document = Document::scan_trusted(input);
+    let redacted = document.redact(&keys);
+    assert_eq!(
+        "{\"user\":{\"name\":\"a\"},\"events\":[{\"n\":1},[]]}",
+        str::from_utf8(&redacted).unwrap()
+    );
 
-            assert_eq!(expected, document.to_value());
+    let redacted = document.redact(&keys.placeholder("\"***\""));
+    assert_eq!(
+        "{\"user\":{\"email\":\"***\",\"name\":\"a\"},\"events\":[{\"password\":\"***\",\"n\":1},[]],\"pass\\u0077ord\":\"***\",\"email\":\"***\"}",
+        str::from_utf8(&redacted).unwrap()
+    );
 
-            let offsets = document.into_offsets();
-            let document = unsafe { offsets.to_document_unchecked(input) };
+    // documents without any matching keys are unchanged
+    let redacted = document.redact(&KeySet::new().key("missing"));
+    assert_eq!(input as &[u8], &*redacted);
 
-            assert_eq!(expected, document.to_value());
-        });
+    // maps and arrays kept lazily are redacted too
+    for lazy_depth in [0, 1] {
+        let document = Document::scan_trusted_with(
+            b"{\"a\":{\"c\":{\"secret\":\"pw\"}},\"b\":[{\"secret\":1}]}",
+            &ScanOptions::new().lazy_depth(lazy_depth),
+        );
+
+        let redacted = document.redact(&KeySet::new().key("secret"));
+        assert_eq!(
+            "{\"a\":{\"c\":{}},\"b\":[{}]}",
+            str::from_utf8(&redacted).unwrap(),
+            "{}",
+            lazy_depth
+        );
+
+        let rewritten = document.rewrite_keys(|key| Cow::Owned(key.to_uppercase()));
+        assert_eq!(
+            "{\"A\":{\"C\":{\"SECRET\":\"pw\"}},\"B\":[{\"SECRET\":1}]}",
+            str::from_utf8(&rewritten).unwrap(),
+            "{}",
+            lazy_depth
+        );
     }
 }
 
 #[test]
-fn read_empty() {
-    let document = Document::scan_trusted_fallback(b"");
+fn rewrite_keys() {
+    let input = b"{\"user_id\":1,\"UserName\":{\"first_name\":\"a\\nb\"},\"tags\":[{\"Tag_Name\":\"c\"}],\"esc\\u0061ped\":null}";
 
-    assert_eq!(json!({}), document.to_value());
+    let document = Document::scan_trusted(input);
+
+    let rewritten = document.rewrite_keys(|key| {
+        let key = key.to_unescaped();
+
+        if key.contains('_') || key.chars().any(|c| c.is_uppercase()) {
+            Cow::Owned(key.replace('_', "").to_lowercase())
+        } else {
+            key
+        }
+    });
+
+    assert_eq!(
+        "{\"userid\":1,\"username\":{\"firstname\":\"a\\nb\"},\"tags\":[{\"tagname\":\"c\"}],\"esc\\u0061ped\":null}",
+        str::from_utf8(&rewritten).unwrap()
+    );
+
+    // keys are escaped when they're written
+    let rewritten = document.rewrite_keys(|key| Cow::Owned(format!("\"{}\\\n", key.as_raw())));
+    let expected: serde_json::Value = serde_json::from_slice(&rewritten).unwrap();
+
+    assert_eq!(json!(1), expected["\"user_id\\\n"]);
 }
 
 #[test]
-fn read_empty_map() {
-    let document = Document::scan_trusted_fallback(b"{}");
+fn rewrite_merge() {
+    let defaults = Document::scan_trusted(
+        b"{\"app\":{\"name\":\"a\",\"version\":1},\"env\":\"prod\",\"tags\":[1]}",
+    );
+    let event = Document::scan_trusted(
+        b"{\"app\":{\"version\":2,\"region\":\"b\"},\"tags\":[2],\"@m\":\"hello\"}",
+    );
 
-    assert_eq!(json!({}), document.to_value());
+    let merged = defaults.merge(&event, MergeStrategy::Shallow);
+    assert_eq!(
+        "{\"app\":{\"version\":2,\"region\":\"b\"},\"env\":\"prod\",\"tags\":[2],\"@m\":\"hello\"}",
+        str::from_utf8(&merged).unwrap()
+    );
+
+    let merged = defaults.merge(&event, MergeStrategy::Deep);
+    assert_eq!(
+        "{\"app\":{\"name\":\"a\",\"version\":2,\"region\":\"b\"},\"env\":\"prod\",\"tags\":[2],\"@m\":\"hello\"}",
+        str::from_utf8(&merged).unwrap()
+    );
+
+    // merged documents can be scanned again
+    assert_eq!(
+        json!({"app": {"name": "a", "version": 2, "region": "b"}, "env": "prod", "tags": [2], "@m": "hello"}),
+        Document::scan_trusted(&merged).to_value()
+    );
+
+    // maps are merged at any depth
+    let base = Document::scan_trusted(b"{\"a\":{\"b\":{\"c\":1,\"d\":2},\"e\":3},\"f\":4}");
+    let other = Document::scan_trusted(b"{\"a\":{\"b\":{\"d\":5,\"g\":6},\"h\":7},\"i\":8}");
+
+    let merged = base.merge(&other, MergeStrategy::Deep);
+    assert_eq!(
+        "{\"a\":{\"b\":{\"c\":1,\"d\":5,\"g\":6},\"e\":3,\"h\":7},\"f\":4,\"i\":8}",
+        str::from_utf8(&merged).unwrap()
+    );
 }
 
 #[test]
-fn read_map_with_trailing_num() {
-    let expected = json!({
-        "a": 123
-    });
+fn rewrite_to_minified_bytes() {
+    let document = Document::scan_trusted(
+        b"{\"a\":\"\\u0041\\/b\\u00e9\",\"b\":[1 ,true],\"c\":-1.5e3 ,\"d\\u0065\":null}",
+    );
 
-    let document = Document::scan_trusted_fallback(b"{\"a\":123}");
+    assert_eq!(
+        "{\"a\":\"A/b\u{e9}\",\"b\":[1,true],\"c\":-1.5e3,\"de\":null}",
+        str::from_utf8(&document.to_minified_bytes()).unwrap()
+    );
 
-    assert_eq!(expected, document.to_value());
+    for input in [
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+    ] {
+        let document = Document::scan_trusted(input);
+        let minified = document.to_minified_bytes();
+
+        let expected: serde_json::Value = serde_json::from_slice(input).unwrap();
+        let actual: serde_json::Value = serde_json::from_slice(&minified).unwrap();
+
+        assert_eq!(expected, actual);
+        assert!(minified.len() <= input.len());
+    }
 }
 
 #[test]
-fn read_arr_of_empty_maps() {
+fn rewrite_summarize() {
+    let input = b"{\"a\":\"hello \\\"world\\\"\",\"b\":[1,2,3,4],\"c\":{\"d\":{\"e\":[]},\"f\":{}},\"g\":\"\xc3\xa9\xc3\xa9\xc3\xa9\"}";
+    let document = Document::scan_trusted(input);
+
+    for (limits, expected) in [
+        (
+            SummaryLimits::new(),
+            json!({"a": "hello \"world\"", "b": [1, 2, 3, 4], "c": {"d": {"e": []}, "f": {}}, "g": "ééé"}),
+        ),
+        (
+            SummaryLimits::new().max_str_chars(7),
+            json!({"a": "hello \"…", "b": [1, 2, 3, 4], "c": {"d": {"e": []}, "f": {}}, "g": "ééé"}),
+        ),
+        (
+            SummaryLimits::new().max_str_chars(2),
+            json!({"a": "he…", "b": [1, 2, 3, 4], "c": {"d": {"e": []}, "f": {}}, "g": "éé…"}),
+        ),
+        (
+            SummaryLimits::new().max_arr_len(2),
+            json!({"a": "hello \"world\"", "b": [1, 2, "…"], "c": {"d": {"e": []}, "f": {}}, "g": "ééé"}),
+        ),
+        (
+            SummaryLimits::new().max_arr_len(0),
+            json!({"a": "hello \"world\"", "b": ["…"], "c": {"d": {"e": []}, "f": {}}, "g": "ééé"}),
+        ),
+        (
+            SummaryLimits::new().max_depth(1),
+            json!({"a": "hello \"world\"", "b": [1, 2, 3, 4], "c": {"d": "…", "f": {}}, "g": "ééé"}),
+        ),
+        (
+            SummaryLimits::new().max_depth(0),
+            json!({"a": "hello \"world\"", "b": "…", "c": "…", "g": "ééé"}),
+        ),
+    ] {
+        let summary = document.summarize(&limits);
+
+        assert_eq!(
+            expected,
+            serde_json::from_slice::<serde_json::Value>(&summary).unwrap(),
+            "{:?}",
+            limits
+        );
+        assert!(!Document::scan_trusted(&summary).is_err());
+    }
+
+    let input = include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8];
+    let summary = Document::scan_trusted(input).summarize(
+        &SummaryLimits::new()
+            .max_str_chars(32)
+            .max_arr_len(2)
+            .max_depth(2),
+    );
+
+    assert!(summary.len() < input.len());
+    assert!(serde_json::from_slice::<serde_json::Value>(&summary).is_ok());
+}
+
+#[test]
+fn write_rescan() {
+    let mut writer = Writer::new();
+
+    writer
+        .key("a")
+        .str("a \"quoted\"\n\u{1}string 😄")
+        .key("b\\")
+        .num(42u64)
+        .key("c")
+        .num(-1.5f64)
+        .key("d")
+        .num(f64::NAN)
+        .key("e")
+        .arr_begin()
+        .bool(true)
+        .null()
+        .map_begin()
+        .map_end()
+        .arr_begin()
+        .arr_end()
+        .arr_end()
+        .key("f")
+        .map_begin()
+        .key("g")
+        .bool(false)
+        .map_end();
+
+    let written = writer.finish();
+
     let expected = json!({
-        "a": [{},{},{}]
+        "a": "a \"quoted\"\n\u{1}string 😄",
+        "b\\": 42,
+        "c": -1.5,
+        "d": null,
+        "e": [true, null, {}, []],
+        "f": {"g": false},
     });
 
-    let document = Document::scan_trusted_fallback(b"{\"a\":[{},{},{}]}");
+    assert_eq!(
+        expected,
+        serde_json::from_slice::<serde_json::Value>(&written).unwrap()
+    );
 
+    let document = Document::scan_trusted(&written);
+    assert!(!document.is_err());
     assert_eq!(expected, document.to_value());
+
+    assert_eq!(b"{}" as &[u8], &*Writer::new().finish());
 }
 
 #[test]
-fn read_arr_of_numbers() {
-    let expected = json!({
-        "a": [
-            34785u64,
-            78234.2f64,
-        ]
+fn escape_align_start() {
+    let input = "This string has a lot of content 😄\nYou can think of it 壁 like a really big stacktrace.\nThere are so \"many\" errors \u{1f} and escaped \\ chars in it.\n";
+    let expected = serde_json::to_string(input).unwrap();
+
+    test_alignment(input.as_bytes(), 32, |input| {
+        let mut escaped = Vec::new();
+        escape_str(&mut escaped, str::from_utf8(input).unwrap());
+
+        assert_eq!(expected.as_bytes(), &*escaped);
     });
+}
 
-    let document = Document::scan_trusted_fallback(b"{\"a\":[34785,78234.2]}");
+#[test]
+fn escape_every_position() {
+    for len in 0..80 {
+        for (i, c) in ['"', '\\', '\n', '\u{0}', '\u{1f}', '\u{7f}', '壁']
+            .into_iter()
+            .cycle()
+            .take(len)
+            .enumerate()
+        {
+            let mut input = "a".repeat(len);
+            input.replace_range(i..i + 1, &c.to_string());
 
-    assert_eq!(expected, document.to_value());
+            let mut escaped = Vec::new();
+            escape_str(&mut escaped, &input);
+
+            assert_eq!(
+                serde_json::to_string(&input).unwrap().as_bytes(),
+                &*escaped,
+                "{:?}",
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn find_every_position() {
+    let haystack = include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8];
+
+    for needle in [
+        b"\"" as &[u8],
+        b"\":",
+        b"\"@t\":",
+        b"\"Exception\":\"",
+        b"at Microsoft.AspNetCore",
+        b"not in the input",
+        b"",
+    ] {
+        for input_offset in (0..haystack.len() + 2).step_by(7) {
+            let expected = haystack
+                .get(input_offset..)
+                .and_then(|rest| rest.windows(needle.len().max(1)).position(|w| w == needle))
+                .map(|i| input_offset + i);
+            let expected = if needle.is_empty() && input_offset <= haystack.len() {
+                Some(input_offset)
+            } else {
+                expected
+            };
+
+            assert_eq!(
+                expected,
+                find(haystack, needle, input_offset),
+                "{:?} {}",
+                str::from_utf8(needle).unwrap(),
+                input_offset
+            );
+        }
+    }
+
+    // needles that straddle blocks are found at every position
+    for len in 1..80 {
+        for i in 0..len {
+            let mut input = vec![b'a'; len + 40];
+            input[i..i + 3].copy_from_slice(b"\"b:");
+
+            assert_eq!(Some(i), find(&input, b"\"b:", 0));
+        }
+    }
+}
+
+#[test]
+fn validate_utf8() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        "😄 a string with multibyte characters that straddle blocks 壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁"
+            .as_bytes(),
+        "a string that ends with a multibyte character that straddles the last block 😄😄"
+            .as_bytes(),
+    ] {
+        test_alignment(input, 32, |input| {
+            assert_eq!(str::from_utf8(input), utf8::from_utf8(input));
+        });
+    }
+}
+
+#[test]
+fn transcode_utf16() {
+    let mut inputs: Vec<Vec<u16>> = [
+        include_str!("../../cases/serilog_embedded.json"),
+        include_str!("../../cases/10kb_event_stacktrace.json"),
+        "😄 a string with multibyte characters that straddle blocks 壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁",
+        "a string that ends with a surrogate pair that straddles the last block 😄😄",
+    ]
+    .iter()
+    .map(|input| input.encode_utf16().collect())
+    .collect();
+
+    // unpaired surrogates, including ones at the end of blocks and the end of the input
+    let mut unpaired: Vec<u16> = "an unpaired surrogate ".encode_utf16().collect();
+    unpaired.extend([0xd83d, b'a' as u16, 0xde04, 0xd83d, 0xd83d, 0xde04]);
+    unpaired.extend("in the middle of a string".encode_utf16());
+    unpaired.push(0xd83d);
+    inputs.push(unpaired);
+
+    for input in inputs {
+        for i in 0..input.len().min(32) {
+            let input = &input[i..];
+
+            let mut transcoded = b"existing".to_vec();
+            transcode_utf16_to_utf8(input, &mut transcoded);
+
+            assert_eq!(
+                format!("existing{}", String::from_utf16_lossy(input)).as_bytes(),
+                &*transcoded
+            );
+        }
+    }
+}
+
+#[test]
+fn read_scan_utf16() {
+    let input = "{\"a\":\"😄 壁\",\"b\":[1,true,{\"c\":null}],\"d\":\"a string that's long enough to be vectorized\"}";
+
+    let document = OwnedDocument::scan_utf16(&input.encode_utf16().collect::<Vec<_>>());
+
+    assert!(!document.as_document().is_err());
+    assert_eq!(input.as_bytes(), document.input());
+    assert_eq!(
+        Document::scan_trusted(input.as_bytes()).to_value(),
+        document.as_document().to_value()
+    );
+}
+
+#[test]
+fn read_interleave_utf8() {
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        "{\"a\":\"😄 a string with multibyte characters that straddle blocks 壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁壁\",\"😄\":[1,true,{\"壁\":null}],\"c\":\"a string that ends with a multibyte character that straddles the last block 😄😄\"}  \n"
+            .as_bytes(),
+    ] {
+        test_alignment(input, 32, |input| {
+            let expected = Document::scan_trusted(input);
+            let interleaved =
+                Document::scan_trusted_with(input, &ScanOptions::new().interleave_utf8(true));
+
+            assert!(!interleaved.is_err());
+            assert_eq!(expected.to_value(), interleaved.to_value());
+            assert!(expected
+                .offsets()
+                .iter()
+                .map(|offset| offset.input_range())
+                .eq(interleaved.offsets().iter().map(|offset| offset.input_range())));
+        });
+    }
+}
+
+#[test]
+fn read_prefetch() {
+    // documents larger than L2 are prefetched
+    let event = str::from_utf8(include_bytes!("../../cases/10kb_event_stacktrace.json")).unwrap();
+
+    let mut input = b"{\"events\":[".to_vec();
+    while input.len() < 2 * 1024 * 1024 {
+        escape_str(&mut input, &event[..4000]);
+        input.push(b',');
+    }
+    input.extend(b"null]}");
+
+    let expected = Document::scan_trusted_with(&input, &ScanOptions::new());
+    let prefetched = Document::scan_trusted_with(&input, &ScanOptions::new().prefetch(true));
+
+    assert!(!prefetched.is_err());
+    assert!(expected
+        .offsets()
+        .iter()
+        .map(|offset| offset.input_range())
+        .eq(prefetched
+            .offsets()
+            .iter()
+            .map(|offset| offset.input_range())));
+}
+
+#[test]
+fn prefilter_candidates() {
+    let batch = [
+        b"{\"@l\":\"Error\",\"a\":1}" as &[u8],
+        b"{\"@l\":\"Information\",\"a\":\"Error\"}",
+        b"{\"@m\":\"no level\",\"b\":[1,2,3]}",
+        b"{\"@l\":\"Error\",\"b\":{\"c\":\"\\\"quoted\\\"\"}}",
+        include_bytes!("../../cases/10kb_event_stacktrace.json"),
+    ];
+
+    assert_eq!(
+        vec![0, 1, 3, 4],
+        Prefilter::new().key("@l").candidates(batch)
+    );
+    assert_eq!(
+        vec![0, 1, 3, 4],
+        Prefilter::new().str("Error").candidates(batch)
+    );
+    assert_eq!(
+        vec![0, 1, 2, 3, 4],
+        Prefilter::new().str("Error").key("b").candidates(batch)
+    );
+    assert_eq!(
+        vec![3],
+        Prefilter::new()
+            .str("Error")
+            .key("b")
+            .require_all(true)
+            .candidates(batch)
+    );
+    assert_eq!(
+        vec![3],
+        Prefilter::new().str("\"quoted\"").candidates(batch)
+    );
+    assert_eq!(vec![2], Prefilter::new().needle("[1,2").candidates(batch));
+    assert_eq!(vec![0, 1, 2, 3, 4], Prefilter::new().candidates(batch));
+
+    // candidates only need to be scanned to check the filter properly
+    for input in batch {
+        let document = Document::scan_trusted(input);
+        let is_error = document
+            .as_map()
+            .get("@l")
+            .and_then(|l| l.as_str())
+            .map(|l| l.as_raw())
+            == Some("Error");
+
+        assert!(
+            !is_error
+                || Prefilter::new()
+                    .key("@l")
+                    .str("Error")
+                    .require_all(true)
+                    .could_match(input)
+        );
+    }
+}
+
+#[test]
+fn batch_filter() {
+    let batch = [
+        b"{\"@l\":\"Error\",\"a\":{\"b\":[1,{\"c\":\"x\"}]}}" as &[u8],
+        b"{\"@l\":\"Information\",\"a\":{\"b\":[2.5,{\"c\":\"xyz\"}]}}",
+        b"{\"@m\":\"@l\",\"a\":{\"b\":[]}}",
+        b"{\"@l\":\"Err\\u006fr\",\"a\":{\"b\":[-1e3]}}",
+        b"{\"@l\":\"Error\"",
+    ];
+
+    let matches = |path, predicate| {
+        batch::filter(batch, path, predicate)
+            .map(|input| batch.iter().position(|i| *i == input).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(vec![0, 3, 4], matches("@l", Predicate::StrEq("Error")));
+    assert_eq!(vec![0, 1, 3, 4], matches("@l", Predicate::StrContains("r")));
+    assert_eq!(vec![0, 1], matches("a.b.1.c", Predicate::StrContains("x")));
+    assert_eq!(vec![1], matches("a.b.1.c", Predicate::StrEq("xyz")));
+    assert_eq!(
+        vec![0, 1],
+        matches("a.b.0", Predicate::NumRange(0.0..=10.0))
+    );
+    assert_eq!(
+        vec![3],
+        matches("a.b.0", Predicate::NumRange(f64::NEG_INFINITY..=-1000.0))
+    );
+    assert!(matches("a.b", Predicate::StrEq("")).is_empty());
+    assert!(matches("a.b.2", Predicate::NumRange(0.0..=10.0)).is_empty());
+    assert!(matches("", Predicate::StrEq("")).is_empty());
+
+    // every leaf can be filtered on
+    for input in [
+        include_bytes!("../../cases/serilog_embedded.json") as &[u8],
+        include_bytes!("../../cases/10kb_event_stacktrace.json") as &[u8],
+        include_bytes!("../../cases/600b_event_no_escape.json") as &[u8],
+    ] {
+        let document = Document::scan_trusted(input);
+
+        for (path, value) in document.flatten_joined(".") {
+            let predicate = match value {
+                Kind::Str(s) => Predicate::StrEq(s.as_raw()),
+                Kind::Num(n) => {
+                    let n = n.parse::<f64>().unwrap();
+                    Predicate::NumRange(n..=n)
+                }
+                _ => continue,
+            };
+
+            // strings with escapes would need to be unescaped to compare
+            if let Predicate::StrEq(s) = predicate {
+                if s.contains('\\') {
+                    continue;
+                }
+            }
+
+            assert_eq!(
+                1,
+                batch::filter([input], &path, predicate).count(),
+                "{}",
+                path
+            );
+        }
+    }
+}
+
+#[test]
+fn batch_key_histogram() {
+    let docs = [
+        Document::scan_trusted(b"{\"a\":1,\"b\":{\"c\":2},\"a\":3}"),
+        Document::scan_trusted(b"{\"a\":1,\"d\":[{\"e\":1},{\"e\":2,\"f\":3}]}"),
+        Document::scan_trusted(b"{\"b\\u0020\":{\"c\":[]}}"),
+    ];
+
+    // duplicate keys are only counted once per document
+    let counts = batch::key_histogram(&docs);
+
+    assert_eq!(4, counts.len());
+    assert_eq!(Some(&2), counts.get("a"));
+    assert_eq!(Some(&1), counts.get("b"));
+    assert_eq!(Some(&1), counts.get("b "));
+    assert_eq!(Some(&1), counts.get("d"));
+
+    let mut histogram = KeyHistogram::new().nested(".");
+
+    for doc in &docs {
+        histogram.add(doc);
+    }
+
+    assert_eq!(3, histogram.documents());
+    assert_eq!(2, histogram.get("a"));
+    assert_eq!(1, histogram.get("b.c"));
+    assert_eq!(1, histogram.get("b .c"));
+    assert_eq!(1, histogram.get("d.e"));
+    assert_eq!(1, histogram.get("d.f"));
+    assert_eq!(0, histogram.get("c"));
+    assert_eq!(8, histogram.iter().count());
+}
+
+#[test]
+fn batch_to_arrow() {
+    use std::sync::Arc;
+
+    use arrow_array::{Array, BooleanArray, Float64Array, Int64Array, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+
+    let docs = [
+        Document::scan_trusted(b"{\"@m\":\"a\\nb\",\"n\":1,\"ok\":true,\"x\":{\"y\":[1.5,2]}}"),
+        Document::scan_trusted(b"{\"@m\":\"c\",\"n\":1.5,\"ok\":\"true\"}"),
+        Document::scan_trusted(b"{\"n\":-3,\"x\":{\"y\":[0,-2e1]}}"),
+    ];
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("@m", DataType::Utf8, true),
+        Field::new("n", DataType::Int64, true),
+        Field::new("ok", DataType::Boolean, true),
+        Field::new("x.y.1", DataType::Float64, true),
+    ]));
+
+    let batch = batch::to_arrow(&docs, schema).unwrap();
+
+    assert_eq!(3, batch.num_rows());
+
+    let m = batch
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(
+        vec![Some("a\nb"), Some("c"), None],
+        m.iter().collect::<Vec<_>>()
+    );
+
+    let n = batch
+        .column(1)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(vec![Some(1), None, Some(-3)], n.iter().collect::<Vec<_>>());
+
+    let ok = batch
+        .column(2)
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .unwrap();
+    assert_eq!(vec![Some(true), None, None], ok.iter().collect::<Vec<_>>());
+
+    let y = batch
+        .column(3)
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_eq!(
+        vec![Some(2.0), None, Some(-20.0)],
+        y.iter().collect::<Vec<_>>()
+    );
+
+    // missing values can't be written to fields that aren't nullable
+    let schema = Arc::new(Schema::new(vec![Field::new("@m", DataType::Utf8, false)]));
+    assert!(batch::to_arrow(&docs, schema).is_err());
+
+    // only some data types are supported
+    let schema = Arc::new(Schema::new(vec![Field::new("n", DataType::Int32, true)]));
+    assert!(batch::to_arrow(&docs, schema).is_err());
+}
+
+#[test]
+fn read_metrics() {
+    let input = include_bytes!("../../cases/10kb_event_stacktrace.json");
+
+    // other tests may be scanning documents at the same time
+    let before = metrics_snapshot();
+    let document = Document::scan_trusted(input);
+    let after = metrics_snapshot();
+
+    assert!(!document.is_err());
+
+    assert!(after.documents_scanned > before.documents_scanned);
+    assert!(after.bytes_scanned - before.bytes_scanned >= input.len() as u64);
+    assert!(after.escapes > before.escapes);
+    assert!(after.fallback_bytes > before.fallback_bytes);
+
+    if de::is_simd_supported() {
+        assert!(after.vectorized_bytes > before.vectorized_bytes);
+    }
 }
 
 #[test]
@@ -198,3 +3329,163 @@ fn unescape_surrogate_pair() {
 
     assert_eq!("😄", unescaped);
 }
+
+#[test]
+fn unescape_dense_unicode() {
+    // escapes are decoded together with the escape that follows them, so pairs may straddle blocks
+    let input = b"\\u4F60\\u597d \\ud83d\\ude04\\ud83d\\ude04\\u00e9!\\u0041\\ud83d\\ude04\\u58c1";
+    let expected = "你好 😄😄é!A😄壁";
+    test_alignment(input, 32, |input| {
+        let unescaped = unsafe { unescape_trusted(str::from_utf8(input).unwrap()) };
+
+        assert_eq!(expected, unescaped);
+    });
+}
+
+#[test]
+fn unescape_chunked() {
+    // escapes and surrogate pairs that straddle chunk boundaries are kept together
+    let mut value = String::new();
+    while value.len() < 64 * 1024 {
+        value.push_str("a\\\\\\n\\\"b\\ud83d\\ude04c壁\\u58c1\\t");
+    }
+
+    let input = format!("{{\"a\":\"{}\"}}", value);
+    let document = Document::scan_trusted(input.as_bytes());
+
+    let s = document
+        .as_map()
+        .entries()
+        .next()
+        .unwrap()
+        .1
+        .as_str()
+        .unwrap();
+
+    let mut written = Vec::new();
+    s.write_unescaped(&mut written).unwrap();
+
+    assert_eq!(s.to_unescaped(), str::from_utf8(&written).unwrap());
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&input).unwrap()["a"],
+        str::from_utf8(&written).unwrap()
+    );
+}
+
+#[test]
+fn unescape_inline() {
+    // raw strings up to the inline capacity are unescaped without allocating
+    let short = "a\\nb\\u58c1\\ud83d\\ude04".repeat(2);
+    let exact = format!("{}{}", "x".repeat(62), "\\t");
+    let long = format!("{}{}", "x".repeat(63), "\\t");
+
+    let input = format!(
+        "{{\"none\":\"no escapes\",\"short\":\"{}\",\"exact\":\"{}\",\"long\":\"{}\"}}",
+        short, exact, long
+    );
+    let document = Document::scan_trusted(input.as_bytes());
+
+    for (key, allocated) in [
+        ("none", false),
+        ("short", false),
+        ("exact", false),
+        ("long", true),
+    ] {
+        let s = document.as_map().get_str(key).unwrap();
+        let unescaped = s.to_unescaped_inline();
+
+        assert_eq!(allocated, unescaped.is_allocated(), "{}", key);
+        assert_eq!(s.to_unescaped(), unescaped.as_str());
+        assert_eq!(s.to_unescaped(), unescaped.into_cow());
+    }
+
+    assert!(document
+        .as_map()
+        .get_str("short")
+        .unwrap()
+        .to_unescaped_inline()
+        .starts_with("a\nb壁😄a"));
+}
+
+#[test]
+fn unescape_scratch() {
+    let long = "x\\n".repeat(64);
+    let input = format!(
+        "{{\"none\":\"no escapes\",\"short\":\"a\\nb\\u58c1\\ud83d\\ude04\",\"long\":\"{}\"}}",
+        long
+    );
+    let document = Document::scan_trusted(input.as_bytes());
+
+    let mut scratch = UnescapeScratch::new();
+
+    // strings without escapes are borrowed, so the scratch buffer is never used
+    let none = document.as_map().get_str("none").unwrap();
+    assert_eq!("no escapes", none.to_unescaped_with(&mut scratch));
+    assert_eq!(0, scratch.capacity());
+
+    for key in ["long", "short", "long"] {
+        let s = document.as_map().get_str(key).unwrap();
+
+        assert_eq!(
+            s.to_unescaped(),
+            s.to_unescaped_with(&mut scratch),
+            "{}",
+            key
+        );
+    }
+
+    // the buffer is reused once it's large enough
+    let capacity = scratch.capacity();
+    let s = document.as_map().get_str("short").unwrap();
+    assert_eq!("a\nb壁😄", s.to_unescaped_with(&mut scratch));
+    assert_eq!(capacity, scratch.capacity());
+}
+
+#[test]
+fn unescape_json_str_valid() {
+    for (input, expected) in [
+        ("", ""),
+        ("no escapes", "no escapes"),
+        ("\\\\", "\\"),
+        ("a\\nb\\u58c1\\ud83d\\ude04", "a\nb壁😄"),
+    ] {
+        assert_eq!(expected, unescape_json_str(input).unwrap());
+    }
+}
+
+#[test]
+fn unescape_json_str_policy() {
+    for (input, pass_through, replacement_char) in [
+        ("a\\nb\\ud83d\\ude04", "a\nb😄", "a\nb😄"),
+        ("a\\xb", "axb", "a\u{fffd}b"),
+        ("\\\\x", "\\x", "\\x"),
+        ("\\é!", "é!", "\u{fffd}!"),
+        ("\\u12g", "12g", "\u{fffd}12g"),
+        ("\\ud83d!\\n", "!\n", "\u{fffd}!\n"),
+    ] {
+        assert_eq!(
+            pass_through,
+            unescape_json_str_with(input, UnescapePolicy::PassThrough).unwrap(),
+            "{}",
+            input
+        );
+        assert_eq!(
+            replacement_char,
+            unescape_json_str_with(input, UnescapePolicy::ReplacementChar).unwrap(),
+            "{}",
+            input
+        );
+    }
+
+    // only the surrogates that aren't part of a pair are replaced
+    assert_eq!(
+        "\u{fffd}😄",
+        unescape_json_str_with("\\ude04\\ud83d\\ude04", UnescapePolicy::ReplacementChar).unwrap()
+    );
+
+    // strings without invalid escapes are unescaped the same way by every policy
+    assert_eq!(
+        "a\nb😄",
+        unescape_json_str_with("a\\nb\\ud83d\\ude04", UnescapePolicy::Error).unwrap()
+    );
+}
@@ -10,20 +10,254 @@ This implementation follows the same basic design as `de` for supporting a vecto
 fallback implementation using a shared set of functions. It's docs have some more details.
 */
 
-use std::{borrow::BorrowMut, ptr, str};
+use std::{borrow::BorrowMut, error, fmt, ptr};
 
 mod fallback;
 
 #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 mod simd;
 
+/**
+Unescape the contents of a JSON string, without its surrounding quotes.
+
+This uses the same vectorized implementation as [`Str::to_unescaped`](crate::de::Str::to_unescaped),
+so it accepts the same input. Escape sequences that aren't valid JSON are passed through without
+their leading `\` rather than returning an error. The only invalid input is a string that ends with
+a `\` that isn't escaped.
+
+Use [`unescape_json_str_with`] to handle escape sequences that aren't valid JSON differently.
+*/
+pub fn unescape_json_str(input: &str) -> Result<String, UnescapeError> {
+    unescape_json_str_with(input, UnescapePolicy::PassThrough)
+}
+
+/**
+Unescape the contents of a JSON string, without its surrounding quotes, using a policy
+for escape sequences that aren't valid JSON.
+
+Unknown escapes like `\x`, `\u` escapes without 4 hex digits, and `\u` escapes for surrogates
+that aren't part of a pair are all considered invalid. A string that ends with a `\` that isn't
+escaped is always an error.
+*/
+pub fn unescape_json_str_with(
+    input: &str,
+    policy: UnescapePolicy,
+) -> Result<String, UnescapeError> {
+    let trailing = input.bytes().rev().take_while(|b| *b == b'\\').count();
+
+    if trailing % 2 != 0 {
+        return Err(UnescapeError {
+            input_offset: input.len() - 1,
+            unterminated: true,
+        });
+    }
+
+    match policy {
+        UnescapePolicy::PassThrough => (),
+        UnescapePolicy::Error => {
+            if let Some(invalid) = invalid_escapes(input.as_bytes()).next() {
+                return Err(UnescapeError {
+                    input_offset: invalid.input_offset,
+                    unterminated: false,
+                });
+            }
+        }
+        UnescapePolicy::ReplacementChar => {
+            let mut unescaped = String::with_capacity(input.len());
+            let mut start = 0;
+
+            // the input is unescaped in chunks between each invalid escape
+            // the chunks end just before a `\` that begins an escape, so they can't end with one
+            for invalid in invalid_escapes(input.as_bytes()) {
+                // SAFETY: The chunk doesn't end with an unescaped `\`
+                unsafe {
+                    unescape_trusted_into(&input[start..invalid.input_offset], &mut unescaped)
+                };
+                unescaped.push(char::REPLACEMENT_CHARACTER);
+
+                start = invalid.input_offset + invalid.len;
+            }
+
+            // SAFETY: The string doesn't end with an unescaped `\`
+            unsafe { unescape_trusted_into(&input[start..], &mut unescaped) };
+
+            return Ok(unescaped);
+        }
+    }
+
+    // SAFETY: The string doesn't end with an unescaped `\`
+    Ok(unsafe { unescape_trusted(input) })
+}
+
+/**
+What to do with escape sequences that aren't valid JSON when unescaping a string.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnescapePolicy {
+    /**
+    Pass the escape sequence through without its leading `\`.
+
+    This is the same way strings in documents are unescaped.
+    */
+    PassThrough,
+    /**
+    Replace the escape sequence with `U+FFFD REPLACEMENT CHARACTER`.
+
+    Only the `\` and the character following it are replaced for unknown escapes,
+    or `\u` escapes without 4 hex digits.
+    */
+    ReplacementChar,
+    /**
+    Return an error for the first escape sequence that isn't valid JSON.
+    */
+    Error,
+}
+
+impl Default for UnescapePolicy {
+    #[inline]
+    fn default() -> Self {
+        UnescapePolicy::PassThrough
+    }
+}
+
+/**
+A reusable buffer for unescaping strings into.
+
+Unescaping a string with [`Str::to_unescaped`](crate::de::Str::to_unescaped) allocates a new
+`String` for each escaped string. Unescaping with
+[`Str::to_unescaped_with`](crate::de::Str::to_unescaped_with) writes into this buffer instead,
+so a document's escaped strings can be processed one at a time without allocating for each.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct UnescapeScratch {
+    buf: String,
+}
+
+impl UnescapeScratch {
+    /**
+    Create an empty scratch buffer.
+    */
+    #[inline]
+    pub fn new() -> Self {
+        UnescapeScratch::default()
+    }
+
+    /**
+    Create a scratch buffer that can unescape strings up to `capacity` bytes without growing.
+    */
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        UnescapeScratch {
+            buf: String::with_capacity(capacity),
+        }
+    }
+
+    /**
+    The number of bytes the scratch buffer can unescape into without growing.
+    */
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    // SAFETY: The string must not end with a `\` unless it's been escaped
+    // The string replaces anything previously unescaped into the buffer
+    pub(crate) unsafe fn unescape_trusted(&mut self, input: &str) -> &str {
+        self.buf.clear();
+        unescape_trusted_into(input, &mut self.buf);
+
+        &self.buf
+    }
+}
+
+/**
+An error attempting to unescape a string.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnescapeError {
+    input_offset: usize,
+    unterminated: bool,
+}
+
+impl UnescapeError {
+    /**
+    The offset in the input of the `\` that begins the escape sequence that couldn't be unescaped.
+    */
+    #[inline]
+    pub fn input_offset(&self) -> usize {
+        self.input_offset
+    }
+}
+
+impl fmt::Display for UnescapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.unterminated {
+            write!(
+                f,
+                "unterminated escape sequence at input offset {}",
+                self.input_offset
+            )
+        } else {
+            write!(
+                f,
+                "invalid escape sequence at input offset {}",
+                self.input_offset
+            )
+        }
+    }
+}
+
+impl error::Error for UnescapeError {}
+
 // SAFETY: The string must not end with a `\` unless it's been escaped
 // This is guaranteed for strings parsed from JSON, because string boundaries
 // with a leading `\` are considered escapes and won't terminate the string
-#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub(crate) unsafe fn unescape_trusted(input: &str) -> String {
+    let mut unescaped = String::new();
+    unescape_trusted_into(input, &mut unescaped);
+
+    unescaped
+}
+
+// SAFETY: The string must not end with a `\` unless it's been escaped
+// The unescaped string is appended to the buffer
+pub(crate) unsafe fn unescape_trusted_into(input: &str, buf: &mut String) {
+    let input = input.as_bytes();
+
+    // unescaping never makes a string longer, so the input length is enough to unescape into
+    let buf = buf.as_mut_vec();
+    buf.reserve(input.len());
+
+    let start = buf.len();
+
+    // SAFETY: the buffer has capacity for the input after its current length
+    let mut unescaped = Unescaped::new(buf.as_mut_ptr().add(start), input.len());
+    unescape_raw(input, &mut unescaped);
+
+    buf.set_len(start + unescaped.len);
+
+    test_assert!(std::str::from_utf8(&buf[start..]).is_ok());
+}
+
+// SAFETY: The string must not end with a `\` unless it's been escaped
+// SAFETY: The buffer must be at least as long as the string
+// The unescaped string is written to the start of the buffer, and its length is returned
+pub(crate) unsafe fn unescape_trusted_into_slice(input: &str, buf: &mut [u8]) -> usize {
     let input = input.as_bytes();
 
+    test_assert!(buf.len() >= input.len());
+
+    let mut unescaped = Unescaped::new(buf.as_mut_ptr(), input.len());
+    unescape_raw(input, &mut unescaped);
+
+    unescaped.len
+}
+
+// SAFETY: The input must be UTF8 and not end with an unescaped `\`
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[inline]
+unsafe fn unescape_raw(input: &[u8], unescaped: &mut Unescaped) {
     let mut scan = Scan {
         input_offset: 0,
         escape: false,
@@ -31,10 +265,6 @@ pub(crate) unsafe fn unescape_trusted(input: &str) -> String {
         first_surrogate: None,
     };
 
-    let mut unescaped = Unescaped {
-        buf: Vec::with_capacity(input.len()),
-    };
-
     // when SIMD is available, we can vectorize
     #[cfg(target_arch = "x86_64")]
     {
@@ -43,7 +273,7 @@ pub(crate) unsafe fn unescape_trusted(input: &str) -> String {
         {
             // SAFETY: the input is UTF8
             // SAFETY: avx2 is available
-            simd::unescape_x86_64_avx2(input, &mut scan, &mut unescaped);
+            simd::unescape_x86_64_avx2(input, &mut scan, unescaped);
             return unescape_end(input, scan, unescaped);
         }
     }
@@ -54,21 +284,21 @@ pub(crate) unsafe fn unescape_trusted(input: &str) -> String {
         {
             // SAFETY: the input is UTF8
             // SAFETY: neon is available
-            simd::unescape_aarch64_neon(input, &mut scan, &mut unescaped);
+            simd::unescape_aarch64_neon(input, &mut scan, unescaped);
             return unescape_end(input, scan, unescaped);
         }
     }
 
     // when avx2 is not available, we need to fallback
     // SAFETY: the input is UTF8
-    fallback::unescape(input, &mut scan, &mut unescaped);
+    fallback::unescape(input, &mut scan, unescaped);
     unescape_end(input, scan, unescaped)
 }
 
+// SAFETY: The input must be UTF8 and not end with an unescaped `\`
 #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
-pub(crate) unsafe fn unescape_trusted(input: &str) -> String {
-    let input = input.as_bytes();
-
+#[inline]
+unsafe fn unescape_raw(input: &[u8], unescaped: &mut Unescaped) {
     let mut scan = Scan {
         input_offset: 0,
         escape: false,
@@ -76,20 +306,157 @@ pub(crate) unsafe fn unescape_trusted(input: &str) -> String {
         first_surrogate: None,
     };
 
-    let mut unescaped = Unescaped {
-        buf: Vec::with_capacity(input.len()),
-    };
-
     // SAFETY: the input is UTF8
-    fallback::unescape(input, &mut scan, &mut unescaped);
+    fallback::unescape(input, &mut scan, unescaped);
     unescape_end(input, scan, unescaped)
 }
 
 #[inline]
-fn unescape_end(input: &[u8], mut scan: Scan, mut unescaped: Unescaped) -> String {
-    flush(input, input.len(), &mut scan, &mut unescaped);
+fn unescape_end(input: &[u8], mut scan: Scan, unescaped: &mut Unescaped) {
+    flush(input, input.len(), &mut scan, unescaped);
+}
 
-    owned_from_utf8_unchecked!(unescaped.buf)
+/**
+An escape sequence that isn't valid JSON.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct InvalidEscape {
+    /**
+    The offset of the `\` that begins the escape sequence.
+    */
+    pub(crate) input_offset: usize,
+    /**
+    The length of the escape sequence in bytes.
+    */
+    pub(crate) len: usize,
+    /**
+    Whether the escape sequence is a `\u` escape for a surrogate that isn't part of a pair.
+    */
+    pub(crate) lone_surrogate: bool,
+}
+
+/**
+Find the escape sequences in a string that aren't valid JSON.
+
+The string is expected to be UTF8 and to not end with a `\` that isn't escaped.
+*/
+pub(crate) fn invalid_escapes(s: &[u8]) -> impl Iterator<Item = InvalidEscape> + '_ {
+    let mut i = 0;
+
+    std::iter::from_fn(move || {
+        while i < s.len() {
+            if s[i] != b'\\' {
+                i += 1;
+                continue;
+            }
+
+            let input_offset = i;
+            let invalid = |len, lone_surrogate| InvalidEscape {
+                input_offset,
+                len,
+                lone_surrogate,
+            };
+
+            match s.get(i + 1) {
+                Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => i += 2,
+                Some(b'u') => match hex4(s, i + 2) {
+                    // a high surrogate must be followed by a low surrogate
+                    Some(0xD800..=0xDBFF) => {
+                        let low = match (s.get(i + 6), s.get(i + 7)) {
+                            (Some(b'\\'), Some(b'u')) => hex4(s, i + 8),
+                            _ => None,
+                        };
+
+                        if let Some(0xDC00..=0xDFFF) = low {
+                            i += 12;
+                        } else {
+                            i += 6;
+                            return Some(invalid(6, true));
+                        }
+                    }
+                    // a low surrogate can't appear by itself
+                    Some(0xDC00..=0xDFFF) => {
+                        i += 6;
+                        return Some(invalid(6, true));
+                    }
+                    Some(_) => i += 6,
+                    None => {
+                        i += 2;
+                        return Some(invalid(2, false));
+                    }
+                },
+                Some(&b) => {
+                    // the escaped character may be more than a single byte
+                    let len = 1 + utf8_len(b);
+
+                    i += len;
+                    return Some(invalid(len, false));
+                }
+                None => {
+                    i += 1;
+                    return Some(invalid(1, false));
+                }
+            }
+        }
+
+        None
+    })
+}
+
+/**
+Parse the 4 hex digits of a `\u` escape, starting at the given offset.
+*/
+pub(crate) fn hex4(s: &[u8], start: usize) -> Option<u16> {
+    let digits = s.get(start..start + 4)?;
+
+    digits.iter().try_fold(0u16, |code, &b| {
+        let digit = (b as char).to_digit(16)?;
+        Some((code << 4) | digit as u16)
+    })
+}
+
+#[inline]
+fn utf8_len(first: u8) -> usize {
+    match first {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        _ => 4,
+    }
+}
+
+/**
+Find the end of the next chunk of an escaped string that can be unescaped by itself.
+
+The chunk ends at or after `target`, just past an ASCII character that isn't part of an escape sequence,
+so escapes and surrogate pairs are never split across chunks.
+Hex digits are never chunk boundaries, because they may be the end of the first half of a surrogate pair.
+*/
+pub(crate) fn chunk_end(input: &str, target: usize) -> usize {
+    let input = input.as_bytes();
+
+    let mut end = target.max(1);
+
+    while end < input.len() {
+        let last = input[end - 1];
+
+        if last.is_ascii() && last != b'\\' && !last.is_ascii_hexdigit() {
+            // the last character is escaped if it's preceded by an odd number of `\`
+            let escapes = input[..end - 1]
+                .iter()
+                .rev()
+                .take_while(|b| **b == b'\\')
+                .count();
+
+            if escapes % 2 == 0 {
+                return end;
+            }
+        }
+
+        end += 1;
+    }
+
+    input.len()
 }
 
 struct Scan {
@@ -111,8 +478,46 @@ struct Scan {
     first_surrogate: Option<u16>,
 }
 
+/**
+The destination that a string is unescaped into.
+
+Unescaping never makes a string longer, so the destination only needs to be as long as
+the input. That means it can be a fixed-size buffer, and writes into it don't need to
+check whether there's room for them.
+*/
 struct Unescaped {
-    buf: Vec<u8>,
+    dst: *mut u8,
+    len: usize,
+    // only used to check writes stay within the destination in tests
+    #[cfg_attr(not(test), allow(dead_code))]
+    cap: usize,
+}
+
+impl Unescaped {
+    // SAFETY: `dst` must be valid for writes of `cap` bytes
+    #[inline]
+    unsafe fn new(dst: *mut u8, cap: usize) -> Self {
+        Unescaped { dst, len: 0, cap }
+    }
+
+    #[inline(always)]
+    fn push(&mut self, b: u8) {
+        test_assert!(self.len < self.cap);
+
+        // SAFETY: the destination has room for the unescaped string
+        unsafe { self.dst.add(self.len).write(b) };
+        self.len += 1;
+    }
+
+    #[inline(always)]
+    fn extend(&mut self, bytes: &[u8]) {
+        test_assert!(self.len + bytes.len() <= self.cap);
+
+        // SAFETY: the destination has room for the unescaped string,
+        // and can't overlap with a slice we're holding a reference to
+        unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), self.dst.add(self.len), bytes.len()) };
+        self.len += bytes.len();
+    }
 }
 
 struct ScanFnInput<'a> {
@@ -132,23 +537,24 @@ fn flush(input: &[u8], flush_to: usize, scan: &mut Scan, unescaped: &mut Unescap
     let cnt = flush_to - scan.start as usize;
 
     test_assert!(cnt > 0);
-    test_assert!(unescaped.buf.len() + cnt <= unescaped.buf.capacity());
+    test_assert!(unescaped.len + cnt <= unescaped.cap);
 
-    // manually copy into the vec, knowing the slices don't overlap
+    // manually copy into the destination, knowing the slices don't overlap
     // this is more efficient than `extend_from_slice` and friends,
     // because those methods can't guarantee there's no overlapping
 
     // SAFETY: The `src` and `cnt` slice is within `input`,
-    // and the `dst` and `cnt` slice is within `buf`'s capacity.
+    // and the `dst` and `cnt` slice is within the destination's capacity.
     // SAFETY: We're only copying bytes, that are `Copy`.
     unsafe {
         let src = input.as_ptr().offset(scan.start);
-        let dst = unescaped.buf.as_mut_ptr().add(unescaped.buf.len());
+        let dst = unescaped.dst.add(unescaped.len);
 
         ptr::copy_nonoverlapping(src, dst, cnt);
-        unescaped.buf.set_len(unescaped.buf.len() + cnt);
     }
 
+    unescaped.len += cnt;
+
     scan.start = flush_to as isize;
 }
 
@@ -163,7 +569,7 @@ impl<'a> ScanFnInput<'a> {
 
     #[inline(always)]
     fn push_unescaped_byte(&mut self, b: u8) {
-        self.unescaped.buf.push(b);
+        self.unescaped.push(b);
 
         // skip over the escape char
         self.scan.start += 1;
@@ -174,7 +580,7 @@ impl<'a> ScanFnInput<'a> {
         let mut buf = [0; 4];
 
         let encoded = c.encode_utf8(&mut buf);
-        self.unescaped.buf.extend(encoded.as_bytes());
+        self.unescaped.extend(encoded.as_bytes());
 
         // skip over the escape chars
         self.scan.start += 4;
@@ -190,9 +596,14 @@ impl<'a> ScanFnInput<'a> {
 }
 
 #[inline(always)]
-fn interest_unescape<'a, I: BorrowMut<ScanFnInput<'a>>>(mut i: I) {
+fn interest_unescape<'a, D: DecodeHex, I: BorrowMut<ScanFnInput<'a>>>(mut i: I) {
     let i = i.borrow_mut();
 
+    // the escape may have already been unescaped as the second half of a surrogate pair
+    if i.curr_offset < i.scan.start as usize {
+        return;
+    }
+
     let escaped = i.scan.escape;
     i.scan.escape = !escaped;
 
@@ -221,47 +632,8 @@ fn interest_unescape<'a, I: BorrowMut<ScanFnInput<'a>>>(mut i: I) {
                 i.curr_offset += 1;
 
                 // we have at least 4 bytes left for an escape code
-                if i.input
-                    .len()
-                    .checked_sub(4usize)
-                    .map(|start| i.curr_offset <= start)
-                    .unwrap_or(false)
-                {
-                    let mut unescape = || {
-                        let digits = str::from_utf8(offset_from_raw_parts!(
-                            i.input.as_ptr(),
-                            i.input.len(),
-                            i.curr_offset,
-                            4
-                        ))
-                        .map_err(|_| ())?;
-                        let code = u16::from_str_radix(digits, 16).map_err(|_| ())?;
-
-                        // if we get this far then we're looking at a hex number
-                        // we guarantee there are no `\` in the 4 bytes we've just looked through
-                        // NOTE: only attempting to match the surrogate here means we'll accept `\u`
-                        // escapes with other characters between them, but still guarantee valid UTF8
-                        match i.scan.first_surrogate.take() {
-                            // if we had a surrogate pair, then attempt to map it to a multibyte
-                            Some(first) => {
-                                let ch = crate::std_ext::char::try_from_utf16_surrogate_pair(
-                                    first, code,
-                                )
-                                .map_err(|_| ())?;
-                                i.push_unescaped_char(ch);
-                            }
-                            // if we didn't have a surrogate pair,
-                            // then attempt to interpret the code as a 2-4 byte character
-                            None => match char::try_from(code as u32) {
-                                Ok(ch) => i.push_unescaped_char(ch),
-                                Err(_) => i.begin_surrogate_pair(code),
-                            },
-                        }
-
-                        Ok::<(), ()>(())
-                    };
-
-                    let _ = unescape();
+                if i.curr_offset + 4 <= i.input.len() {
+                    unescape_code::<D>(i);
                 }
             }
             // fallback case
@@ -275,3 +647,99 @@ fn interest_unescape<'a, I: BorrowMut<ScanFnInput<'a>>>(mut i: I) {
         i.scan.escape = false;
     }
 }
+
+/**
+Unescape the 4 hex digits of a `\u` escape at the current offset.
+
+If the escape is immediately followed by another `\u` escape then both are decoded together,
+so surrogate pairs can be unescaped in one go.
+*/
+#[inline(always)]
+fn unescape_code<D: DecodeHex>(i: &mut ScanFnInput) {
+    let digits = get_unchecked!(i.input, i.curr_offset..i.curr_offset + 4);
+
+    // if there's another `\u` escape then its digits are decoded along with these ones
+    let next = i
+        .input
+        .get(i.curr_offset + 4..i.curr_offset + 10)
+        .filter(|next| next[..2] == *b"\\u");
+
+    let mut hex = [b'0'; 8];
+    hex[..4].copy_from_slice(digits);
+
+    if let Some(next) = next {
+        hex[4..].copy_from_slice(&next[2..]);
+    }
+
+    // if the next escape isn't valid then these digits may still be
+    let (code, next_code) = match D::decode_hex8(hex) {
+        Some([code, next_code]) => (code, next.map(|_| next_code)),
+        None if next.is_some() => {
+            hex[4..].copy_from_slice(b"0000");
+
+            match D::decode_hex8(hex) {
+                Some([code, _]) => (code, None),
+                None => return,
+            }
+        }
+        None => return,
+    };
+
+    // if we get this far then we're looking at a hex number
+    // we guarantee there are no `\` in the 4 bytes we've just looked through
+    // NOTE: only attempting to match the surrogate here means we'll accept `\u`
+    // escapes with other characters between them, but still guarantee valid UTF8
+    match i.scan.first_surrogate.take() {
+        // if we had a surrogate pair, then attempt to map it to a multibyte
+        Some(first) => {
+            if let Ok(ch) = crate::std_ext::char::try_from_utf16_surrogate_pair(first, code) {
+                i.push_unescaped_char(ch);
+            }
+        }
+        // if we didn't have a surrogate pair,
+        // then attempt to interpret the code as a 2-4 byte character
+        None => match char::try_from(code as u32) {
+            Ok(ch) => i.push_unescaped_char(ch),
+            Err(_) => {
+                let pair = next_code.and_then(|next_code| {
+                    crate::std_ext::char::try_from_utf16_surrogate_pair(code, next_code).ok()
+                });
+
+                match pair {
+                    // the next escape is the second half of the pair, so both are unescaped now
+                    Some(ch) => {
+                        i.push_unescaped_char(ch);
+
+                        // skip over the next escape
+                        i.scan.start += 6;
+                    }
+                    None => i.begin_surrogate_pair(code),
+                }
+            }
+        },
+    }
+}
+
+/**
+A way to decode the hex digits in `\u` escapes.
+*/
+trait DecodeHex {
+    /**
+    Decode two sets of 4 hex digits.
+
+    If any of the digits aren't hex then `None` is returned.
+    */
+    fn decode_hex8(hex: [u8; 8]) -> Option<[u16; 2]>;
+}
+
+/**
+Decode hex digits one at a time.
+*/
+struct ScalarHex;
+
+impl DecodeHex for ScalarHex {
+    #[inline]
+    fn decode_hex8(hex: [u8; 8]) -> Option<[u16; 2]> {
+        Some([hex4(&hex, 0)?, hex4(&hex, 4)?])
+    }
+}
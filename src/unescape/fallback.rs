@@ -20,7 +20,7 @@ fn unescape_block(i: ScanBlockInput) {
         let curr = offset_deref_unchecked!(i.input, i.scan.input_offset);
 
         if let b'\\' = curr {
-            interest_unescape(ScanFnInput {
+            interest_unescape::<ScalarHex, _>(ScanFnInput {
                 curr_offset,
                 input: i.input,
                 scan: i.scan,
@@ -5,7 +5,7 @@ use super::*;
 #[cfg(test)]
 const MAX_BLOCK_SIZE: usize = 32;
 
-trait UnescapeSimd {
+trait UnescapeSimd: DecodeHex {
     type Block: Sized + Clone + Copy;
     const BLOCK_SIZE: usize = mem::size_of::<Self::Block>();
 
@@ -53,7 +53,7 @@ pub(super) const AARCH64_NEON_VECTORIZATION_THRESHOLD: usize = aarch64::Neon::BL
 
 // SAFETY: Callers must ensure `input` is valid UTF8
 // SAFETY: Callers must ensure `input` does not end with an unescaped `\`
-#[inline]
+#[inline(always)]
 unsafe fn unescape_simd<V>(input: &[u8], scan: &mut Scan, unescaped: &mut Unescaped)
 where
     V: UnescapeSimd,
@@ -86,7 +86,7 @@ where
             let curr_offset = scan.input_offset as usize + block_offset as usize;
             test_assert!(curr_offset < input.len() as usize);
 
-            interest_unescape(&mut ScanFnInput {
+            interest_unescape::<V, _>(&mut ScanFnInput {
                 curr_offset,
                 input,
                 scan,
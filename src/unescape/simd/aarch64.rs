@@ -27,10 +27,61 @@ impl UnescapeSimd for Neon {
     }
 }
 
+impl DecodeHex for Neon {
+    #[inline(always)]
+    fn decode_hex8(hex: [u8; 8]) -> Option<[u16; 2]> {
+        // SAFETY: In this module, Neon is always available
+        unsafe {
+            let hex = vld1_u8(hex.as_ptr());
+
+            // digits are mapped from `0`..`9` to 0..9
+            // letters are mapped from `a`..`f` or `A`..`F` to 10..15
+            let digits = vsub_u8(hex, vdup_n_u8(b'0'));
+            let letters = vsub_u8(vorr_u8(hex, vdup_n_u8(0x20)), vdup_n_u8(b'a' - 10));
+
+            // each byte must be either a digit or a letter
+            let is_digit = vcle_u8(digits, vdup_n_u8(9));
+            let is_letter = vcle_u8(vsub_u8(letters, vdup_n_u8(10)), vdup_n_u8(5));
+
+            if vminv_u8(vorr_u8(is_digit, is_letter)) != 0xFF {
+                return None;
+            }
+
+            let nibbles =
+                vget_lane_u64::<0>(vreinterpret_u64_u8(vbsl_u8(is_digit, digits, letters)));
+
+            // combine pairs of nibbles into bytes, then pairs of bytes into codes
+            let bytes =
+                ((nibbles & 0x000F_000F_000F_000F) << 4) | ((nibbles >> 8) & 0x000F_000F_000F_000F);
+            let codes =
+                ((bytes & 0x0000_00FF_0000_00FF) << 8) | ((bytes >> 16) & 0x0000_00FF_0000_00FF);
+
+            Some([codes as u16, (codes >> 32) as u16])
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn decode_hex8_matches_scalar() {
+        let alphabet = b"0123456789abcdefABCDEFgG/:@`\\u\x00\xff";
+
+        for a in alphabet {
+            for b in alphabet {
+                for i in 0..8 {
+                    let mut hex = *b"09afAF3c";
+                    hex[i] = *a;
+                    hex[7 - i] = *b;
+
+                    assert_eq!(ScalarHex::decode_hex8(hex), Neon::decode_hex8(hex));
+                }
+            }
+        }
+    }
+
     #[test]
     fn block_offset_is_8_bytes() {
         assert_eq!(8, Neon::BLOCK_SIZE);
@@ -20,10 +20,72 @@ impl UnescapeSimd for AVX2 {
     }
 }
 
+impl DecodeHex for AVX2 {
+    #[inline(always)]
+    fn decode_hex8(hex: [u8; 8]) -> Option<[u16; 2]> {
+        unsafe {
+            let hex = _mm_cvtsi64_si128(i64::from_le_bytes(hex));
+
+            // digits are mapped from `0`..`9` to 0..9
+            // letters are mapped from `a`..`f` or `A`..`F` to 10..15
+            let digits = _mm_sub_epi8(hex, _mm_set1_epi8(b'0' as i8));
+            let letters = _mm_sub_epi8(
+                _mm_or_si128(hex, _mm_set1_epi8(0x20)),
+                _mm_set1_epi8((b'a' - 10) as i8),
+            );
+
+            // each byte must be either a digit or a letter
+            let is_digit = _mm_cmpeq_epi8(_mm_min_epu8(digits, _mm_set1_epi8(9)), digits);
+            let letters_from_10 = _mm_sub_epi8(letters, _mm_set1_epi8(10));
+            let is_letter = _mm_cmpeq_epi8(
+                _mm_min_epu8(letters_from_10, _mm_set1_epi8(5)),
+                letters_from_10,
+            );
+
+            if _mm_movemask_epi8(_mm_or_si128(is_digit, is_letter)) & 0xFF != 0xFF {
+                return None;
+            }
+
+            let nibbles = _mm_or_si128(
+                _mm_and_si128(is_digit, digits),
+                _mm_andnot_si128(is_digit, letters),
+            );
+
+            // combine pairs of nibbles into bytes, then pairs of bytes into codes
+            let bytes = _mm_maddubs_epi16(nibbles, _mm_set1_epi16(0x0110));
+            let codes = _mm_madd_epi16(bytes, _mm_set1_epi32(0x0001_0100));
+
+            let codes = _mm_cvtsi128_si64(codes) as u64;
+            Some([codes as u16, (codes >> 32) as u16])
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn decode_hex8_matches_scalar() {
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+
+        let alphabet = b"0123456789abcdefABCDEFgG/:@`\\u\x00\xff";
+
+        for a in alphabet {
+            for b in alphabet {
+                for i in 0..8 {
+                    let mut hex = *b"09afAF3c";
+                    hex[i] = *a;
+                    hex[7 - i] = *b;
+
+                    assert_eq!(ScalarHex::decode_hex8(hex), AVX2::decode_hex8(hex));
+                }
+            }
+        }
+    }
+
     #[test]
     fn block_offset_is_32_bytes() {
         assert_eq!(32, AVX2::BLOCK_SIZE);
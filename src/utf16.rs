@@ -0,0 +1,121 @@
+/*!
+UTF16 transcoding for input buffers.
+
+This module contains a vectorized implementation for transcoding UTF16 into UTF8.
+
+Documents are mostly 7bit ASCII, so the vectorized implementation just looks for blocks
+of code units that are all ASCII and narrows them into bytes. Blocks that aren't ASCII are
+transcoded one character at a time.
+
+This implementation follows the same basic design as `unescape` for supporting a vectorized
+and fallback implementation.
+*/
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+mod simd;
+
+/**
+Transcode a UTF16 buffer into UTF8, appending it to a caller-provided buffer.
+
+The input is in the native byte order. Unpaired surrogates are replaced with `U+FFFD`,
+the same way [`String::from_utf16_lossy`] replaces them, so the output is always valid UTF8.
+
+A transcoded JSON document can be scanned like any other, see [`OwnedDocument::scan_utf16`]
+for a document that owns its transcoded input.
+
+[`OwnedDocument::scan_utf16`]: crate::OwnedDocument::scan_utf16
+*/
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[inline]
+pub fn transcode_utf16_to_utf8(input: &[u16], out: &mut Vec<u8>) {
+    // HEURISTIC: most documents are ASCII, which transcode into one byte per code unit
+    out.reserve(input.len());
+
+    // when SIMD is available, we can vectorize
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2")
+            && input.len() > simd::X86_64_AVX2_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: avx2 is available
+            return unsafe { simd::transcode_x86_64_avx2(input, out) };
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon")
+            && input.len() > simd::AARCH64_NEON_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: neon is available
+            return unsafe { simd::transcode_aarch64_neon(input, out) };
+        }
+    }
+
+    // when SIMD is not available, we need to fallback
+    transcode_fallback(input, 0, out)
+}
+
+/**
+Transcode a UTF16 buffer into UTF8, appending it to a caller-provided buffer.
+
+The input is in the native byte order. Unpaired surrogates are replaced with `U+FFFD`,
+the same way [`String::from_utf16_lossy`] replaces them, so the output is always valid UTF8.
+
+A transcoded JSON document can be scanned like any other, see [`OwnedDocument::scan_utf16`]
+for a document that owns its transcoded input.
+
+[`OwnedDocument::scan_utf16`]: crate::OwnedDocument::scan_utf16
+*/
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[inline]
+pub fn transcode_utf16_to_utf8(input: &[u16], out: &mut Vec<u8>) {
+    out.reserve(input.len());
+
+    transcode_fallback(input, 0, out)
+}
+
+/**
+Transcode the rest of the input one character at a time, starting from an offset.
+*/
+#[inline]
+fn transcode_fallback(input: &[u16], mut input_offset: usize, out: &mut Vec<u8>) {
+    while input_offset < input.len() {
+        input_offset = transcode_char(input, input_offset, out);
+    }
+}
+
+/**
+Transcode the character that starts at an offset.
+
+Returns the offset of the next character.
+*/
+#[inline(always)]
+fn transcode_char(input: &[u16], input_offset: usize, out: &mut Vec<u8>) -> usize {
+    test_assert!(input_offset < input.len());
+
+    let unit = *get_unchecked!(input, input_offset);
+
+    let (c, len) = match unit {
+        0..=0x7f => {
+            out.push(unit as u8);
+            return input_offset + 1;
+        }
+        // a leading surrogate needs to be followed by a trailing surrogate
+        0xd800..=0xdbff => match input.get(input_offset + 1) {
+            Some(trailing @ 0xdc00..=0xdfff) => {
+                let c = 0x10000 + (((unit as u32) - 0xd800) << 10) + ((*trailing as u32) - 0xdc00);
+
+                (char::from_u32(c), 2)
+            }
+            _ => (None, 1),
+        },
+        unit => (char::from_u32(unit as u32), 1),
+    };
+
+    // unpaired surrogates aren't characters
+    let c = c.unwrap_or(char::REPLACEMENT_CHARACTER);
+
+    out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+
+    input_offset + len
+}
@@ -0,0 +1,88 @@
+use std::mem;
+
+use super::*;
+
+#[cfg(test)]
+const MAX_BLOCK_LEN: usize = 16;
+
+trait Utf16Simd {
+    type Block: Sized + Clone + Copy;
+    // the number of code units in a block
+    const BLOCK_LEN: usize = mem::size_of::<Self::Block>() / mem::size_of::<u16>();
+
+    fn load_block_unaligned(ptr: *const u16) -> Self::Block;
+    fn is_ascii(block: Self::Block) -> bool;
+    unsafe fn store_ascii_unaligned(block: Self::Block, ptr: *mut u8);
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64;
+
+// SAFETY: Callers must ensure `avx2` is available
+#[cfg(target_arch = "x86_64")]
+#[inline]
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn transcode_x86_64_avx2(input: &[u16], out: &mut Vec<u8>) {
+    transcode_simd::<x86_64::AVX2>(input, out)
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(super) const X86_64_AVX2_VECTORIZATION_THRESHOLD: usize = x86_64::AVX2::BLOCK_LEN;
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64;
+
+// SAFETY: Callers must ensure `neon` is available
+#[cfg(target_arch = "aarch64")]
+#[inline]
+#[target_feature(enable = "neon")]
+pub(super) unsafe fn transcode_aarch64_neon(input: &[u16], out: &mut Vec<u8>) {
+    transcode_simd::<aarch64::Neon>(input, out)
+}
+
+#[cfg(target_arch = "aarch64")]
+pub(super) const AARCH64_NEON_VECTORIZATION_THRESHOLD: usize = aarch64::Neon::BLOCK_LEN;
+
+#[inline(always)]
+unsafe fn transcode_simd<V>(input: &[u16], out: &mut Vec<u8>)
+where
+    V: Utf16Simd,
+{
+    test_assert!(V::BLOCK_LEN <= MAX_BLOCK_LEN);
+    test_assert!(input.len() >= V::BLOCK_LEN);
+
+    // HEURISTIC: we're transcoding the input once, so we just do unaligned loads
+
+    let last_block_start = input.len() - V::BLOCK_LEN;
+
+    let mut input_offset = 0;
+
+    'unaligned: while input_offset <= last_block_start {
+        test_assert!(input_offset + V::BLOCK_LEN <= input.len());
+
+        // we explicitly perform an unaligned load
+        let i = V::load_block_unaligned(input.as_ptr().add(input_offset));
+
+        if V::is_ascii(i) {
+            // each ASCII code unit is narrowed into a single byte
+            out.reserve(V::BLOCK_LEN);
+
+            let out_len = out.len();
+            V::store_ascii_unaligned(i, out.as_mut_ptr().add(out_len));
+            out.set_len(out_len + V::BLOCK_LEN);
+
+            input_offset += V::BLOCK_LEN;
+            continue 'unaligned;
+        }
+
+        // the block isn't ASCII, so transcode it one character at a time
+        // a surrogate pair at the end of the block may carry over into the next one
+        let block_end = input_offset + V::BLOCK_LEN;
+        while input_offset < block_end {
+            input_offset = transcode_char(input, input_offset, out);
+        }
+    }
+
+    // finish the input one character at a time
+    transcode_fallback(input, input_offset, out)
+}
@@ -0,0 +1,36 @@
+use super::*;
+
+use std::arch::aarch64::*;
+
+pub(super) struct Neon;
+impl Utf16Simd for Neon {
+    type Block = uint16x8_t;
+
+    #[inline(always)]
+    fn load_block_unaligned(ptr: *const u16) -> Self::Block {
+        // SAFETY: In this module, Neon is always available
+        unsafe { vld1q_u16(ptr) }
+    }
+
+    #[inline(always)]
+    fn is_ascii(block: Self::Block) -> bool {
+        // SAFETY: In this module, Neon is always available
+        unsafe { vmaxvq_u16(block) < 0x80 }
+    }
+
+    #[inline(always)]
+    unsafe fn store_ascii_unaligned(block: Self::Block, ptr: *mut u8) {
+        // each ASCII code unit fits in its low byte
+        vst1_u8(ptr, vmovn_u16(block));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_len_is_8_units() {
+        assert_eq!(8, Neon::BLOCK_LEN);
+    }
+}
@@ -0,0 +1,40 @@
+use super::*;
+
+use std::arch::x86_64::*;
+
+pub(super) struct AVX2;
+impl Utf16Simd for AVX2 {
+    type Block = __m256i;
+
+    #[inline(always)]
+    fn load_block_unaligned(ptr: *const u16) -> Self::Block {
+        unsafe { _mm256_loadu_si256(ptr as *const _) }
+    }
+
+    #[inline(always)]
+    fn is_ascii(block: Self::Block) -> bool {
+        // ASCII code units don't have any bits set above the lowest 7
+        unsafe { _mm256_testz_si256(block, _mm256_set1_epi16(0xff80u16 as i16)) == 1 }
+    }
+
+    #[inline(always)]
+    unsafe fn store_ascii_unaligned(block: Self::Block, ptr: *mut u8) {
+        // pack each half of the block into bytes, keeping them in order
+        let narrowed = _mm_packus_epi16(
+            _mm256_castsi256_si128(block),
+            _mm256_extracti128_si256(block, 1),
+        );
+
+        _mm_storeu_si128(ptr as *mut _, narrowed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_len_is_16_units() {
+        assert_eq!(16, AVX2::BLOCK_LEN);
+    }
+}
@@ -0,0 +1,75 @@
+/*!
+UTF8 validation for input buffers.
+
+This module contains a vectorized implementation for validating UTF8.
+
+Documents are mostly 7bit ASCII, so the vectorized implementation just looks for blocks
+that aren't ASCII and hands them off to the standard library's validator. Any ASCII
+byte is a character boundary, so these runs can be validated independently of each other.
+
+This implementation follows the same basic design as `unescape` for supporting a vectorized
+and fallback implementation.
+*/
+
+use std::str::{self, Utf8Error};
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+mod simd;
+
+/**
+Validate that the input is UTF8.
+*/
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[inline]
+pub(crate) fn from_utf8(input: &[u8]) -> Result<&str, Utf8Error> {
+    // when SIMD is available, we can vectorize
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2")
+            && input.len() > simd::X86_64_AVX2_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: avx2 is available
+            if unsafe { simd::validate_x86_64_avx2(input) } {
+                // SAFETY: the input is UTF8
+                return Ok(from_utf8_unchecked!(input));
+            }
+
+            return from_utf8_err(input);
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon")
+            && input.len() > simd::AARCH64_NEON_VECTORIZATION_THRESHOLD
+        {
+            // SAFETY: neon is available
+            if unsafe { simd::validate_aarch64_neon(input) } {
+                // SAFETY: the input is UTF8
+                return Ok(from_utf8_unchecked!(input));
+            }
+
+            return from_utf8_err(input);
+        }
+    }
+
+    // when SIMD is not available, we need to fallback
+    str::from_utf8(input)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[inline]
+pub(crate) fn from_utf8(input: &[u8]) -> Result<&str, Utf8Error> {
+    str::from_utf8(input)
+}
+
+/**
+Re-validate some input that's known to be invalid to get an error for it.
+*/
+#[cold]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn from_utf8_err(input: &[u8]) -> Result<&str, Utf8Error> {
+    let err = str::from_utf8(input);
+    test_assert!(err.is_err());
+
+    err
+}
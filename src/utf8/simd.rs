@@ -0,0 +1,103 @@
+use std::{mem, str};
+
+#[cfg(test)]
+const MAX_BLOCK_SIZE: usize = 32;
+
+trait Utf8Simd {
+    type Block: Sized + Clone + Copy;
+    const BLOCK_SIZE: usize = mem::size_of::<Self::Block>();
+
+    fn load_block_unaligned(ptr: *const u8) -> Self::Block;
+    fn mask_non_ascii(block: Self::Block) -> i32;
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64;
+
+// SAFETY: Callers must ensure `avx2` is available
+#[cfg(target_arch = "x86_64")]
+#[inline]
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn validate_x86_64_avx2(input: &[u8]) -> bool {
+    validate_simd::<x86_64::AVX2>(input)
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(super) const X86_64_AVX2_VECTORIZATION_THRESHOLD: usize = x86_64::AVX2::BLOCK_SIZE;
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64;
+
+// SAFETY: Callers must ensure `neon` is available
+#[cfg(target_arch = "aarch64")]
+#[inline]
+#[target_feature(enable = "neon")]
+pub(super) unsafe fn validate_aarch64_neon(input: &[u8]) -> bool {
+    validate_simd::<aarch64::Neon>(input)
+}
+
+#[cfg(target_arch = "aarch64")]
+pub(super) const AARCH64_NEON_VECTORIZATION_THRESHOLD: usize = aarch64::Neon::BLOCK_SIZE;
+
+#[inline(always)]
+unsafe fn validate_simd<V>(input: &[u8]) -> bool
+where
+    V: Utf8Simd,
+{
+    test_assert!(V::BLOCK_SIZE <= MAX_BLOCK_SIZE);
+    test_assert!(input.len() >= V::BLOCK_SIZE);
+
+    // HEURISTIC: we're validating the input once, so we just do unaligned loads
+
+    let last_block_start = input.len() - V::BLOCK_SIZE;
+
+    // the start of a run of blocks that contain non-ASCII bytes
+    // these runs are validated as a whole once an ASCII block is found
+    let mut non_ascii_start = None;
+    let mut input_offset = 0;
+
+    'unaligned: while input_offset <= last_block_start {
+        test_assert!(input_offset + V::BLOCK_SIZE <= input.len());
+
+        // we explicitly perform an unaligned load
+        let i = V::load_block_unaligned(input.as_ptr().add(input_offset));
+
+        let mask_non_ascii = V::mask_non_ascii(i);
+
+        match (mask_non_ascii, non_ascii_start) {
+            // the block is ASCII, and so are all the blocks before it
+            (0, None) => (),
+            // the block is ASCII, so the non-ASCII run before it can be validated
+            // the start of this block must be a character boundary
+            (0, Some(start)) => {
+                if !is_utf8(input, start, input_offset) {
+                    return false;
+                }
+
+                non_ascii_start = None;
+            }
+            // the block isn't ASCII, so begin a run
+            (_, None) => non_ascii_start = Some(input_offset),
+            // the block isn't ASCII, and we're already in a run
+            (_, Some(_)) => (),
+        }
+
+        input_offset += V::BLOCK_SIZE;
+    }
+
+    test_assert!(input.len() - input_offset < V::BLOCK_SIZE);
+
+    // finish the input using the standard library
+    is_utf8(input, non_ascii_start.unwrap_or(input_offset), input.len())
+}
+
+#[inline(always)]
+fn is_utf8(input: &[u8], from: usize, to: usize) -> bool {
+    str::from_utf8(offset_from_raw_parts!(
+        input.as_ptr(),
+        input.len(),
+        from,
+        to - from
+    ))
+    .is_ok()
+}
@@ -0,0 +1,29 @@
+use super::*;
+
+use std::arch::x86_64::*;
+
+pub(super) struct AVX2;
+impl Utf8Simd for AVX2 {
+    type Block = __m256i;
+
+    #[inline(always)]
+    fn load_block_unaligned(ptr: *const u8) -> Self::Block {
+        unsafe { _mm256_loadu_si256(ptr as *const _) }
+    }
+
+    #[inline(always)]
+    fn mask_non_ascii(block: Self::Block) -> i32 {
+        // non-ASCII bytes are the ones with their high bit set
+        unsafe { _mm256_movemask_epi8(block) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_offset_is_32_bytes() {
+        assert_eq!(32, AVX2::BLOCK_SIZE);
+    }
+}